@@ -0,0 +1,285 @@
+//! Comparing the public API of two crates based on their JSON output (`rruxwry diff`).
+//!
+//! Items are keyed by their path relative to the crate root, so the unstable item IDs don't get
+//! in the way. Inherent impls are transparent while trait impls are keyed by the trait they
+//! implement. An item counts as changed if its JSON differs once IDs, spans and docs are removed
+//! and the items it contains are disregarded since those get compared on their own.
+
+use crate::{
+    diagnostic::{Diagnostic, info},
+    json::{id_to_key, item_kind},
+};
+use joinery::JoinableIterator;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod test;
+
+/// The items of a crate keyed by their path relative to the crate root.
+pub(crate) type Api = BTreeMap<String, Item>;
+
+pub(crate) struct Item {
+    kind: String,
+    /// The normalized JSON of the item.
+    signature: Value,
+}
+
+/// Collect the items reachable from the root module of the given JSON output.
+pub(crate) fn collect(json: &Value) -> Api {
+    let mut api = Api::new();
+    let mut queue = vec![(id_to_key(&json["root"]), "crate".to_owned())];
+
+    while let Some((id, path)) = queue.pop() {
+        let Some(item) = json["index"].get(&id) else { continue };
+        let kind = item_kind(item).unwrap_or("item");
+
+        // The associated items of inherent impls are located directly under the type.
+        if kind != "impl" || !item["inner"]["impl"]["trait"].is_null() {
+            let signature = normalize(json, item);
+            api.insert(path.clone(), Item { kind: kind.to_owned(), signature });
+        }
+
+        for child in children(item) {
+            let key = id_to_key(child);
+            let Some(child) = json["index"].get(&key) else { continue };
+            let inner = &child["inner"];
+            let name = match (child["name"].as_str(), item_kind(child)) {
+                (Some(name), _) => name.to_owned(),
+                // Older format versions call them imports.
+                (None, Some(kind @ ("use" | "import"))) => {
+                    inner[kind]["name"].as_str().unwrap_or("_").to_owned()
+                }
+                (None, Some("impl")) if inner["impl"]["trait"].is_null() => {
+                    queue.push((key, path.clone()));
+                    continue;
+                }
+                (None, Some("impl")) => impl_name(&inner["impl"]),
+                (None, _) => continue,
+            };
+
+            let mut child_path = format!("{path}::{name}");
+            // Tell apart impls that render the same, e.g., blanket impls.
+            let mut ordinal = 1;
+            while api.contains_key(&child_path) || queue.iter().any(|(_, path)| *path == child_path)
+            {
+                ordinal += 1;
+                child_path = format!("{path}::{name}#{ordinal}");
+            }
+            queue.push((key, child_path));
+        }
+    }
+
+    api
+}
+
+/// The IDs of the items contained in the given item.
+fn children(item: &Value) -> Vec<&Value> {
+    fn ids(ids: &Value) -> impl Iterator<Item = &Value> {
+        ids.as_array().into_iter().flatten().filter(|id| !id.is_null())
+    }
+
+    let inner = &item["inner"];
+
+    match item_kind(item) {
+        Some("module") => ids(&inner["module"]["items"]).collect(),
+        Some("struct") => {
+            let kind = &inner["struct"]["kind"];
+            let fields = ids(&kind["plain"]["fields"]).chain(ids(&kind["tuple"]));
+            fields.chain(ids(&inner["struct"]["impls"])).collect()
+        }
+        Some("union") => {
+            ids(&inner["union"]["fields"]).chain(ids(&inner["union"]["impls"])).collect()
+        }
+        Some("enum") => {
+            ids(&inner["enum"]["variants"]).chain(ids(&inner["enum"]["impls"])).collect()
+        }
+        Some("variant") => {
+            let kind = &inner["variant"]["kind"];
+            ids(&kind["tuple"]).chain(ids(&kind["struct"]["fields"])).collect()
+        }
+        Some("trait") => ids(&inner["trait"]["items"]).collect(),
+        Some("impl") => ids(&inner["impl"]["items"]).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The item without its IDs, span, docs and the lists of the items it contains.
+fn normalize(json: &Value, item: &Value) -> Value {
+    let mut item = item.clone();
+    let Some(object) = item.as_object_mut() else { return item };
+
+    for key in ["id", "crate_id", "name", "span", "docs", "links"] {
+        object.remove(key);
+    }
+
+    let inner = object.get_mut("inner").and_then(Value::as_object_mut);
+    if let Some(inner) =
+        inner.and_then(|inner| inner.values_mut().next()).and_then(Value::as_object_mut)
+    {
+        for key in ["items", "impls", "fields", "variants", "implementations"] {
+            inner.remove(key);
+        }
+        // The fields of structs and variants.
+        if let Some(kind) = inner.get_mut("kind").and_then(Value::as_object_mut) {
+            kind.remove("tuple");
+            for key in ["plain", "struct"] {
+                if let Some(kind) = kind.get_mut(key).and_then(Value::as_object_mut) {
+                    kind.remove("fields");
+                }
+            }
+        }
+    }
+
+    canonicalize(json, &mut item);
+    item
+}
+
+/// Remove all IDs and replace paths as written (e.g., `super::S`) with canonical ones if known.
+fn canonicalize(json: &Value, value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            if let Some(id) = object.remove("id")
+                && object.contains_key("args")
+                && let Some(path) = json["paths"][id_to_key(&id)]["path"].as_array()
+            {
+                // Newer format versions call it `path`, older ones `name`.
+                let key = if object.contains_key("path") { "path" } else { "name" };
+                let path = path.iter().filter_map(Value::as_str).join_with("::").to_string();
+                object.insert(key.to_owned(), path.into());
+            }
+            object.values_mut().for_each(|value| canonicalize(json, value));
+        }
+        Value::Array(values) => values.iter_mut().for_each(|value| canonicalize(json, value)),
+        _ => {}
+    }
+}
+
+/// The name of a trait impl, e.g., `<impl From<u8>>`.
+fn impl_name(implementation: &Value) -> String {
+    let negative = if implementation["is_negative"] == true { "!" } else { "" };
+    let trait_ = &implementation["trait"];
+    let name = match (trait_["name"].as_str(), trait_["path"].as_str()) {
+        (Some(path), _) | (None, Some(path)) => last_segment(path),
+        (None, None) => "_",
+    };
+    format!("<impl {negative}{name}{}>", render_args(&trait_["args"]))
+}
+
+fn render_args(args: &Value) -> String {
+    let args = args["angle_bracketed"]["args"].as_array().into_iter().flatten();
+    let args: Vec<_> = args
+        .map(|argument| match argument.as_object().and_then(|argument| argument.iter().next()) {
+            Some((kind, value)) if kind == "type" => render_type(value),
+            Some((kind, Value::String(lifetime))) if kind == "lifetime" => lifetime.clone(),
+            Some((kind, value)) if kind == "const" => {
+                value["expr"].as_str().unwrap_or("_").to_owned()
+            }
+            _ => "_".to_owned(),
+        })
+        .collect();
+
+    match args.is_empty() {
+        true => String::new(),
+        false => format!("<{}>", args.join(", ")),
+    }
+}
+
+/// Render the given type in a simplified form, good enough for telling impls apart.
+fn render_type(type_: &Value) -> String {
+    let Some((kind, value)) = type_.as_object().and_then(|type_| type_.iter().next()) else {
+        return "_".to_owned();
+    };
+
+    match kind.as_str() {
+        "primitive" | "generic" => value.as_str().unwrap_or("_").to_owned(),
+        "resolved_path" => {
+            let path = value["name"].as_str().or(value["path"].as_str()).unwrap_or("_");
+            format!("{}{}", last_segment(path), render_args(&value["args"]))
+        }
+        "borrowed_ref" => {
+            let mutability = if value["is_mutable"] == true { "mut " } else { "" };
+            format!("&{mutability}{}", render_type(&value["type"]))
+        }
+        "tuple" => {
+            let types = value.as_array().into_iter().flatten().map(render_type);
+            format!("({})", types.join_with(", "))
+        }
+        "slice" => format!("[{}]", render_type(value)),
+        "array" => {
+            format!("[{}; {}]", render_type(&value["type"]), value["len"].as_str().unwrap_or("_"))
+        }
+        _ => "_".to_owned(),
+    }
+}
+
+fn last_segment(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+pub(crate) enum Difference<'a> {
+    Added(&'a str, &'a Item),
+    Removed(&'a str, &'a Item),
+    Changed(&'a str, &'a Item),
+}
+
+/// The items that were added, removed or changed between the two crates.
+pub(crate) fn differences<'a>(old: &'a Api, new: &'a Api) -> Vec<Difference<'a>> {
+    let mut differences = Vec::new();
+
+    for (path, item) in old {
+        match new.get(path) {
+            Some(new) if new.kind == item.kind && new.signature == item.signature => {}
+            Some(new) => differences.push(Difference::Changed(path, new)),
+            None => differences.push(Difference::Removed(path, item)),
+        }
+    }
+    for (path, item) in new {
+        if !old.contains_key(path) {
+            differences.push(Difference::Added(path, item));
+        }
+    }
+
+    differences.sort_by_key(|difference| match difference {
+        Difference::Added(path, _)
+        | Difference::Removed(path, _)
+        | Difference::Changed(path, _) => *path,
+    });
+    differences
+}
+
+impl std::fmt::Display for Difference<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (marker, path, item) = match self {
+            Self::Added(path, item) => ('+', path, item),
+            Self::Removed(path, item) => ('-', path, item),
+            Self::Changed(path, item) => ('~', path, item),
+        };
+        write!(f, "{marker} `{path}` ({})", item.kind)
+    }
+}
+
+/// Report the differences between the public APIs of the given crates.
+pub(crate) fn report(old: &Api, new: &Api, description: &str) {
+    let differences = differences(old, new);
+    let amount = differences.len();
+
+    if amount == 0 {
+        let items = old.len();
+        let s = if items == 1 { "" } else { "s" };
+        info(format!("the public APIs of {description} are identical"))
+            .note(format!("both consist of {items} item{s}"))
+            .emit();
+        return;
+    }
+
+    let s = if amount == 1 { "" } else { "s" };
+    differences
+        .iter()
+        .map(ToString::to_string)
+        .fold(
+            info(format!("the public APIs of {description} differ in {amount} item{s}")),
+            Diagnostic::note,
+        )
+        .emit();
+}
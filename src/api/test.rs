@@ -0,0 +1,87 @@
+use super::{collect, differences};
+use serde_json::{Value, json};
+
+fn paths(json: Value) -> Vec<String> {
+    collect(&json).into_keys().collect()
+}
+
+#[test]
+fn impls() {
+    let paths = paths(json!({
+        "root": 0,
+        "index": {
+            "0": { "name": "krate", "inner": { "module": { "items": [1] } } },
+            "1": { "name": "S", "inner": { "struct": {
+                "kind": { "tuple": [2] },
+                "impls": [3, 5, 7],
+            } } },
+            "2": { "name": "0", "inner": { "struct_field": { "primitive": "u8" } } },
+            "3": { "name": null, "inner": { "impl": { "trait": null, "items": [4] } } },
+            "4": { "name": "new", "inner": { "function": {} } },
+            "5": { "name": null, "inner": { "impl": {
+                "trait": { "name": "From", "id": 9, "args": { "angle_bracketed": {
+                    "args": [{ "type": { "primitive": "u8" } }],
+                } } },
+                "items": [6],
+            } } },
+            "6": { "name": "from", "inner": { "function": {} } },
+            "7": { "name": null, "inner": { "impl": {
+                "trait": { "name": "Send", "id": 10, "args": null },
+                "is_negative": true,
+                "items": [],
+            } } },
+        },
+        "paths": {},
+    }));
+
+    assert_eq!(paths, [
+        "crate",
+        "crate::S",
+        "crate::S::0",
+        "crate::S::<impl !Send>",
+        "crate::S::<impl From<u8>>",
+        "crate::S::<impl From<u8>>::from",
+        "crate::S::new",
+    ]);
+}
+
+#[test]
+fn changes() {
+    let crate_ = |output: Value, extra: Value| {
+        json!({
+            "root": 0,
+            "index": {
+                "0": { "name": "krate", "inner": { "module": { "items": [1, 2] } } },
+                "1": {
+                    "name": "f",
+                    "span": { "filename": "lib.rs" },
+                    "docs": "Some docs.",
+                    "inner": { "function": { "sig": { "inputs": [], "output": output } } },
+                },
+                "2": extra,
+            },
+            "paths": {
+                "3": { "crate_id": 0, "path": ["krate", "S"] },
+            },
+        })
+    };
+
+    let old = collect(&crate_(
+        json!({ "resolved_path": { "name": "S", "id": 3, "args": null } }),
+        json!({ "name": "g", "inner": { "function": {} } }),
+    ));
+    let new = collect(&crate_(
+        json!({ "resolved_path": { "name": "super::S", "id": 3, "args": null } }),
+        json!({ "name": "h", "inner": { "function": {} } }),
+    ));
+    let differences: Vec<_> = differences(&old, &new).iter().map(ToString::to_string).collect();
+    assert_eq!(differences, ["- `crate::g` (function)", "+ `crate::h` (function)"]);
+
+    let new = collect(&crate_(
+        json!({ "primitive": "u8" }),
+        json!({ "name": "g", "inner": { "function": {} } }),
+    ));
+    let differences: Vec<_> =
+        super::differences(&old, &new).iter().map(ToString::to_string).collect();
+    assert_eq!(differences, ["~ `crate::f` (function)"]);
+}
@@ -0,0 +1,126 @@
+//! Auditing the HTML output of rustdoc.
+
+use crate::{
+    diagnostic::{Diagnostic, IntoDiagnostic, error},
+    html::{self, Token},
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+pub(crate) mod accessibility;
+pub(crate) mod anchors;
+pub(crate) mod assets;
+pub(crate) mod definitions;
+pub(crate) mod links;
+
+#[cfg(test)]
+mod test;
+
+/// Resolve the given reference found in the file at `referrer` to a local path.
+///
+/// Returns `None` if it refers to a remote resource or to nothing at all.
+fn resolve(referrer: &Path, reference: &str) -> Option<PathBuf> {
+    let reference = reference.split(['?', '#']).next()?;
+
+    if reference.is_empty()
+        || reference.starts_with("//")
+        || reference.split('/').next()?.contains(':')
+        || reference.contains("${")
+    {
+        return None;
+    }
+
+    // We normalize the path lexically since the target might not exist.
+    let mut path = PathBuf::new();
+    for component in referrer.parent()?.join(reference).components() {
+        match component {
+            Component::ParentDir => _ = path.pop(),
+            Component::CurDir => {}
+            component => path.push(component),
+        }
+    }
+    Some(path)
+}
+
+/// Resolve the `href` of a link found on the page at `referrer` to the local file it leads to
+/// alongside its fragment.
+///
+/// A link to a directory leads to its `index.html`.
+fn link_target<'a>(referrer: &Path, href: &'a str) -> Option<(PathBuf, &'a str)> {
+    let (path, fragment) = href.split_once('#').unwrap_or((href, ""));
+    let mut target = match path.is_empty() {
+        true => referrer.to_owned(),
+        false => resolve(referrer, path)?,
+    };
+    if target.is_dir() {
+        target.push("index.html");
+    }
+    Some((target, fragment))
+}
+
+/// The part of the given fragment that doesn't refer to an anchor on the target page, if any.
+///
+/// Line ranges are of the form `⟨start⟩-⟨end⟩` and refer to the anchors of both lines.
+fn missing_anchor(fragment: &str, contains: impl Fn(&str) -> bool) -> Option<&str> {
+    match is_line_range(fragment) {
+        true => fragment.split('-').find(|line| !contains(line)),
+        false => (!contains(fragment)).then_some(fragment),
+    }
+}
+
+/// The IDs found on each page, read lazily.
+#[derive(Default)]
+struct Anchors(FxHashMap<PathBuf, Option<FxHashSet<String>>>);
+
+impl Anchors {
+    fn get(&mut self, page: &Path) -> io::Result<Option<&FxHashSet<String>>> {
+        if !self.0.contains_key(page) {
+            let ids = match std::fs::read_to_string(page) {
+                Ok(html) => Some(ids(&html)),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+                Err(error) => return Err(error),
+            };
+            self.0.insert(page.to_owned(), ids);
+        }
+
+        Ok(self.0[page].as_ref())
+    }
+}
+
+fn ids(html: &str) -> FxHashSet<String> {
+    html::tokenize(html)
+        .filter_map(|token| match token {
+            Token::StartTag(tag) => tag.attribute("id").map(ToOwned::to_owned),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether the fragment denotes a range of lines on a source page which gets handled by JavaScript.
+fn is_line_range(fragment: &str) -> bool {
+    fragment
+        .split_once('-')
+        .is_some_and(|(start, end)| [start, end].iter().all(|line| line.parse::<u32>().is_ok()))
+}
+
+fn relative<'a>(root: &Path, path: &'a Path) -> std::path::Display<'a> {
+    path.strip_prefix(root).unwrap_or(path).display()
+}
+
+pub(crate) struct Error {
+    /// What got audited.
+    subject: &'static str,
+    findings: Vec<String>,
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        let amount = self.findings.len();
+        let s = if amount == 1 { "" } else { "s" };
+        let diagnostic = error(format!("found {amount} issue{s} with the {}", self.subject));
+        self.findings.into_iter().fold(diagnostic, Diagnostic::note)
+    }
+}
@@ -0,0 +1,389 @@
+//! Checking the accessibility of the docs.
+//!
+//! This is merely a rough approximation of what proper accessibility checkers do.
+
+use super::{Error, relative};
+use crate::{
+    html::{self, Tag, Token},
+    utility::files,
+};
+use rustc_hash::FxHashSet;
+use std::{collections::BTreeMap, path::Path};
+
+#[cfg(test)]
+mod test;
+
+/// The minimum contrast ratio of normal text according to WCAG 2 (level AA).
+const MINIMUM_CONTRAST: f64 = 4.5;
+
+/// The pairs of theme variables denoting foreground and background colors that need to contrast.
+const COLOR_PAIRS: &[(&str, &str)] = &[
+    ("main-color", "main-background-color"),
+    ("link-color", "main-background-color"),
+    ("sidebar-link-color", "sidebar-background-color"),
+    ("code-highlight-kw-color", "code-block-background-color"),
+    ("code-highlight-string-color", "code-block-background-color"),
+    ("code-highlight-comment-color", "code-block-background-color"),
+    ("code-highlight-doc-comment-color", "code-block-background-color"),
+];
+
+/// The number of locations to list per rule.
+const SAMPLES: usize = 3;
+
+/// Check the docs found in `root` for common accessibility issues and check the contrast
+/// of the given theme.
+pub(crate) fn check(root: &Path, theme: &str) -> crate::error::Result {
+    let mut violations = Violations::default();
+
+    for page in files(root, &["html"])? {
+        let html = std::fs::read_to_string(&page)?;
+        check_page(&html, &relative(root, &page).to_string(), &mut violations);
+    }
+
+    for path in files(&root.join("static.files"), &["css"])? {
+        let css = std::fs::read_to_string(&path)?;
+        let Some(variables) = theme_variables(&css, theme) else { continue };
+
+        for &(foreground, background) in COLOR_PAIRS {
+            let colors = (variables.get(foreground), variables.get(background));
+            let (Some(foreground_color), Some(background_color)) = colors else { continue };
+            let (Some(foreground_color), Some(background_color)) =
+                (parse_color(foreground_color), parse_color(background_color))
+            else {
+                continue;
+            };
+
+            let ratio = contrast(foreground_color, background_color);
+            if ratio < MINIMUM_CONTRAST {
+                violations.record(
+                    "insufficient contrast",
+                    format!(
+                        "`--{foreground}` on `--{background}` in theme `{theme}`: {ratio:.2}:1"
+                    ),
+                );
+            }
+        }
+    }
+
+    if !violations.0.is_empty() {
+        let findings = violations
+            .0
+            .into_iter()
+            .map(|(rule, locations)| {
+                let amount = locations.len();
+                let s = if amount == 1 { "" } else { "s" };
+                let samples: Vec<_> = locations.iter().take(SAMPLES).map(String::as_str).collect();
+                let more = if amount > SAMPLES { ", …" } else { "" };
+                format!("{rule}: {amount} occurrence{s} ({}{more})", samples.join(", "))
+            })
+            .collect();
+        return Err(Error { subject: "accessibility of the docs", findings }.into());
+    }
+
+    Ok(())
+}
+
+/// The locations of violations grouped by rule.
+#[derive(Default)]
+struct Violations(BTreeMap<&'static str, Vec<String>>);
+
+impl Violations {
+    fn record(&mut self, rule: &'static str, location: String) {
+        self.0.entry(rule).or_default().push(location);
+    }
+}
+
+fn check_page(html: &str, page: &str, violations: &mut Violations) {
+    let mut ids = FxHashSet::default();
+    let mut references = Vec::new();
+    let mut heading = None;
+
+    for token in html::tokenize(html) {
+        let Token::StartTag(tag) = token else { continue };
+
+        if let Some(id) = tag.attribute("id") {
+            ids.insert(id.to_owned());
+        }
+
+        if tag.name == "img" && tag.attribute("alt").is_none() {
+            violations.record("missing alt text", format!("`{page}`"));
+        }
+
+        if let Some(level) = heading_level(tag.name) {
+            if let Some(previous) = heading
+                && level > previous + 1
+            {
+                violations
+                    .record("skipped heading level", format!("`{page}`: h{previous} → h{level}"));
+            }
+            heading = Some(level);
+        }
+
+        check_aria(&tag, page, &mut references, violations);
+    }
+
+    for (attribute, id) in references {
+        if !ids.contains(&id) {
+            violations
+                .record("dangling ARIA reference", format!("`{page}`: `{attribute}` to `{id}`"));
+        }
+    }
+}
+
+fn check_aria<'a>(
+    tag: &Tag<'a>,
+    page: &str,
+    references: &mut Vec<(&'a str, String)>,
+    violations: &mut Violations,
+) {
+    if let Some(role) = tag.attribute("role")
+        && !role.split_ascii_whitespace().any(|role| ROLES.contains(&role))
+    {
+        violations.record("invalid ARIA role", format!("`{page}`: `{role}`"));
+    }
+
+    for (key, value) in &tag.attributes {
+        let Some(name) = key.strip_prefix("aria-") else { continue };
+
+        if !ATTRIBUTES.contains(&name) {
+            violations.record("unknown ARIA attribute", format!("`{page}`: `{key}`"));
+            continue;
+        }
+
+        if matches!(name, "labelledby" | "describedby" | "controls" | "owns" | "activedescendant") {
+            references.extend(value.split_ascii_whitespace().map(|id| (*key, id.to_owned())));
+        }
+    }
+
+    let is_focusable = match tag.name {
+        "a" => tag.attribute("href").is_some(),
+        "button" | "input" | "select" | "textarea" => tag.attribute("disabled").is_none(),
+        _ => tag
+            .attribute("tabindex")
+            .and_then(|index| index.parse::<i32>().ok())
+            .is_some_and(|index| index >= 0),
+    };
+    if is_focusable && tag.attribute("aria-hidden") == Some("true") {
+        violations.record("hidden focusable element", format!("`{page}`: `<{}>`", tag.name));
+    }
+}
+
+fn heading_level(name: &str) -> Option<u8> {
+    match name.as_bytes() {
+        [b'h', level @ b'1'..=b'6'] => Some(level - b'0'),
+        _ => None,
+    }
+}
+
+/// Extract the CSS variables of the given theme from the stylesheet.
+fn theme_variables<'a>(css: &'a str, theme: &str) -> Option<BTreeMap<&'a str, &'a str>> {
+    let selector = format!(":root[data-theme=\"{theme}\"]");
+    let start = css.find(&selector)?;
+    let block = &css[start..];
+    let block = &block[block.find('{')? + 1..block.find('}')?];
+
+    Some(
+        block
+            .split(';')
+            .filter_map(|declaration| {
+                let (key, value) = declaration.split_once(':')?;
+                Some((key.trim().strip_prefix("--")?, value.trim()))
+            })
+            .collect(),
+    )
+}
+
+/// A color given as sRGB channels in the range `0..=1`.
+type Color = [f64; 3];
+
+/// Parse a CSS color, ignoring its alpha channel.
+fn parse_color(color: &str) -> Option<Color> {
+    let color = color.trim().to_ascii_lowercase();
+
+    if let Some(hex) = color.strip_prefix('#') {
+        let channels: Vec<_> = match hex.len() {
+            3 | 4 => hex.chars().take(3).map(|digit| digit.to_string().repeat(2)).collect(),
+            6 | 8 => (0..3)
+                .map(|index| hex.get(index * 2..index * 2 + 2).map(ToOwned::to_owned))
+                .collect::<Option<_>>()?,
+            _ => return None,
+        };
+        let mut result = [0.0; 3];
+        for (channel, value) in result.iter_mut().zip(channels) {
+            *channel = f64::from(u8::from_str_radix(&value, 16).ok()?) / 255.0;
+        }
+        return Some(result);
+    }
+
+    if let Some(arguments) = color.strip_prefix("rgba(").or_else(|| color.strip_prefix("rgb(")) {
+        let arguments = arguments.strip_suffix(')')?;
+        let mut channels = arguments.split([',', ' ', '/']).filter(|channel| !channel.is_empty());
+        let mut result = [0.0; 3];
+        for channel in &mut result {
+            *channel = channels.next()?.parse::<f64>().ok()? / 255.0;
+        }
+        return Some(result);
+    }
+
+    Some(match &*color {
+        "black" => [0.0; 3],
+        "white" => [1.0; 3],
+        _ => return None,
+    })
+}
+
+/// The contrast ratio of the given colors as defined by WCAG 2.
+fn contrast(foreground: Color, background: Color) -> f64 {
+    let (foreground, background) = (luminance(foreground), luminance(background));
+    let (lighter, darker) =
+        if foreground > background { (foreground, background) } else { (background, foreground) };
+    (lighter + 0.05) / (darker + 0.05)
+}
+
+fn luminance(color: Color) -> f64 {
+    let [red, green, blue] = color.map(|channel| {
+        if channel <= 0.04045 { channel / 12.92 } else { ((channel + 0.055) / 1.055).powf(2.4) }
+    });
+    0.2126 * red + 0.7152 * green + 0.0722 * blue
+}
+
+/// The WAI-ARIA roles (excluding deprecated and abstract ones).
+const ROLES: &[&str] = &[
+    "alert",
+    "alertdialog",
+    "application",
+    "article",
+    "banner",
+    "blockquote",
+    "button",
+    "caption",
+    "cell",
+    "checkbox",
+    "code",
+    "columnheader",
+    "combobox",
+    "complementary",
+    "contentinfo",
+    "definition",
+    "deletion",
+    "dialog",
+    "document",
+    "emphasis",
+    "feed",
+    "figure",
+    "form",
+    "generic",
+    "grid",
+    "gridcell",
+    "group",
+    "heading",
+    "img",
+    "insertion",
+    "link",
+    "list",
+    "listbox",
+    "listitem",
+    "log",
+    "main",
+    "mark",
+    "marquee",
+    "math",
+    "menu",
+    "menubar",
+    "menuitem",
+    "menuitemcheckbox",
+    "menuitemradio",
+    "meter",
+    "navigation",
+    "none",
+    "note",
+    "option",
+    "paragraph",
+    "presentation",
+    "progressbar",
+    "radio",
+    "radiogroup",
+    "region",
+    "row",
+    "rowgroup",
+    "rowheader",
+    "scrollbar",
+    "search",
+    "searchbox",
+    "separator",
+    "slider",
+    "spinbutton",
+    "status",
+    "strong",
+    "subscript",
+    "superscript",
+    "switch",
+    "tab",
+    "table",
+    "tablist",
+    "tabpanel",
+    "term",
+    "textbox",
+    "time",
+    "timer",
+    "toolbar",
+    "tooltip",
+    "tree",
+    "treegrid",
+    "treeitem",
+];
+
+/// The WAI-ARIA states and properties without the `aria-` prefix.
+const ATTRIBUTES: &[&str] = &[
+    "activedescendant",
+    "atomic",
+    "autocomplete",
+    "braillelabel",
+    "brailleroledescription",
+    "busy",
+    "checked",
+    "colcount",
+    "colindex",
+    "colindextext",
+    "colspan",
+    "controls",
+    "current",
+    "describedby",
+    "description",
+    "details",
+    "disabled",
+    "errormessage",
+    "expanded",
+    "flowto",
+    "haspopup",
+    "hidden",
+    "invalid",
+    "keyshortcuts",
+    "label",
+    "labelledby",
+    "level",
+    "live",
+    "modal",
+    "multiline",
+    "multiselectable",
+    "orientation",
+    "owns",
+    "placeholder",
+    "posinset",
+    "pressed",
+    "readonly",
+    "relevant",
+    "required",
+    "roledescription",
+    "rowcount",
+    "rowindex",
+    "rowindextext",
+    "rowspan",
+    "selected",
+    "setsize",
+    "sort",
+    "valuemax",
+    "valuemin",
+    "valuenow",
+    "valuetext",
+];
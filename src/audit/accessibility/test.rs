@@ -0,0 +1,41 @@
+use super::{Violations, check_page, contrast, parse_color, theme_variables};
+
+#[test]
+fn colors() {
+    assert_eq!(parse_color("#fff"), Some([1.0; 3]));
+    assert_eq!(parse_color("#000000"), Some([0.0; 3]));
+    assert_eq!(parse_color("rgba(255, 0, 255, 0.5)"), Some([1.0, 0.0, 1.0]));
+    assert_eq!(parse_color("White"), Some([1.0; 3]));
+    assert_eq!(parse_color("var(--x)"), None);
+}
+
+#[test]
+fn contrast_ratio() {
+    assert_eq!(contrast([0.0; 3], [1.0; 3]), 21.0);
+    assert_eq!(contrast([0.5; 3], [0.5; 3]), 1.0);
+}
+
+#[test]
+fn theme() {
+    let css = r#":root[data-theme="light"]{--main-color:black;--x:1}:root[data-theme="dark"]{--main-color:#ddd}"#;
+    let variables = theme_variables(css, "dark").unwrap();
+    assert_eq!(variables.get("main-color"), Some(&"#ddd"));
+    assert_eq!(variables.get("x"), None);
+}
+
+#[test]
+fn page() {
+    let mut violations = Violations::default();
+    check_page(
+        r#"<h1>A</h1><h3>B</h3><img src="x.svg"><div role="bogus" aria-labelledby="y"></div>"#,
+        "index.html",
+        &mut violations,
+    );
+    let rules: Vec<_> = violations.0.keys().copied().collect();
+    assert_eq!(rules, [
+        "dangling ARIA reference",
+        "invalid ARIA role",
+        "missing alt text",
+        "skipped heading level"
+    ]);
+}
@@ -0,0 +1,113 @@
+//! Checking the uniqueness of anchors and the targets of fragments.
+
+use super::{Error, link_target, missing_anchor, relative};
+use crate::{
+    html::{self, Token},
+    utility::files,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+#[cfg(test)]
+mod test;
+
+/// The IDs found on a page alongside their number of occurrences.
+type Ids = FxHashMap<String, usize>;
+
+/// Check that the IDs on every page of the docs in `root` are unique and that every fragment
+/// of a link to a local page refers to an existing anchor.
+pub(crate) fn check(root: &Path) -> crate::error::Result {
+    let mut findings = Vec::new();
+    let mut anchors = FxHashMap::default();
+    let mut links = BTreeSet::new();
+
+    for page in files(root, &["html"])? {
+        let html = std::fs::read_to_string(&page)?;
+        let (ids, fragments) = scan(&page, &html);
+
+        for (id, count) in duplicates(&ids) {
+            findings.push(format!(
+                "duplicate anchor `{}#{id}` ({count} occurrences)",
+                relative(root, &page)
+            ));
+        }
+
+        links.extend(
+            fragments.into_iter().map(|(target, fragment)| (target, fragment, page.clone())),
+        );
+        anchors.insert(page, ids);
+    }
+
+    for (target, anchor, referrer) in dangling(&anchors, &links) {
+        findings.push(format!(
+            "missing anchor `{}#{anchor}` (linked from `{}`)",
+            relative(root, target),
+            relative(root, referrer)
+        ));
+    }
+
+    if !findings.is_empty() {
+        return Err(Error { subject: "anchors", findings }.into());
+    }
+
+    Ok(())
+}
+
+/// The IDs on the given page alongside the targets of the fragments of its links.
+fn scan(page: &Path, html: &str) -> (Ids, Vec<(PathBuf, String)>) {
+    let mut ids = Ids::default();
+    let mut fragments = Vec::new();
+
+    for token in html::tokenize(html) {
+        let Token::StartTag(tag) = token else { continue };
+
+        if let Some(id) = tag.attribute("id") {
+            *ids.entry(id.to_owned()).or_default() += 1;
+        }
+
+        if tag.name == "a"
+            && let Some((target, fragment)) =
+                tag.attribute("href").and_then(|href| link_target(page, href))
+            && !fragment.is_empty()
+        {
+            fragments.push((target, fragment.to_owned()));
+        }
+    }
+
+    (ids, fragments)
+}
+
+/// The IDs occurring more than once, sorted.
+fn duplicates(ids: &Ids) -> Vec<(&str, usize)> {
+    let mut duplicates: Vec<_> = ids
+        .iter()
+        .filter(|(_, &count)| count > 1)
+        .map(|(id, &count)| (id.as_str(), count))
+        .collect();
+    duplicates.sort();
+    duplicates
+}
+
+/// The anchors missing from the given pages alongside the first page linking to them.
+fn dangling<'a>(
+    anchors: &FxHashMap<PathBuf, Ids>,
+    links: &'a BTreeSet<(PathBuf, String, PathBuf)>,
+) -> Vec<(&'a Path, &'a str, &'a Path)> {
+    let mut reported = FxHashSet::default();
+    let mut dangling = Vec::new();
+
+    for (target, fragment, referrer) in links {
+        // Missing pages are the concern of the link checker.
+        let Some(ids) = anchors.get(target) else { continue };
+        let Some(anchor) = missing_anchor(fragment, |id| ids.contains_key(id)) else { continue };
+        // Only report the first page linking to a given missing anchor.
+        if reported.insert((target, anchor)) {
+            dangling.push((&**target, anchor, &**referrer));
+        }
+    }
+
+    dangling
+}
@@ -0,0 +1,66 @@
+use super::{Ids, dangling, duplicates, scan};
+use rustc_hash::FxHashMap;
+use std::{collections::BTreeSet, path::PathBuf};
+
+fn ids(ids: &[&str]) -> Ids {
+    ids.iter().map(|&id| (id.to_owned(), 1)).collect()
+}
+
+#[test]
+fn duplicate_ids() {
+    let html = r##"<h2 id="impls">Impls</h2><section id="method.f"></section>
+        <section id="method.f"></section><span id="impls"></span><div id="impls"></div>
+        <a href="#main">main</a>"##;
+    let (ids, _) = scan(&PathBuf::from("doc/krate/struct.S.html"), html);
+    assert_eq!(duplicates(&ids), [("impls", 3), ("method.f", 2)]);
+}
+
+#[test]
+fn fragments() {
+    let page = PathBuf::from("doc/krate/struct.S.html");
+    let html = r##"<a href="#method.f">f</a><a href="trait.T.html#tymethod.g">g</a>
+        <a href="../src/krate/lib.rs.html#3-7">source</a><a href="index.html">index</a>
+        <a href="#">top</a><a href="https://example.org/#x">remote</a>
+        <a href="../index.html?search=S#results">search</a><a href="?search=S#results">?</a>"##;
+    let (_, fragments) = scan(&page, html);
+    let fragments: Vec<_> = fragments
+        .iter()
+        .map(|(target, fragment)| (target.to_str().unwrap(), &**fragment))
+        .collect();
+    assert_eq!(fragments, [
+        ("doc/krate/struct.S.html", "method.f"),
+        ("doc/krate/trait.T.html", "tymethod.g"),
+        ("doc/src/krate/lib.rs.html", "3-7"),
+        ("doc/index.html", "results"),
+    ]);
+}
+
+#[test]
+fn dangling_anchors() {
+    let (page, source) = (PathBuf::from("struct.S.html"), PathBuf::from("lib.rs.html"));
+    let anchors = FxHashMap::from_iter([
+        (page.clone(), ids(&["method.f"])),
+        (source.clone(), ids(&["1", "2", "3"])),
+    ]);
+    let link = |target: &PathBuf, fragment: &str, referrer: &str| {
+        (target.clone(), fragment.to_owned(), PathBuf::from(referrer))
+    };
+    let links = BTreeSet::from_iter([
+        link(&page, "method.f", "a.html"),
+        link(&page, "method.g", "b.html"),
+        link(&page, "method.g", "a.html"),
+        link(&source, "1-3", "a.html"),
+        link(&source, "2-4", "a.html"),
+        link(&source, "4", "b.html"),
+        // Missing pages are reported by the link checker instead.
+        link(&PathBuf::from("enum.E.html"), "variant.V", "a.html"),
+    ]);
+
+    let dangling: Vec<_> = dangling(&anchors, &links)
+        .into_iter()
+        .map(|(target, anchor, referrer)| {
+            (target.to_str().unwrap(), anchor, referrer.to_str().unwrap())
+        })
+        .collect();
+    assert_eq!(dangling, [("lib.rs.html", "4", "a.html"), ("struct.S.html", "method.g", "a.html")]);
+}
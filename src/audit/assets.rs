@@ -0,0 +1,118 @@
+//! Checking the integrity of the static assets.
+
+use super::{Error, relative, resolve};
+use crate::{
+    html::{self, Token},
+    utility::files,
+};
+use std::{collections::BTreeMap, path::Path};
+
+#[cfg(test)]
+mod test;
+
+/// Check that every asset referenced by the docs in `root` exists and that every static asset
+/// is referenced somewhere.
+pub(crate) fn check(root: &Path) -> crate::error::Result {
+    let mut findings = Vec::new();
+    // The missing assets alongside the first file referencing them.
+    let mut missing = BTreeMap::new();
+    // The contents of all files that may mention static assets.
+    let mut contents = String::new();
+
+    for path in files(root, &["html", "css", "js"])? {
+        let text = std::fs::read_to_string(&path)?;
+
+        let references = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("html") => html_references(&text),
+            Some("css") => css_references(&text),
+            _ => Vec::new(),
+        };
+
+        for reference in references {
+            if let Some(target) = resolve(&path, &reference)
+                && !target.exists()
+                // These only exist if there are implementations in downstream crates.
+                && !["trait.impl", "type.impl"].iter().any(|dir| target.starts_with(root.join(dir)))
+            {
+                missing.entry(target).or_insert_with(|| path.clone());
+            }
+        }
+
+        contents.push_str(&text);
+    }
+
+    for (target, referrer) in missing {
+        findings.push(format!(
+            "missing asset `{}` (referenced from `{}`)",
+            relative(root, &target),
+            relative(root, &referrer)
+        ));
+    }
+
+    let static_files = root.join("static.files");
+    if static_files.is_dir() {
+        for entry in std::fs::read_dir(&static_files)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else { continue };
+            if !is_optional(name) && !contents.contains(name) {
+                findings.push(format!("unreferenced asset `{}`", relative(root, &path)));
+            }
+        }
+    }
+
+    if !findings.is_empty() {
+        findings.sort();
+        return Err(Error { subject: "static assets", findings }.into());
+    }
+
+    Ok(())
+}
+
+fn html_references(html: &str) -> Vec<String> {
+    let mut references = Vec::new();
+
+    for token in html::tokenize(html) {
+        let Token::StartTag(tag) = token else { continue };
+
+        let attribute = match tag.name {
+            "link" => "href",
+            "script" | "img" | "source" => "src",
+            "meta" if tag.attribute("name") == Some("rustdoc-vars") => {
+                // These get loaded by `main.js` on demand.
+                let root = tag.attribute("data-static-root-path").unwrap_or_default();
+                for key in ["data-search-js", "data-settings-js"] {
+                    if let Some(file) = tag.attribute(key) {
+                        references.push(format!("{root}{file}"));
+                    }
+                }
+                continue;
+            }
+            _ => continue,
+        };
+
+        if let Some(reference) = tag.attribute(attribute) {
+            references.push(reference.to_owned());
+        }
+    }
+
+    references
+}
+
+fn css_references(css: &str) -> Vec<String> {
+    css.split("url(")
+        .skip(1)
+        .filter_map(|rest| {
+            let reference = &rest[..rest.find(')')?];
+            Some(reference.trim().trim_matches(['"', '\'']).to_owned())
+        })
+        .collect()
+}
+
+/// Whether the given static asset may legitimately go unreferenced.
+fn is_optional(name: &str) -> bool {
+    // Licenses are merely shipped alongside the fonts and the like.
+    name.ends_with(".txt")
+        || name.ends_with(".md")
+        // Only loaded by pages containing scraped examples.
+        || name.starts_with("scrape-examples-")
+}
@@ -0,0 +1,34 @@
+use super::{css_references, html_references, is_optional};
+
+#[test]
+fn html() {
+    let html = r#"<link rel="stylesheet" href="../static.files/rustdoc-1.css">
+        <script src="../static.files/main-2.js" defer></script>
+        <img src="../rust-logo.svg" alt=""><a href="struct.S.html">S</a>
+        <meta name="rustdoc-vars" data-static-root-path="../static.files/"
+            data-search-js="search-3.js" data-settings-js="settings-4.js">
+        <meta name="generator" data-search-js="ignored.js">"#;
+    assert_eq!(html_references(html), [
+        "../static.files/rustdoc-1.css",
+        "../static.files/main-2.js",
+        "../rust-logo.svg",
+        "../static.files/search-3.js",
+        "../static.files/settings-4.js",
+    ]);
+}
+
+#[test]
+fn css() {
+    let css = r#"@font-face{src:url("FiraSans-Regular-1.woff2")}
+        .logo{background:url( 'rust-logo.svg' )}a{background:url(wheel.svg)}"#;
+    assert_eq!(css_references(css), ["FiraSans-Regular-1.woff2", "rust-logo.svg", "wheel.svg"]);
+}
+
+#[test]
+fn optional() {
+    assert!(is_optional("FiraSans-LICENSE-1.txt"));
+    assert!(is_optional("COPYRIGHT-2.md"));
+    assert!(is_optional("scrape-examples-help-3.md"));
+    assert!(is_optional("scrape-examples-4.js"));
+    assert!(!is_optional("main-5.js"));
+}
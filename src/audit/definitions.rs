@@ -0,0 +1,74 @@
+//! Checking the output of `--generate-link-to-definition`.
+
+use super::{Anchors, Error, link_target, relative};
+use crate::{
+    html::{self, Token},
+    utility::files,
+};
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod test;
+
+/// Check that the source links of items and the definition links on source pages of the docs
+/// in `root` resolve to existing pages and line anchors.
+pub(crate) fn check(root: &Path) -> crate::error::Result {
+    let sources = root.join("src");
+    let mut findings = Vec::new();
+    let mut anchors = Anchors::default();
+
+    for page in files(root, &["html"])? {
+        let html = std::fs::read_to_string(&page)?;
+
+        for (target, lines) in links(&page, &html, page.starts_with(&sources)) {
+            let Some(ids) = anchors.get(&target)? else {
+                findings.push(format!(
+                    "`{}` links to the missing page `{}`",
+                    relative(root, &page),
+                    relative(root, &target)
+                ));
+                continue;
+            };
+
+            for line in lines.into_iter().filter(|line| !ids.contains(line)) {
+                findings.push(format!(
+                    "`{}` links to the missing line anchor `{}#{line}`",
+                    relative(root, &page),
+                    relative(root, &target)
+                ));
+            }
+        }
+    }
+
+    if !findings.is_empty() {
+        findings.dedup();
+        return Err(Error { subject: "links to definitions", findings }.into());
+    }
+
+    Ok(())
+}
+
+/// The local targets of the links to check on the given page alongside the lines they refer to.
+fn links(page: &Path, html: &str, is_source_page: bool) -> Vec<(PathBuf, Vec<String>)> {
+    let mut links = Vec::new();
+
+    for token in html::tokenize(html) {
+        let Token::StartTag(tag) = token else { continue };
+        if tag.name != "a" {
+            continue;
+        }
+        // On item pages, we only care about the links to the source code.
+        if !is_source_page && tag.attribute("class") != Some("src") {
+            continue;
+        }
+        let Some(href) = tag.attribute("href") else { continue };
+        let Some((target, fragment)) = link_target(page, href) else { continue };
+
+        // Line ranges are of the form `⟨start⟩-⟨end⟩`.
+        let lines =
+            fragment.split('-').filter(|line| !line.is_empty()).map(ToOwned::to_owned).collect();
+        links.push((target, lines));
+    }
+
+    links
+}
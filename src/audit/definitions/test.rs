@@ -0,0 +1,28 @@
+use super::links;
+use std::path::{Path, PathBuf};
+
+fn targets(page: &str, html: &str, is_source_page: bool) -> Vec<(PathBuf, Vec<String>)> {
+    links(Path::new(page), html, is_source_page)
+}
+
+#[test]
+fn item_pages() {
+    let html = r##"<a class="src" href="../src/krate/lib.rs.html#3-7">Source</a>
+        <a href="fn.g.html">g</a><a class="src" href="../src/krate/lib.rs.html#12">Source</a>
+        <a class="src" href="https://example.org/lib.rs.html#1">Source</a>"##;
+    assert_eq!(targets("doc/krate/fn.f.html", html, false), [
+        (PathBuf::from("doc/src/krate/lib.rs.html"), vec!["3".to_owned(), "7".to_owned()]),
+        (PathBuf::from("doc/src/krate/lib.rs.html"), vec!["12".to_owned()]),
+    ]);
+}
+
+#[test]
+fn source_pages() {
+    let html = r##"<a href="#1" id="1">1</a><a href="../../krate/struct.S.html">S</a>
+        <a href="dep.rs.html#4">dep::f</a><a href="?search=S">search</a>"##;
+    assert_eq!(targets("doc/src/krate/lib.rs.html", html, true), [
+        (PathBuf::from("doc/src/krate/lib.rs.html"), vec!["1".to_owned()]),
+        (PathBuf::from("doc/krate/struct.S.html"), vec![]),
+        (PathBuf::from("doc/src/krate/dep.rs.html"), vec!["4".to_owned()]),
+    ]);
+}
@@ -0,0 +1,92 @@
+//! Checking the targets of links, akin to the `linkchecker` tool of the rust repository.
+
+use super::{Anchors, Error, link_target, missing_anchor, relative};
+use crate::{
+    html::{self, Token},
+    utility::files,
+};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+#[cfg(test)]
+mod test;
+
+/// Check that every link on the pages of the docs in `root` that leads to a local file refers
+/// to an existing file and, if it has a fragment and `fragments` is set, to an existing anchor
+/// on that page.
+///
+/// The fragments are left to the [anchor check](super::anchors::check) if that one runs, too.
+pub(crate) fn check(root: &Path, fragments: bool) -> crate::error::Result {
+    let mut anchors = Anchors::default();
+    // The missing files and anchors alongside the first page linking to them.
+    let mut missing = BTreeMap::new();
+    let mut dangling = BTreeMap::new();
+
+    for page in files(root, &["html"])? {
+        let html = std::fs::read_to_string(&page)?;
+
+        for (target, fragment) in local_links(&page, &html) {
+            if !target.exists() && !may_be_missing(root, &target) {
+                missing.entry(target).or_insert_with(|| page.clone());
+                continue;
+            }
+
+            if !fragments
+                || fragment.is_empty()
+                || target.extension().is_none_or(|extension| extension != "html")
+            {
+                continue;
+            }
+            let Some(ids) = anchors.get(&target)? else { continue };
+
+            if let Some(anchor) = missing_anchor(&fragment, |id| ids.contains(id)) {
+                dangling.entry((target, anchor.to_owned())).or_insert_with(|| page.clone());
+            }
+        }
+    }
+
+    let mut findings = Vec::new();
+
+    for (target, referrer) in missing {
+        findings.push(format!(
+            "missing file `{}` (linked from `{}`)",
+            relative(root, &target),
+            relative(root, &referrer)
+        ));
+    }
+
+    for ((target, fragment), referrer) in dangling {
+        findings.push(format!(
+            "missing anchor `{}#{fragment}` (linked from `{}`)",
+            relative(root, &target),
+            relative(root, &referrer)
+        ));
+    }
+
+    if !findings.is_empty() {
+        return Err(Error { subject: "links", findings }.into());
+    }
+
+    Ok(())
+}
+
+/// The local files the links on the given page lead to alongside their fragments.
+fn local_links(page: &Path, html: &str) -> Vec<(PathBuf, String)> {
+    html::tokenize(html)
+        .filter_map(|token| match token {
+            Token::StartTag(tag) if tag.name == "a" => {
+                let (target, fragment) = link_target(page, tag.attribute("href")?)?;
+                Some((target, fragment.to_owned()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether the given file in `root` may be missing even if it's linked to.
+fn may_be_missing(root: &Path, target: &Path) -> bool {
+    // The help and settings pages link to the index page even if there's none.
+    target == root.join("index.html")
+}
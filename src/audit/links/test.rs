@@ -0,0 +1,24 @@
+use super::{local_links, may_be_missing};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn links() {
+    let html = r##"<a href="struct.S.html#method.f">f</a><a href="../index.html?search=S">search</a>
+        <a href="#implementations">impls</a><a href="?search=S">?</a><a>no href</a>
+        <a href="https://doc.rust-lang.org/std/">std</a><a href="mailto:someone@example.org">mail</a>
+        <link rel="stylesheet" href="../static.files/rustdoc.css">"##;
+    let links = local_links(Path::new("doc/krate/index.html"), html);
+    assert_eq!(links, [
+        (PathBuf::from("doc/krate/struct.S.html"), "method.f".to_owned()),
+        (PathBuf::from("doc/index.html"), String::new()),
+        (PathBuf::from("doc/krate/index.html"), "implementations".to_owned()),
+    ]);
+}
+
+#[test]
+fn missing_index_page() {
+    let root = Path::new("doc");
+    assert!(may_be_missing(root, Path::new("doc/index.html")));
+    assert!(!may_be_missing(root, Path::new("doc/krate/index.html")));
+    assert!(!may_be_missing(root, Path::new("doc/settings.html")));
+}
@@ -0,0 +1,69 @@
+use super::{is_line_range, link_target, missing_anchor, resolve};
+use std::path::{Path, PathBuf};
+
+fn resolved(referrer: &str, reference: &str) -> Option<PathBuf> {
+    resolve(Path::new(referrer), reference)
+}
+
+#[test]
+fn local_references() {
+    assert_eq!(
+        resolved("doc/krate/index.html", "struct.S.html"),
+        Some("doc/krate/struct.S.html".into())
+    );
+    assert_eq!(
+        resolved("doc/krate/m/index.html", "../../static.files/main.js"),
+        Some("doc/static.files/main.js".into())
+    );
+    assert_eq!(
+        resolved("doc/krate/index.html", "./fn.f.html#method.g"),
+        Some("doc/krate/fn.f.html".into())
+    );
+    assert_eq!(
+        resolved("doc/index.html", "settings.html?search=S"),
+        Some("doc/settings.html".into())
+    );
+}
+
+#[test]
+fn other_references() {
+    assert_eq!(resolved("doc/krate/index.html", "https://doc.rust-lang.org/std/"), None);
+    assert_eq!(resolved("doc/krate/index.html", "//example.org/logo.svg"), None);
+    assert_eq!(resolved("doc/krate/index.html", "data:image/png;base64,AAAA"), None);
+    assert_eq!(resolved("doc/krate/index.html", "#method.f"), None);
+    assert_eq!(resolved("doc/krate/index.html", "?search=S"), None);
+    assert_eq!(resolved("doc/krate/index.html", "${root}/search.js"), None);
+}
+
+#[test]
+fn link_targets() {
+    let page = Path::new("doc/krate/struct.S.html");
+    let target = |href| link_target(page, href);
+    assert_eq!(target("#method.f"), Some((page.to_owned(), "method.f")));
+    assert_eq!(
+        target("trait.T.html#tymethod.g"),
+        Some(("doc/krate/trait.T.html".into(), "tymethod.g"))
+    );
+    assert_eq!(
+        target("../index.html?search=S#results"),
+        Some(("doc/index.html".into(), "results"))
+    );
+    assert_eq!(target("fn.f.html"), Some(("doc/krate/fn.f.html".into(), "")));
+    assert_eq!(target("https://example.org/#x"), None);
+}
+
+#[test]
+fn missing_anchors() {
+    let ids = ["method.f", "1", "2", "3"];
+    let missing = |fragment| missing_anchor(fragment, |id| ids.contains(&id));
+    assert_eq!(missing("method.f"), None);
+    assert_eq!(missing("method.g"), Some("method.g"));
+    assert_eq!(missing("1-3"), None);
+    assert_eq!(missing("2-4"), Some("4"));
+    // Only numeric ranges are line ranges.
+    assert_eq!(missing("impl-From-for-S"), Some("impl-From-for-S"));
+
+    assert!(is_line_range("10-20"));
+    assert!(!is_line_range("10"));
+    assert!(!is_line_range("a-b"));
+}
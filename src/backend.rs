@@ -0,0 +1,218 @@
+//! Running `rustc` and `rustdoc` elsewhere (`--in-container`, `--ssh`).
+//!
+//! The commands are built as usual and only get rewritten right before they're spawned. The
+//! artifacts always end up locally: A container gets the current directory mounted at the same
+//! path while over SSH, the current directory gets synced to the remote machine before and back
+//! after each command (via `rsync`).
+
+use crate::cli;
+use std::{
+    ffi::{OsStr, OsString},
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    process,
+};
+
+#[cfg(test)]
+mod test;
+
+/// The environment variable selecting the container engine, `docker` by default.
+const ENGINE_VARIABLE: &str = "RRUXWRY_CONTAINER_ENGINE";
+
+#[derive(Clone, Copy)]
+pub(crate) enum Backend<'a> {
+    Local,
+    /// A container of the given image.
+    Container(&'a str),
+    /// The given host in `ssh` syntax, optionally followed by `:` and the remote directory.
+    Ssh(&'a str),
+}
+
+impl<'a> Backend<'a> {
+    pub(crate) fn new(flags: &'a cli::ProgramFlags) -> Self {
+        match (&flags.in_container, &flags.ssh) {
+            (Some(image), _) => Self::Container(image),
+            (_, Some(host)) => Self::Ssh(host),
+            (None, None) => Self::Local,
+        }
+    }
+
+    /// Where the commands run, for verbose output.
+    pub(crate) fn describe(self) -> Option<String> {
+        match self {
+            Self::Local => None,
+            Self::Container(image) => Some(format!("in a container of the image `{image}`")),
+            Self::Ssh(destination) => Some(format!("on `{}`", Remote::parse(destination).host)),
+        }
+    }
+
+    /// The program `rustc` and `rustdoc` get wrapped in.
+    pub(crate) fn program(self) -> Option<&'static str> {
+        match self {
+            Self::Local => None,
+            Self::Container(_) => Some("docker"),
+            Self::Ssh(_) => Some("ssh"),
+        }
+    }
+
+    /// Run the given command via this backend.
+    ///
+    /// As the command can't be inspected for them, `run` has to set up the standard streams.
+    pub(crate) fn run<T>(
+        self,
+        command: &mut process::Command,
+        run: impl FnOnce(&mut process::Command) -> io::Result<T>,
+    ) -> io::Result<T> {
+        match self {
+            Self::Local => run(command),
+            Self::Container(image) => {
+                let directory = std::env::current_dir()?;
+                let engine = std::env::var_os(ENGINE_VARIABLE).unwrap_or_else(|| "docker".into());
+                let mut container = process::Command::new(engine);
+                container.args(container_arguments(command, image, &directory, user()));
+                run(&mut container)
+            }
+            Self::Ssh(destination) => {
+                let remote = Remote::parse(destination);
+                let directory = remote.directory();
+                sync("./", &format!("{}:{directory}/", remote.host), &directory)?;
+
+                let mut ssh = process::Command::new("ssh");
+                ssh.arg(remote.host).arg(remote_script(command, &directory));
+                let result = run(&mut ssh);
+
+                // Collect the artifacts even if the command failed.
+                sync(&format!("{}:{directory}/", remote.host), "./", &directory)?;
+                result
+            }
+        }
+    }
+}
+
+/// The arguments to `docker` for running the command in a container of the given image.
+///
+/// The current directory gets mounted at the same path, so absolute paths stay valid.
+fn container_arguments(
+    command: &process::Command,
+    image: &str,
+    directory: &Path,
+    user: Option<String>,
+) -> Vec<OsString> {
+    let mut volume = directory.as_os_str().to_owned();
+    volume.push(":");
+    volume.push(directory);
+
+    let mut arguments: Vec<OsString> = vec!["run".into(), "--rm".into(), "-v".into(), volume];
+    arguments.extend(["-w".into(), working_directory(command, directory).into_os_string()]);
+    if let Some(user) = user {
+        // Otherwise, the artifacts would be owned by root.
+        arguments.extend(["--user".into(), user.into()]);
+    }
+    for (key, value) in command.get_envs() {
+        // Containers don't inherit the environment anyway.
+        let Some(value) = value else { continue };
+        let mut variable = key.to_owned();
+        variable.push("=");
+        variable.push(value);
+        arguments.extend(["-e".into(), variable]);
+    }
+    arguments.push(image.into());
+    arguments.push(command.get_program().to_owned());
+    arguments.extend(command.get_args().map(OsStr::to_owned));
+    arguments
+}
+
+fn working_directory(command: &process::Command, directory: &Path) -> PathBuf {
+    command.get_current_dir().map_or_else(|| directory.to_owned(), |path| directory.join(path))
+}
+
+/// The user and group to run the container as, in the form `⟨UID⟩:⟨GID⟩`.
+fn user() -> Option<String> {
+    let id = |flag| {
+        let output = process::Command::new("id").arg(flag).output().ok()?;
+        output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    };
+    Some(format!("{}:{}", id("-u")?, id("-g")?))
+}
+
+/// The shell command that runs the command in the given remote directory.
+fn remote_script(command: &process::Command, directory: &str) -> String {
+    let mut script = vec!["cd".to_owned(), quote(directory.as_ref()), "&&".to_owned()];
+    if let Some(path) = command.get_current_dir() {
+        script.extend(["cd".to_owned(), quote(path.as_os_str()), "&&".to_owned()]);
+    }
+
+    let mut environment = Vec::new();
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => {
+                let mut variable = key.to_owned();
+                variable.push("=");
+                variable.push(value);
+                environment.push(quote(&variable));
+            }
+            None => environment.extend(["-u".to_owned(), quote(key)]),
+        }
+    }
+    if !environment.is_empty() {
+        script.push("env".to_owned());
+        script.extend(environment);
+    }
+
+    script.push(quote(command.get_program()));
+    script.extend(command.get_args().map(quote));
+    script.join(" ")
+}
+
+fn quote(argument: &OsStr) -> String {
+    let argument = argument.to_string_lossy();
+    // Only NUL bytes can't be quoted and those can't be part of arguments anyway.
+    shlex::try_quote(&argument).map_or_else(|_| argument.to_string(), Into::into)
+}
+
+/// Copy the contents of one directory into another one, either of which may be remote.
+fn sync(source: &str, destination: &str, remote: &str) -> io::Result<()> {
+    let status = process::Command::new("rsync")
+        .arg("-a")
+        // Create the remote directory if it doesn't exist yet.
+        .arg(format!("--rsync-path=mkdir -p {} && rsync", quote(remote.as_ref())))
+        .arg(source)
+        .arg(destination)
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("failed to sync `{source}` to `{destination}`")));
+    }
+    Ok(())
+}
+
+/// A remote machine and the directory the current directory gets synced to.
+struct Remote<'a> {
+    host: &'a str,
+    directory: Option<&'a str>,
+}
+
+impl<'a> Remote<'a> {
+    fn parse(destination: &'a str) -> Self {
+        match destination.split_once(':') {
+            Some((host, directory)) if !directory.is_empty() => {
+                Self { host, directory: Some(directory) }
+            }
+            Some((host, _)) => Self { host, directory: None },
+            None => Self { host: destination, directory: None },
+        }
+    }
+
+    /// The remote directory, relative to the home directory unless it's absolute.
+    ///
+    /// By default, it's unique to the local directory, so runs in different directories don't
+    /// interfere with each other.
+    fn directory(&self) -> String {
+        if let Some(directory) = self.directory {
+            return directory.to_owned();
+        }
+        let mut hasher = DefaultHasher::new();
+        std::env::current_dir().ok().hash(&mut hasher);
+        format!(".cache/rruxwry/remote/{:016x}", hasher.finish())
+    }
+}
@@ -0,0 +1,53 @@
+use super::{Remote, container_arguments, remote_script};
+use std::{path::Path, process};
+
+#[test]
+fn container() {
+    let mut command = process::Command::new("rustdoc");
+    command.args(["+nightly", "lib.rs"]).env("SOURCE_DATE_EPOCH", "0").env_remove("RUSTC_LOG");
+
+    let arguments = container_arguments(&command, "rust:nightly", Path::new("/work"), None);
+    assert_eq!(arguments, [
+        "run",
+        "--rm",
+        "-v",
+        "/work:/work",
+        "-w",
+        "/work",
+        "-e",
+        "SOURCE_DATE_EPOCH=0",
+        "rust:nightly",
+        "rustdoc",
+        "+nightly",
+        "lib.rs",
+    ]);
+
+    command.current_dir("sub");
+    let arguments =
+        container_arguments(&command, "rust:nightly", Path::new("/work"), Some("1000:100".into()));
+    assert_eq!(arguments[4..8], ["-w", "/work/sub", "--user", "1000:100"]);
+}
+
+#[test]
+fn remote() {
+    let mut command = process::Command::new("rustdoc");
+    command.args(["lib.rs", "--cfg", "feature=\"x y\""]);
+    assert_eq!(remote_script(&command, "dir"), r#"cd dir && rustdoc lib.rs --cfg 'feature="x y"'"#);
+
+    command.env("RUSTC_LOG", "debug").env_remove("RUSTFLAGS").current_dir("sub dir");
+    assert_eq!(
+        remote_script(&command, "dir"),
+        r#"cd dir && cd 'sub dir' && env 'RUSTC_LOG=debug' -u RUSTFLAGS rustdoc lib.rs --cfg 'feature="x y"'"#
+    );
+}
+
+#[test]
+fn destinations() {
+    let remote = Remote::parse("user@host:/tmp/docs");
+    assert_eq!((remote.host, remote.directory), ("user@host", Some("/tmp/docs")));
+    let remote = Remote::parse("host:");
+    assert_eq!((remote.host, remote.directory), ("host", None));
+    let remote = Remote::parse("host");
+    assert_eq!((remote.host, remote.directory), ("host", None));
+    assert!(remote.directory().starts_with(".cache/rruxwry/remote/"));
+}
@@ -0,0 +1,142 @@
+//! Running several builds in a row and summarizing their outcomes.
+
+use crate::{
+    condition::Unmet,
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info, warning},
+    utility::{Str, files},
+};
+use owo_colors::OwoColorize;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Replace the directories among the given paths with the source files they contain (recursively).
+///
+/// Just like `compiletest`, we skip the files inside of `auxiliary/` directories since those are
+/// the dependencies of the tests, not tests themselves.
+pub(crate) fn collect(paths: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut collected = Vec::new();
+
+    for path in paths {
+        // Cargo packages are built as a whole.
+        if !path.is_dir() || crate::cargo::manifest(path).is_some() {
+            collected.push(path.clone());
+            continue;
+        }
+
+        let tests: Vec<_> = files(path, &["rs"])?
+            .into_iter()
+            .filter(|file| !is_auxiliary(file.strip_prefix(path).unwrap_or(file)))
+            .collect();
+        if tests.is_empty() {
+            warning(format!("the directory `{}` doesn't contain any tests", path.display())).emit();
+        }
+        collected.extend(tests);
+    }
+
+    Ok(collected)
+}
+
+fn is_auxiliary(path: &Path) -> bool {
+    path.parent().is_some_and(|parent| {
+        parent.components().any(|component| component.as_os_str() == "auxiliary")
+    })
+}
+
+#[derive(Default)]
+pub(crate) struct Summary {
+    entries: Vec<(Str, Outcome)>,
+}
+
+impl Summary {
+    pub(crate) fn record(&mut self, name: impl Into<Str>, outcome: Outcome) {
+        self.entries.push((name.into(), outcome));
+    }
+
+    fn failures(&self) -> usize {
+        self.entries.iter().filter(|(_, outcome)| matches!(outcome, Outcome::Failure)).count()
+    }
+
+    fn skips(&self) -> usize {
+        self.entries.iter().filter(|(_, outcome)| matches!(outcome, Outcome::Skipped(_))).count()
+    }
+
+    pub(crate) fn publish(&self) {
+        for (name, outcome) in &self.entries {
+            let diagnostic = info(format!("{outcome}: {name}"));
+            match outcome {
+                Outcome::Skipped(unmet) => diagnostic.note(unmet.to_string()),
+                _ => diagnostic,
+            }
+            .emit();
+        }
+
+        let (failures, skips) = (self.failures(), self.skips());
+        let successes = self.entries.len() - failures - skips;
+        let summary = format!("{successes} succeeded, {failures} failed");
+        let summary = match skips {
+            0 => summary,
+            skips => format!("{summary}, {skips} skipped"),
+        };
+        info(summary).emit();
+    }
+
+    /// Turn the summary into an error if any of the entries failed.
+    pub(crate) fn into_result(self, skipped: usize) -> Result<(), Error> {
+        match self.failures() {
+            0 => Ok(()),
+            failures => Err(Error { failures, skipped }),
+        }
+    }
+}
+
+pub(crate) enum Outcome {
+    Success,
+    Failure,
+    Skipped(Unmet),
+}
+
+impl Outcome {
+    pub(crate) const fn to_str(&self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Failure => "failure",
+            Self::Skipped(_) => "skipped",
+        }
+    }
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Success => write!(f, "{}", self.to_str().green()),
+            Self::Failure => write!(f, "{}", self.to_str().red()),
+            Self::Skipped(_) => write!(f, "{}", self.to_str().yellow()),
+        }
+    }
+}
+
+pub(crate) struct Error {
+    failures: usize,
+    /// The amount of entries that weren't run due to an earlier failure.
+    skipped: usize,
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        let s = if self.failures == 1 { "" } else { "s" };
+        let diagnostic = error(format!("{} failure{s} occurred", self.failures));
+
+        match self.skipped {
+            0 => diagnostic,
+            skipped => {
+                // The entries are files in batch mode and revisions with `--all-revisions`.
+                let entries = if skipped == 1 { "entry" } else { "entries" };
+                diagnostic
+                    .note(format!("aborted early, skipping {skipped} remaining {entries}"))
+                    .note("pass `--keep-going` to continue after a failure")
+            }
+        }
+    }
+}
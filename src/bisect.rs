@@ -0,0 +1,236 @@
+//! Reducing a file that makes rustdoc crash to the items necessary to trigger the crash.
+//!
+//! Items get removed by blanking out their spans which preserves the line numbers.
+
+use crate::{
+    builder::{self, BuildMode},
+    command::{Flags, VerbatimFlagsBuf},
+    data::{CrateNameRef, CrateType, Edition},
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info},
+    ice,
+    parser::SourceFileParser,
+    utility::{default, scratch_dir},
+};
+use ra_ap_rustc_lexer::{DocStyle, TokenKind};
+use std::{ops::Range, path::Path};
+
+#[cfg(test)]
+mod test;
+
+/// Find a minimal set of top-level items of the given file that still makes rustdoc ICE
+/// and write the reduced file next to it.
+pub(crate) fn bisect(
+    path: &Path,
+    crate_name: CrateNameRef<'_>,
+    crate_type: CrateType,
+    edition: Edition,
+    flags: Flags<'_>,
+) -> crate::error::Result {
+    let source = std::fs::read_to_string(path)?;
+    let items = items(&source);
+
+    let candidate = path.with_extension("bisect.rs");
+    let log = scratch_dir(flags.program)?.join(format!("rruxwry-{crate_name}-bisect.log"));
+    // Don't litter the working directory with ICE reports.
+    let verbatim_flags =
+        VerbatimFlagsBuf { environment: vec![("RUSTC_ICE", Some("0"))], ..default() }
+            .extended(flags.verbatim);
+    let flags = Flags { verbatim: verbatim_flags.as_ref(), redirect_stderr: Some(&log), ..flags };
+
+    let ices = |kept: &[usize]| -> crate::error::Result<bool> {
+        std::fs::write(&candidate, render(&source, &items, kept))?;
+        let _ = std::fs::remove_file(&log);
+
+        match builder::build(BuildMode::Default, &candidate, crate_name, crate_type, edition, flags)
+        {
+            Ok(_) => Ok(false),
+            Err(crate::error::Error::Process(error)) => Ok(error.code() == Some(ice::EXIT_CODE)),
+            Err(error) => Err(error),
+        }
+    };
+
+    let mut kept: Vec<_> = (0..items.len()).collect();
+    let result = (|| -> crate::error::Result<_> {
+        if !ices(&kept)? {
+            return match flags.program.dry_run {
+                false => Err(Error::NoIce.into()),
+                true => Ok(None),
+            };
+        }
+        let message = std::fs::read_to_string(&log)?
+            .lines()
+            .find(|line| line.contains("internal compiler error") || line.contains("panicked at"))
+            .map(ToOwned::to_owned);
+
+        // Try removing chunks of decreasing size until no single item can be removed anymore.
+        let mut chunk = kept.len().div_ceil(2).max(1);
+        loop {
+            let mut removed = false;
+            let mut index = 0;
+
+            while index < kept.len() {
+                let end = (index + chunk).min(kept.len());
+                let candidate: Vec<_> = [&kept[..index], &kept[end..]].concat();
+                if ices(&candidate)? {
+                    kept = candidate;
+                    removed = true;
+                } else {
+                    index = end;
+                }
+            }
+
+            if chunk == 1 && !removed {
+                break;
+            }
+            chunk = chunk.div_ceil(2);
+        }
+
+        Ok(Some(message))
+    })();
+
+    let _ = std::fs::remove_file(&candidate);
+    let _ = std::fs::remove_file(&log);
+    let Some(message) = result? else { return Ok(()) };
+
+    let reduced = path.with_extension("reduced.rs");
+    std::fs::write(&reduced, render(&source, &items, &kept))?;
+
+    let amount = kept.len();
+    let s = if items.len() == 1 { "" } else { "s" };
+    let mut diagnostic = info(format!("reduced the ICE to {amount} of {} item{s}", items.len()));
+    if let Some(message) = message {
+        diagnostic = diagnostic.note(format!("the ICE: {}", message.trim()));
+    }
+    let diagnostic = kept
+        .iter()
+        .map(|&index| {
+            let item = &source[items[index].clone()];
+            let line = source[..items[index].start].matches('\n').count() + 1;
+            format!("kept the item on line {line}: `{}`", item.lines().next().unwrap_or_default())
+        })
+        .fold(diagnostic, Diagnostic::note);
+    diagnostic.note(format!("wrote the reduced file to `{}`", reduced.display())).emit();
+
+    Ok(())
+}
+
+/// The spans of the top-level items of the given file including their outer attributes.
+///
+/// Inner attributes aren't considered to be items since they tend to be load-bearing.
+fn items(source: &str) -> Vec<Range<usize>> {
+    let mut parser = SourceFileParser::new(source);
+    let mut items: Vec<Range<usize>> = Vec::new();
+    let mut item: Option<Item> = None;
+    let mut depth = 0usize;
+
+    while let Some(token) = parser.peek() {
+        let kind = token.kind;
+        let span = parser.span();
+        parser.advance();
+
+        match kind {
+            TokenKind::Whitespace
+            | TokenKind::LineComment { doc_style: None }
+            | TokenKind::BlockComment { doc_style: None, .. } => continue,
+            TokenKind::LineComment { doc_style: Some(DocStyle::Inner) }
+            | TokenKind::BlockComment { doc_style: Some(DocStyle::Inner), .. }
+                if item.is_none() =>
+            {
+                continue;
+            }
+            // A semicolon following a block like in `const _: () = {};`.
+            TokenKind::Semi if item.is_none() && depth == 0 => {
+                if let Some(last) = items.last_mut() {
+                    last.end = span.end();
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        let current = item.get_or_insert(Item { start: span.start(), tokens: 0, inner: false });
+        current.tokens += 1;
+        if current.tokens == 2
+            && kind == TokenKind::Bang
+            && source[current.start..].starts_with('#')
+        {
+            current.inner = true;
+        }
+
+        let ends = match kind {
+            TokenKind::OpenParen | TokenKind::OpenBracket | TokenKind::OpenBrace => {
+                depth += 1;
+                false
+            }
+            TokenKind::CloseParen => {
+                depth = depth.saturating_sub(1);
+                false
+            }
+            TokenKind::CloseBracket => {
+                depth = depth.saturating_sub(1);
+                depth == 0 && current.inner
+            }
+            TokenKind::CloseBrace => {
+                depth = depth.saturating_sub(1);
+                depth == 0
+            }
+            TokenKind::Semi => depth == 0,
+            _ => false,
+        };
+
+        if ends {
+            let current = item.take().unwrap();
+            if !current.inner {
+                items.push(current.start..span.end());
+            }
+        }
+    }
+
+    if let Some(current) = item
+        && !current.inner
+    {
+        items.push(current.start..source.len());
+    }
+
+    items
+}
+
+struct Item {
+    start: usize,
+    /// The number of tokens of the item encountered so far.
+    tokens: usize,
+    /// Whether this is actually an inner attribute.
+    inner: bool,
+}
+
+/// Blank out the items of the given file that aren't kept.
+fn render(source: &str, items: &[Range<usize>], kept: &[usize]) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut position = 0;
+
+    for (index, item) in items.iter().enumerate() {
+        if kept.contains(&index) {
+            continue;
+        }
+        result.push_str(&source[position..item.start]);
+        result
+            .extend(source[item.clone()].chars().map(|char| if char == '\n' { '\n' } else { ' ' }));
+        position = item.end;
+    }
+
+    result.push_str(&source[position..]);
+    result
+}
+
+pub(crate) enum Error {
+    NoIce,
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::NoIce => error("the file doesn't make `rustdoc` ICE")
+                .note(format!("only crashes with the exit code {} are considered", ice::EXIT_CODE)),
+        }
+    }
+}
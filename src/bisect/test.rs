@@ -0,0 +1,23 @@
+use super::{items, render};
+
+#[test]
+fn top_level_items() {
+    let source = "//! Crate docs.\n#![feature(decl_macro)]\n\n\
+                  /// Docs.\n#[derive(Clone)]\npub struct S { field: [u8; 2] }\n\
+                  fn f() -> u8 { 0 } // trailing\nconst _: () = {};\nmacro m() {}\n\
+                  struct T(u8);";
+    let items: Vec<_> = items(source).into_iter().map(|item| &source[item]).collect();
+    assert_eq!(items, [
+        "/// Docs.\n#[derive(Clone)]\npub struct S { field: [u8; 2] }",
+        "fn f() -> u8 { 0 }",
+        "const _: () = {};",
+        "macro m() {}",
+        "struct T(u8);",
+    ]);
+}
+
+#[test]
+fn blank_out_items() {
+    let source = "struct A;\nstruct B {\n}\nstruct C;";
+    assert_eq!(render(source, &items(source), &[0, 2]), "struct A;\n          \n \nstruct C;");
+}
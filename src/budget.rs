@@ -0,0 +1,125 @@
+//! Size budgets for the generated docs declared in the configuration file.
+//!
+//! ```json
+//! { "budgets": { "total": "50 MiB", "search-index": "2 MiB", "static.files/*.woff2": "1 MiB" } }
+//! ```
+
+use crate::{
+    cache::format_size,
+    diagnostic::{Diagnostic, IntoDiagnostic, error},
+    snapshot::matches,
+};
+use std::path::Path;
+
+#[cfg(test)]
+mod test;
+
+pub(crate) struct Budget {
+    pub(crate) target: Target,
+    /// The maximum size in bytes.
+    pub(crate) limit: u64,
+}
+
+pub(crate) enum Target {
+    /// All files.
+    Total,
+    /// The search index including the item descriptions (which are stored separately).
+    SearchIndex,
+    /// The files matching the glob (relative to the documentation root).
+    Glob(String),
+}
+
+impl Target {
+    pub(crate) fn parse(source: &str) -> Self {
+        match source {
+            "total" => Self::Total,
+            "search-index" => Self::SearchIndex,
+            glob => Self::Glob(glob.to_owned()),
+        }
+    }
+
+    fn includes(&self, path: &str) -> bool {
+        match self {
+            Self::Total => true,
+            Self::SearchIndex => path == "search-index.js" || path.starts_with("search.desc/"),
+            Self::Glob(glob) => matches(glob, path),
+        }
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Total => write!(f, "the total output"),
+            Self::SearchIndex => write!(f, "the search index"),
+            Self::Glob(glob) => write!(f, "the files matching `{glob}`"),
+        }
+    }
+}
+
+/// Check that the docs in `root` stay within the given budgets.
+pub(crate) fn check(root: &Path, budgets: &[Budget]) -> crate::error::Result {
+    let sizes = sizes(root)?;
+    let mut failures = Vec::new();
+
+    for budget in budgets {
+        let size: u64 = sizes
+            .iter()
+            .filter(|(path, _)| budget.target.includes(path))
+            .map(|&(_, size)| size)
+            .sum();
+
+        if size > budget.limit {
+            failures.push(format!(
+                "{}: {} exceed the budget of {} by {}",
+                budget.target,
+                format_size(size),
+                format_size(budget.limit),
+                format_size(size - budget.limit)
+            ));
+        }
+    }
+
+    if !failures.is_empty() {
+        return Err(Error { failures }.into());
+    }
+
+    Ok(())
+}
+
+/// The sizes of all files in the directory `root` (recursively) by their relative path.
+fn sizes(root: &Path) -> std::io::Result<Vec<(String, u64)>> {
+    let mut sizes = Vec::new();
+    let mut directories = vec![root.to_owned()];
+
+    while let Some(directory) = directories.pop() {
+        for entry in std::fs::read_dir(directory)? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                directories.push(path);
+                continue;
+            }
+
+            let Ok(relative) = path.strip_prefix(root) else { continue };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            sizes.push((relative, metadata.len()));
+        }
+    }
+
+    Ok(sizes)
+}
+
+pub(crate) struct Error {
+    failures: Vec<String>,
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        let amount = self.failures.len();
+        let s = if amount == 1 { "" } else { "s" };
+        let diagnostic = error(format!("{amount} size budget{s} exceeded"));
+        self.failures.into_iter().fold(diagnostic, Diagnostic::note)
+    }
+}
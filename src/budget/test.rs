@@ -0,0 +1,11 @@
+use super::Target;
+
+#[test]
+fn targets() {
+    assert!(Target::parse("total").includes("k/struct.S.html"));
+    assert!(Target::parse("search-index").includes("search-index.js"));
+    assert!(Target::parse("search-index").includes("search.desc/k/k-desc-0-.js"));
+    assert!(!Target::parse("search-index").includes("static.files/search-e056c65ede92db13.js"));
+    assert!(Target::parse("static.files/*.woff2").includes("static.files/FiraSans.woff2"));
+    assert!(!Target::parse("k/*.html").includes("k/inner/index.html"));
+}
@@ -3,17 +3,35 @@
 //! The low-level build commands are defined in [`crate::command`].
 
 use crate::{
-    command::{self, ExternCrate, Flags, Strictness},
-    data::{CrateName, CrateNameCow, CrateNameRef, CrateType, Edition},
-    diagnostic::{Diagnostic, IntoDiagnostic, error},
+    attribute::Attributes,
+    cache, cli,
+    command::{self, ExternCrate, Flags, Origin, Scope, Source, Strictness, VerbatimFlagsBuf},
+    condition,
+    data::{CrateName, CrateNameBuf, CrateNameCow, CrateNameRef, CrateType, Edition},
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info, warning},
     directive::Directives,
     error::Result,
-    utility::default,
+    module, query, stderr, timing, toolchain,
+    utility::{default, doc_root, out_path, scratch_dir},
 };
 use joinery::JoinableIterator;
-use rustc_hash::FxHashSet;
-use std::{borrow::Cow, cell::LazyCell, mem, path::Path};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{
+    borrow::Cow,
+    cell::LazyCell,
+    env::consts::{DLL_PREFIX, DLL_SUFFIX},
+    fmt,
+    hash::{DefaultHasher, Hash, Hasher},
+    mem,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{Condvar, LazyLock, Mutex},
+};
+
+#[cfg(test)]
+mod test;
 
+/// Build the given crate and return the names of the documented crates, starting with the root.
 pub(crate) fn build<'a>(
     mode: BuildMode,
     path: &Path,
@@ -21,10 +39,14 @@ pub(crate) fn build<'a>(
     crate_type: CrateType,
     edition: Edition,
     flags: Flags<'_>,
-) -> Result<CrateNameCow<'a>> {
+) -> Result<Vec<CrateNameCow<'a>>> {
     match mode {
-        BuildMode::Default => build_default(path, crate_name, crate_type, edition, flags),
-        BuildMode::CrossCrate => build_cross_crate(path, crate_name, crate_type, edition, flags),
+        BuildMode::Default => {
+            build_default(path, crate_name, crate_type, edition, flags).map(|name| vec![name])
+        }
+        BuildMode::CrossCrate => {
+            build_cross_crate(path, crate_name, crate_type, edition, flags).map(|name| vec![name])
+        }
         BuildMode::Compiletest { query } => {
             build_compiletest(path, crate_name, edition, flags, query)
         }
@@ -38,12 +60,28 @@ fn build_default<'a>(
     edition: Edition,
     flags: Flags<'_>,
 ) -> Result<CrateNameCow<'a>> {
+    let mut extern_crates = extern_prelude_for(crate_type).to_vec();
+
+    // Like Cargo, make the library available to its doctests.
+    if flags.build.doctest {
+        command::compile(
+            path,
+            crate_name,
+            crate_type.to_non_executable(),
+            edition,
+            &extern_crates,
+            flags,
+            Strictness::Lenient,
+        )?;
+        extern_crates.push(ExternCrate::Named { name: crate_name, version: None, path: None });
+    }
+
     command::document(
         path,
         crate_name,
         crate_type,
         edition,
-        extern_prelude_for(crate_type),
+        &extern_crates,
         flags,
         Strictness::Lenient,
     )?;
@@ -58,19 +96,57 @@ fn build_cross_crate(
     edition: Edition,
     flags: Flags<'_>,
 ) -> Result<CrateNameCow<'static>> {
-    command::compile(
-        path,
-        crate_name,
-        crate_type.to_non_executable(),
-        edition,
-        extern_prelude_for(crate_type),
-        flags,
-        Strictness::Lenient,
+    // Here, the given crate is the dependency of the synthesized one.
+    let mut verbatim_flags = VerbatimFlagsBuf::default().extended(flags.verbatim);
+    verbatim_flags.extend(auxiliary_arguments(crate_name, flags.build), Origin::AuxArg);
+    let dependency_flags = Flags { verbatim: verbatim_flags.as_ref(), ..flags };
+
+    let crate_type = crate_type.to_non_executable();
+
+    // The synthesized crate doesn't contain any items of its own.
+    if flags.build.coverage {
+        command::document(
+            path,
+            crate_name,
+            crate_type,
+            edition,
+            extern_prelude_for(crate_type),
+            dependency_flags,
+            Strictness::Lenient,
+        )?;
+        return Ok(crate_name.map(ToOwned::to_owned).map(Cow::Owned));
+    }
+
+    let fingerprint = {
+        let source = std::fs::read_to_string(path).ok();
+        let mut hasher = DefaultHasher::new();
+        fingerprint(path, source.as_deref(), edition, dependency_flags).hash(&mut hasher);
+        crate_type.to_str().hash(&mut hasher);
+        hasher.finish()
+    };
+
+    let library = Auxiliary { crate_name: crate_name.map(ToOwned::to_owned), version: None };
+    build_auxiliary_artifact(
+        &library,
+        Artifact::Library,
+        fingerprint,
+        false,
+        dependency_flags,
+        || {
+            command::compile(
+                path,
+                crate_name,
+                crate_type,
+                edition,
+                extern_prelude_for(crate_type),
+                dependency_flags,
+                Strictness::Lenient,
+            )
+        },
     )?;
 
     let dependent_crate_name = CrateName::new_unchecked(format!("u_{crate_name}"));
-    let dependent_crate_path =
-        path.with_file_name(dependent_crate_name.as_str()).with_extension("rs");
+    let dependent_crate_path = dependent_crate_path(path, dependent_crate_name.as_ref(), flags)?;
 
     if !flags.program.dry_run && !dependent_crate_path.exists() {
         // While we could omit the `extern crate` declaration in `edition >= Edition::Edition2018`,
@@ -79,7 +155,10 @@ fn build_cross_crate(
         // created for a newer edition or not.
         std::fs::write(
             &dependent_crate_path,
-            format!("extern crate {crate_name}; pub use {crate_name}::*;\n"),
+            format!(
+                "extern crate {crate_name}; pub use {crate_name}::*;\n",
+                crate_name = crate_name.to_ident()
+            ),
         )?;
     };
 
@@ -88,19 +167,48 @@ fn build_cross_crate(
         dependent_crate_name.as_ref(),
         default(),
         edition,
-        &[ExternCrate::Named { name: crate_name.as_ref(), path: None }],
-        flags,
+        &[ExternCrate::Named { name: crate_name.as_ref(), version: None, path: None }],
+        Flags { source: Source::Synthesized, ..flags },
         Strictness::Lenient,
     )?;
 
     Ok(dependent_crate_name.map(Cow::Owned))
 }
 
+/// The path to the dependent crate synthesized in cross-crate mode.
+///
+/// It's placed next to the given crate unless `--scratch-dir` is passed.
+pub(crate) fn dependent_crate_path(
+    path: &Path,
+    dependent_crate_name: CrateNameRef<'_>,
+    flags: Flags<'_>,
+) -> Result<PathBuf> {
+    let file_name = format!("{dependent_crate_name}.rs");
+    Ok(match flags.program.scratch_dir {
+        Some(_) => scratch_dir(flags.program)?.join(file_name),
+        None => path.with_file_name(file_name),
+    })
+}
+
+/// The flags passed via `--aux-arg` that target the given dependency.
+fn auxiliary_arguments<'a>(
+    crate_name: CrateNameRef<'_>,
+    flags: &'a cli::BuildFlags,
+) -> Vec<&'a str> {
+    flags
+        .aux_args
+        .iter()
+        .filter(|(name, _)| name.as_str() == crate_name.as_str())
+        .map(|(_, flag)| flag.as_str())
+        .collect()
+}
+
 fn extern_prelude_for(crate_type: CrateType) -> &'static [ExternCrate<'static>] {
     match crate_type {
         // For convenience and just like Cargo we add `libproc_macro` to the external prelude.
         CrateType::ProcMacro => &[ExternCrate::Named {
             name: const { CrateName::new_unchecked("proc_macro") },
+            version: None,
             path: None,
         }],
         _ => [].as_slice(),
@@ -113,11 +221,172 @@ fn build_compiletest<'a>(
     _edition: Edition, // FIXME: should we respect the edition or should we reject it with `clap`?
     flags: Flags<'_>,
     query: Option<QueryMode>,
-) -> Result<CrateNameCow<'a>> {
-    // FIXME: Add a flag `--all-revs`.
+) -> Result<Vec<CrateNameCow<'a>>> {
     // FIXME: Make sure `//@ compile-flags: --extern name` works as expected
     let source = std::fs::read_to_string(path)?;
-    let directives = Directives::parse(&source, query);
+    let mut directives = instantiated_directives(&source, flags, query)?;
+
+    // FIXME: unwrap
+    let auxiliary_base_path = LazyCell::new(|| path.parent().unwrap().join("auxiliary"));
+
+    let (dependencies, documented_dependencies): (Vec<_>, Vec<_>) = build_compiletest_auxiliaries(
+        &directives.dependencies,
+        &auxiliary_base_path,
+        directives.build_aux_docs,
+        false,
+        flags,
+        &[],
+    )?
+    .into_iter()
+    .map(|(dependency, documented, _)| (dependency, documented))
+    .unzip();
+
+    // Like for `tests/rustdoc-ui/`, compare the diagnostics against the stderr file if present.
+    let revision = match &*flags.build.revisions {
+        [revision] => Some(revision.as_str()),
+        _ => None,
+    };
+    let expected_stderr = stderr::path(path, revision);
+    let compare_stderr =
+        !flags.program.dry_run && (flags.program.bless || expected_stderr.exists());
+    let check_stderr = !flags.program.dry_run && !directives.stderr_checks.is_empty();
+    let capture_stderr = compare_stderr || check_stderr;
+    // The diagnostics would end up in the log file instead.
+    if capture_stderr
+        && flags.build.log_file.is_some()
+        && flags.build.rustdoc_log.as_ref().or(flags.build.log.as_ref()).is_some()
+    {
+        return Err(Error::LogFileWithStderrChecks.into());
+    }
+    let actual_stderr = Mutex::new(String::new());
+
+    let mut verbatim_flags = mem::take(&mut directives.verbatim_flags).extended(flags.verbatim);
+    if compare_stderr {
+        // Replaces the line numbers in snippets with `LL`, just like `compiletest` does.
+        verbatim_flags.push("-Zui-testing", Origin::Implied("the `.stderr` comparison"));
+    }
+    let flags = match capture_stderr {
+        true => Flags {
+            verbatim: verbatim_flags.as_ref(),
+            redirect_stderr: None,
+            capture_stderr: Some(&actual_stderr),
+            ..flags
+        },
+        false => Flags { verbatim: verbatim_flags.as_ref(), ..flags },
+    };
+
+    let result = command::document(
+        path,
+        crate_name,
+        default(), // FIXME: respect `@compile-flags: --crate-type`
+        directives.edition.unwrap_or_default(),
+        &dependencies,
+        flags,
+        Strictness::Strict,
+    );
+
+    if capture_stderr {
+        let actual = mem::take(&mut *actual_stderr.lock().unwrap());
+        let directory = path.canonicalize()?.parent().unwrap().to_owned();
+        let actual =
+            stderr::normalize(&actual, &directory, &std::env::current_dir()?.canonicalize()?);
+        if compare_stderr {
+            stderr::check(&expected_stderr, &actual, flags.program.bless)?;
+        }
+        stderr::check_contents(path, &directives.stderr_checks, &actual)?;
+    }
+
+    // The stderr file already captures the expected errors, so only a crash counts as a failure.
+    let may_fail = compare_stderr || directives.check_fail;
+    match result {
+        Err(crate::error::Error::Process(status)) if may_fail && status.code() == Some(1) => {}
+        Ok(()) if directives.check_fail && !flags.program.dry_run => {
+            return Err(Error::UnexpectedSuccess.into());
+        }
+        result => result?,
+    }
+
+    // With `--all-revisions-query`, the caller evaluates the queries of all revisions at once.
+    if query.is_some() && !flags.program.dry_run && !flags.program.all_revisions_query {
+        // FIXME: Respect `-o`/`--out-dir` in `//@ compile-flags`.
+        let root = doc_root(flags.build)?;
+        let mut context =
+            query::Context::new(&root, path, crate_name.as_str(), flags.program.bless);
+        let queries = selected_queries(&directives.queries, flags.program);
+        timing::time("evaluating queries", || query::evaluate(&queries, &mut context))?;
+    }
+
+    let documented = std::iter::once(crate_name.map(Cow::Borrowed))
+        .chain(documented_dependencies.into_iter().flatten().map(Into::into))
+        .collect();
+
+    Ok(documented)
+}
+
+/// The queries to evaluate, i.e., all of them unless `--only-assertions` was passed.
+pub(crate) fn selected_queries<'q>(
+    queries: &'q [query::Query],
+    flags: &cli::ProgramFlags,
+) -> Cow<'q, [query::Query]> {
+    if flags.only_assertions.is_empty() {
+        return Cow::Borrowed(queries);
+    }
+
+    let (selected, unknown) = query::select(queries, &flags.only_assertions);
+    for tag in unknown {
+        warning(format!("none of the queries are tagged `{tag}`"))
+            .note("queries get tagged via `//@ tags: ⟨TAG⟩, …`")
+            .emit();
+    }
+    if flags.verbose {
+        let amount = queries.len();
+        let noun = if amount == 1 { "query" } else { "queries" };
+        info(format!("selected {} of {amount} {noun}", selected.len())).emit();
+    }
+    Cow::Owned(selected)
+}
+
+/// Build the auxiliary libraries of the given test and store them in the cache.
+///
+/// Later runs restore them from there instead of rebuilding them.
+pub(crate) fn prebuild(mode: BuildMode, path: &Path, flags: Flags<'_>) -> Result {
+    let BuildMode::Compiletest { .. } = mode else {
+        return Err(Error::PrebuildWithoutCompiletest.into());
+    };
+
+    let source = std::fs::read_to_string(path)?;
+    let directives = instantiated_directives(&source, flags, None)?;
+
+    if directives.dependencies.is_empty() {
+        info(format!("`{}` doesn't declare any auxiliary crates", path.display())).emit();
+        return Ok(());
+    }
+
+    // FIXME: unwrap
+    let auxiliary_base_path = path.parent().unwrap().join("auxiliary");
+
+    // Documentation isn't cached, so we can ignore `//@ build-aux-docs`.
+    build_compiletest_auxiliaries(
+        &directives.dependencies,
+        &auxiliary_base_path,
+        false,
+        true,
+        flags,
+        &[],
+    )?;
+
+    Ok(())
+}
+
+/// Parse the directives of the given test and instantiate them for the enabled revisions.
+///
+/// Fails if the conditions of the test aren't met.
+fn instantiated_directives<'s>(
+    source: &'s str,
+    flags: Flags<'_>,
+    query: Option<QueryMode>,
+) -> Result<Directives<'s>> {
+    let directives = timing::time("parsing directives", || Directives::parse(source, query));
 
     // FIXME: We should also store Cargo-like features here after having converted them to
     // cfg specs NOTE: This will be fixed once we eagerly expand `-f` to `--cfg`.
@@ -138,113 +407,621 @@ fn build_compiletest<'a>(
 
     revisions.extend(flags.build.cfgs.iter().map(String::as_str));
 
-    let mut directives = directives.into_instantiated(&revisions);
+    let directives = directives.into_instantiated(&revisions);
 
-    // FIXME: unwrap
-    let auxiliary_base_path = LazyCell::new(|| path.parent().unwrap().join("auxiliary"));
+    if !directives.conditions.is_empty() {
+        // The verbatim flags passed on the command line take precedence.
+        let target = flags
+            .verbatim
+            .value_of("--target")
+            .or_else(|| directives.verbatim_flags.as_ref().value_of("--target"))
+            .or(flags.build.target.as_deref());
+        timing::time("evaluating conditions", || {
+            condition::check(&directives.conditions, target, flags.build, flags.program)
+        })?;
+    }
 
-    let dependencies: Vec<_> = directives
-        .dependencies
-        .iter()
-        .map(|dependency| {
-            build_compiletest_auxiliary(
-                dependency,
-                &auxiliary_base_path,
-                directives.build_aux_docs,
-                flags,
-            )
-        })
-        .collect::<Result<_>>()?;
+    Ok(directives)
+}
 
-    let verbatim_flags = mem::take(&mut directives.verbatim_flags).extended(flags.verbatim);
-    let flags = Flags { verbatim: verbatim_flags.as_ref(), ..flags };
+/// Build the given auxiliary crates, the independent ones in parallel (bounded by `--jobs`).
+fn build_compiletest_auxiliaries<'a>(
+    dependencies: &[ExternCrate<'a>],
+    base_path: &Path,
+    document: bool,
+    store: bool,
+    flags: Flags<'_>,
+    stack: &[PathBuf],
+) -> Result<Vec<(ExternCrate<'a>, Vec<CrateNameBuf>, u64)>> {
+    let build = |dependency| {
+        build_compiletest_auxiliary(dependency, base_path, document, store, flags, stack)
+    };
 
-    command::document(
-        path,
-        crate_name,
-        default(), // FIXME: respect `@compile-flags: --crate-type`
-        directives.edition.unwrap_or_default(),
-        &dependencies,
-        flags,
-        Strictness::Strict,
-    )?;
+    // Keep the output in order if there's nothing to gain.
+    if dependencies.len() <= 1 || jobs(flags.program) == 1 {
+        return dependencies.iter().map(build).collect();
+    }
 
-    Ok(crate_name.map(Cow::Borrowed))
+    // The current thread pulls from the queue, too, so it doesn't matter if there are no
+    // helpers left for nested auxiliary crates.
+    let queue = Mutex::new(dependencies.iter().enumerate());
+    let results: Vec<_> = dependencies.iter().map(|_| Mutex::new(None)).collect();
+    let work = || {
+        loop {
+            let Some((index, dependency)) = queue.lock().unwrap().next() else { break };
+            *results[index].lock().unwrap() = Some(build(dependency));
+        }
+    };
+
+    let helpers = Helpers::reserve(dependencies.len() - 1, flags.program);
+    std::thread::scope(|scope| {
+        let threads: Vec<_> = (0..helpers.0).map(|_| scope.spawn(work)).collect();
+        work();
+        for thread in threads {
+            thread.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+        }
+    });
+    drop(helpers);
+
+    results.into_iter().map(|result| result.into_inner().unwrap().unwrap()).collect()
 }
 
-// FIXME: Support nested auxiliaries!
+/// Build the given auxiliary crate after its own auxiliary crates (recursively).
+///
+/// Returns the crate to pass to the dependent, the documented crates and the fingerprint.
+/// Just like in `compiletest`, nested auxiliary crates are resolved relative to the
+/// `auxiliary/` directory of the test, not of the auxiliary crate. The `stack` contains the
+/// dependents for detecting cycles. If `store` is set, the libraries get stored in the cache.
 fn build_compiletest_auxiliary<'a>(
     extern_crate: &ExternCrate<'a>,
     base_path: &Path,
     document: bool,
+    store: bool,
     flags: Flags<'_>,
-) -> Result<ExternCrate<'a>> {
-    let path = match extern_crate {
-        ExternCrate::Unnamed { path } => base_path.join(path),
-        ExternCrate::Named { name, path } => match path {
-            Some(path) => base_path.join(path.as_ref()),
-            None => base_path.join(name.as_str()).with_extension("rs"),
-        },
-    };
+    stack: &[PathBuf],
+) -> Result<(ExternCrate<'a>, Vec<CrateNameBuf>, u64)> {
+    let path = auxiliary_path(extern_crate, base_path);
+
+    let canonical_path = path.canonicalize().unwrap_or_else(|_| path.clone());
+    if let Some(start) = stack.iter().position(|dependent| *dependent == canonical_path) {
+        let cycle = stack[start..].iter().chain([&canonical_path]).cloned().collect();
+        return Err(Error::CyclicAuxiliaries { cycle }.into());
+    }
 
     let source = std::fs::read_to_string(&path);
 
-    // FIXME: unwrap
-    let crate_name = CrateName::adjust_and_parse_file_path(&path).unwrap();
+    let version = match *extern_crate {
+        ExternCrate::Named { version, .. } => version,
+        ExternCrate::Unnamed { .. } | ExternCrate::ProcMacro { .. } => None,
+    };
+    let crate_name = match (extern_crate, version) {
+        (ExternCrate::Named { name, .. }, Some(_)) => name.map(ToOwned::to_owned),
+        // FIXME: unwrap
+        _ => CrateName::adjust_and_parse_file_path(&path).unwrap(),
+    };
+    let auxiliary = Auxiliary { crate_name: crate_name.clone(), version: version.map(Into::into) };
 
     // FIXME: What about instantiation???
-    let mut directives =
-        source.as_ref().map(|source| Directives::parse(source, None)).unwrap_or_default();
+    let mut directives = timing::time(format!("parsing directives of `{crate_name}`"), || {
+        source.as_ref().map(|source| Directives::parse(source, None)).unwrap_or_default()
+    });
+
+    let mut dependencies = Vec::new();
+    let mut documented = Vec::new();
+    let mut fingerprints = Vec::new();
+
+    let stack: Vec<_> = stack.iter().cloned().chain([canonical_path]).collect();
+    for (dependency, documented_dependencies, fingerprint) in build_compiletest_auxiliaries(
+        &directives.dependencies,
+        base_path,
+        document,
+        store,
+        flags,
+        &stack,
+    )? {
+        dependencies.push(dependency);
+        documented.extend(documented_dependencies);
+        fingerprints.push(fingerprint);
+    }
 
     let edition = directives.edition.unwrap_or_default();
 
-    let verbatim_flags = mem::take(&mut directives.verbatim_flags).extended(flags.verbatim);
-    let flags = Flags { verbatim: verbatim_flags.as_ref(), ..flags };
+    let mut verbatim_flags = mem::take(&mut directives.verbatim_flags).extended(flags.verbatim);
+    verbatim_flags.extend(auxiliary_arguments(crate_name.as_ref(), flags.build), Origin::AuxArg);
+    let shim = version.map(version_flags);
+    verbatim_flags.extend(
+        shim.iter().flatten().map(String::as_str),
+        Origin::Implied("the version of `//@ aux-crate`"),
+    );
+    let flags = Flags { verbatim: verbatim_flags.as_ref(), scope: Scope::Dependency, ..flags };
 
-    command::compile(
-        &path,
-        crate_name.as_ref(),
-        // FIXME: Verify this works with `@compile-flags:--crate-type=proc-macro`
-        // FIXME: I don't think it works rn
-        CrateType::Lib,
+    let crate_type = auxiliary_crate_type(
+        extern_crate,
+        source.as_deref().unwrap_or_default(),
+        flags.verbatim.value_of("--crate-type"),
         edition,
-        &[],
-        flags,
-        Strictness::Strict,
-    )?;
+        &flags.build.cfgs,
+    );
+    dependencies.extend(extern_prelude_for(crate_type).iter().cloned());
 
-    // FIXME: Is this how `//@ build-aux-docs` is supposed to work?
-    if document {
-        command::document(
+    // The artifact needs to be rebuilt if any of its dependencies changed.
+    let fingerprint = {
+        let mut hasher = DefaultHasher::new();
+        fingerprint(&path, source.as_deref().ok(), edition, flags).hash(&mut hasher);
+        fingerprints.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    build_auxiliary_artifact(&auxiliary, Artifact::Library, fingerprint, store, flags, || {
+        command::compile(
             &path,
             crate_name.as_ref(),
-            // FIXME: Verify this works with `@compile-flags:--crate-type=proc_macro`
-            // FIXME: I don't think it works rn
-            default(),
+            crate_type,
             edition,
-            &[],
+            &dependencies,
             flags,
             Strictness::Strict,
+        )
+    })?;
+
+    // FIXME: Is this how `//@ build-aux-docs` is supposed to work?
+    if document {
+        // E.g., the companion JSON build of `--check-consistency` mustn't reuse the HTML docs.
+        let fingerprint = {
+            let mut hasher = DefaultHasher::new();
+            fingerprint.hash(&mut hasher);
+            hash_doc_flags(flags.build, &mut hasher);
+            hasher.finish()
+        };
+        build_auxiliary_artifact(
+            &auxiliary,
+            Artifact::Documentation,
+            fingerprint,
+            false,
+            flags,
+            || {
+                command::document(
+                    &path,
+                    crate_name.as_ref(),
+                    crate_type,
+                    edition,
+                    &dependencies,
+                    flags,
+                    Strictness::Strict,
+                )
+            },
         )?;
     }
 
     // FIXME: Do we need to respect `compile-flags: --crate-name` and adjust `ExternCrate` accordingly?
-    Ok(match *extern_crate {
+    let extern_crate = match *extern_crate {
         // FIXME: probably doesn't handle `//@ aux-build: ../file.rs` correctly since `-L.` wouldn't pick it up
         ExternCrate::Unnamed { path } => ExternCrate::Unnamed { path },
+        ExternCrate::ProcMacro { path } => ExternCrate::ProcMacro { path },
         // FIXME: For some reason `compiletest` doesn't support `//@ aux-crate: name=../`
-        ExternCrate::Named { name, .. } => {
-            // FIXME: unwrap
-            let crate_name = CrateName::adjust_and_parse_file_path(&path).unwrap();
-
-            ExternCrate::Named {
-                name,
-                // FIXME: needs to be relative to the base_path
-                // FIXME: layer violation?? should this be the job of mod command?
-                path: (name != crate_name.as_ref()).then(|| format!("lib{crate_name}.rlib").into()),
+        ExternCrate::Named { name, version, .. } => ExternCrate::Named {
+            name,
+            version,
+            // FIXME: needs to be relative to the base_path
+            // FIXME: layer violation?? should this be the job of mod command?
+            // The versions of a crate share its name, so the search path would be ambiguous.
+            path: (name != crate_name.as_ref() || version.is_some()).then(|| {
+                let stem = auxiliary.file_stem();
+                let library = match crate_type {
+                    CrateType::ProcMacro => format!("{DLL_PREFIX}{stem}{DLL_SUFFIX}"),
+                    _ => format!("lib{stem}.rlib"),
+                };
+                out_path(flags.build, library).display().to_string().into()
+            }),
+        },
+    };
+
+    let documented = documented.into_iter().chain(document.then_some(crate_name)).collect();
+    Ok((extern_crate, documented, fingerprint))
+}
+
+/// The flags distinguishing the given version of an auxiliary crate from its other versions by
+/// their metadata and their file names.
+pub(crate) fn version_flags(version: &str) -> [String; 2] {
+    [format!("-Cmetadata={version}"), format!("-Cextra-filename=-{version}")]
+}
+
+/// The crate type of the given auxiliary crate given its source and the value of `--crate-type`
+/// in its verbatim flags (e.g., from `//@ compile-flags` or `--aux-arg`) if any.
+///
+/// Auxiliary crates are libraries unless they are declared to be proc macros via
+/// `//@ proc-macro`, `--crate-type` or `#![crate_type]`.
+pub(crate) fn auxiliary_crate_type(
+    extern_crate: &ExternCrate<'_>,
+    source: &str,
+    crate_type_flag: Option<&str>,
+    edition: Edition,
+    cfgs: &[String],
+) -> CrateType {
+    if let ExternCrate::ProcMacro { .. } = extern_crate {
+        return CrateType::ProcMacro;
+    }
+    let crate_type = match crate_type_flag {
+        Some(crate_type) => crate_type.parse().ok(),
+        None => Attributes::parse(source, cfgs, edition, false).crate_type,
+    };
+    match crate_type {
+        Some(CrateType::ProcMacro) => CrateType::ProcMacro,
+        _ => CrateType::Lib,
+    }
+}
+
+/// The path to the source file of the given auxiliary crate.
+pub(crate) fn auxiliary_path(extern_crate: &ExternCrate<'_>, base_path: &Path) -> PathBuf {
+    match extern_crate {
+        ExternCrate::Unnamed { path } | ExternCrate::ProcMacro { path } => base_path.join(path),
+        ExternCrate::Named { name, path, .. } => match path {
+            Some(path) => base_path.join(path.as_ref()),
+            None => base_path.join(name.as_str()).with_extension("rs"),
+        },
+    }
+}
+
+/// The fingerprints of the auxiliary artifacts built so far by this process.
+///
+/// Since the artifacts of auxiliary crates end up in the current directory, they are keyed by
+/// their crate name. In batch mode, this allows tests to share identical auxiliary crates
+/// instead of rebuilding them over and over again, just like `compiletest` does.
+// FIXME: Invalidate entries if a non-auxiliary crate of the same name gets built.
+static AUXILIARY_ARTIFACTS: LazyLock<Mutex<FxHashMap<(Auxiliary, Artifact), u64>>> =
+    LazyLock::new(default);
+
+/// Forget the auxiliary documentation built so far, e.g., after the documentation root was removed.
+pub(crate) fn forget_auxiliary_docs() {
+    AUXILIARY_ARTIFACTS
+        .lock()
+        .unwrap()
+        .retain(|(_, artifact), _| *artifact != Artifact::Documentation);
+}
+
+/// The auxiliary artifacts that are currently being built by some thread.
+static BUILDING: (Mutex<Vec<(Auxiliary, Artifact)>>, Condvar) =
+    (Mutex::new(Vec::new()), Condvar::new());
+
+/// Exclusive access to an auxiliary artifact, so dependencies shared by several auxiliary crates
+/// that are built in parallel only get built once.
+struct Building((Auxiliary, Artifact));
+
+impl Building {
+    fn start(key: (Auxiliary, Artifact)) -> Self {
+        let (building, finished) = &BUILDING;
+        let mut building = building.lock().unwrap();
+        while building.contains(&key) {
+            building = finished.wait(building).unwrap();
+        }
+        building.push(key.clone());
+        Self(key)
+    }
+}
+
+impl Drop for Building {
+    fn drop(&mut self) {
+        let (building, finished) = &BUILDING;
+        building.lock().unwrap().retain(|key| *key != self.0);
+        finished.notify_all();
+    }
+}
+
+/// The number of auxiliary crates that are currently being built.
+static JOBS: (Mutex<usize>, Condvar) = (Mutex::new(0), Condvar::new());
+
+/// A slot for running `rust{c,doc}` (`--jobs`).
+///
+/// Only the builds themselves take up a slot, not the waiting for dependencies, so nested
+/// auxiliary crates can't deadlock.
+struct Job;
+
+impl Job {
+    fn acquire(flags: &cli::ProgramFlags) -> Self {
+        let (running, finished) = &JOBS;
+        let mut running = running.lock().unwrap();
+        while *running >= jobs(flags) {
+            running = finished.wait(running).unwrap();
+        }
+        *running += 1;
+        Self
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        let (running, finished) = &JOBS;
+        *running.lock().unwrap() -= 1;
+        finished.notify_one();
+    }
+}
+
+/// The number of threads that currently help with building auxiliary crates.
+static HELPERS: Mutex<usize> = Mutex::new(0);
+
+/// Threads for building auxiliary crates in parallel besides the current one.
+///
+/// Across all nesting levels, the auxiliary crates get built by at most [`jobs`] threads.
+struct Helpers(usize);
+
+impl Helpers {
+    /// Reserve up to `wanted` threads, fewer if the other threads already take up the jobs.
+    fn reserve(wanted: usize, flags: &cli::ProgramFlags) -> Self {
+        let mut helpers = HELPERS.lock().unwrap();
+        let amount = wanted.min(jobs(flags).saturating_sub(*helpers + 1));
+        *helpers += amount;
+        Self(amount)
+    }
+}
+
+impl Drop for Helpers {
+    fn drop(&mut self) {
+        *HELPERS.lock().unwrap() -= self.0;
+    }
+}
+
+/// The maximum number of auxiliary crates to build at the same time.
+fn jobs(flags: &cli::ProgramFlags) -> usize {
+    flags.jobs.or_else(|| std::thread::available_parallelism().ok()).map_or(1, NonZeroUsize::get)
+}
+
+/// An auxiliary crate, one of several versions of it if there's a version.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Auxiliary {
+    crate_name: CrateNameBuf,
+    version: Option<String>,
+}
+
+impl Auxiliary {
+    /// The name of the artifacts without the prefix `lib` and the extension.
+    fn file_stem(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{}-{version}", self.crate_name),
+            None => self.crate_name.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Auxiliary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.crate_name)?;
+        match &self.version {
+            Some(version) => write!(f, "@{version}"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Artifact {
+    Library,
+    Documentation,
+}
+
+/// Build the given auxiliary artifact unless an identical one was already built.
+///
+/// Libraries may also come from the cache which they get stored in. If `store` is set (when
+/// prebuilding), a cache is required and `--no-cache` doesn't prevent storing them.
+fn build_auxiliary_artifact(
+    auxiliary: &Auxiliary,
+    artifact: Artifact,
+    fingerprint: u64,
+    store: bool,
+    flags: Flags<'_>,
+    build: impl FnOnce() -> Result,
+) -> Result {
+    let key = (auxiliary.clone(), artifact);
+    let _building = Building::start(key.clone());
+
+    {
+        let mut artifacts = AUXILIARY_ARTIFACTS.lock().unwrap();
+
+        if artifacts.get(&key) == Some(&fingerprint) {
+            if flags.program.verbose {
+                let artifact = match artifact {
+                    Artifact::Library => "library",
+                    Artifact::Documentation => "documentation",
+                };
+                info(format!("reusing the {artifact} of the crate `{auxiliary}`")).emit();
             }
+            return Ok(());
         }
-    })
+
+        // We no longer know the state of the artifact if the build below fails.
+        artifacts.remove(&key);
+    }
+
+    // The documentation shares its output directory with the other crates, so we don't cache it.
+    let entry = match artifact {
+        Artifact::Library if !flags.program.dry_run => cache_entry(fingerprint, store, flags)?,
+        _ => None,
+    };
+
+    if let Some(entry) = &entry
+        && !flags.program.no_cache
+        && restore_library(entry, flags.build)?
+    {
+        if flags.program.verbose {
+            info(format!("restored the library of the crate `{auxiliary}` from the cache")).emit();
+        }
+        AUXILIARY_ARTIFACTS.lock().unwrap().insert(key, fingerprint);
+        return Ok(());
+    }
+
+    {
+        let _job = Job::acquire(flags.program);
+        build()?;
+    }
+
+    if !flags.program.dry_run {
+        if let Some(entry) = &entry {
+            if store_library(entry, auxiliary, flags.build)? && flags.program.verbose {
+                info(format!("stored the library of the crate `{auxiliary}` in the cache")).emit();
+            }
+        }
+        AUXILIARY_ARTIFACTS.lock().unwrap().insert(key, fingerprint);
+    }
+
+    Ok(())
+}
+
+/// The location of the given auxiliary library in the cache.
+///
+/// Unlike the fingerprint, the key also covers the version of the toolchain since the cache
+/// outlives the current run and toolchains like `nightly` get updated in the meantime.
+fn cache_entry(fingerprint: u64, store: bool, flags: Flags<'_>) -> Result<Option<PathBuf>> {
+    /// The versions of the toolchains probed so far by name since a run may use several.
+    static VERSIONS: LazyLock<Mutex<FxHashMap<Option<String>, Option<String>>>> =
+        LazyLock::new(default);
+
+    if flags.program.no_cache && !store {
+        return Ok(None);
+    }
+    let directory = match cache::directory() {
+        Ok(directory) => directory.join("dependencies"),
+        Err(error) if store => return Err(error.into()),
+        Err(_) => return Ok(None),
+    };
+
+    let version = VERSIONS
+        .lock()
+        .unwrap()
+        .entry(flags.build.toolchain.clone())
+        .or_insert_with(|| {
+            toolchain::probe(flags.build, flags.program).map(|version| version.to_string())
+        })
+        .clone();
+    let Some(version) = version else {
+        if store {
+            warning("failed to probe the toolchain")
+                .note("not storing the auxiliary libraries in the cache")
+                .emit();
+        }
+        return Ok(None);
+    };
+
+    let mut hasher = DefaultHasher::new();
+    fingerprint.hash(&mut hasher);
+    version.hash(&mut hasher);
+    Ok(Some(directory.join(format!("{:016x}", hasher.finish()))))
+}
+
+/// Copy the files of the cached library into the output directory where `rustc` put them.
+///
+/// Returns whether the library was cached.
+fn restore_library(entry: &Path, flags: &cli::BuildFlags) -> std::io::Result<bool> {
+    let files = match std::fs::read_dir(entry) {
+        Ok(files) => files,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(error) => return Err(error),
+    };
+
+    for file in files {
+        let file = file?;
+        std::fs::copy(file.path(), out_path(flags, file.file_name()))?;
+    }
+
+    Ok(true)
+}
+
+/// Copy the files `rustc` emitted for the given library into the cache.
+///
+/// Returns whether there were any files to store.
+fn store_library(
+    entry: &Path,
+    auxiliary: &Auxiliary,
+    flags: &cli::BuildFlags,
+) -> std::io::Result<bool> {
+    // Concurrent runs shouldn't be able to observe a partially written entry.
+    let temporary = entry.with_extension("tmp");
+    if temporary.exists() {
+        std::fs::remove_dir_all(&temporary)?;
+    }
+    std::fs::create_dir_all(&temporary)?;
+
+    let stem = auxiliary.file_stem();
+    let mut stored = false;
+    for file in std::fs::read_dir(out_path(flags, "."))? {
+        let file = file?;
+        if is_artifact(&file.file_name().to_string_lossy(), &stem) && file.file_type()?.is_file() {
+            std::fs::copy(file.path(), temporary.join(file.file_name()))?;
+            stored = true;
+        }
+    }
+
+    // Otherwise, the entry would count as cached and we'd never build the library again.
+    if !stored {
+        std::fs::remove_dir(&temporary)?;
+        return Ok(false);
+    }
+
+    if entry.exists() {
+        std::fs::remove_dir_all(entry)?;
+    }
+    std::fs::rename(&temporary, entry)?;
+    Ok(true)
+}
+
+/// Whether the file of the given name is an artifact of the library with the given file stem.
+fn is_artifact(name: &str, stem: &str) -> bool {
+    // Proc macros are dynamic libraries which don't have the prefix `lib` on every platform.
+    name.strip_prefix("lib")
+        .and_then(|name| name.strip_prefix(stem))
+        .is_some_and(|rest| rest.starts_with('.'))
+        || name == format!("{DLL_PREFIX}{stem}{DLL_SUFFIX}")
+}
+
+/// Compute the fingerprint of an auxiliary crate from everything that may affect its artifacts.
+///
+/// Within a single run, the crate may get built with different build flags, e.g., once per
+/// revision with `--all-revisions` or once per toolchain with `--compare-toolchain`.
+fn fingerprint(path: &Path, source: Option<&str>, edition: Edition, flags: Flags<'_>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    path.canonicalize().as_deref().unwrap_or(path).hash(&mut hasher);
+    source.hash(&mut hasher);
+    // The out-of-line modules of the crate.
+    for file in module::files(path).iter().skip(1) {
+        file.hash(&mut hasher);
+        std::fs::read(file).ok().hash(&mut hasher);
+    }
+    edition.hash(&mut hasher);
+    flags.verbatim.arguments.hash(&mut hasher);
+    flags.verbatim.environment.hash(&mut hasher);
+    hash_build_flags(flags.build, &mut hasher);
+    hasher.finish()
+}
+
+/// Hash the build flags that affect the artifacts of auxiliary crates.
+fn hash_build_flags(flags: &cli::BuildFlags, hasher: &mut DefaultHasher) {
+    flags.toolchain.hash(hasher);
+    flags.target.hash(hasher);
+    flags.sysroot.hash(hasher);
+    flags.cfgs.hash(hasher);
+    flags.cargo_features.hash(hasher);
+    flags.rustc_features.hash(hasher);
+    flags.rustc_verbose_internals.hash(hasher);
+    flags.reproducible.hash(hasher);
+    // Lints may turn warnings into errors or suppress them.
+    flags.cap_lints.hash(hasher);
+    flags.cap_dep_lints.hash(hasher);
+    flags.lints.hash(hasher);
+    flags.config_lints.hash(hasher);
+    flags.dep_lints.hash(hasher);
+    std::env::var_os("RUSTFLAGS").hash(hasher);
+}
+
+/// Hash the build flags that additionally affect the documentation of auxiliary crates.
+fn hash_doc_flags(flags: &cli::BuildFlags, hasher: &mut DefaultHasher) {
+    flags.json.hash(hasher);
+    flags.output_format.hash(hasher);
+    flags.private.hash(hasher);
+    flags.hidden.hash(hasher);
+    flags.layout.hash(hasher);
+    flags.link_to_definition.hash(hasher);
+    flags.normalize.hash(hasher);
+    flags.theme.hash(hasher);
+    flags.rustdoc_threads.hash(hasher);
+    flags.dep_crate_versions.hash(hasher);
 }
 
 #[derive(Clone, Copy)]
@@ -262,6 +1039,10 @@ pub(crate) enum QueryMode {
 
 pub(crate) enum Error {
     UnknownRevision { unknown: String, available: FxHashSet<String> },
+    CyclicAuxiliaries { cycle: Vec<PathBuf> },
+    PrebuildWithoutCompiletest,
+    LogFileWithStderrChecks,
+    UnexpectedSuccess,
 }
 
 impl IntoDiagnostic for Error {
@@ -273,8 +1054,22 @@ impl IntoDiagnostic for Error {
 
                 error(format!("unknown revision `{unknown}`"))
                     .note(format!("available revisions are: {available}"))
-                    .note(format!("you can use `--cfg` over `--rev` to suppress this check"))
+                    .note("you can use `--cfg` over `--rev` to suppress this check")
+            }
+            Error::CyclicAuxiliaries { cycle } => {
+                let cycle =
+                    cycle.iter().map(|path| format!("`{}`", path.display())).join_with(" → ");
+                error("the auxiliary crates depend on each other cyclically")
+                    .note(format!("cycle: {cycle}"))
+            }
+            Error::PrebuildWithoutCompiletest => error("`prebuild` requires `-T`/`--compiletest`")
+                .note("only the auxiliary crates of compiletest tests can be prebuilt"),
+            Error::LogFileWithStderrChecks => {
+                error("`--log-file` can't be combined with checking the stderr of the test")
+                    .note("the diagnostics would end up in the log file next to the logs")
             }
+            Error::UnexpectedSuccess => error("`rustdoc` succeeded unexpectedly")
+                .note("the test is expected to fail due to `//@ check-fail`"),
         }
     }
 }
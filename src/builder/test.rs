@@ -0,0 +1,88 @@
+use super::{fingerprint, hash_doc_flags, is_artifact};
+use crate::{
+    cli,
+    command::{Flags, Scope, Source, VerbatimFlags},
+    data::Edition,
+};
+use clap::Parser;
+use std::{
+    env::consts::{DLL_PREFIX, DLL_SUFFIX},
+    hash::{DefaultHasher, Hasher},
+    path::Path,
+};
+
+/// The fingerprint of the same auxiliary crate built with the given flags.
+fn fingerprint_with(flags: &[&str]) -> u64 {
+    let arguments = ["rruxwry", "test.rs"].iter().chain(flags);
+    let arguments = cli::Arguments::try_parse_from(arguments).unwrap();
+    let flags = Flags {
+        build: &arguments.build_flags,
+        verbatim: VerbatimFlags { arguments: &[], origins: &[], environment: &[] },
+        program: &arguments.program_flags,
+        scope: Scope::Dependency,
+        source: Source::Written,
+        redirect_stderr: None,
+        capture_stderr: None,
+    };
+    fingerprint(Path::new("auxiliary/dep.rs"), Some("pub struct S;"), Edition::Edition2021, flags)
+}
+
+#[test]
+fn fingerprint_is_stable() {
+    assert_eq!(fingerprint_with(&["--cfg", "a"]), fingerprint_with(&["--cfg", "a"]));
+    // Flags that don't affect the artifacts.
+    assert_eq!(fingerprint_with(&[]), fingerprint_with(&["--open"]));
+}
+
+#[test]
+fn fingerprint_covers_build_flags() {
+    let base = fingerprint_with(&["--cfg", "a"]);
+    // E.g., the revisions built with `--all-revisions`.
+    assert_ne!(base, fingerprint_with(&["--cfg", "b"]));
+    // E.g., the toolchains compared with `--compare-toolchain`.
+    assert_ne!(base, fingerprint_with(&["--cfg", "a", "-t", "nightly-2024-09-20"]));
+    assert_ne!(base, fingerprint_with(&["--cfg", "a", "--target", "wasm32-unknown-unknown"]));
+    assert_ne!(base, fingerprint_with(&["--cfg", "a", "--sysroot", "sysroot"]));
+    assert_ne!(base, fingerprint_with(&["--cfg", "a", "-f", "std"]));
+    assert_ne!(base, fingerprint_with(&["--cfg", "a", "-F", "rustc_attrs"]));
+}
+
+#[test]
+fn fingerprint_covers_lints() {
+    let base = fingerprint_with(&[]);
+    assert_ne!(base, fingerprint_with(&["--lint", "missing_docs=deny"]));
+    assert_ne!(base, fingerprint_with(&["--dep-lint", "missing_docs=deny"]));
+    assert_ne!(base, fingerprint_with(&["--cap-dep-lints", "allow"]));
+}
+
+/// The hash of the flags that only affect the documentation of auxiliary crates.
+fn doc_flags_hash(flags: &[&str]) -> u64 {
+    let arguments = ["rruxwry", "test.rs"].iter().chain(flags);
+    let arguments = cli::Arguments::try_parse_from(arguments).unwrap();
+    let mut hasher = DefaultHasher::new();
+    hash_doc_flags(&arguments.build_flags, &mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn doc_flags_hash_covers_doc_flags() {
+    let base = doc_flags_hash(&[]);
+    // E.g., the companion JSON build of `--check-consistency`.
+    assert_ne!(base, doc_flags_hash(&["--json"]));
+    assert_ne!(base, doc_flags_hash(&["-P"]));
+    assert_ne!(base, doc_flags_hash(&["-H"]));
+    assert_ne!(base, doc_flags_hash(&["--theme", "dark"]));
+    assert_ne!(base, doc_flags_hash(&["--dep-crate-version", "dep=1.0.0"]));
+    assert_eq!(base, doc_flags_hash(&["--open"]));
+}
+
+#[test]
+fn artifacts() {
+    assert!(is_artifact("libdep.rlib", "dep"));
+    assert!(is_artifact("libdep.rmeta", "dep"));
+    assert!(is_artifact("libdep-1.0.rlib", "dep-1.0"));
+    assert!(is_artifact(&format!("{DLL_PREFIX}dep{DLL_SUFFIX}"), "dep"));
+    assert!(!is_artifact("libdependency.rlib", "dep"));
+    assert!(!is_artifact("libdep-1.0.rlib", "dep"));
+    assert!(!is_artifact("dep.rs", "dep"));
+}
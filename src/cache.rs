@@ -0,0 +1,243 @@
+//! Managing the cache directory.
+//!
+//! The cache directory contains one subdirectory per kind of artifact (e.g., dependency builds).
+//! The entries of these subdirectories are the units that get pruned.
+
+use crate::{
+    cli,
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info},
+};
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+#[cfg(test)]
+mod test;
+
+/// The location of the cache directory.
+///
+/// It can be overridden with the environment variable `RRUXWRY_CACHE_DIR`.
+pub(crate) fn directory() -> Result<PathBuf, Error> {
+    if let Some(path) = std::env::var_os("RRUXWRY_CACHE_DIR") {
+        return Ok(path.into());
+    }
+    if let Some(path) = std::env::var_os("XDG_CACHE_HOME") {
+        return Ok(Path::new(&path).join("rruxwry"));
+    }
+    match std::env::var_os("HOME") {
+        Some(path) => Ok(Path::new(&path).join(".cache/rruxwry")),
+        None => Err(Error::UnknownDirectory),
+    }
+}
+
+pub(crate) fn run(command: &cli::CacheCommand, flags: &cli::ProgramFlags) -> crate::error::Result {
+    let directory = directory()?;
+    let entries = entries(&directory)?;
+
+    match *command {
+        cli::CacheCommand::Stats => stats(&directory, &entries),
+        cli::CacheCommand::Gc { max_size, max_age } => {
+            collect_garbage(entries, max_size, max_age, flags)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn stats(directory: &Path, entries: &[Entry]) {
+    let mut categories = BTreeMap::<_, (usize, u64)>::new();
+    for entry in entries {
+        let (amount, size) = categories.entry(&entry.category).or_default();
+        *amount += 1;
+        *size += entry.size;
+    }
+
+    let total: u64 = entries.iter().map(|entry| entry.size).sum();
+    let diagnostic =
+        info(format!("the cache directory `{}` uses {}", directory.display(), format_size(total)));
+    categories
+        .into_iter()
+        .map(|(category, (amount, size))| {
+            let s = if amount == 1 { "y" } else { "ies" };
+            format!("`{category}`: {} in {amount} entr{s}", format_size(size))
+        })
+        .fold(diagnostic, Diagnostic::note)
+        .emit();
+}
+
+/// Remove the entries older than `max_age`, then the oldest entries until the total size
+/// doesn't exceed `max_size` anymore.
+fn collect_garbage(
+    mut entries: Vec<Entry>,
+    max_size: Option<u64>,
+    max_age: Option<Duration>,
+    flags: &cli::ProgramFlags,
+) -> io::Result<()> {
+    // Oldest first.
+    entries.sort_by_key(|entry| entry.modified);
+
+    let now = SystemTime::now();
+    let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+    let mut removed = Vec::new();
+
+    for entry in entries {
+        let age = now.duration_since(entry.modified).unwrap_or_default();
+        let too_old = max_age.is_some_and(|max_age| age > max_age);
+        let too_large = max_size.is_some_and(|max_size| total > max_size);
+        if !too_old && !too_large {
+            continue;
+        }
+
+        if flags.verbose {
+            let verb = if !flags.dry_run { "removing" } else { "skipping the removal of" };
+            info(format!("{verb} `{}`", entry.path.display())).emit();
+        }
+        if !flags.dry_run {
+            match entry.path.is_dir() {
+                true => std::fs::remove_dir_all(&entry.path)?,
+                false => std::fs::remove_file(&entry.path)?,
+            }
+        }
+
+        total -= entry.size;
+        removed.push(entry);
+    }
+
+    let amount = removed.len();
+    let s = if amount == 1 { "y" } else { "ies" };
+    let size: u64 = removed.iter().map(|entry| entry.size).sum();
+    let verb = if !flags.dry_run { "removed" } else { "would remove" };
+    info(format!("{verb} {amount} cache entr{s} freeing {}", format_size(size)))
+        .note(format!("the cache now uses {}", format_size(total)))
+        .emit();
+
+    Ok(())
+}
+
+struct Entry {
+    path: PathBuf,
+    category: String,
+    size: u64,
+    /// The most recent modification time of any file of the entry.
+    modified: SystemTime,
+}
+
+fn entries(directory: &Path) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+
+    let categories = match std::fs::read_dir(directory) {
+        Ok(categories) => categories,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(entries),
+        Err(error) => return Err(error),
+    };
+
+    for category in categories {
+        let category = category?.path();
+        if !category.is_dir() {
+            continue;
+        }
+        let name = category.file_name().unwrap_or_default().to_string_lossy().into_owned();
+
+        for entry in std::fs::read_dir(&category)? {
+            let path = entry?.path();
+            let (size, modified) = measure(&path)?;
+            entries.push(Entry { path, category: name.clone(), size, modified });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// The total size and the most recent modification time of the given file or directory.
+fn measure(path: &Path) -> io::Result<(u64, SystemTime)> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    let mut size = if metadata.is_dir() { 0 } else { metadata.len() };
+    let mut modified = metadata.modified()?;
+
+    if metadata.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            let (entry_size, entry_modified) = measure(&entry?.path())?;
+            size += entry_size;
+            modified = modified.max(entry_modified);
+        }
+    }
+
+    Ok((size, modified))
+}
+
+/// Parse sizes like `500MB`, `5 GB` or `1GiB`.
+pub(crate) fn parse_size(source: &str) -> Result<u64, &'static str> {
+    const UNITS: &[(&str, u64)] = &[
+        ("KiB", 1 << 10),
+        ("MiB", 1 << 20),
+        ("GiB", 1 << 30),
+        ("TiB", 1 << 40),
+        ("KB", 1_000),
+        ("MB", 1_000_000),
+        ("GB", 1_000_000_000),
+        ("TB", 1_000_000_000_000),
+        ("B", 1),
+    ];
+
+    let source = source.trim();
+    let (amount, factor) = UNITS
+        .iter()
+        .find_map(|&(unit, factor)| Some((source.strip_suffix(unit)?, factor)))
+        .unwrap_or((source, 1));
+
+    let amount: f64 = amount.trim().parse().map_err(|_| "expected a size like `5GB`")?;
+    if amount.is_sign_negative() || !amount.is_finite() {
+        return Err("expected a non-negative size");
+    }
+
+    Ok((amount * factor as f64) as u64)
+}
+
+/// Parse durations like `30d`, `12h` or `2w`.
+pub(crate) fn parse_age(source: &str) -> Result<Duration, &'static str> {
+    const UNITS: &[(char, u64)] =
+        &[('s', 1), ('m', 60), ('h', 60 * 60), ('d', 24 * 60 * 60), ('w', 7 * 24 * 60 * 60)];
+
+    let source = source.trim();
+    let error = "expected an age like `30d` (units: `s`, `m`, `h`, `d`, `w`)";
+    let unit = source.chars().last().ok_or(error)?;
+    let &(_, factor) = UNITS.iter().find(|(candidate, _)| *candidate == unit).ok_or(error)?;
+    let amount: u64 = source[..source.len() - 1].trim().parse().map_err(|_| error)?;
+
+    Ok(Duration::from_secs(amount.saturating_mul(factor)))
+}
+
+pub(crate) fn format_size(size: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+
+    if size < 1 << 10 {
+        return format!("{size} B");
+    }
+
+    let mut size = size as f64;
+    let mut unit = "B";
+    for candidate in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = candidate;
+    }
+    format!("{size:.1} {unit}")
+}
+
+pub(crate) enum Error {
+    UnknownDirectory,
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::UnknownDirectory => error("failed to locate the cache directory")
+                .note("neither `RRUXWRY_CACHE_DIR`, `XDG_CACHE_HOME` nor `HOME` are set"),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use super::{format_size, parse_age, parse_size};
+use std::time::Duration;
+
+#[test]
+fn sizes() {
+    assert_eq!(parse_size("5GB"), Ok(5_000_000_000));
+    assert_eq!(parse_size("1 GiB"), Ok(1 << 30));
+    assert_eq!(parse_size("1.5KB"), Ok(1_500));
+    assert_eq!(parse_size("42"), Ok(42));
+    assert!(parse_size("5 quux").is_err());
+    assert!(parse_size("-1MB").is_err());
+}
+
+#[test]
+fn ages() {
+    assert_eq!(parse_age("30d"), Ok(Duration::from_secs(30 * 24 * 60 * 60)));
+    assert_eq!(parse_age("12h"), Ok(Duration::from_secs(12 * 60 * 60)));
+    assert!(parse_age("30").is_err());
+    assert!(parse_age("d").is_err());
+}
+
+#[test]
+fn formatted_sizes() {
+    assert_eq!(format_size(512), "512 B");
+    assert_eq!(format_size(1536), "1.5 KiB");
+    assert_eq!(format_size(5 << 30), "5.0 GiB");
+}
@@ -0,0 +1,228 @@
+//! Documenting Cargo packages by driving `rustdoc` directly.
+//!
+//! We obtain the metadata of the package from `cargo metadata` and the metadata of its
+//! dependencies from `cargo check` but invoke `rustdoc` on the root crate ourselves, so all of
+//! the usual flags apply (unlike with `cargo doc` which hides a lot).
+
+use crate::{
+    cli,
+    data::{CrateNameBuf, CrateType, Edition},
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info},
+};
+use rustc_hash::FxHashMap;
+use serde_json::Value;
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
+
+#[cfg(test)]
+mod test;
+
+pub(crate) struct Package {
+    /// The path to the root source file of the crate.
+    pub(crate) path: PathBuf,
+    pub(crate) crate_name: CrateNameBuf,
+    pub(crate) crate_type: CrateType,
+    pub(crate) edition: Edition,
+    /// The enabled features, incl. the default ones.
+    pub(crate) features: Vec<String>,
+    /// The flags for locating the dependencies, i.e., `--extern` and `-L`.
+    pub(crate) arguments: Vec<String>,
+}
+
+/// The manifest of the package at the given path if it's a manifest or a directory containing one.
+pub(crate) fn manifest(path: &Path) -> Option<PathBuf> {
+    if path.file_name().is_some_and(|name| name == "Cargo.toml") {
+        return Some(path.to_owned());
+    }
+    let manifest = path.join("Cargo.toml");
+    (path.is_dir() && manifest.is_file()).then_some(manifest)
+}
+
+/// Load the package and build the metadata of its (normal) dependencies.
+// FIXME: Respect the `cfg`s and environment variables set by build scripts.
+pub(crate) fn load(
+    manifest: &Path,
+    build_flags: &cli::BuildFlags,
+    program_flags: &cli::ProgramFlags,
+) -> crate::error::Result<Package> {
+    let manifest_path = manifest.to_string_lossy();
+    let features = build_flags.cargo_features.join(",");
+    let mut arguments = vec!["--manifest-path", &manifest_path];
+    if !features.is_empty() {
+        arguments.extend(["--features", &features]);
+    }
+
+    let cargo = |subcommand: &[&str]| {
+        let arguments: Vec<_> = subcommand.iter().chain(&arguments).copied().collect();
+        cargo(&arguments, build_flags, program_flags)
+    };
+
+    let output = cargo(&["metadata", "--format-version", "1"])?;
+    output.status.exit_ok()?;
+    let metadata: Value =
+        serde_json::from_slice(&output.stdout).map_err(|_| Error::InvalidMetadata)?;
+
+    let root = metadata["resolve"]["root"].as_str().ok_or(Error::VirtualManifest)?;
+    let package = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|package| package["id"] == root)
+        .ok_or(Error::InvalidMetadata)?;
+    let node = metadata["resolve"]["nodes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|node| node["id"] == root)
+        .ok_or(Error::InvalidMetadata)?;
+
+    let (target, crate_type) = target(package).ok_or(Error::MissingTarget)?;
+    let crate_name = target["name"].as_str().ok_or(Error::InvalidMetadata)?;
+    let crate_name =
+        CrateNameBuf::adjust_and_parse(crate_name).map_err(|()| Error::InvalidMetadata)?;
+    let path = target["src_path"].as_str().ok_or(Error::InvalidMetadata)?.into();
+    // Cargo defaults to the 2015 edition, too.
+    let edition =
+        target["edition"].as_str().and_then(|edition| edition.parse().ok()).unwrap_or_default();
+
+    let features = node["features"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|feature| Some(feature.as_str()?.to_owned()))
+        .collect();
+
+    // The dependencies by their package ID alongside the name they're known as to the root crate.
+    let dependencies: Vec<_> = node["deps"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|dependency| {
+            dependency["dep_kinds"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .any(|kind| kind["kind"].is_null())
+        })
+        .filter_map(|dependency| Some((dependency["pkg"].as_str()?, dependency["name"].as_str()?)))
+        .collect();
+
+    let arguments = match dependencies.is_empty() {
+        true => Vec::new(),
+        false => {
+            // Checking the dependencies on their own (via `--package`) wouldn't enable the same
+            // features, so we check the entire package. We don't care if the root crate fails to
+            // compile, its errors are part of the JSON output which we otherwise ignore.
+            let output = cargo(&["check", "--message-format", "json"])?;
+            dependency_arguments(&output.stdout, &dependencies)?
+        }
+    };
+
+    Ok(Package { path, crate_name, crate_type, edition, features, arguments })
+}
+
+/// The library target of the package or, failing that, its first binary target.
+fn target(package: &Value) -> Option<(&Value, CrateType)> {
+    let targets = package["targets"].as_array()?;
+    let has_kind = |target: &Value, kinds: &[&str]| {
+        target["kind"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .any(|kind| kind.as_str().is_some_and(|kind| kinds.contains(&kind)))
+    };
+
+    if let Some(target) = targets.iter().find(|target| has_kind(target, &["proc-macro"])) {
+        return Some((target, CrateType::ProcMacro));
+    }
+    if let Some(target) = targets.iter().find(|target| has_kind(target, &["lib", "rlib"])) {
+        return Some((target, CrateType::Lib));
+    }
+    targets.iter().find(|target| has_kind(target, &["bin"])).map(|target| (target, CrateType::Bin))
+}
+
+/// Run `cargo` of the selected toolchain, capturing its stdout.
+fn cargo(
+    arguments: &[&str],
+    build_flags: &cli::BuildFlags,
+    program_flags: &cli::ProgramFlags,
+) -> std::io::Result<process::Output> {
+    let mut command = process::Command::new("cargo");
+    if let Some(toolchain) = &build_flags.toolchain {
+        command.arg(format!("+{toolchain}"));
+    }
+    command.args(arguments);
+
+    if program_flags.verbose {
+        info(format!("running `cargo {}`", arguments.join(" "))).emit();
+    }
+
+    command.stderr(process::Stdio::inherit()).output()
+}
+
+/// The flags for passing the given dependencies to `rustdoc` based on the messages of `cargo`.
+fn dependency_arguments(
+    messages: &[u8],
+    dependencies: &[(&str, &str)],
+) -> Result<Vec<String>, Error> {
+    // The library artifact of each checked package.
+    let mut artifacts = FxHashMap::default();
+    for message in String::from_utf8_lossy(messages).lines() {
+        let Ok(message) = serde_json::from_str::<Value>(message) else { continue };
+        if message["reason"] != "compiler-artifact" {
+            continue;
+        }
+        let is_library = message["target"]["kind"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .any(|kind| *kind != "bin" && *kind != "custom-build");
+        let Some(id) = message["package_id"].as_str() else { continue };
+        let filename = message["filenames"].as_array().and_then(|filenames| filenames.last());
+        if is_library && let Some(filename) = filename.and_then(Value::as_str) {
+            artifacts.insert(id.to_owned(), PathBuf::from(filename));
+        }
+    }
+
+    let mut arguments = Vec::new();
+    let mut directories = Vec::new();
+    for (id, name) in dependencies {
+        let artifact =
+            artifacts.get(*id).ok_or_else(|| Error::MissingArtifact((*name).to_owned()))?;
+        arguments.push(format!("--extern={name}={}", artifact.display()));
+        if let Some(directory) = artifact.parent()
+            && !directories.contains(&directory)
+        {
+            directories.push(directory);
+        }
+    }
+    // For the transitive dependencies.
+    for directory in directories {
+        arguments.push(format!("-Ldependency={}", directory.display()));
+    }
+
+    Ok(arguments)
+}
+
+pub(crate) enum Error {
+    InvalidMetadata,
+    VirtualManifest,
+    MissingTarget,
+    MissingArtifact(String),
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::InvalidMetadata => error("failed to understand the output of `cargo metadata`"),
+            Self::VirtualManifest => error("the manifest is a virtual manifest")
+                .note("pass the manifest of one of the workspace members instead"),
+            Self::MissingTarget => error("the package doesn't have a library or binary target"),
+            Self::MissingArtifact(name) => error(format!(
+                "`cargo check` didn't produce the metadata of the dependency `{name}`"
+            )),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use super::{dependency_arguments, target};
+use crate::data::CrateType;
+use serde_json::json;
+
+#[test]
+fn library_target_first() {
+    let package = json!({ "targets": [
+        { "name": "main", "kind": ["bin"] },
+        { "name": "lib", "kind": ["lib"] },
+    ] });
+    let (library, crate_type) = target(&package).unwrap();
+    assert_eq!((library["name"].as_str(), crate_type), (Some("lib"), CrateType::Lib));
+
+    let package = json!({ "targets": [{ "name": "main", "kind": ["bin"] }] });
+    assert_eq!(target(&package).unwrap().1, CrateType::Bin);
+}
+
+#[test]
+fn dependency_flags() {
+    let messages = [
+        json!({ "reason": "compiler-artifact", "package_id": "build", "target": { "kind": ["custom-build"] }, "filenames": ["target/debug/build/x/build-script-build"] }),
+        json!({ "reason": "compiler-artifact", "package_id": "dep", "target": { "kind": ["lib"] }, "filenames": ["target/debug/deps/libdep-0.rmeta"] }),
+        json!({ "reason": "build-finished", "success": false }),
+    ]
+    .map(|message| message.to_string())
+    .join("\n");
+
+    assert_eq!(dependency_arguments(messages.as_bytes(), &[("dep", "renamed")]).ok().unwrap(), [
+        "--extern=renamed=target/debug/deps/libdep-0.rmeta",
+        "-Ldependency=target/debug/deps"
+    ]);
+    assert!(dependency_arguments(messages.as_bytes(), &[("build", "build")]).is_err());
+}
@@ -1,21 +1,63 @@
 //! The command-line interface.
 
-use crate::data::{CrateNameBuf, CrateType, Edition};
-use clap::{ColorChoice, Parser};
+use crate::{
+    cache,
+    data::{Backtrace, CrateNameBuf, CrateType, Edition, ErrorFormat},
+    ice, json, probe,
+    toolchain::Date,
+};
+use clap::{ArgGroup, ColorChoice, Parser, Subcommand, ValueEnum};
 use joinery::JoinableIterator;
-use std::path::PathBuf;
+use std::{num::NonZeroUsize, path::PathBuf, time::Duration};
+
+#[cfg(test)]
+mod test;
 
 #[derive(Parser)]
-#[command(about)]
+#[command(
+    about,
+    subcommand_negates_reqs(true),
+    override_usage(
+        "rruxwry [OPTIONS] <PATH>... [-- <VERBATIM>...]\n       rruxwry [OPTIONS] <COMMAND>"
+    )
+)]
 pub(crate) struct Arguments {
-    /// Path to the source file.
-    pub(crate) path: PathBuf,
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+    /// Paths to the source files (`-` for stdin).
+    #[arg(required(true), value_name("PATH"))]
+    pub(crate) paths: Vec<PathBuf>,
     /// Flags passed to `rustc` and `rustdoc` verbatim.
     #[arg(last(true), value_name("VERBATIM"))]
     pub(crate) verbatim_flags: Vec<String>,
-    /// Open the generated docs in a browser.
-    #[arg(short, long)]
-    pub(crate) open: bool,
+    /// The verbatim flags obtained from the configuration files.
+    #[arg(skip)]
+    pub(crate) config_verbatim_flags: Vec<String>,
+    /// Open the generated docs in a browser, optionally those of the given crate, page or item.
+    #[arg(
+        short,
+        long,
+        value_name("TARGET"),
+        num_args(0..=1),
+        require_equals(true),
+        conflicts_with("stdout")
+    )]
+    pub(crate) open: Option<Option<String>>,
+    /// Print the generated docs as text, optionally those of the given crate, page or item.
+    #[arg(
+        long,
+        value_name("TARGET"),
+        num_args(0..=1),
+        require_equals(true),
+        conflicts_with_all(["stdout", "json", "output_format"])
+    )]
+    pub(crate) view: Option<Option<String>>,
+    /// Open a rendered failure report in a browser if the build or a check fails.
+    #[arg(long, conflicts_with("stdout"))]
+    pub(crate) open_on_failure: bool,
+    /// Print the paths to the generated docs.
+    #[arg(long, conflicts_with("stdout"))]
+    pub(crate) print_paths: bool,
     /// Set the name of the (base) crate.
     #[arg(short = 'n', long, value_name("NAME"), value_parser = CrateNameBuf::parse_cli_style)]
     pub(crate) crate_name: Option<CrateNameBuf>,
@@ -25,6 +67,9 @@ pub(crate) struct Arguments {
     /// Set the edition of the source files.
     #[arg(short, long, value_parser = Edition::parse_cli_style)]
     pub(crate) edition: Option<Edition>,
+    /// Embed the snippet in the given template to turn it into a valid crate root.
+    #[arg(long, value_name("TEMPLATE"))]
+    pub(crate) wrap: Option<Wrap>,
     #[command(flatten)]
     pub(crate) build_flags: BuildFlags,
     /// Enable the cross-crate re-export mode.
@@ -43,12 +88,147 @@ pub(crate) struct Arguments {
     pub(crate) color: ColorChoice,
 }
 
+#[derive(Subcommand)]
+pub(crate) enum Command {
+    /// Manage the cache directory.
+    #[command(subcommand)]
+    Cache(CacheCommand),
+    /// Introspect the selected toolchain.
+    #[command(subcommand)]
+    Toolchain(ToolchainCommand),
+    /// Inspect and compare the runs recorded with `--record`.
+    #[command(subcommand)]
+    History(HistoryCommand),
+    /// Print a single piece of information about the selected toolchain.
+    #[command(subcommand)]
+    Print(PrintCommand),
+    /// Compare the public API of two crates (or of one crate built in two ways) using JSON.
+    Diff {
+        /// Path to the source file of the baseline.
+        old: PathBuf,
+        /// Path to the source file to compare against the baseline [default: OLD].
+        new: Option<PathBuf>,
+        /// Document NEW with the given toolchain.
+        #[arg(long, value_name("NAME"))]
+        new_toolchain: Option<String>,
+        /// Pass the given flag to `rustc` and `rustdoc` verbatim when building NEW.
+        #[arg(long = "new-arg", value_name("ARG"), allow_hyphen_values(true))]
+        new_args: Vec<String>,
+    },
+    /// Run the steps of a pipeline defined in the configuration files one after the other.
+    Run {
+        /// The name of the pipeline.
+        pipeline: String,
+        /// Paths to pass to each step.
+        #[arg(value_name("PATH"))]
+        paths: Vec<PathBuf>,
+    },
+    /// Find the first nightly for which the build of the crate fulfills the predicate.
+    Bisect {
+        /// Path to the source file.
+        path: PathBuf,
+        /// The date of a nightly for which the predicate doesn't hold yet (e.g., `2024-09-01`).
+        #[arg(long, value_name("DATE"), value_parser = parse_date)]
+        start: Date,
+        /// The date of a nightly for which the predicate holds.
+        #[arg(long, value_name("DATE"), value_parser = parse_date)]
+        end: Date,
+        /// What makes a nightly bad.
+        #[arg(long, value_enum, default_value("failure"))]
+        predicate: Predicate,
+    },
+    /// Print the part of the JSON docs selected by a JSON pointer or the path of an item.
+    Extract {
+        /// Path to the source file.
+        path: PathBuf,
+        /// The JSON pointer to the value (e.g., `/index/123/inner`).
+        #[arg(long, value_name("POINTER"), required_unless_present("item"))]
+        pointer: Option<String>,
+        /// The path to the item (e.g., `krate::Type::method`).
+        #[arg(long, value_name("PATH"), conflicts_with("pointer"))]
+        item: Option<String>,
+    },
+    /// Build the auxiliary crates of compiletest tests and store them in the cache.
+    Prebuild {
+        /// Paths to the tests.
+        #[arg(required(true), value_name("PATH"))]
+        paths: Vec<PathBuf>,
+    },
+    /// Print the completion script for the given shell.
+    #[command(hide(true))]
+    Completions { shell: Shell },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum Predicate {
+    /// The build fails, e.g., due to an error, an ICE or a failing query.
+    Failure,
+    /// `rustdoc` crashes with an internal compiler error.
+    Ice,
+    /// The build succeeds (for finding the nightly that fixed an issue).
+    Success,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum ToolchainCommand {
+    /// Show what the toolchain resolves to for the given flags.
+    Info,
+}
+
+#[derive(Subcommand, Clone, Copy)]
+pub(crate) enum PrintCommand {
+    /// Print the sysroot of `rustc`.
+    Sysroot,
+    /// Print the directory of the libraries of the host target.
+    TargetLibdir,
+    /// Print the version of `rustdoc`.
+    RustdocVersion,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum HistoryCommand {
+    /// List the recorded runs from the oldest to the latest one.
+    List,
+    /// Compare two recorded runs.
+    Compare {
+        /// The ID (or a prefix of it) of the earlier run [default: the previous run with the same arguments].
+        baseline: Option<String>,
+        /// The ID (or a prefix of it) of the later run [default: the latest run].
+        current: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub(crate) enum CacheCommand {
+    /// Show what's using space in the cache directory.
+    Stats,
+    /// Prune the cache directory.
+    Gc {
+        /// Remove the oldest entries until the cache is at most this large (e.g., `5GB`).
+        #[arg(long, value_name("SIZE"), value_parser = cache::parse_size)]
+        max_size: Option<u64>,
+        /// Remove the entries that weren't modified within this duration (e.g., `30d`).
+        #[arg(long, value_name("AGE"), value_parser = cache::parse_age)]
+        max_age: Option<Duration>,
+    },
+}
+
 /// Flags that get passed to `rustc` and `rustdoc` in a lowered form.
-#[derive(Parser)]
+#[derive(Parser, Clone)]
+#[command(group(ArgGroup::new("logging").multiple(true)))]
 pub(crate) struct BuildFlags {
     /// Set the toolchain.
     #[arg(short, long, value_name("NAME"))]
     pub(crate) toolchain: Option<String>,
+    /// Build for the given target (e.g., `wasm32-unknown-unknown`).
+    #[arg(long, value_name("TRIPLE"), conflicts_with("compare_targets"))]
+    pub(crate) target: Option<String>,
+    /// Use the given sysroot instead of the one of the toolchain.
+    #[arg(long, value_name("PATH"))]
+    pub(crate) sysroot: Option<PathBuf>,
+    /// Build `core` and `alloc` from the given Rust checkout and use them as the sysroot.
+    #[arg(long, value_name("PATH"), conflicts_with("sysroot"))]
+    pub(crate) build_sysroot: Option<PathBuf>,
     /// Enable a `cfg`.
     #[arg(long = "cfg", value_name("SPEC"))]
     pub(crate) cfgs: Vec<String>,
@@ -64,9 +244,54 @@ pub(crate) struct BuildFlags {
     /// Output JSON instead of HTML.
     #[arg(short, long, conflicts_with("open"))]
     pub(crate) json: bool,
+    /// Request an unstable output format other than JSON by name (e.g., `doctest`).
+    #[arg(
+        long,
+        value_name("NAME"),
+        value_parser = parse_output_format,
+        conflicts_with_all(["json", "open"])
+    )]
+    pub(crate) output_format: Option<String>,
+    /// Run the doctests instead of documenting the crate (`rustdoc --test`).
+    #[arg(
+        long,
+        conflicts_with_all(["json", "output_format", "open", "cross_crate", "compare_targets"])
+    )]
+    pub(crate) doctest: bool,
+    /// Pass the given argument to the test harness of the doctests (e.g., a filter).
+    #[arg(long = "test-arg", value_name("ARG"), allow_hyphen_values(true), requires("doctest"))]
+    pub(crate) test_args: Vec<String>,
+    /// Don't capture the output of the doctests.
+    #[arg(long, requires("doctest"))]
+    pub(crate) nocapture: bool,
+    /// Report the documentation coverage instead of documenting the crate (`--show-coverage`).
+    #[arg(long, conflicts_with_all(["doctest", "output_format", "open", "compare_targets"]))]
+    pub(crate) coverage: bool,
+    /// Put the libraries and the docs (`doc/`) into the given directory instead of the current one.
+    #[arg(long, value_name("PATH"))]
+    pub(crate) out_dir: Option<PathBuf>,
+    /// Document dependencies that would overwrite the docs of another crate into a separate directory.
+    #[arg(long, conflicts_with("stdout"))]
+    pub(crate) isolate_clobbered: bool,
+    /// Write the JSON output to stdout.
+    #[arg(long, requires("json"))]
+    pub(crate) stdout: bool,
+    /// Set the number of threads used by `rustdoc` (`-Zthreads`).
+    #[arg(long, value_name("N"))]
+    pub(crate) rustdoc_threads: Option<NonZeroUsize>,
     /// Set the version of the (root) crate.
     #[arg(short = 'v', long, value_name("VERSION"))]
     pub(crate) crate_version: Option<String>,
+    /// Set the version of the given (non-root) crate, e.g., of an auxiliary crate.
+    #[arg(
+        long = "dep-crate-version",
+        value_name("NAME=VERSION"),
+        value_parser = parse_crate_version
+    )]
+    pub(crate) dep_crate_versions: Vec<(CrateNameBuf, String)>,
+    /// Pass the given flag to `rust{c,doc}` verbatim but only when building the given dependency.
+    #[arg(long = "aux-arg", value_name("NAME=FLAG"), value_parser = parse_auxiliary_argument)]
+    pub(crate) aux_args: Vec<(CrateNameBuf, String)>,
     /// Document private items.
     #[arg(short = 'P', long)]
     pub(crate) private: bool,
@@ -82,25 +307,104 @@ pub(crate) struct BuildFlags {
     /// Normalize types and constants.
     #[arg(long)]
     pub(crate) normalize: bool,
+    /// Make the output reproducible and report residual nondeterminism.
+    #[arg(long)]
+    pub(crate) reproducible: bool,
     /// Set the theme.
     #[arg(long, default_value("ayu"))]
     pub(crate) theme: String,
     /// Cap lints at a level.
     #[arg(long, value_name("LEVEL"))]
     pub(crate) cap_lints: Option<String>,
+    /// Cap the lints of dependencies at a level, overriding `--cap-lints` for them.
+    #[arg(long, value_name("LEVEL"))]
+    pub(crate) cap_dep_lints: Option<String>,
+    /// Set the level of a lint.
+    #[arg(long = "lint", value_name("NAME=LEVEL"), value_parser = parse_lint_level)]
+    pub(crate) lints: Vec<(String, String)>,
+    /// The lints obtained from the configuration file, overridden by `--lint`.
+    #[arg(skip)]
+    pub(crate) config_lints: Vec<(String, String)>,
+    /// Set the level of a lint for dependencies, overriding `--lint` for them.
+    #[arg(long = "dep-lint", value_name("NAME=LEVEL"), value_parser = parse_lint_level)]
+    pub(crate) dep_lints: Vec<(String, String)>,
+    /// Set the format of diagnostics.
+    #[arg(long, value_name("FORMAT"), value_parser = ErrorFormat::parse_cli_style)]
+    pub(crate) error_format: Option<ErrorFormat>,
+    /// Configure the JSON output of diagnostics.
+    #[arg(long, value_name("CONFIG"), requires("error_format"))]
+    pub(crate) diagnostic_json: Vec<String>,
     /// Enable rustc's `-Zverbose-internals`.
     #[arg(short = '#', long = "internals")]
     pub(crate) rustc_verbose_internals: bool,
-    /// Override `RUSTC_LOG` to be `debug`.
-    #[arg(long)]
-    pub(crate) log: bool,
-    /// Override `RUST_BACKTRACE` to be `0`.
-    #[arg(short = 'B', long)]
-    pub(crate) no_backtrace: bool,
+    /// Override `RUST{C,DOC}_LOG` to be `debug` or the given filter.
+    #[arg(
+        long,
+        value_name("FILTER"),
+        num_args(0..=1),
+        require_equals(true),
+        default_missing_value("debug"),
+        group("logging")
+    )]
+    pub(crate) log: Option<String>,
+    /// Override `RUSTC_LOG` of `rustc` to be `debug` or the given filter.
+    #[arg(
+        long,
+        value_name("FILTER"),
+        num_args(0..=1),
+        require_equals(true),
+        default_missing_value("debug"),
+        group("logging")
+    )]
+    pub(crate) rustc_log: Option<String>,
+    /// Override `RUSTDOC_LOG` of `rustdoc` to be `debug` or the given filter.
+    #[arg(
+        long,
+        value_name("FILTER"),
+        num_args(0..=1),
+        require_equals(true),
+        default_missing_value("debug"),
+        group("logging")
+    )]
+    pub(crate) rustdoc_log: Option<String>,
+    /// Redirect the log output (i.e., stderr) of `rust{c,doc}` to a file.
+    #[arg(long, value_name("PATH"), requires("logging"))]
+    pub(crate) log_file: Option<PathBuf>,
+    /// Override `RUST_BACKTRACE` to be the given level.
+    #[arg(short = 'B', long, value_name("LEVEL"), value_parser = Backtrace::parse_cli_style)]
+    pub(crate) backtrace: Option<Backtrace>,
+    /// Set an environment variable of `rust{c,doc}`.
+    #[arg(long, value_name("KEY=VALUE"), value_parser = parse_environment_variable)]
+    pub(crate) env: Vec<(String, String)>,
+    /// Remove an environment variable of `rust{c,doc}`.
+    #[arg(long, value_name("KEY"))]
+    pub(crate) env_remove: Vec<String>,
+    /// The variables set via `--env` and removed via `--env-remove` in the order they were passed.
+    ///
+    /// Populated by [`Self::order_environment`].
+    #[arg(skip)]
+    pub(crate) environment: Vec<(String, Option<String>)>,
+}
+
+impl BuildFlags {
+    /// Merge the variables of `--env` and `--env-remove` in the order they were passed.
+    ///
+    /// Setting and removing the same variable in succession is not the same as doing it the
+    /// other way around.
+    pub(crate) fn order_environment(&mut self, matches: &clap::ArgMatches) {
+        let indices = |id| matches.indices_of(id).into_iter().flatten();
+        let set = self.env.drain(..).map(|(key, value)| (key, Some(value)));
+        let removed = self.env_remove.drain(..).map(|key| (key, None));
+        let mut environment: Vec<_> =
+            indices("env").zip(set).chain(indices("env_remove").zip(removed)).collect();
+        environment.sort_by_key(|&(index, _)| index);
+        self.environment = environment.into_iter().map(|(_, variable)| variable).collect();
+    }
 }
 
 /// Flags that are specific to `rruxwry` itself.
-#[derive(Parser)]
+#[derive(Parser, Clone)]
+#[command(group(ArgGroup::new("comparison").multiple(true)))]
 pub(crate) struct ProgramFlags {
     /// Use verbose output.
     #[arg(short = 'V', long)]
@@ -109,6 +413,311 @@ pub(crate) struct ProgramFlags {
     /// Run through without making any changes.
     #[arg(short = '0', long)]
     pub(crate) dry_run: bool,
+
+    /// Pretty-print the JSON output written to stdout.
+    #[arg(long, requires("stdout"))]
+    pub(crate) pretty: bool,
+
+    /// Filter the JSON output of the root crate (`kind=KIND`, `path=PREFIX`, `strip-spans`).
+    #[arg(
+        long,
+        value_name("FILTER,..."),
+        value_delimiter(','),
+        value_parser = json::filter::Filter::parse_cli_style,
+        requires("json")
+    )]
+    pub(crate) json_filter: Vec<json::filter::Filter>,
+
+    /// Write a JSON report to the given path.
+    #[arg(long, value_name("PATH"))]
+    pub(crate) report: Option<PathBuf>,
+
+    /// Record a summary of the run in the history (see `rruxwry history`).
+    #[arg(long)]
+    pub(crate) record: bool,
+
+    /// Write the diagnostics emitted across the run grouped by lint and file to the given path.
+    #[arg(long, value_name("PATH"), conflicts_with("error_format"))]
+    pub(crate) lint_summary: Option<PathBuf>,
+
+    /// Compare the lint summary against the one of a previous run.
+    #[arg(long, value_name("PATH"), requires("lint_summary"))]
+    pub(crate) lint_baseline: Option<PathBuf>,
+
+    /// Run `rust{c,doc}` with a lower CPU priority, i.e., the given niceness (10 by default).
+    #[arg(
+        long,
+        value_name("N"),
+        num_args(0..=1),
+        require_equals(true),
+        default_missing_value("10"),
+        allow_negative_numbers(true),
+        value_parser = clap::value_parser!(i8).range(-20..=19)
+    )]
+    pub(crate) nice: Option<i8>,
+
+    /// Run `rust{c,doc}` with the idle I/O scheduling class (via `ionice`).
+    #[arg(long)]
+    pub(crate) idle_io: bool,
+
+    /// Limit the memory of `rust{c,doc}` (e.g., `8GB`) via a transient systemd scope.
+    #[arg(long, value_name("SIZE"), value_parser = cache::parse_size)]
+    pub(crate) memory_limit: Option<u64>,
+
+    /// Run `rust{c,doc}` in a container of the given image (via `docker`).
+    #[arg(long, value_name("IMAGE"), conflicts_with_all(["ssh", "memory_limit"]))]
+    pub(crate) in_container: Option<String>,
+
+    /// Run `rust{c,doc}` on the given host (via `ssh` and `rsync`).
+    #[arg(long, value_name("HOST[:DIR]"), conflicts_with("memory_limit"))]
+    pub(crate) ssh: Option<String>,
+
+    /// Put temporary and synthesized files into the given directory.
+    #[arg(long, value_name("PATH"))]
+    pub(crate) scratch_dir: Option<PathBuf>,
+
+    /// Print the given information before building.
+    #[arg(long, value_name("WHAT"))]
+    pub(crate) print: Option<Print>,
+
+    /// Reduce the file to the top-level items necessary to make `rustdoc` ICE.
+    #[arg(long, conflicts_with_all(["cross_crate", "compiletest", "log_file"]))]
+    pub(crate) bisect_ice: bool,
+
+    /// Re-run `rustdoc` on ICEs to obtain backtraces (`backtrace`, `treat-err-as-bug[=N]`).
+    #[arg(
+        long,
+        value_name("VARIANT,..."),
+        num_args(0..=1),
+        require_equals(true),
+        value_delimiter(','),
+        default_missing_value("backtrace"),
+        value_parser = ice::Variant::parse_cli_style
+    )]
+    pub(crate) rerun_ices: Option<Vec<ice::Variant>>,
+
+    /// Rebuild whenever the source file or one of its dependencies changes.
+    #[arg(long, conflicts_with_all(["bisect_ice", "serve"]))]
+    pub(crate) watch: bool,
+
+    /// Serve the docs over HTTP at the given address (`127.0.0.1:8000` by default).
+    #[arg(
+        long,
+        value_name("ADDRESS"),
+        num_args(0..=1),
+        require_equals(true),
+        default_missing_value("127.0.0.1:8000"),
+        conflicts_with("stdout")
+    )]
+    pub(crate) serve: Option<String>,
+
+    /// Also build the docs with the given toolchain and serve them alongside for comparison.
+    #[arg(long, value_name("TOOLCHAIN"), requires("serve"))]
+    pub(crate) serve_baseline: Option<String>,
+
+    /// Upload the docs (and the report) to a local path, an rsync target or an S3 URL on success.
+    #[arg(long, value_name("DEST"), conflicts_with("stdout"))]
+    pub(crate) upload: Option<String>,
+
+    /// Apply machine-applicable suggestions to the source file.
+    #[arg(long, conflicts_with_all(["error_format", "log_file"]))]
+    pub(crate) apply_suggestions: bool,
+
+    /// Build both the HTML and the JSON output and check that they match.
+    #[arg(long, conflicts_with_all(["json", "query"]))]
+    pub(crate) check_consistency: bool,
+
+    /// Report the trait implementations of all types and check that they're rendered.
+    #[arg(long, conflicts_with_all(["json", "query"]))]
+    pub(crate) trait_impls: bool,
+
+    /// Build the docs with and without `--normalize` and compare the rendered signatures.
+    #[arg(long, group("comparison"), conflicts_with_all(["json", "normalize"]))]
+    pub(crate) compare_normalize: bool,
+
+    /// Build the docs with each of the given numbers of `rustdoc` threads and compare the outputs.
+    #[arg(
+        long,
+        value_name("N,..."),
+        value_delimiter(','),
+        group("comparison"),
+        conflicts_with("rustdoc_threads")
+    )]
+    pub(crate) compare_threads: Vec<NonZeroUsize>,
+
+    /// Build each revision of the compiletest test and compare the outputs.
+    #[arg(
+        long,
+        group("comparison"),
+        requires("compiletest"),
+        conflicts_with_all(["revisions", "query"])
+    )]
+    pub(crate) all_revisions_diff: bool,
+
+    /// Only evaluate the queries tagged with any of the given tags (via `//@ tags`).
+    #[arg(long, value_name("TAG,..."), value_delimiter(','), requires("query"))]
+    pub(crate) only_assertions: Vec<String>,
+
+    /// Evaluate the queries for each revision of the test and print a table of the results.
+    #[arg(long, requires("query"), conflicts_with_all(["revisions", "all_revisions_diff"]))]
+    pub(crate) all_revisions_query: bool,
+
+    /// Build each revision of the test into `out/<CRATE>/<REVISION>/` and summarize the outcomes.
+    #[arg(
+        long,
+        visible_alias("all-revs"),
+        requires("compiletest"),
+        conflicts_with_all(["revisions", "all_revisions_diff", "all_revisions_query"])
+    )]
+    pub(crate) all_revisions: bool,
+
+    /// Build the JSON docs for each of the given targets and report the items only some have.
+    #[arg(
+        long,
+        value_name("TARGET,..."),
+        value_delimiter(','),
+        conflicts_with_all(["stdout", "all_revisions", "all_revisions_query"])
+    )]
+    pub(crate) compare_targets: Vec<String>,
+
+    /// Also build the docs with the given toolchain and compare the (normalized) outputs.
+    #[arg(long, value_name("TOOLCHAIN"), group("comparison"), conflicts_with("stdout"))]
+    pub(crate) compare_toolchain: Option<String>,
+
+    /// Generate a side-by-side viewer for the differences found by a comparison and open it.
+    #[arg(long, requires("comparison"))]
+    pub(crate) diff_viewer: bool,
+
+    /// Explain how the item at the given path got resolved and rendered.
+    #[arg(long, value_name("PATH"), conflicts_with_all(["json", "query"]))]
+    pub(crate) trace: Option<String>,
+
+    /// Check that all static assets exist and are referenced.
+    #[arg(long, conflicts_with("json"))]
+    pub(crate) check_assets: bool,
+
+    /// Check that all anchors are unique and that all fragments of links exist.
+    #[arg(long, conflicts_with("json"))]
+    pub(crate) check_anchors: bool,
+
+    /// Check that all links to local files lead to existing files and anchors.
+    #[arg(long, conflicts_with("json"))]
+    pub(crate) check_links: bool,
+
+    /// Check that the links to source pages and to definitions resolve.
+    #[arg(long, requires("link_to_definition"), conflicts_with("json"))]
+    pub(crate) check_definitions: bool,
+
+    /// Check the rendered type layout sections.
+    #[arg(long, requires("layout"), conflicts_with("json"))]
+    pub(crate) check_layout: bool,
+
+    /// Cross-check the sizes of the type layout sections against `rustc -Zprint-type-sizes`.
+    // FIXME: Support extern crates.
+    #[arg(long, requires("check_layout"), conflicts_with_all(["cross_crate", "compiletest"]))]
+    pub(crate) cross_check_layout: bool,
+
+    /// Check the docs and the contrast of the selected theme for accessibility issues.
+    #[arg(long, conflicts_with("json"))]
+    pub(crate) check_accessibility: bool,
+
+    /// Inject an overlay into the pages that shows IDs, anchors and XPaths of elements on hover.
+    #[arg(long, conflicts_with_all(["json", "stdout", "output_format"]))]
+    pub(crate) debug_overlay: bool,
+
+    /// Print the nodes matched by the given XPath in the generated pages (can be repeated).
+    #[arg(
+        long,
+        value_name("XPATH"),
+        value_parser = probe::Expr::parse_cli_style,
+        conflicts_with_all(["json", "stdout", "output_format"])
+    )]
+    pub(crate) query_expr: Vec<probe::Expr>,
+
+    /// Compare the selected pages (by item path or glob) against their snapshots.
+    #[arg(long = "snapshot", value_name("PATTERN"), conflicts_with("json"))]
+    pub(crate) snapshots: Vec<String>,
+
+    /// Update the snapshots instead of comparing against them.
+    #[arg(long)]
+    pub(crate) bless: bool,
+
+    /// Run the rustdoc-js-style search tests found in the given file.
+    #[arg(long, value_name("PATH"), conflicts_with("json"))]
+    pub(crate) search_tests: Option<PathBuf>,
+
+    /// Run the GUI tests (in the format of `browser-ui-test`) found in the given file.
+    #[cfg(feature = "gui")]
+    #[arg(long, value_name("PATH"), conflicts_with("json"))]
+    pub(crate) gui_tests: Option<PathBuf>,
+
+    /// Show the status of the tests in an interactive terminal dashboard.
+    #[cfg(feature = "tui")]
+    #[arg(long, conflicts_with_all(["watch", "report", "lint_summary", "upload", "open"]))]
+    pub(crate) dashboard: bool,
+
+    /// Fail if `rust{c,doc}` emit any warnings.
+    #[arg(long, conflicts_with_all(["log_file", "error_format"]))]
+    pub(crate) deny_warnings: bool,
+
+    /// Continue with the remaining files (or revisions) after a failure.
+    #[arg(long, overrides_with("no_keep_going"))]
+    pub(crate) keep_going: bool,
+
+    /// Abort on the first failure (default).
+    #[arg(long, overrides_with("keep_going"))]
+    pub(crate) no_keep_going: bool,
+
+    /// Ignore the configuration files entirely.
+    #[arg(long)]
+    pub(crate) no_config: bool,
+
+    /// Don't restore the libraries of dependencies from the cache or store them in it.
+    #[arg(long)]
+    pub(crate) no_cache: bool,
+
+    /// Build at most N auxiliary crates at the same time (the number of CPUs by default).
+    #[arg(long, value_name("N"))]
+    pub(crate) jobs: Option<NonZeroUsize>,
+
+    /// Expand to the flags of the given preset (built-in: `rustdoc-test`, `rustdoc-json-test`,
+    /// `xcrate`).
+    #[arg(long, value_name("NAME"))]
+    pub(crate) preset: Vec<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum Print {
+    /// The crates about to be built as a tree.
+    #[value(name = "build-plan")]
+    Tree,
+    /// The crates about to be built as a graph in the DOT language.
+    #[value(name = "build-plan-dot")]
+    Dot,
+    /// The crates about to be built as JSON incl. their flags and artifacts.
+    #[value(name = "build-plan-json")]
+    Json,
+    /// The files watched for changes in watch mode.
+    #[value(name = "watch-list")]
+    WatchList,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum Wrap {
+    /// Items, e.g., an `impl` block together with the type it's for.
+    Item,
+    /// Statements or an expression, put into the body of a function.
+    Fn,
+    /// Items, put into a module.
+    Module,
 }
 
 impl Edition {
@@ -117,15 +726,19 @@ impl Edition {
             "D" => Ok(Self::default()),
             "S" => Ok(Self::LATEST_STABLE),
             "U" => Ok(Self::BLEEDING_EDGE),
+            "F" => Ok(Self::Future),
             source => source.parse(),
         }
-        .map_err(|()| possible_values(Self::elements().map(Self::to_str).chain(["D", "S", "U"])))
+        .map_err(|()| {
+            possible_values(Self::elements().map(Self::to_str).chain(["D", "S", "U", "F"]))
+        })
     }
 }
 
 impl CrateNameBuf {
     fn parse_cli_style(source: &str) -> Result<Self, &'static str> {
-        Self::adjust_and_parse(source).map_err(|()| "not a non-empty alphanumeric string")
+        Self::adjust_and_parse(source)
+            .map_err(|()| "not a valid crate name (expected a non-empty Rust identifier)")
     }
 }
 
@@ -135,6 +748,65 @@ impl CrateType {
     }
 }
 
+impl Backtrace {
+    fn parse_cli_style(source: &str) -> Result<Self, String> {
+        source.parse().map_err(|()| possible_values(Self::elements().map(Self::to_str)))
+    }
+}
+
+impl ErrorFormat {
+    fn parse_cli_style(source: &str) -> Result<Self, String> {
+        source.parse().map_err(|()| possible_values(Self::elements().map(Self::to_str)))
+    }
+}
+
+fn parse_environment_variable(source: &str) -> Result<(String, String), &'static str> {
+    match source.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_owned(), value.to_owned())),
+        _ => Err("expected `KEY=VALUE`"),
+    }
+}
+
+fn parse_lint_level(source: &str) -> Result<(String, String), &'static str> {
+    match source.split_once('=') {
+        Some((name, level @ ("allow" | "warn" | "force-warn" | "deny" | "forbid")))
+            if !name.is_empty() =>
+        {
+            Ok((name.to_owned(), level.to_owned()))
+        }
+        _ => Err(
+            "expected `NAME=LEVEL` where `LEVEL` is `allow`, `warn`, `force-warn`, `deny` or `forbid`",
+        ),
+    }
+}
+
+fn parse_date(source: &str) -> Result<Date, &'static str> {
+    source.parse().map_err(|()| "expected a date like `2024-09-27`")
+}
+
+fn parse_output_format(source: &str) -> Result<String, &'static str> {
+    match source {
+        "html" => Err("HTML is the default output format"),
+        "json" => Err("use `-j`/`--json` for the JSON output format"),
+        "" => Err("expected the name of an output format"),
+        _ => Ok(source.to_owned()),
+    }
+}
+
+fn parse_crate_version(source: &str) -> Result<(CrateNameBuf, String), &'static str> {
+    let (name, version) = source.split_once('=').ok_or("expected `NAME=VERSION`")?;
+    let name = CrateNameBuf::adjust_and_parse(name)
+        .map_err(|()| "not a valid crate name (expected a non-empty Rust identifier)")?;
+    Ok((name, version.to_owned()))
+}
+
+fn parse_auxiliary_argument(source: &str) -> Result<(CrateNameBuf, String), &'static str> {
+    let (name, flag) = source.split_once('=').ok_or("expected `NAME=FLAG`")?;
+    let name = CrateNameBuf::adjust_and_parse(name)
+        .map_err(|()| "not a valid crate name (expected a non-empty Rust identifier)")?;
+    Ok((name, flag.to_owned()))
+}
+
 fn possible_values(values: impl IntoIterator<Item: std::fmt::Display, IntoIter: Clone>) -> String {
     format!(
         "possible values: {}",
@@ -0,0 +1,38 @@
+use super::Arguments;
+use clap::{CommandFactory, FromArgMatches};
+
+#[test]
+fn environment_in_order() {
+    let arguments = [
+        "rruxwry",
+        "lib.rs",
+        "--env=A=1",
+        "--env-remove=A",
+        "--env=B=2",
+        "--env-remove=C",
+        "--env=A=3",
+    ];
+    let matches = Arguments::command().try_get_matches_from(arguments).unwrap();
+    let mut arguments = Arguments::from_arg_matches(&matches).unwrap();
+    arguments.build_flags.order_environment(&matches);
+
+    let variable = |key: &str, value: Option<&str>| (key.to_owned(), value.map(ToOwned::to_owned));
+    assert_eq!(arguments.build_flags.environment, [
+        variable("A", Some("1")),
+        variable("A", None),
+        variable("B", Some("2")),
+        variable("C", None),
+        variable("A", Some("3")),
+    ]);
+}
+
+#[test]
+fn log_file_requires_logging() {
+    let parse = |arguments: &[&str]| {
+        let arguments = ["rruxwry", "lib.rs"].iter().chain(arguments);
+        Arguments::command().try_get_matches_from(arguments).map(drop)
+    };
+    assert!(parse(&["--log-file", "log.txt"]).is_err());
+    assert!(parse(&["--log", "--log-file", "log.txt"]).is_ok());
+    assert!(parse(&["--rustdoc-log=rustdoc::clean", "--log-file", "log.txt"]).is_ok());
+}
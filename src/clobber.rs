@@ -0,0 +1,126 @@
+//! Detecting crates of a run that write their docs to the same place (`--isolate-clobbered`).
+//!
+//! That's usually the case for auxiliary crates of the same name, e.g., ones located in different
+//! directories or different versions of the same crate. rustdoc silently overwrites the docs of
+//! the earlier crate (or merges them with the ones of the later one) which makes for confusing
+//! results. With `--isolate-clobbered`, later dependencies get their own documentation root.
+
+use crate::{
+    command::{Flags, Scope},
+    data::CrateNameRef,
+    diagnostic::warning,
+    utility::out_path,
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+#[cfg(test)]
+mod test;
+
+/// The docs written so far in this run.
+static OUTPUTS: Mutex<Vec<Output>> = Mutex::new(Vec::new());
+
+struct Output {
+    /// The path the docs get written to.
+    path: PathBuf,
+    /// The canonicalized path to the source file of the crate.
+    source: PathBuf,
+    /// The path to the source file of the crate as shown to the user.
+    shown: PathBuf,
+}
+
+/// Forget the docs written so far, e.g., before documenting the next test of a batch.
+pub(crate) fn forget() {
+    OUTPUTS.lock().unwrap().clear();
+}
+
+/// Record that the given crate is about to be documented and warn if it clobbers the docs of
+/// another crate.
+///
+/// Returns the documentation root to use instead if the crate should be isolated.
+pub(crate) fn record(
+    crate_name: CrateNameRef<'_>,
+    path: &Path,
+    flags: Flags<'_>,
+) -> Option<PathBuf> {
+    let root = out_path(flags.build, "doc");
+    let output = Output {
+        path: output(&root, crate_name.as_str(), flags)?,
+        source: path.canonicalize().unwrap_or_else(|_| path.to_owned()),
+        shown: path.to_owned(),
+    };
+
+    let mut outputs = OUTPUTS.lock().unwrap();
+    let clobbered = clobbered(&outputs, &output)?;
+    let Some(first) = clobbered.first() else {
+        outputs.push(output);
+        return None;
+    };
+
+    let isolate = flags.build.isolate_clobbered && matches!(flags.scope, Scope::Dependency);
+    let isolated = isolate.then(|| isolated_root(flags, crate_name.as_str(), clobbered.len()));
+
+    let diagnostic = warning(format!(
+        "the docs of the crate `{crate_name}` at `{}` clobber the ones at `{}`",
+        output.shown.display(),
+        first.shown.display(),
+    ))
+    .note(format!("both get written to `{}`", output.path.display()));
+    match &isolated {
+        Some(root) => diagnostic.note(format!(
+            "documenting `{}` into `{}` instead",
+            output.shown.display(),
+            root.display()
+        )),
+        None if matches!(flags.scope, Scope::Root) => {
+            diagnostic.note("the root crate doesn't get isolated")
+        }
+        None => {
+            diagnostic.note("pass `--isolate-clobbered` to document it into a separate directory")
+        }
+    }
+    .emit();
+
+    if isolated.is_none() {
+        outputs.push(output);
+    }
+    isolated
+}
+
+/// The docs written so far that get clobbered by the given ones.
+///
+/// Returns `None` if the same crate got documented already.
+fn clobbered<'a>(outputs: &'a [Output], output: &Output) -> Option<Vec<&'a Output>> {
+    let clobbered: Vec<_> = outputs.iter().filter(|other| other.path == output.path).collect();
+    if clobbered.iter().any(|other| other.source == output.source) {
+        return None;
+    }
+    Some(clobbered)
+}
+
+/// The path the given crate writes its docs to, if it's known.
+fn output(root: &Path, crate_name: &str, flags: Flags<'_>) -> Option<PathBuf> {
+    let build = flags.build;
+    // These don't write any docs (or we don't know where).
+    if build.doctest
+        || build.coverage
+        || build.output_format.is_some()
+        || (build.stdout && matches!(flags.scope, Scope::Root))
+        || flags.verbatim.contains_flag("-o")
+        || flags.verbatim.contains_flag("--out-dir")
+    {
+        return None;
+    }
+
+    Some(match build.json {
+        true => root.join(format!("{crate_name}.json")),
+        false => root.join(crate_name),
+    })
+}
+
+/// The separate documentation root of the given clobbering crate.
+fn isolated_root(flags: Flags<'_>, crate_name: &str, clobbered: usize) -> PathBuf {
+    out_path(flags.build, format!("doc.isolated/{crate_name}-{}", clobbered + 1))
+}
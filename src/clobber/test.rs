@@ -0,0 +1,84 @@
+use super::{Output, clobbered, isolated_root, output};
+use crate::{
+    cli,
+    command::{Flags, Scope, Source, VerbatimFlags},
+};
+use clap::Parser;
+use std::path::{Path, PathBuf};
+
+/// Call `f` with the given flags of a crate of the given scope.
+fn with_flags<R>(
+    flags: &[&str],
+    verbatim: &[&str],
+    scope: Scope,
+    f: impl FnOnce(Flags<'_>) -> R,
+) -> R {
+    let arguments = ["rruxwry", "test.rs"].iter().chain(flags);
+    let arguments = cli::Arguments::try_parse_from(arguments).unwrap();
+    f(Flags {
+        build: &arguments.build_flags,
+        verbatim: VerbatimFlags { arguments: verbatim, origins: &[], environment: &[] },
+        program: &arguments.program_flags,
+        scope,
+        source: Source::Written,
+        redirect_stderr: None,
+        capture_stderr: None,
+    })
+}
+
+/// Where the crate `dep` documented with the given flags writes its docs to.
+fn output_with(flags: &[&str], verbatim: &[&str], scope: Scope) -> Option<PathBuf> {
+    with_flags(flags, verbatim, scope, |flags| output(Path::new("doc"), "dep", flags))
+}
+
+#[test]
+fn outputs() {
+    assert_eq!(output_with(&[], &[], Scope::Dependency), Some("doc/dep".into()));
+    assert_eq!(output_with(&["--json"], &[], Scope::Dependency), Some("doc/dep.json".into()));
+    assert_eq!(
+        output_with(&["--json", "--stdout"], &[], Scope::Dependency),
+        Some("doc/dep.json".into())
+    );
+
+    // These don't write any docs (or we don't know where).
+    assert_eq!(output_with(&["--json", "--stdout"], &[], Scope::Root), None);
+    assert_eq!(output_with(&["--doctest"], &[], Scope::Root), None);
+    assert_eq!(output_with(&[], &["--out-dir=elsewhere"], Scope::Dependency), None);
+    assert_eq!(output_with(&[], &["-o", "elsewhere"], Scope::Root), None);
+}
+
+#[test]
+fn clobbered_paths() {
+    let output = |path: &str, source: &str| Output {
+        path: path.into(),
+        source: source.into(),
+        shown: source.into(),
+    };
+    let outputs = [
+        output("doc/dep", "auxiliary/dep.rs"),
+        output("doc/other", "auxiliary/other.rs"),
+        output("doc/dep", "vendor/dep.rs"),
+    ];
+    let sources = |clobbered: Vec<&Output>| -> Vec<_> {
+        clobbered.iter().map(|output| output.source.to_str().unwrap().to_owned()).collect()
+    };
+
+    let clobbering = output("doc/dep", "dep-0.2/dep.rs");
+    assert_eq!(sources(clobbered(&outputs, &clobbering).unwrap()), [
+        "auxiliary/dep.rs",
+        "vendor/dep.rs"
+    ]);
+    assert_eq!(clobbered(&outputs, &output("doc/new", "new.rs")).map(sources), Some(vec![]));
+    // Documenting the same crate again doesn't clobber anything.
+    assert!(clobbered(&outputs, &output("doc/dep", "vendor/dep.rs")).is_none());
+}
+
+#[test]
+fn isolated_roots() {
+    let root = with_flags(&[], &[], Scope::Dependency, |flags| isolated_root(flags, "dep", 1));
+    assert_eq!(root, Path::new("doc.isolated/dep-2"));
+    let root = with_flags(&["--out-dir", "out"], &[], Scope::Dependency, |flags| {
+        isolated_root(flags, "dep", 2)
+    });
+    assert_eq!(root, Path::new("out/doc.isolated/dep-3"));
+}
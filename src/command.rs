@@ -10,24 +10,31 @@
 //        as well as those passed via the `RUST{,DOC}FLAGS` env vars.
 
 use crate::{
-    cli,
-    data::{CrateName, CrateNameRef, CrateType, Edition},
-    diagnostic::info,
+    backend::Backend,
+    cli, clobber, config, coverage,
+    data::{CrateName, CrateNameCow, CrateNameRef, CrateType, Edition},
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info, warning},
     error::Result,
-    utility::default,
+    fix, ice, json, lints, timing,
+    utility::{default, doc_root, out_path, scratch_dir, strip_ansi_escapes},
 };
+use joinery::JoinableIterator;
 use owo_colors::OwoColorize;
 use std::{
     borrow::Cow,
-    ffi::OsStr,
     fmt,
+    io::{self, IsTerminal, Write},
     ops::{Deref, DerefMut},
-    path::Path,
+    path::{Path, PathBuf},
     process,
+    sync::Mutex,
 };
 
 mod environment;
 
+#[cfg(test)]
+mod test;
+
 pub(crate) fn compile(
     path: &Path,
     crate_name: CrateNameRef<'_>,
@@ -40,28 +47,139 @@ pub(crate) fn compile(
     let mut command = Command::new("rustc", flags.program, strictness);
 
     command.set_env_vars(flags.build);
+    command.set_log_filter("RUSTC_LOG", flags.build.rustc_log.as_ref(), flags.build);
     command.set_toolchain(flags.build);
 
-    command.arg(path);
+    command.set_path(path);
+
+    command.set_crate_type(crate_type, flags.verbatim);
+    command.set_crate_name(crate_name, path, flags.verbatim);
+    command.set_edition(edition, flags.verbatim);
+    command.set_target(crate_type, flags);
+    command.set_sysroot(crate_type, flags);
 
-    command.set_crate_type(crate_type);
-    command.set_crate_name(crate_name, path);
-    command.set_edition(edition);
+    command.set_extern_crates(extern_crates, flags.build);
 
-    command.set_extern_crates(extern_crates);
+    if let Some(directory) = &flags.build.out_dir {
+        command.origin(option("--out-dir"));
+        command.arg("--out-dir");
+        command.arg(directory);
+    }
 
     command.set_cfgs(flags.build);
     command.set_rustc_features(flags.build);
-    command.set_cap_lints(flags.build);
+    command.set_lints(flags);
+    command.set_diagnostic_format(flags.build);
     command.set_internals_mode(flags.build);
+    command.set_reproducible(flags.build)?;
+
+    command.set_verbatim_flags(flags.verbatim, path);
+
+    if let Some(flags) = environment::rustc_flags(flags.verbatim.environment) {
+        command.origin("the environment variable `RUSTFLAGS`");
+        command.args(&*flags);
+    }
+
+    command.set_json_diagnostics(path, flags);
+
+    let output =
+        timing::time(format!("compiling `{crate_name}`"), || command.execute(Capture::Nothing))?;
+
+    apply_suggestions(path, &output, flags)?;
+    output.status.exit_ok()?;
+
+    Ok(())
+}
+
+/// Compile the given crate of the standard library into the given sysroot (`--build-sysroot`).
+pub(crate) fn compile_sysroot_crate(
+    path: &Path,
+    crate_name: CrateNameRef<'_>,
+    edition: Edition,
+    sysroot: &Path,
+    output: &Path,
+    build_flags: &cli::BuildFlags,
+    program_flags: &cli::ProgramFlags,
+) -> Result {
+    let mut command = Command::new("rustc", program_flags, Strictness::Lenient);
+
+    command.set_env_vars(build_flags);
+    command.set_toolchain(build_flags);
+
+    command.set_path(path);
+
+    command.origin(option("--build-sysroot"));
+    command.arg("--crate-type=lib");
+    command.arg("--crate-name");
+    command.arg(crate_name.as_str());
+    command.arg(format!("--edition={}", edition.to_str()));
+    command.arg("--sysroot");
+    command.arg(sysroot);
+    command.arg("-Zforce-unstable-if-unmarked");
+    command.arg("-o");
+    command.arg(output);
+    // The standard library uses unstable features which is fine on any channel.
+    command.env("RUSTC_BOOTSTRAP", "1");
+
+    if let Some(target) = &build_flags.target {
+        command.origin(option("--target"));
+        command.arg("--target");
+        command.arg(target);
+    }
+
+    let output =
+        timing::time(format!("compiling `{crate_name}`"), || command.execute(Capture::Nothing))?;
+    output.status.exit_ok()?;
 
-    command.set_verbatim_flags(flags.verbatim);
+    Ok(())
+}
+
+/// Compile the given crate with `-Zprint-type-sizes` and return the report.
+///
+/// Only metadata gets emitted (into the temporary directory) to keep this cheap.
+pub(crate) fn print_type_sizes(
+    path: &Path,
+    crate_name: CrateNameRef<'_>,
+    crate_type: CrateType,
+    edition: Edition,
+    flags: Flags<'_>,
+) -> Result<String> {
+    let mut command = Command::new("rustc", flags.program, Strictness::Lenient);
 
-    if let Some(flags) = environment::rustc_flags() {
-        command.args(flags);
+    command.set_env_vars(flags.build);
+    command.set_toolchain(flags.build);
+
+    command.set_path(path);
+
+    command.set_crate_type(crate_type, flags.verbatim);
+    command.set_crate_name(crate_name, path, flags.verbatim);
+    command.set_edition(edition, flags.verbatim);
+    command.set_target(crate_type, flags);
+    command.set_sysroot(crate_type, flags);
+
+    command.set_cfgs(flags.build);
+    command.set_rustc_features(flags.build);
+    command.set_lints(flags);
+
+    command.set_verbatim_flags(flags.verbatim, path);
+
+    if let Some(flags) = environment::rustc_flags(flags.verbatim.environment) {
+        command.origin("the environment variable `RUSTFLAGS`");
+        command.args(&*flags);
     }
 
-    command.execute()
+    command.origin("implied by the type size report");
+    command.arg("-Zprint-type-sizes");
+    let metadata =
+        scratch_dir(flags.program)?.join(format!("rruxwry-{crate_name}-type-sizes.rmeta"));
+    command.arg(format!("--emit=metadata={}", metadata.display()));
+
+    let output = timing::time(format!("computing the type sizes of `{crate_name}`"), || {
+        command.execute(Capture::Stdout)
+    })?;
+    output.status.exit_ok()?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
 }
 
 pub(crate) fn document(
@@ -76,70 +194,407 @@ pub(crate) fn document(
     let mut command = Command::new("rustdoc", flags.program, strictness);
 
     command.set_env_vars(flags.build);
+    command.set_log_filter("RUSTDOC_LOG", flags.build.rustdoc_log.as_ref(), flags.build);
+    if let Some(path) = flags.redirect_stderr {
+        command.log_file = Some(path);
+    }
+    command.stderr_sink = flags.capture_stderr;
     command.set_toolchain(flags.build);
 
-    command.arg(path.as_os_str());
+    command.set_path(path);
 
-    command.set_crate_name(crate_name, path);
-    command.set_crate_type(crate_type);
-    command.set_edition(edition);
+    command.set_crate_name(crate_name, path, flags.verbatim);
+    command.set_crate_type(crate_type, flags.verbatim);
+    command.set_edition(edition, flags.verbatim);
+    command.set_target(crate_type, flags);
+    command.set_sysroot(crate_type, flags);
 
-    command.set_extern_crates(extern_crates);
+    command.set_extern_crates(extern_crates, flags.build);
 
     if flags.build.json {
+        command.origin(option("--json"));
         command.arg("--output-format");
         command.arg("json");
         command.uses_unstable_options = true;
+        // Like `compiletest` does for `tests/rustdoc-json/`.
+        if let Strictness::Strict = command.strictness {
+            command.arg("-Zunstable-options");
+        }
+    }
+
+    // All output formats besides HTML are unstable.
+    if let Some(output_format) = &flags.build.output_format {
+        command.origin(option("--output-format"));
+        command.arg("--output-format");
+        command.arg(output_format);
+        command.uses_unstable_options = true;
     }
 
-    if flags.build.private {
+    if flags.build.doctest {
+        command.origin(option("--doctest"));
+        command.arg("--test");
+
+        if !flags.build.test_args.is_empty() {
+            command.origin(option("--test-arg"));
+            for argument in &flags.build.test_args {
+                command.arg("--test-args");
+                command.arg(argument);
+            }
+        }
+
+        if flags.build.nocapture {
+            command.origin(option("--nocapture"));
+            command.arg("--nocapture");
+            command.uses_unstable_options = true;
+        }
+    }
+
+    if flags.build.coverage {
+        command.origin(option("--coverage"));
+        command.arg("--show-coverage");
+        command.set_unstable_gate("--show-coverage", flags.verbatim);
+    }
+
+    let write_to_stdout = flags.build.stdout && matches!(flags.scope, Scope::Root);
+
+    if write_to_stdout {
+        command.origin(option("--stdout"));
+        command.arg("-o");
+        command.arg("-");
+    } else if let Some(isolated) = clobber::record(crate_name, path, flags) {
+        command.origin(option("--isolate-clobbered"));
+        command.arg("-o");
+        command.arg(isolated);
+    } else if flags.build.out_dir.is_some() {
+        command.origin(option("--out-dir"));
+        command.arg("-o");
+        command.arg(out_path(flags.build, "doc"));
+    }
+
+    // Rustdoc accepts duplicates but let verbatim flags take precedence for consistency.
+    if flags.build.private && !flags.verbatim.contains_flag("--document-private-items") {
+        command.origin(option("--private"));
         command.arg("--document-private-items");
     }
 
-    if flags.build.hidden {
+    let verbatim_hidden = flags.verbatim.contains_flag("--document-hidden-items");
+    if flags.build.hidden && !verbatim_hidden {
+        command.origin(option("--hidden"));
         command.arg("--document-hidden-items");
-        command.uses_unstable_options = true;
+    }
+
+    if flags.build.hidden || verbatim_hidden {
+        command.set_unstable_gate("--document-hidden-items", flags.verbatim);
     }
 
     if flags.build.layout {
+        command.origin(option("--layout"));
         command.arg("--show-type-layout");
         command.uses_unstable_options = true;
     }
 
     if flags.build.link_to_definition {
+        command.origin(option("--link-to-definition"));
         command.arg("--generate-link-to-definition");
         command.uses_unstable_options = true;
     }
 
     if flags.build.normalize {
+        command.origin(option("--normalize"));
         command.arg("-Znormalize-docs");
     }
 
-    if let Some(crate_version) = &flags.build.crate_version {
+    if let Some(threads) = flags.build.rustdoc_threads {
+        command.origin(option("--rustdoc-threads"));
+        command.arg(format!("-Zthreads={threads}"));
+    }
+
+    let crate_version = match flags.scope {
+        Scope::Root => flags.build.crate_version.as_ref(),
+        Scope::Dependency => flags
+            .build
+            .dep_crate_versions
+            .iter()
+            .rfind(|(name, _)| name.as_str() == crate_name.as_str())
+            .map(|(_, version)| version),
+    };
+    if let Some(crate_version) = crate_version {
+        command.origin(match flags.scope {
+            Scope::Root => option("--crate-version"),
+            Scope::Dependency => option("--dep-crate-version"),
+        });
         command.arg("--crate-version");
         command.arg(crate_version);
     }
 
+    command.origin(option("--theme"));
     command.arg("--default-theme");
     command.arg(&flags.build.theme);
 
     command.set_cfgs(flags.build);
     command.set_rustc_features(flags.build);
-    command.set_cap_lints(flags.build);
+    command.set_lints(flags);
+    command.set_diagnostic_format(flags.build);
     command.set_internals_mode(flags.build);
+    if command.set_reproducible(flags.build)? {
+        // Path remapping is unstable in rustdoc.
+        command.set_unstable_gate("--remap-path-prefix", flags.verbatim);
+    }
+
+    command.set_verbatim_flags(flags.verbatim, path);
+
+    if let Some(flags) = environment::rustdoc_flags(flags.verbatim.environment) {
+        command.origin("the environment variable `RUSTDOCFLAGS`");
+        command.args(&*flags);
+    }
+
+    command.set_json_diagnostics(path, flags);
+
+    // Filtered output always gets pretty-printed.
+    let pretty = write_to_stdout && (flags.program.pretty || !flags.program.json_filter.is_empty());
+    // The JSON coverage report gets rendered once all crates are documented.
+    let coverage = flags.build.coverage && flags.build.json && !write_to_stdout;
+    let capture = if pretty || coverage { Capture::Stdout } else { Capture::Nothing };
+
+    let activity = match (flags.build.doctest, flags.build.coverage) {
+        (true, _) => format!("running the doctests of `{crate_name}`"),
+        (_, true) => format!("computing the documentation coverage of `{crate_name}`"),
+        _ => format!("documenting `{crate_name}`"),
+    };
+    // The test harness exits with 101 if any test failed while compilation errors lead to 1.
+    let failed = |error: crate::error::Error| match error {
+        crate::error::Error::Process(status)
+            if flags.build.doctest && status.code() == Some(101) =>
+        {
+            Error::FailedDoctests(crate_name.as_str().to_owned()).into()
+        }
+        error => error,
+    };
+
+    // In doctest mode, the exit code of ICEs is ambiguous.
+    let rerun = match &flags.program.rerun_ices {
+        Some(variants) if !flags.build.doctest && command.log_file.is_none() => {
+            command.suppress_ice_output = true;
+            Some((command.duplicate(), variants))
+        }
+        _ => None,
+    };
 
-    command.set_verbatim_flags(flags.verbatim);
+    let output = timing::time(activity, || command.execute(capture)).map_err(failed)?;
 
-    if let Some(flags) = environment::rustdoc_flags() {
-        command.args(flags);
+    if let Some((command, variants)) = rerun
+        && output.status.code() == Some(ice::EXIT_CODE)
+    {
+        rerun_ice(&command, variants, crate_name)?;
     }
 
-    command.execute()
+    apply_suggestions(path, &output, flags)?;
+    output.status.exit_ok().map_err(|error| failed(error.into()))?;
+
+    if coverage && !flags.program.dry_run {
+        // FIXME: Report malformed JSON more gracefully.
+        let report: serde_json::Value =
+            serde_json::from_slice(&output.stdout).map_err(io::Error::from)?;
+        coverage::record(crate_name.as_str(), &report);
+    }
+
+    if pretty && !flags.program.dry_run {
+        timing::time("pretty-printing the JSON output", || {
+            // FIXME: Report malformed JSON more gracefully.
+            let mut output: serde_json::Value =
+                serde_json::from_slice(&output.stdout).map_err(io::Error::from)?;
+            json::filter::apply(&mut output, &flags.program.json_filter);
+            let stdout = io::stdout();
+            let colored = stdout.is_terminal();
+            json::print_pretty(&output, colored, &mut stdout.lock())
+        })?;
+    }
+
+    Ok(())
 }
 
-pub(crate) fn open(crate_name: CrateNameRef<'_>, flags: &cli::ProgramFlags) -> Result {
-    let path = std::env::current_dir()?.join("doc").join(crate_name.as_str()).join("index.html");
+/// Re-run the crashed command once per variant and record the resulting backtraces.
+fn rerun_ice(
+    command: &Command<'_>,
+    variants: &[ice::Variant],
+    crate_name: CrateNameRef<'_>,
+) -> Result {
+    for &variant in variants {
+        info(format!("`rustdoc` crashed on `{crate_name}`, re-running it with {variant}")).emit();
+
+        let mut command = command.duplicate();
+        command.suppress_ice_output = false;
+        command.capture_stderr = true;
+        // The backtraces are not part of the diagnostics of the crate.
+        command.stderr_sink = None;
+        command.env("RUST_BACKTRACE", "full");
+        // Don't litter the working directory with further ICE reports.
+        command.env("RUSTC_ICE", "0");
+        if let Some(flag) = variant.flag() {
+            command.origin(option("--rerun-ices"));
+            command.arg(flag);
+        }
 
+        let output =
+            timing::time(format!("re-running `rustdoc` on `{crate_name}` with {variant}"), || {
+                command.execute(Capture::Nothing)
+            })?;
+        ice::record(crate_name.as_str(), variant, &output.stderr);
+    }
+
+    Ok(())
+}
+
+fn apply_suggestions(path: &Path, output: &Output, flags: Flags<'_>) -> Result {
+    // We might have only requested the diagnostics for the lint summary.
+    let synthesized = matches!(flags.source, Source::Synthesized);
+    let requested = flags.program.apply_suggestions && matches!(flags.scope, Scope::Root);
+    if output.diagnostics.is_empty() || !(requested || synthesized) {
+        return Ok(());
+    }
+
+    timing::time("applying suggestions", || {
+        fix::apply_suggestions(path, &output.diagnostics, flags.program)
+    })?;
+
+    Ok(())
+}
+
+/// The path to the entry point of the generated docs of the given crate.
+pub(crate) fn entry_point(
+    crate_name: CrateNameRef<'_>,
+    flags: &cli::BuildFlags,
+) -> io::Result<PathBuf> {
+    let root = doc_root(flags)?;
+
+    Ok(match flags.json {
+        true => root.join(format!("{crate_name}.json")),
+        // We don't know where other output formats put their artifacts (if anywhere).
+        false if flags.output_format.is_some() => root,
+        false => root.join(crate_name.as_str()).join("index.html"),
+    })
+}
+
+/// Resolve the target of `--open` to a path and optionally an anchor on the page.
+///
+/// The target is either the name of one of the documented crates, a page relative to the
+/// documentation root, e.g., `krate/struct.Type.html`, or the path of an item, e.g.,
+/// `krate::Type` or `krate::Type::method`. It defaults to the root crate.
+pub(crate) fn resolve_open_target(
+    target: Option<&str>,
+    documented: &[CrateNameCow<'_>],
+    flags: &cli::BuildFlags,
+) -> Result<(PathBuf, Option<String>)> {
+    let Some(target) = target else {
+        return Ok((entry_point(documented[0].as_ref(), flags)?, None));
+    };
+
+    if let Some(crate_name) = documented.iter().find(|crate_name| crate_name.as_str() == target) {
+        return Ok((entry_point(crate_name.as_ref(), flags)?, None));
+    }
+
+    if target.contains('/') || target.ends_with(".html") || target.ends_with(".json") {
+        return Ok((doc_root(flags)?.join(target), None));
+    }
+
+    if target.contains("::") {
+        return resolve_item_path(&doc_root(flags)?, target)?
+            .ok_or_else(|| Error::UnknownItem(target.to_owned()).into());
+    }
+
+    let error = Error::UnknownOpenTarget {
+        unknown: target.to_owned(),
+        available: documented.iter().map(ToString::to_string).collect(),
+    };
+    Err(error.into())
+}
+
+/// Find the page of the item at the given path among the generated pages.
+///
+/// Associated items, variants and fields are located via their anchor on the page of their
+/// parent. Pages of re-exports that merely redirect elsewhere are only picked as a last resort.
+fn resolve_item_path(root: &Path, path: &str) -> io::Result<Option<(PathBuf, Option<String>)>> {
+    let segments: Vec<_> = path.split("::").collect();
+
+    if let Some(page) = item_page(root, &segments)? {
+        return Ok(Some((page, None)));
+    }
+
+    let [parent @ .., name] = &segments[..] else { return Ok(None) };
+    // The parent has to be an item itself, not the crate.
+    if parent.len() < 2 {
+        return Ok(None);
+    }
+    let Some(page) = item_page(root, parent)? else { return Ok(None) };
+    let html = std::fs::read_to_string(&page)?;
+    Ok(find_anchor(&html, name).map(|anchor| (page, Some(anchor))))
+}
+
+/// The page of the module or item at the given path, e.g., `krate/module/struct.Type.html`.
+fn item_page(root: &Path, segments: &[&str]) -> io::Result<Option<PathBuf>> {
+    let [modules @ .., name] = segments else { return Ok(None) };
+    let directory =
+        modules.iter().fold(root.to_owned(), |directory, module| directory.join(module));
+
+    let module = directory.join(name).join("index.html");
+    if module.exists() {
+        return Ok(Some(module));
+    }
+
+    let Ok(entries) = std::fs::read_dir(&directory) else { return Ok(None) };
+    let mut candidates = Vec::new();
+    for entry in entries {
+        let page = entry?.path();
+        let Some(file_name) = page.file_name().and_then(|name| name.to_str()) else { continue };
+        // The pages of items are named `⟨KIND⟩.⟨NAME⟩.html`.
+        if file_name
+            .strip_suffix(".html")
+            .and_then(|stem| stem.split_once('.'))
+            .is_some_and(|(_, item)| item == *name)
+        {
+            candidates.push((crate::consistency::is_redirect(&page)?, page));
+        }
+    }
+
+    candidates.sort();
+    if let Some((_, page)) = candidates.into_iter().next() {
+        return Ok(Some(page));
+    }
+
+    // Re-exports that don't get inlined are merely listed on the page of the module.
+    let Ok(index) = std::fs::read_to_string(directory.join("index.html")) else { return Ok(None) };
+    Ok(reexport_target(&index, name)
+        .map(|target| directory.join(target))
+        .filter(|page| page.exists()))
+}
+
+/// The link target of the re-export of the given name listed on the page of a module.
+fn reexport_target<'a>(html: &'a str, name: &str) -> Option<&'a str> {
+    let (_, reexport) = html.split_once(&format!("id=\"reexport.{name}\""))?;
+    let (reexport, _) = reexport.split_once("</code>")?;
+    // The last link refers to the re-exported item itself, the ones before to its parents.
+    let (_, target) = reexport.rsplit_once("href=\"")?;
+    let (target, _) = target.split_once('"')?;
+    (!target.contains("://")).then_some(target)
+}
+
+/// The anchor of the associated item, variant or field of the given name on the page.
+fn find_anchor(html: &str, name: &str) -> Option<String> {
+    ["method", "tymethod", "associatedtype", "associatedconstant", "variant", "structfield"]
+        .into_iter()
+        .map(|kind| format!("{kind}.{name}"))
+        .find(|anchor| html.contains(&format!("id=\"{anchor}\"")))
+}
+
+/// Open the given page in a browser, scrolled to the given anchor if any.
+pub(crate) fn open_at(page: &Path, anchor: Option<&str>, flags: &cli::ProgramFlags) -> Result {
+    let Some(anchor) = anchor else { return open(page, flags) };
+    // Browsers only honor the anchor if they're given a URL instead of a path.
+    let page = std::path::absolute(page)?;
+    open(Path::new(&format!("file://{}#{anchor}", page.display())), flags)
+}
+
+pub(crate) fn open(path: &Path, flags: &cli::ProgramFlags) -> Result {
     if flags.verbose {
         let verb = match flags.dry_run {
             false => "running",
@@ -163,34 +618,209 @@ pub(crate) fn open(crate_name: CrateNameRef<'_>, flags: &cli::ProgramFlags) -> R
 
 struct Command<'a> {
     command: process::Command,
+    /// The name of the wrapped program, e.g., `rustdoc`.
+    program: &'static str,
+    /// The first program wrapping `program` if any, e.g., `nice`.
+    wrapper: Option<&'static str>,
     flags: &'a cli::ProgramFlags,
     strictness: Strictness,
     uses_unstable_options: bool,
+    log_file: Option<&'a Path>,
+    json_diagnostics: bool,
+    /// Capture stderr even if we don't need to process it.
+    capture_stderr: bool,
+    /// Don't print the captured stderr if the program crashes since it gets re-run anyway.
+    suppress_ice_output: bool,
+    /// Where to record the rendered stderr for further processing by the caller.
+    stderr_sink: Option<&'a Mutex<String>>,
+    /// The path to the source file if it was synthesized by us.
+    synthesized: Option<PathBuf>,
+    /// Where the arguments came from as the index of the first argument of each group alongside
+    /// a description of its origin.
+    origins: Vec<(usize, String)>,
 }
 
 impl<'a> Command<'a> {
-    fn new(
-        program: impl AsRef<OsStr>,
-        flags: &'a cli::ProgramFlags,
-        strictness: Strictness,
-    ) -> Self {
+    fn new(program: &'static str, flags: &'a cli::ProgramFlags, strictness: Strictness) -> Self {
+        let mut wrappers = Vec::new();
+
+        // The transient scope has to come first, so it covers all processes.
+        if let Some(limit) = flags.memory_limit {
+            wrappers.extend(
+                ["systemd-run", "--user", "--scope", "--quiet", "--collect"].map(String::from),
+            );
+            wrappers.extend(["-p".into(), format!("MemoryMax={limit}")]);
+            wrappers.extend(["-p", "MemorySwapMax=0"].map(String::from));
+        }
+        if let Some(niceness) = flags.nice {
+            wrappers.extend(["nice".into(), "-n".into(), niceness.to_string()]);
+        }
+        if flags.idle_io {
+            wrappers.extend(["ionice", "-c", "3"].map(String::from));
+        }
+
+        let wrapper = ["systemd-run", "nice", "ionice"]
+            .into_iter()
+            .find(|&wrapper| wrappers.first().is_some_and(|first| first == wrapper));
+        let origins = match wrapper {
+            Some(_) => vec![(0, "the options `--memory-limit`, `--nice` and `--idle-io`".into())],
+            None => Vec::new(),
+        };
+        let command = match wrappers.split_first() {
+            Some((wrapper, arguments)) => {
+                let mut command = process::Command::new(wrapper);
+                command.args(arguments).arg(program);
+                command
+            }
+            None => process::Command::new(program),
+        };
+
         Self {
-            command: process::Command::new(program),
+            command,
+            program,
+            wrapper,
             flags,
             strictness,
             uses_unstable_options: false,
+            log_file: None,
+            json_diagnostics: false,
+            capture_stderr: false,
+            suppress_ice_output: false,
+            stderr_sink: None,
+            synthesized: None,
+            origins,
         }
     }
 
-    fn execute(mut self) -> Result {
+    /// A copy of the command that can be executed separately.
+    fn duplicate(&self) -> Self {
+        let mut command = process::Command::new(self.command.get_program());
+        command.args(self.command.get_args());
+        for (key, value) in self.command.get_envs() {
+            match value {
+                Some(value) => command.env(key, value),
+                None => command.env_remove(key),
+            };
+        }
+        if let Some(directory) = self.command.get_current_dir() {
+            command.current_dir(directory);
+        }
+
+        Self {
+            command,
+            program: self.program,
+            wrapper: self.wrapper,
+            flags: self.flags,
+            strictness: self.strictness,
+            uses_unstable_options: self.uses_unstable_options,
+            log_file: self.log_file,
+            json_diagnostics: self.json_diagnostics,
+            capture_stderr: self.capture_stderr,
+            suppress_ice_output: self.suppress_ice_output,
+            stderr_sink: self.stderr_sink,
+            synthesized: self.synthesized.clone(),
+            origins: self.origins.clone(),
+        }
+    }
+
+    /// Attribute the arguments added next to the given origin (in verbose mode).
+    fn origin(&mut self, origin: impl Into<String>) {
+        self.origins.push((self.get_args().len(), origin.into()));
+    }
+
+    /// Execute the command and return the captured output if requested.
+    fn execute(mut self, capture: Capture) -> Result<Output> {
         self.set_unstable_options();
 
+        let json_diagnostics = self.json_diagnostics && self.log_file.is_none();
+        let capture_stderr = (self.flags.deny_warnings
+            || json_diagnostics
+            || self.capture_stderr
+            || self.suppress_ice_output
+            || self.stderr_sink.is_some())
+            && self.log_file.is_none();
+        // The recorded stderr gets compared against plain text, so it mustn't contain any colors.
+        let colored = io::stderr().is_terminal() && self.stderr_sink.is_none();
+
+        if json_diagnostics {
+            self.origin("implied by rruxwry for processing the diagnostics");
+            self.arg("--error-format=json");
+            if colored {
+                self.arg("--json=diagnostic-rendered-ansi");
+            }
+        // Preserve the colors even though stderr isn't a terminal from the perspective of the child.
+        } else if capture_stderr && colored && !self.has_arg("--color") {
+            self.origin("implied by rruxwry for preserving the colors");
+            self.arg("--color=always");
+        }
+
         self.print(); // FIXME partially inline this
-        if !self.flags.dry_run {
-            self.status()?.exit_ok()?;
+        if self.flags.dry_run {
+            return Ok(default());
         }
 
-        Ok(())
+        let capture_stdout = matches!(capture, Capture::Stdout);
+
+        let stderr = match self.log_file {
+            Some(path) => std::fs::File::options().create(true).append(true).open(path)?.into(),
+            None if capture_stderr => process::Stdio::piped(),
+            None => process::Stdio::inherit(),
+        };
+
+        let backend = Backend::new(self.flags);
+        // The backend wraps the wrappers, so it's the outermost program.
+        let wrapper = backend.program().or(self.wrapper);
+        let missing = |error: io::Error| -> crate::error::Error {
+            match (error.kind(), wrapper) {
+                (io::ErrorKind::NotFound, Some(wrapper)) => Error::MissingWrapper(wrapper).into(),
+                _ => error.into(),
+            }
+        };
+
+        if !capture_stdout && !capture_stderr {
+            backend
+                .run(&mut self.command, |command| command.stderr(stderr).status())
+                .map_err(missing)?
+                .exit_ok()?;
+            return Ok(default());
+        }
+
+        let stdout =
+            if capture_stdout { process::Stdio::piped() } else { process::Stdio::inherit() };
+        let output = backend
+            .run(&mut self.command, |command| command.stderr(stderr).stdout(stdout).output())
+            .map_err(missing)?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        let (rendered, diagnostics) = match json_diagnostics {
+            true => parse_json_diagnostics(&stderr, self.synthesized.as_deref()),
+            false => (stderr.into_owned(), Vec::new()),
+        };
+
+        if self.flags.lint_summary.is_some() {
+            lints::record(&diagnostics);
+        }
+
+        if !(self.suppress_ice_output && output.status.code() == Some(ice::EXIT_CODE)) {
+            io::stderr().write_all(rendered.as_bytes())?;
+        }
+
+        if let Some(sink) = self.stderr_sink {
+            sink.lock().unwrap().push_str(&rendered);
+        }
+
+        if output.status.success() && self.flags.deny_warnings {
+            let warnings = match json_diagnostics {
+                true => count_warnings(&diagnostics),
+                false => count_rendered_warnings(&rendered),
+            };
+            if warnings != 0 {
+                let program = self.program.to_owned();
+                return Err(Error::DeniedWarnings { program, warnings }.into());
+            }
+        }
+
+        Ok(Output { status: output.status, stdout: output.stdout, stderr: rendered, diagnostics })
     }
 
     fn print(&self) {
@@ -203,88 +833,209 @@ impl<'a> Command<'a> {
         message += " ";
         self.render_into(&mut message).unwrap();
 
-        info(message).emit();
+        if let Some(path) = self.log_file {
+            message += &format!(" {}", format!("2>> {}", path.display()).color(palette::ARGUMENT));
+        }
+
+        let location = Backend::new(self.flags).describe();
+        self.provenance().into_iter().chain(location).fold(info(message), Diagnostic::note).emit();
+    }
+
+    /// The arguments grouped by their origin.
+    fn provenance(&self) -> Vec<String> {
+        let arguments: Vec<_> =
+            self.get_args().map(|argument| argument.to_string_lossy()).collect();
+        let mut groups: Vec<(&str, Vec<&str>)> = Vec::new();
+
+        for (index, (start, origin)) in self.origins.iter().enumerate() {
+            let end = self.origins.get(index + 1).map_or(arguments.len(), |&(end, _)| end);
+            if *start == end {
+                continue;
+            }
+            let group = arguments[*start..end].iter().map(|argument| &**argument);
+            match groups.last_mut() {
+                Some((previous, arguments)) if previous == origin => arguments.extend(group),
+                _ => groups.push((origin, group.collect())),
+            }
+        }
+
+        groups
+            .into_iter()
+            .filter(|(origin, _)| !origin.is_empty())
+            .map(|(origin, arguments)| format!("`{}`: {origin}", arguments.join(" ")))
+            .collect()
     }
 
     fn set_toolchain(&mut self, flags: &cli::BuildFlags) {
         if let Some(toolchain) = &flags.toolchain {
+            self.origin(option("--toolchain"));
             self.arg(format!("+{toolchain}"));
         }
     }
 
-    fn set_crate_name(&mut self, crate_name: CrateNameRef<'_>, path: &Path) {
+    fn set_path(&mut self, path: &Path) {
+        // The path is self-explanatory.
+        self.origin("");
+        self.arg(path);
+    }
+
+    // NB: In the `set_crate_*` and `set_edition` methods, we let verbatim flags take precedence
+    //     since rustc would reject duplicates (see also `warn_about_confusable_verbatim_flags`).
+
+    fn set_crate_name(
+        &mut self,
+        crate_name: CrateNameRef<'_>,
+        path: &Path,
+        verbatim: VerbatimFlags<'_>,
+    ) {
+        if verbatim.contains_flag("--crate-name") {
+            return;
+        }
         if let Ok(fiducial_crate_name) = CrateName::adjust_and_parse_file_path(path)
             && crate_name == fiducial_crate_name.as_ref()
         {
             return;
         }
 
+        self.origin(inferred("--crate-name"));
         self.arg("--crate-name");
         self.arg(crate_name.as_str());
     }
 
-    fn set_crate_type(&mut self, crate_type: CrateType) {
-        if crate_type == default() {
+    fn set_crate_type(&mut self, crate_type: CrateType, verbatim: VerbatimFlags<'_>) {
+        if crate_type == default() || verbatim.contains_flag("--crate-type") {
             return;
         }
 
+        self.origin(inferred("--crate-type"));
         self.arg("--crate-type");
         self.arg(crate_type.to_str());
     }
 
-    fn set_edition(&mut self, edition: Edition) {
-        if edition == default() {
+    fn set_edition(&mut self, edition: Edition, verbatim: VerbatimFlags<'_>) {
+        if edition == default() || verbatim.contains_flag("--edition") {
             return;
         }
         if !edition.is_stable() {
             self.uses_unstable_options = true;
         }
 
+        self.origin(inferred("--edition"));
         self.arg("--edition");
         self.arg(edition.to_str());
     }
 
-    fn set_extern_crates(&mut self, extern_crates: &[ExternCrate<'_>]) {
+    fn set_target(&mut self, crate_type: CrateType, flags: Flags<'_>) {
+        // Like Cargo, build proc macros for the host.
+        if crate_type == CrateType::ProcMacro || flags.verbatim.contains_flag("--target") {
+            return;
+        }
+        if let Some(target) = &flags.build.target {
+            self.origin(option("--target"));
+            self.arg("--target");
+            self.arg(target);
+        }
+    }
+
+    fn set_sysroot(&mut self, crate_type: CrateType, flags: Flags<'_>) {
+        // Proc macros need the `proc_macro` crate of the host.
+        if crate_type == CrateType::ProcMacro || flags.verbatim.contains_flag("--sysroot") {
+            return;
+        }
+        if let Some(sysroot) = &flags.build.sysroot {
+            self.origin(match flags.build.build_sysroot {
+                Some(_) => option("--build-sysroot"),
+                None => option("--sysroot"),
+            });
+            self.arg("--sysroot");
+            self.arg(sysroot);
+        }
+    }
+
+    fn set_extern_crates(&mut self, extern_crates: &[ExternCrate<'_>], flags: &cli::BuildFlags) {
         // FIXME: should we skip this if Strictness::Strict?
         // What does `compiletest` do?
         if !extern_crates.is_empty() {
+            let directory = flags.out_dir.as_deref().unwrap_or(Path::new("."));
+            self.origin("the auxiliary crates");
             // FIXME: Does this work with proc macro deps? I think so?
-            self.arg("-Lcrate=.");
+            self.arg(format!("-Lcrate={}", directory.display()));
+            // For the dependencies of nested auxiliary crates.
+            self.arg(format!("-Ldependency={}", directory.display()));
         }
 
         for extern_crate in extern_crates {
-            let ExternCrate::Named { name, path } = extern_crate else {
-                continue;
-            };
-
-            self.arg("--extern");
-            match path {
-                Some(path) => self.arg(format!("{name}={path}")),
-                None => self.arg(name.as_str()),
-            };
+            match extern_crate {
+                ExternCrate::Unnamed { .. } => {}
+                ExternCrate::Named { name, path, .. } => {
+                    self.arg("--extern");
+                    match path {
+                        Some(path) => self.arg(format!("{name}={path}")),
+                        None => self.arg(name.as_str()),
+                    };
+                }
+                // The library gets picked up from the search path.
+                ExternCrate::ProcMacro { path } => {
+                    if let Ok(name) = CrateName::adjust_and_parse_file_path(Path::new(path)) {
+                        self.arg("--extern");
+                        self.arg(name.as_str());
+                    }
+                }
+            }
         }
     }
 
     fn set_internals_mode(&mut self, flags: &cli::BuildFlags) {
         if flags.rustc_verbose_internals {
+            self.origin(option("--internals"));
             self.arg("-Zverbose-internals");
         }
     }
 
-    fn set_env_vars(&mut self, flags: &cli::BuildFlags) {
-        if flags.log {
-            self.env("RUSTC_LOG", "debug");
+    /// Remap the current directory and fix the timestamps if requested, returning whether we did.
+    fn set_reproducible(&mut self, flags: &cli::BuildFlags) -> Result<bool> {
+        if !flags.reproducible {
+            return Ok(false);
         }
-        if flags.no_backtrace {
-            self.env("RUST_BACKTRACE", "0");
+
+        self.origin(option("--reproducible"));
+        let directory = std::env::current_dir()?;
+        self.arg(format!("--remap-path-prefix={}=.", directory.display()));
+        // Respect the epoch of the caller, e.g., of a distro build.
+        if std::env::var_os("SOURCE_DATE_EPOCH").is_none() {
+            self.env("SOURCE_DATE_EPOCH", "0");
+        }
+        Ok(true)
+    }
+
+    fn set_env_vars(&mut self, flags: &cli::BuildFlags) {
+        if let Some(backtrace) = flags.backtrace {
+            self.env("RUST_BACKTRACE", backtrace.to_str());
         }
     }
 
+    /// Set the logging filter, preferring the program-specific `filter` over the general one.
+    fn set_log_filter(
+        &mut self,
+        key: &str,
+        filter: Option<&'a String>,
+        flags: &'a cli::BuildFlags,
+    ) {
+        let Some(filter) = filter.or(flags.log.as_ref()) else {
+            return;
+        };
+
+        self.env(key, filter);
+        self.log_file = flags.log_file.as_deref();
+    }
+
     fn set_cfgs(&mut self, flags: &cli::BuildFlags) {
+        self.origin(option("--cfg"));
         for cfg in &flags.cfgs {
             self.arg("--cfg");
             self.arg(cfg);
         }
+        self.origin(option("--cargo-feature"));
         for feature in &flags.cargo_features {
             // FIXME: Warn on conflicts with `cfgs` from `self.arguments.cfgs`.
             self.arg("--cfg");
@@ -293,34 +1044,155 @@ impl<'a> Command<'a> {
     }
 
     fn set_rustc_features(&mut self, flags: &cli::BuildFlags) {
+        self.origin(option("--rustc-feature"));
         for feature in &flags.rustc_features {
             self.arg(format!("-Zcrate-attr=feature({feature})"));
         }
     }
 
-    fn set_cap_lints(&mut self, flags: &cli::BuildFlags) {
-        if let Some(level) = &flags.cap_lints {
+    fn set_lints(&mut self, flags: Flags<'_>) {
+        if let Scope::Root = flags.scope
+            && !flags.build.config_lints.is_empty()
+        {
+            self.origin(format!("the `lints` of `{}`", config::FILE_NAME));
+            for (name, level) in &flags.build.config_lints {
+                self.arg(format!("--{level}"));
+                self.arg(name);
+            }
+        }
+
+        // Later lint flags take precedence over earlier ones.
+        let lints = flags.build.lints.iter();
+        let (cap, lints) = match flags.scope {
+            Scope::Root => (flags.build.cap_lints.as_ref(), lints.chain(&[])),
+            Scope::Dependency => (
+                flags.build.cap_dep_lints.as_ref().or(flags.build.cap_lints.as_ref()),
+                lints.chain(&flags.build.dep_lints),
+            ),
+        };
+
+        self.origin(match flags.scope {
+            Scope::Root => option("--lint"),
+            Scope::Dependency => "the options `--lint` and `--dep-lint`".into(),
+        });
+        for (name, level) in lints {
+            self.arg(format!("--{level}"));
+            self.arg(name);
+        }
+
+        if let Some(level) = cap {
+            self.origin(match flags.scope {
+                Scope::Root => option("--cap-lints"),
+                Scope::Dependency => "the options `--cap-lints` and `--cap-dep-lints`".into(),
+            });
             self.arg("--cap-lints");
             self.arg(level);
         }
     }
 
+    fn set_diagnostic_format(&mut self, flags: &cli::BuildFlags) {
+        if let Some(format) = flags.error_format {
+            if !format.is_stable() {
+                self.uses_unstable_options = true;
+            }
+
+            self.origin(option("--error-format"));
+            self.arg("--error-format");
+            self.arg(format.to_str());
+        }
+
+        if !flags.diagnostic_json.is_empty() {
+            self.origin(option("--diagnostic-json"));
+            self.arg(format!("--json={}", flags.diagnostic_json.join(",")));
+        }
+    }
+
+    /// Request JSON diagnostics if we need to inspect machine-applicable suggestions or
+    /// if we need to rewrite spans that point into synthesized source files.
+    fn set_json_diagnostics(&mut self, path: &Path, flags: Flags<'_>) {
+        let apply_suggestions =
+            flags.program.apply_suggestions && matches!(flags.scope, Scope::Root);
+        // We can only rewrite diagnostics that are rendered in the default format.
+        let synthesized =
+            matches!(flags.source, Source::Synthesized) && flags.build.error_format.is_none();
+
+        if synthesized {
+            self.synthesized = Some(path.to_owned());
+        }
+
+        // `rust{c,doc}` reject the flag if it's passed more than once, so we can't override it.
+        if flags.verbatim.contains_flag("--error-format") {
+            let lint_summary = flags.program.lint_summary.is_some();
+            let ignored: Vec<_> = [
+                (apply_suggestions, "`--apply-suggestions`"),
+                (lint_summary && matches!(flags.scope, Scope::Root), "`--lint-summary`"),
+            ]
+            .into_iter()
+            .filter_map(|(ignored, option)| ignored.then_some(option))
+            .collect();
+
+            if !ignored.is_empty() {
+                let (s, verb) = if ignored.len() == 1 { ("", "has") } else { ("s", "have") };
+                warning("the verbatim flag `--error-format` prevents processing the diagnostics")
+                    .note(format!("the option{s} {} {verb} no effect", ignored.join(" and ")))
+                    .emit();
+            }
+            return;
+        }
+
+        // The warnings can only be counted reliably in the JSON output.
+        self.json_diagnostics = apply_suggestions
+            || synthesized
+            || flags.program.deny_warnings
+            || flags.program.lint_summary.is_some();
+    }
+
+    /// Enable the given unstable flag which requires `-Zunstable-options`.
+    ///
+    /// In strict mode, we'd normally leave it to the test to pass the gate but the flag doesn't
+    /// necessarily originate from the test.
+    fn set_unstable_gate(&mut self, flag: &str, verbatim: VerbatimFlags<'_>) {
+        match self.strictness {
+            Strictness::Lenient => self.uses_unstable_options = true,
+            Strictness::Strict if !verbatim.contains_unstable_options() => {
+                self.origin(format!("implied by `{flag}`"));
+                self.arg("-Zunstable-options");
+            }
+            Strictness::Strict => {}
+        }
+    }
+
     fn set_unstable_options(&mut self) {
         if let Strictness::Lenient = self.strictness
             && self.uses_unstable_options
         {
+            self.origin("implied by the unstable flags");
             self.arg("-Zunstable-options");
         }
     }
 
-    fn set_verbatim_flags(&mut self, flags: VerbatimFlags<'_>) {
+    /// Whether the given flag was already added, either on its own or with `=`.
+    fn has_arg(&self, flag: &str) -> bool {
+        self.get_args().any(|argument| {
+            argument
+                .to_str()
+                .and_then(|argument| argument.strip_prefix(flag))
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('='))
+        })
+    }
+
+    fn set_verbatim_flags(&mut self, flags: VerbatimFlags<'_>, path: &Path) {
         for (key, value) in flags.environment {
             match value {
                 Some(value) => self.env(key, value),
                 None => self.env_remove(key),
             };
         }
-        self.args(flags.arguments);
+        for (index, argument) in flags.arguments.iter().enumerate() {
+            let origin = flags.origins.get(index).copied().unwrap_or(Origin::CommandLine);
+            self.origin(origin.describe(path));
+            self.arg(argument);
+        }
     }
 }
 
@@ -338,6 +1210,160 @@ impl DerefMut for Command<'_> {
     }
 }
 
+/// Parse the JSON diagnostics emitted by `rust{c,doc}` and render them for human consumption.
+///
+/// Lines that aren't JSON diagnostics (e.g., the output of ICEs) get passed through verbatim.
+///
+/// Locations that point into the `synthesized` source file get replaced with a description of
+/// the generated construct since the user never wrote that file.
+fn parse_json_diagnostics(
+    stderr: &str,
+    synthesized: Option<&Path>,
+) -> (String, Vec<serde_json::Value>) {
+    let mut rendered = String::new();
+    let mut diagnostics = Vec::new();
+
+    for line in stderr.lines() {
+        match serde_json::from_str::<serde_json::Value>(line) {
+            Ok(diagnostic) if diagnostic.is_object() => {
+                if let Some(line) = diagnostic["rendered"].as_str() {
+                    match synthesized {
+                        Some(path) => {
+                            rendered += &describe_synthesized_spans(line, &diagnostic, path)
+                        }
+                        None => rendered += line,
+                    }
+                }
+                diagnostics.push(diagnostic);
+            }
+            _ => {
+                rendered += line;
+                rendered += "\n";
+            }
+        }
+    }
+
+    (rendered, diagnostics)
+}
+
+fn describe_synthesized_spans(
+    rendered: &str,
+    diagnostic: &serde_json::Value,
+    path: &Path,
+) -> String {
+    let mut rendered = rendered.to_owned();
+
+    for span in diagnostic["spans"].as_array().into_iter().flatten() {
+        let Some(file_name) = span["file_name"].as_str() else { continue };
+        if Path::new(file_name) != path {
+            continue;
+        }
+
+        let (Some(line), Some(column)) =
+            (span["line_start"].as_u64(), span["column_start"].as_u64())
+        else {
+            continue;
+        };
+
+        let description = match describe_synthesized_span(span) {
+            Some(construct) => format!("⟨generated `{construct}`⟩"),
+            None => "⟨generated wrapper crate⟩".into(),
+        };
+        rendered = rendered.replace(&format!("{file_name}:{line}:{column}"), &description);
+    }
+
+    for child in diagnostic["children"].as_array().into_iter().flatten() {
+        rendered = describe_synthesized_spans(&rendered, child, path);
+    }
+
+    rendered
+}
+
+/// Find the generated construct the given span points into.
+///
+/// Returns `None` if the span covers more than a single construct.
+fn describe_synthesized_span(span: &serde_json::Value) -> Option<&str> {
+    let text = &span["text"].as_array()?[..];
+    let [text] = text else { return None };
+    let line = text["text"].as_str()?;
+    let start = text["highlight_start"].as_u64()?.checked_sub(1)? as usize;
+    let end = text["highlight_end"].as_u64()?.checked_sub(1)? as usize;
+
+    let offset = |column| line.char_indices().nth(column).map_or(line.len(), |(index, _)| index);
+    let (start, end) = (offset(start), offset(end));
+
+    // The generated constructs are items which are all terminated by a semicolon.
+    let construct_start = line[..start].rfind(';').map_or(0, |index| index + 1);
+    let construct_end = line[start..].find(';').map_or(line.len(), |index| start + index);
+    if end > construct_end + 1 {
+        return None;
+    }
+
+    Some(line[construct_start..construct_end].trim()).filter(|construct| !construct.is_empty())
+}
+
+/// Count the warnings among the JSON diagnostics.
+fn count_warnings(diagnostics: &[serde_json::Value]) -> usize {
+    diagnostics
+        .iter()
+        .filter(|diagnostic| {
+            // Skip the summary, e.g., `2 warnings emitted`, which doesn't have a location.
+            let is_summary = diagnostic["spans"].as_array().is_none_or(Vec::is_empty)
+                && diagnostic["message"].as_str().is_some_and(|message| {
+                    message.ends_with(" warning emitted") || message.ends_with(" warnings emitted")
+                });
+            diagnostic["level"] == "warning" && !is_summary
+        })
+        .count()
+}
+
+/// Count the warnings in the (potentially colored) diagnostic output of an arbitrary format.
+///
+/// This is only used if the format was passed verbatim, so we couldn't request JSON diagnostics.
+fn count_rendered_warnings(stderr: &str) -> usize {
+    stderr
+        .lines()
+        .map(strip_ansi_escapes)
+        .filter(|line| {
+            if let Ok(diagnostic) = serde_json::from_str::<serde_json::Value>(line)
+                && diagnostic.is_object()
+            {
+                return count_warnings(std::slice::from_ref(&diagnostic)) != 0;
+            }
+            // The short format prefixes the level with the location, e.g., `w.rs:1:6: warning`.
+            let line = match line.split_once(": warning") {
+                Some((location, rest)) if is_location(location) => rest,
+                _ => match line.strip_prefix("warning") {
+                    Some(rest) => rest,
+                    None => return false,
+                },
+            };
+            // Skip the summary, e.g., `warning: 2 warnings emitted`.
+            let is_summary = line.strip_prefix(": ").is_some_and(|line| {
+                line.starts_with(|char: char| char.is_ascii_digit())
+                    && (line.ends_with(" warning emitted") || line.ends_with(" warnings emitted"))
+            });
+            (line.starts_with(": ") || line.starts_with('[')) && !is_summary
+        })
+        .count()
+}
+
+/// Whether the given string has the form `PATH:LINE:COLUMN`.
+fn is_location(location: &str) -> bool {
+    let mut parts = location.rsplitn(3, ':');
+    let numeric = parts.by_ref().take(2).filter(|part| part.parse::<u32>().is_ok()).count() == 2;
+    numeric && parts.next().is_some_and(|path| !path.is_empty())
+}
+
+fn option(name: &str) -> String {
+    format!("the option `{name}`")
+}
+
+/// The origin of flags that are either passed explicitly or inferred from the crate or directives.
+fn inferred(name: &str) -> String {
+    format!("the option `{name}` (or inferred)")
+}
+
 trait CommandExt {
     fn render_into(&self, buffer: &mut String) -> fmt::Result;
 }
@@ -347,9 +1373,21 @@ impl CommandExt for process::Command {
     fn render_into(&self, buffer: &mut String) -> fmt::Result {
         use std::fmt::Write;
 
+        // Print removed vars before added vars just like `Command`'s `Debug` impl.
+        let mut removed = self.get_envs().filter(|(_, value)| value.is_none()).peekable();
+        if removed.peek().is_some() {
+            write!(buffer, "{}", "env".color(palette::COMMAND).bold())?;
+            for (key, _) in removed {
+                write!(
+                    buffer,
+                    " {}",
+                    format!("-u {}", key.to_string_lossy()).color(palette::VARIABLE)
+                )?;
+            }
+            write!(buffer, " ")?;
+        }
+
         for (key, value) in self.get_envs() {
-            // FIXME: Print `env -u VAR` for removed vars before
-            // added vars just like `Command`'s `Debug` impl.
             let Some(value) = value else { continue };
 
             write!(
@@ -381,8 +1419,20 @@ mod palette {
 
 #[derive(Clone)]
 pub(crate) enum ExternCrate<'src> {
-    Unnamed { path: &'src str },
-    Named { name: CrateNameRef<'src>, path: Option<Cow<'src, str>> },
+    Unnamed {
+        path: &'src str,
+    },
+    /// If there's a version, the crate gets built under the given name (instead of the one
+    /// derived from the path) with distinct metadata, so several versions can coexist.
+    Named {
+        name: CrateNameRef<'src>,
+        version: Option<&'src str>,
+        path: Option<Cow<'src, str>>,
+    },
+    /// A proc macro (`//@ proc-macro`) which is passed under the name derived from its path.
+    ProcMacro {
+        path: &'src str,
+    },
 }
 
 #[derive(Clone, Copy)]
@@ -390,32 +1440,214 @@ pub(crate) struct Flags<'a> {
     pub(crate) build: &'a cli::BuildFlags,
     pub(crate) verbatim: VerbatimFlags<'a>,
     pub(crate) program: &'a cli::ProgramFlags,
+    pub(crate) scope: Scope,
+    pub(crate) source: Source,
+    /// Append the stderr of `rustdoc` to the given file, taking precedence over `--log-file`.
+    pub(crate) redirect_stderr: Option<&'a Path>,
+    /// Record the rendered stderr of `rustdoc` in the given buffer in addition to printing it.
+    pub(crate) capture_stderr: Option<&'a Mutex<String>>,
+}
+
+/// Whether the crate is the one the user is interested in or merely a dependency of it.
+#[derive(Clone, Copy)]
+pub(crate) enum Scope {
+    Root,
+    Dependency,
+}
+
+/// Whether the source file was written by the user or synthesized by us.
+#[derive(Clone, Copy)]
+pub(crate) enum Source {
+    Written,
+    Synthesized,
 }
 
 #[derive(Clone, Copy)]
 pub(crate) struct VerbatimFlags<'a> {
     pub(crate) arguments: &'a [&'a str],
+    /// The origin of each argument.
+    pub(crate) origins: &'a [Origin],
     pub(crate) environment: &'a [(&'a str, Option<&'a str>)],
 }
 
+/// Where a verbatim flag came from, for `--verbose`.
+#[derive(Clone, Copy)]
+pub(crate) enum Origin {
+    /// Passed after `--`.
+    CommandLine,
+    /// A `compile-flags` directive on the given line of the source file.
+    Directive { line: usize },
+    /// Obtained from Cargo, e.g., for locating the dependencies.
+    Cargo,
+    /// Passed via `--aux-arg`.
+    AuxArg,
+    /// The defaults of the configuration files.
+    Config,
+    /// Added by us to implement the given feature.
+    Implied(&'static str),
+}
+
+impl Origin {
+    fn describe(self, path: &Path) -> String {
+        match self {
+            Self::CommandLine => "the verbatim flags".into(),
+            Self::Directive { line } => format!("the directive at `{}:{line}`", path.display()),
+            Self::Cargo => "the Cargo package".into(),
+            Self::AuxArg => option("--aux-arg"),
+            Self::Config => "the configuration files".into(),
+            Self::Implied(feature) => format!("implied by {feature}"),
+        }
+    }
+}
+
+impl<'a> VerbatimFlags<'a> {
+    /// The value of the last occurrence of the given (long) flag, e.g., `--target`.
+    pub(crate) fn value_of(&self, flag: &str) -> Option<&'a str> {
+        let arguments = self.arguments;
+        arguments.iter().enumerate().rev().find_map(|(index, argument)| {
+            let rest = argument.strip_prefix(flag)?;
+            match rest.strip_prefix('=') {
+                Some(value) => Some(value),
+                None if rest.is_empty() => arguments.get(index + 1).copied(),
+                None => None,
+            }
+        })
+    }
+
+    /// Whether the verbatim arguments contain `-Zunstable-options` in any of its spellings.
+    fn contains_unstable_options(&self) -> bool {
+        self.arguments.iter().enumerate().any(|(index, argument)| match *argument {
+            "-Zunstable-options" => true,
+            "-Z" => self.arguments.get(index + 1) == Some(&"unstable-options"),
+            _ => false,
+        })
+    }
+
+    /// Whether the verbatim arguments contain the given (long) flag, e.g., `--edition`.
+    pub(crate) fn contains_flag(&self, flag: &str) -> bool {
+        self.arguments.iter().any(|argument| {
+            argument.strip_prefix(flag).is_some_and(|rest| rest.is_empty() || rest.starts_with('='))
+        })
+    }
+}
+
+/// Warn about verbatim flags for which rruxwry already has structured options.
+pub(crate) fn warn_about_confusable_verbatim_flags(verbatim: VerbatimFlags<'_>) {
+    const CONFUSABLES: &[(&str, &str)] = &[
+        ("--crate-name", "-n`/`--crate-name"),
+        ("--crate-type", "-y`/`--crate-type"),
+        ("--edition", "-e`/`--edition"),
+        ("--document-private-items", "-P`/`--private"),
+        ("--document-hidden-items", "-H`/`--hidden"),
+    ];
+
+    for &(flag, option) in CONFUSABLES {
+        if verbatim.contains_flag(flag) {
+            warning(format!("the verbatim flag `{flag}` duplicates the option `{option}`"))
+                .note(format!("the former takes precedence, rruxwry won't pass its own `{flag}`"))
+                .note(format!("consider using `{option}` instead"))
+                .emit();
+        }
+    }
+}
+
 #[derive(Clone, Default)]
 pub(crate) struct VerbatimFlagsBuf<'a> {
     pub(crate) arguments: Vec<&'a str>,
+    /// The origin of each argument.
+    pub(crate) origins: Vec<Origin>,
     pub(crate) environment: Vec<(&'a str, Option<&'a str>)>,
 }
 
 impl<'a> VerbatimFlagsBuf<'a> {
     pub(crate) fn extended(mut self, other: VerbatimFlags<'a>) -> Self {
         self.arguments.extend_from_slice(other.arguments);
+        self.origins.extend_from_slice(other.origins);
         self.environment.extend_from_slice(other.environment);
         self
     }
 
+    pub(crate) fn push(&mut self, argument: &'a str, origin: Origin) {
+        self.arguments.push(argument);
+        self.origins.push(origin);
+    }
+
+    pub(crate) fn extend(&mut self, arguments: impl IntoIterator<Item = &'a str>, origin: Origin) {
+        for argument in arguments {
+            self.push(argument, origin);
+        }
+    }
+
     pub(crate) fn as_ref(&self) -> VerbatimFlags<'_> {
-        VerbatimFlags { arguments: &self.arguments, environment: &self.environment }
+        VerbatimFlags {
+            arguments: &self.arguments,
+            origins: &self.origins,
+            environment: &self.environment,
+        }
+    }
+}
+
+enum Capture {
+    Nothing,
+    Stdout,
+}
+
+#[derive(Default)]
+struct Output {
+    /// The exit status which callers are responsible for checking.
+    ///
+    /// This allows them to process the diagnostics even if the command failed.
+    status: process::ExitStatus,
+    stdout: Vec<u8>,
+    /// The rendered stderr if it was captured.
+    stderr: String,
+    /// The JSON diagnostics if they were requested.
+    diagnostics: Vec<serde_json::Value>,
+}
+
+pub(crate) enum Error {
+    DeniedWarnings { program: String, warnings: usize },
+    MissingWrapper(&'static str),
+    UnknownItem(String),
+    FailedDoctests(String),
+    UnknownOpenTarget { unknown: String, available: Vec<String> },
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::DeniedWarnings { program, warnings } => {
+                let s = if warnings == 1 { "" } else { "s" };
+                error(format!("`{program}` emitted {warnings} warning{s}"))
+                    .note("warnings are denied due to `--deny-warnings`")
+            }
+            Self::MissingWrapper(program) => {
+                error(format!("failed to find `{program}`")).note(match program {
+                    "systemd-run" => "`--memory-limit` requires systemd",
+                    "nice" => "`--nice` requires `nice`",
+                    "docker" => "`--in-container` requires `docker` or `$RRUXWRY_CONTAINER_ENGINE`",
+                    "ssh" => "`--ssh` requires `ssh` and `rsync`",
+                    _ => "`--idle-io` requires `ionice` from util-linux",
+                })
+            }
+            Self::UnknownItem(path) => error(format!("failed to find the page of `{path}`"))
+                .note("item paths get resolved against the pages of the HTML output"),
+            Self::FailedDoctests(crate_name) => error(format!(
+                "some doctests of `{crate_name}` failed"
+            ))
+            .note("pass `--test-arg ⟨NAME⟩` to only run the doctests whose name contains it"),
+            Self::UnknownOpenTarget { unknown, available } => {
+                let available = available.iter().map(|name| format!("`{name}`")).join_with(", ");
+
+                error(format!("unknown crate `{unknown}`"))
+                    .note(format!("documented crates are: {available}"))
+                    .note("pages need to be given relative to the documentation root")
+            }
+        }
     }
 }
 
+#[derive(Clone, Copy)]
 pub(crate) enum Strictness {
     Strict,
     Lenient,
@@ -1,12 +1,20 @@
 use rustc_hash::FxHashMap;
 use std::{
+    borrow::Cow,
     ffi::{OsStr, OsString},
     sync::LazyLock,
 };
 
 type Environment = FxHashMap<OsString, OsString>;
 
-pub(crate) fn rustc_flags<'a>() -> Option<&'a [String]> {
+/// An explicit adjustment of the environment, e.g., via `--env` or `--env-remove`.
+type Override<'a> = (&'a str, Option<&'a str>);
+
+pub(crate) fn rustc_flags(overrides: &[Override<'_>]) -> Option<Cow<'static, [String]>> {
+    if let Some(flags) = overridden_flags("RUSTFLAGS", overrides) {
+        return flags.map(Cow::Owned);
+    }
+
     static RUSTFLAGS: LazyLock<Option<Vec<String>>> = LazyLock::new(|| {
         parse_flags(
             OsStr::new("RUSTFLAGS"),
@@ -15,15 +23,37 @@ pub(crate) fn rustc_flags<'a>() -> Option<&'a [String]> {
         )
     });
 
-    RUSTFLAGS.as_deref()
+    RUSTFLAGS.as_deref().map(Cow::Borrowed)
 }
 
-pub(crate) fn rustdoc_flags<'a>() -> Option<&'a [String]> {
+pub(crate) fn rustdoc_flags(overrides: &[Override<'_>]) -> Option<Cow<'static, [String]>> {
+    if let Some(flags) = overridden_flags("RUSTDOCFLAGS", overrides) {
+        return flags.map(Cow::Owned);
+    }
+
     static RUSTDOCFLAGS: LazyLock<Option<Vec<String>>> = LazyLock::new(|| {
         parse_flags(OsStr::new("RUSTDOCFLAGS"), &[OsStr::new("RUSTDOC_FLAGS")], &ENVIRONMENT)
     });
 
-    RUSTDOCFLAGS.as_deref()
+    RUSTDOCFLAGS.as_deref().map(Cow::Borrowed)
+}
+
+/// Obtain the flags from the last override of the given environment variable if there is one.
+fn overridden_flags(key: &str, overrides: &[Override<'_>]) -> Option<Option<Vec<String>>> {
+    let &(_, flags) = overrides.iter().rev().find(|&&(candidate, _)| candidate == key)?;
+    let Some(flags) = flags else { return Some(None) };
+
+    let flags = shlex::split(flags);
+
+    if flags.is_none() {
+        warning::malformed_environment_variable(
+            OsStr::new(key),
+            "its content is not properly escaped",
+        )
+        .emit();
+    }
+
+    Some(flags)
 }
 
 static ENVIRONMENT: LazyLock<Environment> = LazyLock::new(|| std::env::vars_os().collect());
@@ -47,7 +77,7 @@ fn parse_flags(
         return None;
     };
 
-    let flags = shlex::split(&flags);
+    let flags = shlex::split(flags);
 
     if flags.is_none() {
         warning::malformed_environment_variable(key, "its content is not properly escaped").emit();
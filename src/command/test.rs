@@ -0,0 +1,102 @@
+use super::{
+    VerbatimFlags, count_rendered_warnings, count_warnings, describe_synthesized_span,
+    describe_synthesized_spans, find_anchor, reexport_target,
+};
+use serde_json::{Value, json};
+use std::path::Path;
+
+const SOURCE: &str = "extern crate w; pub use w::*;";
+
+fn span(highlight_start: u64, highlight_end: u64) -> Value {
+    json!({
+        "file_name": "u_w.rs",
+        "line_start": 1,
+        "column_start": highlight_start,
+        "text": [{ "text": SOURCE, "highlight_start": highlight_start, "highlight_end": highlight_end }],
+    })
+}
+
+#[test]
+fn synthesized_span_single_construct() {
+    assert_eq!(describe_synthesized_span(&span(17, 30)), Some("pub use w::*"));
+    assert_eq!(describe_synthesized_span(&span(25, 26)), Some("pub use w::*"));
+    assert_eq!(describe_synthesized_span(&span(1, 16)), Some("extern crate w"));
+}
+
+#[test]
+fn synthesized_span_several_constructs() {
+    assert_eq!(describe_synthesized_span(&span(1, 30)), None);
+}
+
+#[test]
+fn synthesized_spans_rewritten() {
+    let diagnostic = json!({ "spans": [span(25, 26)], "children": [] });
+    let rendered = "warning: lint\n --> u_w.rs:1:25\n  |\n";
+    assert_eq!(
+        describe_synthesized_spans(rendered, &diagnostic, Path::new("u_w.rs")),
+        "warning: lint\n --> ⟨generated `pub use w::*`⟩\n  |\n"
+    );
+}
+
+#[test]
+fn open_target_anchors() {
+    let html = r#"<section id="method.new" class="method"></section><div id="variant.V"></div>"#;
+    assert_eq!(find_anchor(html, "new").as_deref(), Some("method.new"));
+    assert_eq!(find_anchor(html, "V").as_deref(), Some("variant.V"));
+    assert_eq!(find_anchor(html, "ne"), None);
+}
+
+#[test]
+fn open_target_reexports() {
+    let html = r#"<div id="reexport.Renamed"><code>pub use <a href="m/index.html">m</a>::<a class="struct" href="m/struct.S.html">S</a> as Renamed;</code></div><div id="reexport.Vec"><code>pub use <a href="https://doc.rust-lang.org/vec/struct.Vec.html">Vec</a>;</code></div>"#;
+    assert_eq!(reexport_target(html, "Renamed"), Some("m/struct.S.html"));
+    assert_eq!(reexport_target(html, "Vec"), None);
+    assert_eq!(reexport_target(html, "S"), None);
+}
+
+#[test]
+fn verbatim_unstable_options() {
+    let flags = |arguments| VerbatimFlags { arguments, origins: &[], environment: &[] };
+    assert!(flags(&["--document-hidden-items", "-Zunstable-options"]).contains_unstable_options());
+    assert!(flags(&["-Z", "unstable-options"]).contains_unstable_options());
+    assert!(!flags(&["-Z", "ui-testing", "unstable-options"]).contains_unstable_options());
+    assert!(!flags(&["--document-hidden-items"]).contains_unstable_options());
+}
+
+#[test]
+fn warnings_in_json_diagnostics() {
+    let diagnostics = [
+        json!({ "level": "warning", "message": "unresolved link to `x`", "spans": [span(1, 2)] }),
+        json!({ "level": "error", "message": "cannot find type `T`", "spans": [span(1, 2)] }),
+        json!({ "level": "warning", "message": "unused import", "spans": [span(1, 2)] }),
+        json!({ "level": "warning", "message": "2 warnings emitted", "spans": [] }),
+    ];
+    assert_eq!(count_warnings(&diagnostics), 2);
+    assert_eq!(count_warnings(&diagnostics[1..2]), 0);
+}
+
+#[test]
+fn warnings_in_human_output() {
+    let stderr = "\x1b[1m\x1b[33mwarning\x1b[0m: unresolved link to `x`\n --> w.rs:1:6\n  |\n\
+                  warning[E0170]: pattern binding\n\
+                  warning: 2 warnings emitted\n";
+    assert_eq!(count_rendered_warnings(stderr), 2);
+    assert_eq!(count_rendered_warnings("error: cannot find type `T`\n"), 0);
+}
+
+#[test]
+fn warnings_in_short_output() {
+    let stderr = "w.rs:1:6: warning: unresolved link to `x`\n\
+                  w.rs:2:1: error: cannot find type `T`\n\
+                  note: w.rs:3: warning: not a location\n\
+                  warning: 1 warning emitted\n";
+    assert_eq!(count_rendered_warnings(stderr), 1);
+}
+
+#[test]
+fn warnings_in_verbatim_json_output() {
+    let stderr = r#"{"level":"warning","message":"unresolved link","spans":[{}]}
+{"level":"warning","message":"1 warning emitted","spans":[]}
+"#;
+    assert_eq!(count_rendered_warnings(stderr), 1);
+}
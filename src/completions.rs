@@ -0,0 +1,46 @@
+//! Generating shell completion scripts from the definition of the command-line interface.
+
+use crate::cli::Shell;
+use clap::ValueHint;
+
+#[cfg(test)]
+mod test;
+
+/// Print the completion script for the given shell to stdout.
+pub(crate) fn print(shell: Shell) {
+    let command = <crate::cli::Arguments as clap::CommandFactory>::command();
+    print!("{}", generate(command, shell));
+}
+
+fn generate(command: clap::Command, shell: Shell) -> String {
+    let shell = match shell {
+        Shell::Bash => clap_complete::Shell::Bash,
+        Shell::Zsh => clap_complete::Shell::Zsh,
+        Shell::Fish => clap_complete::Shell::Fish,
+        Shell::Powershell => clap_complete::Shell::PowerShell,
+    };
+    let mut command = hint_paths(command);
+    let name = command.get_name().to_owned();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, name, &mut script);
+    String::from_utf8_lossy(&script).into_owned()
+}
+
+/// Make the shells complete paths for the values of arguments like `--out-dir <PATH>`.
+fn hint_paths(command: clap::Command) -> clap::Command {
+    let subcommands: Vec<_> =
+        command.get_subcommands().map(|subcommand| subcommand.get_name().to_owned()).collect();
+    let command = subcommands
+        .iter()
+        .fold(command, |command, subcommand| command.mut_subcommand(subcommand, hint_paths));
+    command.mut_args(|argument| {
+        let path = argument.get_action().takes_values()
+            && argument
+                .get_value_names()
+                .is_some_and(|names| names.iter().any(|name| name.contains("PATH")));
+        match path && argument.get_value_hint() == ValueHint::Unknown {
+            true => argument.value_hint(ValueHint::AnyPath),
+            false => argument,
+        }
+    })
+}
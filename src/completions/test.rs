@@ -0,0 +1,41 @@
+use super::{generate, hint_paths};
+use crate::cli::Shell;
+use clap::{Arg, ArgAction, Command, ValueHint};
+
+fn command() -> Command {
+    Command::new("tool")
+        .arg(Arg::new("verbose").short('V').long("verbose").action(ArgAction::SetTrue))
+        .arg(Arg::new("report").long("report").value_name("PATH"))
+        .arg(Arg::new("theme").long("theme").value_name("NAME"))
+        .subcommand(
+            Command::new("cache").subcommand(
+                Command::new("gc").arg(Arg::new("into").long("into").value_name("PATH")),
+            ),
+        )
+}
+
+fn hint(command: &Command, name: &str) -> ValueHint {
+    let argument = command.get_arguments().find(|argument| argument.get_id() == name);
+    argument.unwrap().get_value_hint()
+}
+
+#[test]
+fn paths_hinted() {
+    let command = hint_paths(command());
+    assert_eq!(hint(&command, "report"), ValueHint::AnyPath);
+    assert_eq!(hint(&command, "theme"), ValueHint::Unknown);
+
+    let cache = command.find_subcommand("cache").unwrap();
+    assert_eq!(hint(cache.find_subcommand("gc").unwrap(), "into"), ValueHint::AnyPath);
+}
+
+#[test]
+fn scripts_of_the_actual_interface() {
+    for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::Powershell] {
+        let command = <crate::cli::Arguments as clap::CommandFactory>::command();
+        let script = generate(command, shell);
+        assert!(script.contains("rruxwry"));
+        assert!(script.contains("out-dir"));
+        assert!(script.contains("completions"));
+    }
+}
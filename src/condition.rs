@@ -0,0 +1,404 @@
+//! Evaluating the `ignore-*`, `only-*` and `needs-*` directives of compiletest tests.
+//!
+//! We only support the conditions that can be decided by probing the toolchain. Unknown ones
+//! never prevent a test from running.
+
+use crate::{
+    cli,
+    diagnostic::{Diagnostic, warning},
+    directive::{Condition, ConditionKind},
+    toolchain,
+};
+use std::{fmt, path::PathBuf, process};
+
+#[cfg(test)]
+mod test;
+
+/// Check that none of the given conditions prevents the test from running.
+pub(crate) fn check(
+    conditions: &[Condition<'_>],
+    target: Option<&str>,
+    build_flags: &cli::BuildFlags,
+    program_flags: &cli::ProgramFlags,
+) -> crate::error::Result {
+    let target = Target::probe(target, build_flags, program_flags)?;
+    let mut unknowns = Vec::new();
+
+    for condition in conditions {
+        match evaluate(condition, &target) {
+            Evaluation::Met => {}
+            Evaluation::Unmet(reason) => {
+                let reason = match condition.comment {
+                    Some(comment) => format!("{reason} ({comment})"),
+                    None => reason,
+                };
+                return Err(Unmet { directive: condition.to_string(), reason }.into());
+            }
+            Evaluation::Unknown => unknowns.push(condition),
+        }
+    }
+
+    if !unknowns.is_empty() {
+        let (s, pronoun) =
+            if unknowns.len() == 1 { ("", "it doesn't") } else { ("s", "they don't") };
+        unknowns
+            .into_iter()
+            .map(|condition| format!("`//@ {condition}`"))
+            .fold(warning(format!("unsupported condition{s}")), Diagnostic::note)
+            .note(format!("assuming {pronoun} prevent the test from running"))
+            .emit();
+    }
+
+    Ok(())
+}
+
+/// A condition that prevents the test from running.
+#[derive(Clone)]
+pub(crate) struct Unmet {
+    pub(crate) directive: String,
+    pub(crate) reason: String,
+}
+
+impl fmt::Display for Unmet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.directive, self.reason)
+    }
+}
+
+enum Evaluation {
+    Met,
+    Unmet(String),
+    Unknown,
+}
+
+fn evaluate(condition: &Condition<'_>, target: &Target) -> Evaluation {
+    match condition.kind {
+        ConditionKind::Ignore => match matches(condition.name, target) {
+            Some(Some(description)) => Evaluation::Unmet(format!("ignored {description}")),
+            Some(None) => Evaluation::Met,
+            None => Evaluation::Unknown,
+        },
+        ConditionKind::Only => match matches(condition.name, target) {
+            Some(Some(_)) => Evaluation::Met,
+            Some(None) => Evaluation::Unmet(match condition.name {
+                "nightly" | "beta" | "stable" => format!(
+                    "only runs on the {} channel but the toolchain is on the {} channel",
+                    condition.name, target.channel
+                ),
+                name => format!("only runs on `{name}` but the target is `{}`", target.triple),
+            }),
+            None => Evaluation::Unknown,
+        },
+        ConditionKind::Needs => needs(condition.name, target),
+    }
+}
+
+/// Whether the name of an `ignore-*` or `only-*` condition matches the target.
+///
+/// Returns a description of the match if it does and `None` if the name is unknown.
+fn matches(name: &str, target: &Target) -> Option<Option<String>> {
+    let found = |description: String| Some(Some(description));
+
+    if name == "test" {
+        return found("unconditionally".to_owned());
+    }
+    if name == target.triple {
+        return found(format!("on `{name}`"));
+    }
+    if name == "cross-compile" {
+        return match target.triple != target.host {
+            true => found(format!("when cross-compiling to `{}`", target.triple)),
+            false => Some(None),
+        };
+    }
+    if let "nightly" | "beta" | "stable" = name {
+        return match name == target.channel {
+            true => found(format!("on the {name} channel")),
+            false => Some(None),
+        };
+    }
+    if let Some(width) = name.strip_suffix("bit") {
+        return match target.cfg("target_pointer_width").any(|value| value == width) {
+            true => found(format!("on {width}-bit targets")),
+            false => Some(None),
+        };
+    }
+    if let Some(endian) = name.strip_prefix("endian-") {
+        return match target.cfg("target_endian").any(|value| value == endian) {
+            true => found(format!("on {endian}-endian targets")),
+            false => Some(None),
+        };
+    }
+
+    const CATEGORIES: &[(&str, &str, &[&str])] = &[
+        ("target_arch", "architecture", ARCHITECTURES),
+        ("target_os", "OS", OPERATING_SYSTEMS),
+        ("target_env", "environment", ENVIRONMENTS),
+        ("target_family", "family", FAMILIES),
+        ("target_vendor", "vendor", VENDORS),
+    ];
+
+    let mut known = false;
+    for &(key, category, values) in CATEGORIES {
+        if target.cfg(key).any(|value| value == name) {
+            return found(format!("on the {category} `{name}` (target `{}`)", target.triple));
+        }
+        known |= values.contains(&name);
+    }
+
+    known.then_some(None)
+}
+
+fn needs(name: &str, target: &Target) -> Evaluation {
+    let require = |met: bool, reason: &str| match met {
+        true => Evaluation::Met,
+        false => Evaluation::Unmet(reason.to_owned()),
+    };
+
+    if let Some(sanitizer) = name.strip_prefix("sanitizer-") {
+        let Some(sanitizers) = target.supported_sanitizers() else {
+            return Evaluation::Unknown;
+        };
+        if sanitizers.is_empty() {
+            return Evaluation::Unmet("toolchain lacks sanitizer support".to_owned());
+        }
+        return match sanitizer {
+            "support" => Evaluation::Met,
+            _ => require(
+                sanitizers.contains(&sanitizer),
+                &format!("the target doesn't support the {sanitizer} sanitizer"),
+            ),
+        };
+    }
+
+    match name {
+        "unwind" => require(
+            target.cfg("panic").any(|strategy| strategy == "unwind"),
+            "the target doesn't unwind on panic",
+        ),
+        "threads" => {
+            let wasm = target.cfg("target_family").any(|family| family == "wasm");
+            let atomics = target.cfg("target_feature").any(|feature| feature == "atomics");
+            let bare = target.cfg("target_os").any(|os| os == "none");
+            require((atomics || !wasm) && !bare, "the target doesn't support threads")
+        }
+        "asm-support" => require(
+            target.cfg("target_arch").any(|arch| ASM_ARCHITECTURES.contains(&arch)),
+            "the target doesn't support inline assembly",
+        ),
+        "dynamic-linking" => match target.spec_flag("dynamic-linking") {
+            Some(dynamic_linking) => {
+                require(dynamic_linking, "the target doesn't support dynamic linking")
+            }
+            None => Evaluation::Unknown,
+        },
+        "profiler-runtime" | "profiler-support" => match &target.libraries {
+            Some(libraries) => require(
+                libraries.iter().any(|library| library.starts_with("libprofiler_builtins-")),
+                "toolchain lacks the profiler runtime",
+            ),
+            None => Evaluation::Unknown,
+        },
+        _ => Evaluation::Unknown,
+    }
+}
+
+/// The facts about the target that conditions can depend on.
+struct Target {
+    triple: String,
+    host: String,
+    channel: &'static str,
+    /// The output of `rustc --print cfg`.
+    cfgs: Vec<(String, Option<String>)>,
+    /// The output of `rustc --print target-spec-json` which requires a nightly toolchain.
+    spec: Option<serde_json::Value>,
+    /// The file names in the library directory of the target.
+    libraries: Option<Vec<String>>,
+}
+
+impl Target {
+    fn probe(
+        target: Option<&str>,
+        build_flags: &cli::BuildFlags,
+        program_flags: &cli::ProgramFlags,
+    ) -> crate::error::Result<Self> {
+        let query = |arguments: &[&str], stderr| {
+            let mut arguments = arguments.to_vec();
+            if let Some(target) = target {
+                arguments.extend(["--target", target]);
+            }
+            toolchain::query("rustc", &arguments, stderr, build_flags, program_flags)
+        };
+
+        let version = query(&["-vV"], process::Stdio::inherit())?;
+        let host = version
+            .lines()
+            .find_map(|line| line.strip_prefix("host: "))
+            .unwrap_or_default()
+            .to_owned();
+        let release = version.lines().find_map(|line| line.strip_prefix("release: "));
+        let channel = match release {
+            Some(release) if release.contains("nightly") || release.contains("dev") => "nightly",
+            Some(release) if release.contains("beta") => "beta",
+            _ => "stable",
+        };
+
+        let cfgs = query(&["--print", "cfg"], process::Stdio::inherit())?
+            .lines()
+            .map(|line| match line.split_once('=') {
+                Some((key, value)) => (key.to_owned(), Some(value.trim_matches('"').to_owned())),
+                None => (line.to_owned(), None),
+            })
+            .collect();
+
+        let spec =
+            query(&["-Zunstable-options", "--print", "target-spec-json"], process::Stdio::null())
+                .ok()
+                .and_then(|spec| serde_json::from_str(&spec).ok());
+
+        let libraries = query(&["--print", "target-libdir"], process::Stdio::inherit())
+            .ok()
+            .and_then(|directory| std::fs::read_dir(PathBuf::from(directory)).ok())
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| {
+                        Some(entry.ok()?.file_name().to_string_lossy().into_owned())
+                    })
+                    .collect()
+            });
+
+        Ok(Self {
+            triple: target.map_or_else(|| host.clone(), ToOwned::to_owned),
+            host,
+            channel,
+            cfgs,
+            spec,
+            libraries,
+        })
+    }
+
+    fn cfg<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.cfgs
+            .iter()
+            .filter(move |(candidate, _)| candidate == key)
+            .filter_map(|(_, value)| value.as_deref())
+    }
+
+    fn supported_sanitizers(&self) -> Option<Vec<&str>> {
+        let spec = self.spec.as_ref()?;
+        // The field gets omitted if the target doesn't support any sanitizers.
+        Some(match spec.get("supported-sanitizers") {
+            Some(sanitizers) => {
+                sanitizers.as_array()?.iter().filter_map(serde_json::Value::as_str).collect()
+            }
+            None => Vec::new(),
+        })
+    }
+
+    fn spec_flag(&self, key: &str) -> Option<bool> {
+        let spec = self.spec.as_ref()?;
+        // Flags that are unset get omitted.
+        Some(spec.get(key).and_then(serde_json::Value::as_bool).unwrap_or_default())
+    }
+}
+
+// These lists only serve to tell apart misspelled or unsupported conditions from ones that
+// just don't match the target. They don't need to be exhaustive.
+
+const ARCHITECTURES: &[&str] = &[
+    "aarch64",
+    "arm",
+    "arm64ec",
+    "avr",
+    "bpf",
+    "csky",
+    "hexagon",
+    "loongarch64",
+    "m68k",
+    "mips",
+    "mips64",
+    "msp430",
+    "nvptx64",
+    "powerpc",
+    "powerpc64",
+    "riscv32",
+    "riscv64",
+    "s390x",
+    "sparc",
+    "sparc64",
+    "wasm32",
+    "wasm64",
+    "x86",
+    "x86_64",
+    "xtensa",
+];
+
+const OPERATING_SYSTEMS: &[&str] = &[
+    "aix",
+    "android",
+    "cuda",
+    "dragonfly",
+    "emscripten",
+    "espidf",
+    "freebsd",
+    "fuchsia",
+    "haiku",
+    "hermit",
+    "horizon",
+    "hurd",
+    "illumos",
+    "ios",
+    "l4re",
+    "linux",
+    "macos",
+    "netbsd",
+    "none",
+    "nto",
+    "nuttx",
+    "openbsd",
+    "psp",
+    "redox",
+    "solaris",
+    "tvos",
+    "uefi",
+    "visionos",
+    "vita",
+    "vxworks",
+    "wasi",
+    "watchos",
+    "windows",
+];
+
+const ENVIRONMENTS: &[&str] = &[
+    "gnu", "macabi", "msvc", "musl", "newlib", "ohos", "p1", "p2", "relibc", "sgx", "sim", "uclibc",
+];
+
+const FAMILIES: &[&str] = &["unix", "wasm", "windows"];
+
+const VENDORS: &[&str] =
+    &["apple", "fortanix", "kmc", "nintendo", "nvidia", "pc", "sony", "unknown", "uwp", "wrs"];
+
+const ASM_ARCHITECTURES: &[&str] = &[
+    "aarch64",
+    "arm",
+    "arm64ec",
+    "avr",
+    "bpf",
+    "csky",
+    "hexagon",
+    "loongarch64",
+    "m68k",
+    "mips",
+    "mips64",
+    "msp430",
+    "nvptx64",
+    "powerpc",
+    "powerpc64",
+    "riscv32",
+    "riscv64",
+    "s390x",
+    "sparc",
+    "sparc64",
+    "x86",
+    "x86_64",
+];
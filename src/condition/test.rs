@@ -0,0 +1,85 @@
+use super::{Evaluation, Target, evaluate};
+use crate::directive::{Condition, ConditionKind};
+
+fn linux() -> Target {
+    let cfgs = [
+        ("panic", Some("unwind")),
+        ("target_arch", Some("x86_64")),
+        ("target_endian", Some("little")),
+        ("target_env", Some("gnu")),
+        ("target_family", Some("unix")),
+        ("target_os", Some("linux")),
+        ("target_pointer_width", Some("64")),
+        ("target_vendor", Some("unknown")),
+        ("unix", None),
+    ];
+
+    Target {
+        triple: "x86_64-unknown-linux-gnu".to_owned(),
+        host: "x86_64-unknown-linux-gnu".to_owned(),
+        channel: "nightly",
+        cfgs: cfgs
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value.map(ToOwned::to_owned)))
+            .collect(),
+        spec: Some(serde_json::json!({ "dynamic-linking": true })),
+        libraries: Some(Vec::new()),
+    }
+}
+
+fn check(kind: ConditionKind, name: &str) -> Evaluation {
+    evaluate(&Condition { kind, name, comment: None }, &linux())
+}
+
+fn is_met(evaluation: Evaluation) -> bool {
+    matches!(evaluation, Evaluation::Met)
+}
+
+fn reason(evaluation: Evaluation) -> String {
+    match evaluation {
+        Evaluation::Unmet(reason) => reason,
+        Evaluation::Met => panic!("the condition is met"),
+        Evaluation::Unknown => panic!("the condition is unknown"),
+    }
+}
+
+#[test]
+fn ignore() {
+    assert_eq!(
+        reason(check(ConditionKind::Ignore, "linux")),
+        "ignored on the OS `linux` (target `x86_64-unknown-linux-gnu`)"
+    );
+    assert_eq!(reason(check(ConditionKind::Ignore, "64bit")), "ignored on 64-bit targets");
+    assert_eq!(reason(check(ConditionKind::Ignore, "nightly")), "ignored on the nightly channel");
+    assert_eq!(reason(check(ConditionKind::Ignore, "test")), "ignored unconditionally");
+    assert!(is_met(check(ConditionKind::Ignore, "windows")));
+    assert!(is_met(check(ConditionKind::Ignore, "cross-compile")));
+    assert!(matches!(check(ConditionKind::Ignore, "stage1"), Evaluation::Unknown));
+}
+
+#[test]
+fn only() {
+    assert!(is_met(check(ConditionKind::Only, "x86_64")));
+    assert!(is_met(check(ConditionKind::Only, "unix")));
+    assert!(is_met(check(ConditionKind::Only, "x86_64-unknown-linux-gnu")));
+    assert_eq!(
+        reason(check(ConditionKind::Only, "msvc")),
+        "only runs on `msvc` but the target is `x86_64-unknown-linux-gnu`"
+    );
+}
+
+#[test]
+fn needs() {
+    assert!(is_met(check(ConditionKind::Needs, "unwind")));
+    assert!(is_met(check(ConditionKind::Needs, "threads")));
+    assert!(is_met(check(ConditionKind::Needs, "dynamic-linking")));
+    assert_eq!(
+        reason(check(ConditionKind::Needs, "sanitizer-address")),
+        "toolchain lacks sanitizer support"
+    );
+    assert_eq!(
+        reason(check(ConditionKind::Needs, "profiler-runtime")),
+        "toolchain lacks the profiler runtime"
+    );
+    assert!(matches!(check(ConditionKind::Needs, "rust-lld"), Evaluation::Unknown));
+}
@@ -0,0 +1,490 @@
+//! The per-project configuration file `.rruxwry.toml` and the per-user one `config.toml`.
+//!
+//! The former gets looked up in the current directory and its ancestors, the latter in
+//! `$XDG_CONFIG_HOME/rruxwry/` or `~/.config/rruxwry/`. Their absence is equivalent to an
+//! empty configuration.
+//!
+//! ```toml
+//! [normalize]
+//! builtins = { resource-hashes = false }
+//! rules = [{ pattern = 'id="impl-\d+"', replacement = 'id="impl-$$N"' }]
+//!
+//! [budgets]
+//! search-index = "2 MiB"
+//! total = "50 MiB"
+//!
+//! [defaults]
+//! toolchain = "nightly"
+//! theme = "dark"
+//! open = true
+//! verbatim-flags = ["-Zunstable-options", "--html-in-header=head.html"]
+//! env = { RUST_MIN_STACK = "16777216", RUSTDOC_LOG = false }
+//!
+//! [presets]
+//! ui = ["-T", "--rev", "next", "--deny-warnings"]
+//!
+//! [pipelines]
+//! verify = [["-T", "-Q"], ["--coverage"], ["--compare-toolchain", "nightly"]]
+//!
+//! [lints.rust]
+//! missing_docs = "warn"
+//!
+//! [lints.rustdoc]
+//! all = { level = "warn", priority = -1 }
+//! broken_intra_doc_links = "deny"
+//! ```
+//!
+//! Since TOML lacks a null value, variables under `defaults.env` get unset via `false`.
+//!
+//! Just like in Cargo's `[lints]` table, the lints are grouped by tool and lints of a lower priority
+//! get passed first (i.e., can be overridden by the others). Those lints only apply to the root
+//! crate, not to its dependencies.
+//!
+//! Only the defaults, the presets and the pipelines are read from the per-user file. The ones of
+//! the project take precedence over them and the command line takes precedence over both.
+
+use crate::{
+    budget::{Budget, Target},
+    cache::parse_size,
+    cli,
+    diagnostic::{Diagnostic, IntoDiagnostic, error},
+    snapshot::{Builtin, Normalizer, Rule},
+    utility,
+};
+use clap::parser::ValueSource;
+use regex::Regex;
+use rustc_hash::FxHashSet;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod test;
+
+pub(crate) const FILE_NAME: &str = ".rruxwry.toml";
+
+#[derive(Default)]
+pub(crate) struct Config {
+    pub(crate) normalizer: Normalizer,
+    pub(crate) budgets: Vec<Budget>,
+    pub(crate) defaults: Defaults,
+    pub(crate) presets: Vec<(String, Vec<String>)>,
+    pub(crate) pipelines: Vec<Pipeline>,
+    /// The lints and their levels in the order they should be passed in.
+    pub(crate) lints: Vec<(String, String)>,
+}
+
+/// A named sequence of steps, each of which is a list of arguments (`rruxwry run`).
+pub(crate) type Pipeline = (String, Vec<Vec<String>>);
+
+/// Default values for command-line flags.
+#[derive(Default)]
+pub(crate) struct Defaults {
+    pub(crate) toolchain: Option<String>,
+    pub(crate) theme: Option<String>,
+    pub(crate) open: Option<bool>,
+    pub(crate) verbatim_flags: Vec<String>,
+    /// Environment variables to set or (if `None`) to unset.
+    pub(crate) env: Vec<(String, Option<String>)>,
+    pub(crate) lints: Vec<(String, String)>,
+}
+
+impl Defaults {
+    /// Overlay the given defaults which take precedence.
+    fn overlay(&mut self, other: Self) {
+        self.toolchain = other.toolchain.or(self.toolchain.take());
+        self.theme = other.theme.or(self.theme.take());
+        self.open = other.open.or(self.open);
+        self.verbatim_flags.extend(other.verbatim_flags);
+        self.env.extend(other.env);
+    }
+
+    /// Apply the defaults to the flags not passed on the command line.
+    pub(crate) fn apply(self, arguments: &mut cli::Arguments, matches: &clap::ArgMatches) {
+        let passed = |id| matches.value_source(id) == Some(ValueSource::CommandLine);
+        let build_flags = &mut arguments.build_flags;
+
+        if let Some(toolchain) = self.toolchain
+            && !passed("toolchain")
+        {
+            build_flags.toolchain = Some(toolchain);
+        }
+        if let Some(theme) = self.theme
+            && !passed("theme")
+        {
+            build_flags.theme = theme;
+        }
+        // Opening the docs conflicts with these flags.
+        if self.open == Some(true)
+            && !passed("open")
+            && !build_flags.json
+            && !build_flags.stdout
+            && build_flags.output_format.is_none()
+        {
+            arguments.open = Some(None);
+        }
+
+        arguments.config_verbatim_flags = self.verbatim_flags;
+        // Lints passed on the command line come later, so they take precedence.
+        build_flags.config_lints = self.lints;
+
+        // Variables set or unset on the command line take precedence.
+        let passed: FxHashSet<_> =
+            build_flags.environment.iter().map(|(key, _)| key.clone()).collect();
+        let env = self.env.into_iter().filter(|(key, _)| !passed.contains(key));
+        build_flags.environment.splice(0..0, env);
+    }
+}
+
+/// Load the configuration file of the project containing the current directory if there is one.
+///
+/// With `--no-config` (`no_config`), the file is ignored entirely.
+pub(crate) fn load(no_config: bool) -> crate::error::Result<Config> {
+    if no_config {
+        return Ok(Config::default());
+    }
+    match project_path()? {
+        Some(path) => read(path),
+        None => Ok(Config::default()),
+    }
+}
+
+/// The location of the configuration file of the project containing the current directory.
+fn project_path() -> std::io::Result<Option<PathBuf>> {
+    let directory = std::env::current_dir()?;
+    Ok(directory.ancestors().map(|directory| directory.join(FILE_NAME)).find(|path| path.is_file()))
+}
+
+/// The configuration files that exist, i.e., the ones of the user and of the project.
+pub(crate) fn paths() -> Vec<PathBuf> {
+    let user = user_path().filter(|path| path.is_file());
+    user.into_iter().chain(project_path().ok().flatten()).collect()
+}
+
+/// Load the defaults of the per-user and of the per-project configuration file.
+pub(crate) fn load_defaults() -> crate::error::Result<Defaults> {
+    let mut defaults = load_user()?.defaults;
+    let project = load(false)?;
+    defaults.overlay(project.defaults);
+    // Like Cargo's `[lints]` table, the lints are specific to the project.
+    defaults.lints = project.lints;
+    Ok(defaults)
+}
+
+/// Load the presets of the per-user and of the per-project configuration file.
+///
+/// Later presets take precedence over earlier ones of the same name.
+pub(crate) fn load_presets() -> crate::error::Result<Vec<(String, Vec<String>)>> {
+    let mut presets = load_user()?.presets;
+    presets.extend(load(false)?.presets);
+    Ok(presets)
+}
+
+/// Load the pipelines of the per-user and of the per-project configuration file.
+///
+/// Later pipelines take precedence over earlier ones of the same name.
+pub(crate) fn load_pipelines() -> crate::error::Result<Vec<Pipeline>> {
+    let mut pipelines = load_user()?.pipelines;
+    pipelines.extend(load(false)?.pipelines);
+    Ok(pipelines)
+}
+
+fn load_user() -> crate::error::Result<Config> {
+    match user_path().filter(|path| path.is_file()) {
+        Some(path) => read(path),
+        None => Ok(Config::default()),
+    }
+}
+
+/// The location of the per-user configuration file.
+fn user_path() -> Option<PathBuf> {
+    let directory = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(&std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(directory.join("rruxwry/config.toml"))
+}
+
+fn read(path: PathBuf) -> crate::error::Result<Config> {
+    let source = std::fs::read_to_string(&path)?;
+    parse(&source).map_err(|message| Error { path, message }.into())
+}
+
+fn parse(source: &str) -> Result<Config, String> {
+    // The structure gets validated below, so it's simpler to deal with a single kind of value.
+    let value: Value = toml::from_str(source).map_err(|error| toml_error(source, &error))?;
+    let mut config = Config::default();
+
+    for (key, value) in object(&value, "the configuration")? {
+        match key.as_str() {
+            "normalize" => config.normalizer = parse_normalizer(value)?,
+            "budgets" => config.budgets = parse_budgets(value)?,
+            "defaults" => config.defaults = parse_defaults(value)?,
+            "presets" => config.presets = parse_presets(value)?,
+            "pipelines" => config.pipelines = parse_pipelines(value)?,
+            "lints" => config.lints = parse_lints(value)?,
+            _ => return Err(format!("unknown key `{key}`")),
+        }
+    }
+
+    Ok(config)
+}
+
+fn parse_normalizer(value: &Value) -> Result<Normalizer, String> {
+    let mut normalizer = Normalizer::default();
+
+    for (key, value) in object(value, "`normalize`")? {
+        match key.as_str() {
+            "builtins" => {
+                for (name, enabled) in object(value, "`normalize.builtins`")? {
+                    let Some(builtin) = Builtin::ALL.into_iter().find(|it| it.name() == name)
+                    else {
+                        let names: Vec<_> =
+                            Builtin::ALL.iter().map(|it| format!("`{}`", it.name())).collect();
+                        return Err(format!(
+                            "unknown built-in normalization `{name}` (expected one of {})",
+                            names.join(", ")
+                        ));
+                    };
+                    let Some(enabled) = enabled.as_bool() else {
+                        return Err(format!("`normalize.builtins.{name}` must be a boolean"));
+                    };
+                    normalizer.builtins.retain(|&it| it != builtin);
+                    if enabled {
+                        normalizer.builtins.push(builtin);
+                    }
+                }
+                // Regardless of the order in the file, apply them in the canonical order.
+                normalizer
+                    .builtins
+                    .sort_by_key(|builtin| Builtin::ALL.iter().position(|it| it == builtin));
+            }
+            "rules" => {
+                let Some(rules) = value.as_array() else {
+                    return Err("`normalize.rules` must be an array".to_owned());
+                };
+                for (index, rule) in rules.iter().enumerate() {
+                    normalizer.rules.push(parse_rule(rule).map_err(|message| {
+                        format!("in rule #{} of `normalize.rules`: {message}", index + 1)
+                    })?);
+                }
+            }
+            _ => return Err(format!("unknown key `normalize.{key}`")),
+        }
+    }
+
+    Ok(normalizer)
+}
+
+fn parse_budgets(value: &Value) -> Result<Vec<Budget>, String> {
+    object(value, "`budgets`")?
+        .iter()
+        .map(|(target, limit)| {
+            let Some(limit) = limit.as_str() else {
+                return Err(format!("`budgets.{target}` must be a string"));
+            };
+            let limit = parse_size(limit).map_err(|error| {
+                format!("invalid size `{limit}` for `budgets.{target}`: {error}")
+            })?;
+            Ok(Budget { target: Target::parse(target), limit })
+        })
+        .collect()
+}
+
+fn parse_defaults(value: &Value) -> Result<Defaults, String> {
+    let mut defaults = Defaults::default();
+
+    for (key, value) in object(value, "`defaults`")? {
+        match key.as_str() {
+            "toolchain" => defaults.toolchain = Some(string(value, "`defaults.toolchain`")?),
+            "theme" => defaults.theme = Some(string(value, "`defaults.theme`")?),
+            "open" => {
+                let Some(open) = value.as_bool() else {
+                    return Err("`defaults.open` must be a boolean".to_owned());
+                };
+                defaults.open = Some(open);
+            }
+            "verbatim-flags" => {
+                let Some(flags) = value.as_array() else {
+                    return Err("`defaults.verbatim-flags` must be an array".to_owned());
+                };
+                for flag in flags {
+                    defaults.verbatim_flags.push(string(flag, "a verbatim flag")?);
+                }
+            }
+            "env" => {
+                for (name, value) in object(value, "`defaults.env`")? {
+                    let value = match value {
+                        Value::Bool(false) => None,
+                        value => Some(string(value, &format!("`defaults.env.{name}`"))?),
+                    };
+                    defaults.env.push((name.clone(), value));
+                }
+            }
+            _ => return Err(format!("unknown key `defaults.{key}`")),
+        }
+    }
+
+    Ok(defaults)
+}
+
+fn parse_presets(value: &Value) -> Result<Vec<(String, Vec<String>)>, String> {
+    object(value, "`presets`")?
+        .iter()
+        .map(|(name, flags)| {
+            let Some(flags) = flags.as_array() else {
+                return Err(format!("`presets.{name}` must be an array"));
+            };
+            let flags = flags
+                .iter()
+                .map(|flag| string(flag, &format!("a flag of `presets.{name}`")))
+                .collect::<Result<_, _>>()?;
+            Ok((name.clone(), flags))
+        })
+        .collect()
+}
+
+fn parse_pipelines(value: &Value) -> Result<Vec<Pipeline>, String> {
+    object(value, "`pipelines`")?
+        .iter()
+        .map(|(name, steps)| {
+            let Some(steps) = steps.as_array() else {
+                return Err(format!("`pipelines.{name}` must be an array"));
+            };
+            let steps = steps
+                .iter()
+                .enumerate()
+                .map(|(index, step)| {
+                    let description = format!("step #{} of `pipelines.{name}`", index + 1);
+                    let Some(flags) = step.as_array() else {
+                        return Err(format!("{description} must be an array"));
+                    };
+                    let flags: Vec<_> = flags
+                        .iter()
+                        .map(|flag| string(flag, &format!("an argument of {description}")))
+                        .collect::<Result<_, _>>()?;
+                    if flags.first().is_some_and(|flag| flag == "run") {
+                        return Err(format!("{description} runs another pipeline"));
+                    }
+                    Ok(flags)
+                })
+                .collect::<Result<_, _>>()?;
+            Ok((name.clone(), steps))
+        })
+        .collect()
+}
+
+fn parse_lints(value: &Value) -> Result<Vec<(String, String)>, String> {
+    let mut lints = Vec::new();
+
+    for (tool, table) in object(value, "`lints`")? {
+        if !matches!(tool.as_str(), "rust" | "rustdoc" | "clippy") {
+            return Err(format!(
+                "unknown tool `lints.{tool}` (expected one of `rust`, `rustdoc`, `clippy`)"
+            ));
+        }
+        for (name, lint) in object(table, &format!("`lints.{tool}`"))? {
+            let description = format!("`lints.{tool}.{name}`");
+            let (level, priority) = match lint {
+                Value::Object(lint) => {
+                    let priority = match lint.get("priority") {
+                        Some(priority) => priority.as_i64().ok_or_else(|| {
+                            format!("the priority of {description} must be an integer")
+                        })?,
+                        None => 0,
+                    };
+                    if let Some(key) =
+                        lint.keys().find(|key| !matches!(key.as_str(), "level" | "priority"))
+                    {
+                        return Err(format!("unknown key `{key}` in {description}"));
+                    }
+                    let level = lint
+                        .get("level")
+                        .ok_or_else(|| format!("missing key `level` in {description}"))?;
+                    (string(level, &format!("the level of {description}"))?, priority)
+                }
+                lint => (string(lint, &description)?, 0),
+            };
+            if !matches!(level.as_str(), "allow" | "warn" | "force-warn" | "deny" | "forbid") {
+                return Err(format!(
+                    "invalid level `{level}` for {description} (expected `allow`, `warn`, \
+                     `force-warn`, `deny` or `forbid`)"
+                ));
+            }
+            let name = match tool.as_str() {
+                "rust" => name.clone(),
+                tool => format!("{tool}::{name}"),
+            };
+            lints.push((priority, name, level));
+        }
+    }
+
+    // Like Cargo, order them by priority and then by name for determinism.
+    lints.sort();
+    Ok(lints.into_iter().map(|(_, name, level)| (name, level)).collect())
+}
+
+fn string(value: &Value, description: &str) -> Result<String, String> {
+    match value.as_str() {
+        Some(value) => Ok(value.to_owned()),
+        None => Err(format!("{description} must be a string")),
+    }
+}
+
+fn parse_rule(value: &Value) -> Result<Rule, String> {
+    let mut pattern = None;
+    let mut replacement = None;
+
+    for (key, value) in object(value, "a rule")? {
+        let Some(value) = value.as_str() else {
+            return Err(format!("`{key}` must be a string"));
+        };
+        match key.as_str() {
+            "pattern" => {
+                let regex = Regex::new(value).map_err(|error| {
+                    format!("invalid pattern `{value}`: {}", utility::regex_error(&error))
+                })?;
+                pattern = Some(regex);
+            }
+            "replacement" => replacement = Some(value.to_owned()),
+            _ => return Err(format!("unknown key `{key}`")),
+        }
+    }
+
+    Ok(Rule {
+        pattern: pattern.ok_or("missing key `pattern`")?,
+        replacement: replacement.ok_or("missing key `replacement`")?,
+    })
+}
+
+/// Render the given TOML error on a single line, pointing at the offending line.
+fn toml_error(source: &str, error: &toml::de::Error) -> String {
+    let message = match error.message().lines().collect::<Vec<_>>().join("; ") {
+        message if message.is_empty() => "malformed TOML".to_owned(),
+        message => message,
+    };
+    match error.span() {
+        Some(span) => {
+            let line = source[..span.start].matches('\n').count() + 1;
+            format!("{message} on line {line}")
+        }
+        None => message,
+    }
+}
+
+fn object<'a>(
+    value: &'a Value,
+    description: &str,
+) -> Result<&'a serde_json::Map<String, Value>, String> {
+    value.as_object().ok_or_else(|| format!("{description} must be an object"))
+}
+
+pub(crate) struct Error {
+    path: PathBuf,
+    message: String,
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        error(format!("invalid configuration file `{}`", self.path.display())).note(self.message)
+    }
+}
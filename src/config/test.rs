@@ -0,0 +1,158 @@
+use super::parse;
+use crate::snapshot::Builtin;
+
+#[test]
+fn normalizer() {
+    let config = parse(
+        r#"
+        [normalize]
+        builtins = { resource-hashes = false, line-endings = true }
+        rules = [{ pattern = 'impl-\d+', replacement = "impl-N" }]
+        "#,
+    )
+    .ok()
+    .unwrap();
+
+    let normalizer = &config.normalizer;
+    assert!(normalizer.builtins == [Builtin::LineEndings, Builtin::RustdocVersion]);
+    assert_eq!(
+        normalizer.normalize("impl-1-0123456789abcdef.css\r\n"),
+        "impl-N-0123456789abcdef.css\n"
+    );
+}
+
+#[test]
+fn budgets() {
+    let config =
+        parse(r#"budgets = { search-index = "2 MiB", "k/*.html" = "100KB" }"#).ok().unwrap();
+
+    let budgets: Vec<_> =
+        config.budgets.iter().map(|budget| (budget.target.to_string(), budget.limit)).collect();
+    assert_eq!(budgets, [
+        ("the files matching `k/*.html`".to_owned(), 100_000),
+        ("the search index".to_owned(), 2 << 20)
+    ]);
+}
+
+#[test]
+fn errors() {
+    let error = |source| parse(source).err().unwrap();
+
+    assert_eq!(error("[normalise]"), "unknown key `normalise`");
+    assert_eq!(
+        error("normalize.builtins = { hashes = false }"),
+        "unknown built-in normalization `hashes` (expected one of `line-endings`, \
+         `rustdoc-version`, `resource-hashes`)"
+    );
+    assert_eq!(
+        error(r#"normalize.rules = [{ pattern = "(", replacement = "" }]"#),
+        "in rule #1 of `normalize.rules`: invalid pattern `(`: unclosed group"
+    );
+    assert_eq!(
+        error(r#"normalize.rules = [{ pattern = "a" }]"#),
+        "in rule #1 of `normalize.rules`: missing key `replacement`"
+    );
+    assert_eq!(
+        error(r#"budgets = { total = "lots" }"#),
+        "invalid size `lots` for `budgets.total`: expected a size like `5GB`"
+    );
+    assert_eq!(error("[budgets]\ntotal = lots"), "invalid string; expected `\"`, `'` on line 2");
+    assert_eq!(error("[budgets]\ntotal = "), "malformed TOML on line 2");
+}
+
+#[test]
+fn defaults() {
+    let config = parse(
+        r#"
+        [defaults]
+        toolchain = "nightly"
+        open = true
+        verbatim-flags = ["-Zunstable-options"]
+        env = { A = "1", B = false }
+        "#,
+    )
+    .ok()
+    .unwrap();
+
+    let defaults = &config.defaults;
+    assert_eq!(defaults.toolchain.as_deref(), Some("nightly"));
+    assert_eq!(defaults.theme, None);
+    assert_eq!(defaults.open, Some(true));
+    assert_eq!(defaults.verbatim_flags, ["-Zunstable-options"]);
+    assert_eq!(defaults.env, [("A".to_owned(), Some("1".to_owned())), ("B".to_owned(), None)]);
+}
+
+#[test]
+fn overlaid_defaults() {
+    let mut defaults =
+        parse(r#"defaults = { toolchain = "stable", theme = "dark" }"#).ok().unwrap().defaults;
+    defaults.overlay(parse(r#"defaults = { toolchain = "nightly" }"#).ok().unwrap().defaults);
+
+    assert_eq!(defaults.toolchain.as_deref(), Some("nightly"));
+    assert_eq!(defaults.theme.as_deref(), Some("dark"));
+}
+
+#[test]
+fn invalid_defaults() {
+    assert_eq!(
+        parse(r#"defaults = { open = "yes" }"#).err().unwrap(),
+        "`defaults.open` must be a boolean"
+    );
+    assert_eq!(
+        parse(r#"defaults = { edition = "2021" }"#).err().unwrap(),
+        "unknown key `defaults.edition`"
+    );
+    assert_eq!(
+        parse("defaults = { env = { A = true } }").err().unwrap(),
+        "`defaults.env.A` must be a string"
+    );
+}
+
+#[test]
+fn pipelines() {
+    let config =
+        parse(r#"pipelines = { verify = [["-T", "-Q"], ["diff", "--new-toolchain", "beta"]] }"#)
+            .ok()
+            .unwrap();
+    assert_eq!(config.pipelines, [("verify".to_owned(), vec![
+        vec!["-T".to_owned(), "-Q".to_owned()],
+        vec!["diff".to_owned(), "--new-toolchain".to_owned(), "beta".to_owned()],
+    ])]);
+
+    assert_eq!(
+        parse(r#"pipelines = { all = [["run", "verify"]] }"#).err().unwrap(),
+        "step #1 of `pipelines.all` runs another pipeline"
+    );
+    assert_eq!(
+        parse(r#"pipelines = { verify = ["-T"] }"#).err().unwrap(),
+        "step #1 of `pipelines.verify` must be an array"
+    );
+}
+
+#[test]
+fn lints() {
+    let config = parse(
+        r#"
+        [lints.rustdoc]
+        broken_intra_doc_links = "deny"
+        all = { level = "warn", priority = -1 }
+
+        [lints.rust]
+        missing_docs = { level = "allow" }
+        "#,
+    )
+    .ok()
+    .unwrap();
+
+    let lints: Vec<_> =
+        config.lints.iter().map(|(name, level)| (name.as_str(), level.as_str())).collect();
+    assert_eq!(lints, [
+        ("rustdoc::all", "warn"),
+        ("missing_docs", "allow"),
+        ("rustdoc::broken_intra_doc_links", "deny"),
+    ]);
+
+    let error = parse(r#"lints.rust = { missing_docs = "loud" }"#).err().unwrap();
+    assert!(error.starts_with("invalid level `loud` for `lints.rust.missing_docs`"));
+    assert!(parse("lints.cargo = {}").is_err());
+}
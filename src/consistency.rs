@@ -0,0 +1,324 @@
+//! Cross-checking the JSON output against the HTML output of rustdoc.
+//!
+//! Since the JSON output doesn't tell us which re-exports got inlined in the HTML output,
+//! we approximate rustdoc's behavior: Re-exports of otherwise unreachable items, of items
+//! from other crates and re-exports marked `#[doc(inline)]` are considered to be inlined.
+//!
+//! Modules from other crates that get inlined are opaque to us since the JSON output
+//! doesn't contain their items. We therefore skip any HTML pages found inside of them.
+//! Notably, this renders the check rather weak in cross-crate mode.
+
+use crate::{
+    data::CrateNameRef,
+    diagnostic::{Diagnostic, IntoDiagnostic, error},
+    json::{id_to_key, item_kind},
+};
+use rustc_hash::FxHashSet;
+use serde_json::Value;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(test)]
+mod test;
+
+/// Check that the JSON and the HTML output of the given crate found in `root` match.
+pub(crate) fn check(root: &Path, crate_name: CrateNameRef<'_>) -> crate::error::Result {
+    let json = std::fs::read(root.join(format!("{crate_name}.json")))?;
+    let json: Value = serde_json::from_slice(&json).map_err(io::Error::from)?;
+    let crate_root = root.join(crate_name.as_str());
+
+    let mut locations = Locations { json: &json, ..Default::default() };
+    locations.entries.push(Location {
+        path: crate_name.to_string(),
+        page: "index.html".into(),
+        anchors: Vec::new(),
+    });
+    locations.collect(&json["root"], &mut vec![crate_name.to_string()]);
+    locations.collect_reexports();
+
+    let mut mismatches = Vec::new();
+    let mut pages = FxHashSet::default();
+
+    for location in &locations.entries {
+        let page = crate_root.join(&location.page);
+        let Ok(contents) = std::fs::read_to_string(&page) else {
+            mismatches
+                .push(format!("missing HTML page `{}` for `{}`", location.page, location.path));
+            continue;
+        };
+
+        for anchor in &location.anchors {
+            if !contents.contains(&format!("id=\"{anchor}\"")) {
+                mismatches.push(format!(
+                    "missing HTML anchor `{}#{anchor}` for `{}`",
+                    location.page, location.path
+                ));
+            }
+        }
+
+        pages.insert(page);
+    }
+
+    for page in html_pages(&crate_root)? {
+        if pages.contains(&page) || is_redirect(&page)? {
+            continue;
+        }
+
+        let page = page.strip_prefix(&crate_root).unwrap_or(&page);
+        if locations.opaque.iter().any(|module| page.starts_with(module)) {
+            continue;
+        }
+
+        mismatches.push(format!("missing JSON item for HTML page `{}`", page.display()));
+    }
+
+    if !mismatches.is_empty() {
+        return Err(Error { mismatches }.into());
+    }
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct Locations<'a> {
+    json: &'a Value,
+    entries: Vec<Location>,
+    /// The IDs of the items that have already been visited.
+    visited: FxHashSet<String>,
+    /// The re-exports alongside their module path which get processed last so we know
+    /// which items are reachable by other means.
+    reexports: Vec<(&'a Value, Vec<String>)>,
+    /// The modules (relative to the crate root) that contain inlined items unknown to us.
+    opaque: Vec<PathBuf>,
+}
+
+/// An item of the JSON output together with the HTML page it's expected on.
+struct Location {
+    path: String,
+    page: String,
+    anchors: Vec<String>,
+}
+
+impl<'a> Locations<'a> {
+    fn item(&self, id: &Value) -> Option<&'a Value> {
+        self.json["index"].get(id_to_key(id))
+    }
+
+    fn collect(&mut self, module: &Value, path: &mut Vec<String>) {
+        let Some(module) = self.item(module) else { return };
+        self.visited.insert(id_to_key(&module["id"]));
+
+        let items = module["inner"]["module"]["items"].as_array().into_iter().flatten();
+
+        for id in items {
+            let Some(item) = self.item(id) else { continue };
+            match item_kind(item) {
+                Some("use") => self.reexports.push((item, path.clone())),
+                Some(_) => self.collect_item(item, None, path),
+                None => {}
+            }
+        }
+    }
+
+    fn collect_reexports(&mut self) {
+        // Processing re-exports may uncover further re-exports (e.g., via glob re-exports).
+        while !self.reexports.is_empty() {
+            for (item, mut path) in std::mem::take(&mut self.reexports) {
+                let reexport = &item["inner"]["use"];
+                let Some(target) = self.item(&reexport["id"]) else {
+                    self.collect_foreign_item(reexport, &path);
+                    continue;
+                };
+                if self.visited.contains(&id_to_key(&target["id"])) && !is_inline(item) {
+                    continue;
+                }
+
+                if reexport["is_glob"] == true {
+                    if item_kind(target) == Some("module") {
+                        self.collect(&target["id"], &mut path);
+                    }
+                } else {
+                    self.collect_item(target, reexport["name"].as_str(), &mut path);
+                }
+            }
+        }
+    }
+
+    fn collect_item(&mut self, item: &Value, name: Option<&str>, path: &mut Vec<String>) {
+        let Some(name) = name.or(item["name"].as_str()) else { return };
+        let Some(kind) = item_kind(item) else { return };
+
+        if kind == "module" {
+            path.push(name.to_owned());
+            self.entries.push(Location {
+                path: path.join("::"),
+                page: format!("{}index.html", module_prefix(&path[1..])),
+                anchors: Vec::new(),
+            });
+            self.collect(&item["id"], path);
+            path.pop();
+            return;
+        }
+
+        let Some(prefix) = page_prefix(kind, &item["inner"][kind]) else { return };
+        self.visited.insert(id_to_key(&item["id"]));
+
+        self.entries.push(Location {
+            path: format!("{}::{name}", path.join("::")),
+            page: format!("{}{prefix}.{name}.html", module_prefix(&path[1..])),
+            anchors: self.anchors(kind, &item["inner"][kind]),
+        });
+    }
+
+    /// Collect the target of a re-export of an item from another crate.
+    fn collect_foreign_item(&mut self, reexport: &Value, path: &[String]) {
+        let kind = self.json["paths"][id_to_key(&reexport["id"])]["kind"].as_str();
+        let prefix = kind.and_then(|kind| page_prefix(kind, &Value::Null));
+
+        match (prefix, reexport["name"].as_str()) {
+            (Some(prefix), Some(name)) if reexport["is_glob"] != true => {
+                self.entries.push(Location {
+                    path: format!("{}::{name}", path.join("::")),
+                    page: format!("{}{prefix}.{name}.html", module_prefix(&path[1..])),
+                    anchors: Vec::new(),
+                });
+            }
+            // Glob re-exports or re-exports of modules (of unknown kind).
+            _ => {
+                let mut module: PathBuf = path[1..].iter().collect();
+                if reexport["is_glob"] != true
+                    && let Some(name) = reexport["name"].as_str()
+                {
+                    module.push(name);
+                }
+                self.opaque.push(module);
+            }
+        }
+    }
+
+    /// The anchors of the fields, variants and associated items of the given item.
+    fn anchors(&self, kind: &str, inner: &Value) -> Vec<String> {
+        let ids = match kind {
+            "struct" => match &inner["kind"] {
+                Value::Object(kind) if kind.contains_key("plain") => {
+                    &inner["kind"]["plain"]["fields"]
+                }
+                Value::Object(kind) if kind.contains_key("tuple") => &inner["kind"]["tuple"],
+                _ => &Value::Null,
+            },
+            "union" => &inner["fields"],
+            "enum" => &inner["variants"],
+            "trait" => &inner["items"],
+            _ => &Value::Null,
+        };
+
+        ids.as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(index, id)| {
+                let item = self.item(id)?;
+                let name = item["name"].as_str();
+                let prefix = match item_kind(item)? {
+                    "struct_field" => "structfield",
+                    "variant" => "variant",
+                    "function" if item["inner"]["function"]["has_body"] == true => "method",
+                    "function" => "tymethod",
+                    "assoc_type" => "associatedtype",
+                    "assoc_const" => "associatedconstant",
+                    _ => return None,
+                };
+                // Tuple fields are named after their index.
+                let name = name.map_or_else(|| index.to_string(), ToOwned::to_owned);
+                Some(format!("{prefix}.{name}"))
+            })
+            .collect()
+    }
+}
+
+/// The file name prefix rustdoc uses for pages of the given kind of item.
+///
+/// Returns `None` if items of this kind don't get a page of their own.
+pub(crate) fn page_prefix(kind: &str, inner: &Value) -> Option<&'static str> {
+    Some(match kind {
+        "struct" => "struct",
+        "enum" => "enum",
+        "union" => "union",
+        "trait" => "trait",
+        "trait_alias" => "traitalias",
+        "function" => "fn",
+        "type_alias" => "type",
+        "constant" => "constant",
+        "static" => "static",
+        "macro" => "macro",
+        "proc_macro" => match inner["kind"].as_str()? {
+            "attr" => "attr",
+            "derive" => "derive",
+            _ => "macro",
+        },
+        // These come from the `paths` of the JSON output.
+        "proc_attribute" => "attr",
+        "proc_derive" => "derive",
+        "extern_type" => "foreigntype",
+        "primitive" => "primitive",
+        _ => return None,
+    })
+}
+
+fn module_prefix(modules: &[String]) -> String {
+    modules.iter().flat_map(|module| [module.as_str(), "/"]).collect()
+}
+
+fn is_inline(reexport: &Value) -> bool {
+    reexport["attrs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|attr| attr.as_str().is_some_and(|attr| attr.replace(' ', "") == "#[doc(inline)]"))
+}
+
+/// The HTML pages of items, i.e., excluding auxiliary pages like `all.html`.
+fn html_pages(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut pages = Vec::new();
+    let mut directories = vec![root.to_owned()];
+
+    while let Some(directory) = directories.pop() {
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                directories.push(path);
+                continue;
+            }
+
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else { continue };
+            let is_item_page =
+                name == "index.html" || name.ends_with(".html") && name.split('.').count() >= 3;
+            if is_item_page {
+                pages.push(path);
+            }
+        }
+    }
+
+    pages.sort();
+    Ok(pages)
+}
+
+pub(crate) fn is_redirect(page: &Path) -> io::Result<bool> {
+    Ok(std::fs::read_to_string(page)?.contains("http-equiv=\"refresh\""))
+}
+
+pub(crate) struct Error {
+    mismatches: Vec<String>,
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        let amount = self.mismatches.len();
+        let s = if amount == 1 { "y" } else { "ies" };
+        let diagnostic =
+            error(format!("found {amount} inconsistenc{s} between the JSON and the HTML output"));
+        self.mismatches.into_iter().fold(diagnostic, Diagnostic::note)
+    }
+}
@@ -0,0 +1,152 @@
+use super::{Locations, page_prefix};
+use serde_json::{Value, json};
+use std::path::PathBuf;
+
+/// An item path together with its expected page and anchors.
+type Entry = (String, String, Vec<String>);
+
+/// The expected pages (and anchors) of the items in the given crate `krate`.
+fn locations(json: &Value) -> (Vec<Entry>, Vec<PathBuf>) {
+    let mut locations = Locations { json, ..Default::default() };
+    locations.collect(&json["root"], &mut vec!["krate".to_owned()]);
+    locations.collect_reexports();
+    let entries = locations
+        .entries
+        .into_iter()
+        .map(|location| (location.path, location.page, location.anchors))
+        .collect();
+    (entries, locations.opaque)
+}
+
+fn entry(path: &str, page: &str, anchors: &[&str]) -> Entry {
+    (path.to_owned(), page.to_owned(), anchors.iter().map(|&anchor| anchor.to_owned()).collect())
+}
+
+#[test]
+fn items_and_anchors() {
+    let json = json!({
+        "root": 0,
+        "index": {
+            "0": { "id": 0, "name": "krate", "inner": { "module": { "items": [1, 2, 5, 7] } } },
+            "1": { "id": 1, "name": "f", "inner": { "function": { "has_body": true } } },
+            "2": {
+                "id": 2,
+                "name": "S",
+                "inner": { "struct": { "kind": { "plain": { "fields": [3] } } } },
+            },
+            "3": { "id": 3, "name": "field", "inner": { "struct_field": {} } },
+            "5": { "id": 5, "name": "m", "inner": { "module": { "items": [6] } } },
+            "6": { "id": 6, "name": "E", "inner": { "enum": { "variants": [8] } } },
+            "7": { "id": 7, "name": "T", "inner": { "trait": { "items": [9, 10, 11] } } },
+            "8": { "id": 8, "name": "V", "inner": { "variant": {} } },
+            "9": { "id": 9, "name": "required", "inner": { "function": { "has_body": false } } },
+            "10": { "id": 10, "name": "provided", "inner": { "function": { "has_body": true } } },
+            "11": { "id": 11, "name": "Assoc", "inner": { "assoc_type": {} } },
+        },
+        "paths": {},
+    });
+
+    let (entries, opaque) = locations(&json);
+    assert_eq!(entries, [
+        entry("krate::f", "fn.f.html", &[]),
+        entry("krate::S", "struct.S.html", &["structfield.field"]),
+        entry("krate::m", "m/index.html", &[]),
+        entry("krate::m::E", "m/enum.E.html", &["variant.V"]),
+        entry("krate::T", "trait.T.html", &[
+            "tymethod.required",
+            "method.provided",
+            "associatedtype.Assoc"
+        ]),
+    ]);
+    assert!(opaque.is_empty());
+}
+
+#[test]
+fn tuple_fields() {
+    let json = json!({
+        "root": 0,
+        "index": {
+            "0": { "id": 0, "name": "krate", "inner": { "module": { "items": [1] } } },
+            "1": { "id": 1, "name": "S", "inner": { "struct": { "kind": { "tuple": [2, 3] } } } },
+            "2": { "id": 2, "name": null, "inner": { "struct_field": {} } },
+            "3": { "id": 3, "name": null, "inner": { "struct_field": {} } },
+        },
+        "paths": {},
+    });
+
+    let (entries, _) = locations(&json);
+    assert_eq!(entries, [entry("krate::S", "struct.S.html", &["structfield.0", "structfield.1"])]);
+}
+
+#[test]
+fn reexports() {
+    let json = json!({
+        "root": "0:0",
+        "index": {
+            "0:0": {
+                "id": "0:0",
+                "name": "krate",
+                "inner": { "module": { "items": ["0:1", "0:2", "0:3", "0:4", "0:9"] } },
+            },
+            // A re-export of an otherwise unreachable item gets inlined.
+            "0:1": {
+                "id": "0:1",
+                "attrs": [],
+                "inner": { "use": { "id": "0:5", "name": "Renamed", "is_glob": false } },
+            },
+            // A re-export of a reachable item doesn't get inlined unless marked as such.
+            "0:2": {
+                "id": "0:2",
+                "attrs": [],
+                "inner": { "use": { "id": "0:9", "name": "Alias", "is_glob": false } },
+            },
+            "0:3": {
+                "id": "0:3",
+                "attrs": ["#[doc(inline)]"],
+                "inner": { "use": { "id": "0:9", "name": "Inlined", "is_glob": false } },
+            },
+            // A glob re-export of a module of another crate is opaque.
+            "0:4": {
+                "id": "0:4",
+                "attrs": [],
+                "inner": { "use": { "id": "1:0", "name": "dep", "is_glob": true } },
+            },
+            "0:5": { "id": "0:5", "name": "Private", "inner": { "struct": { "kind": "unit" } } },
+            "0:9": { "id": "0:9", "name": "Public", "inner": { "struct": { "kind": "unit" } } },
+        },
+        "paths": { "1:0": { "kind": "module" } },
+    });
+
+    let (entries, opaque) = locations(&json);
+    assert_eq!(entries, [
+        entry("krate::Public", "struct.Public.html", &[]),
+        entry("krate::Renamed", "struct.Renamed.html", &[]),
+        entry("krate::Inlined", "struct.Inlined.html", &[]),
+    ]);
+    assert_eq!(opaque, [PathBuf::new()]);
+}
+
+#[test]
+fn foreign_reexports() {
+    let json = json!({
+        "root": 0,
+        "index": {
+            "0": { "id": 0, "name": "krate", "inner": { "module": { "items": [1, 2] } } },
+            "1": { "id": 1, "inner": { "use": { "id": 20, "name": "Dep", "is_glob": false } } },
+            "2": { "id": 2, "inner": { "use": { "id": 21, "name": "utils", "is_glob": false } } },
+        },
+        "paths": { "20": { "kind": "proc_derive" }, "21": { "kind": "module" } },
+    });
+
+    let (entries, opaque) = locations(&json);
+    assert_eq!(entries, [entry("krate::Dep", "derive.Dep.html", &[])]);
+    assert_eq!(opaque, [PathBuf::from("utils")]);
+}
+
+#[test]
+fn page_prefixes() {
+    assert_eq!(page_prefix("type_alias", &Value::Null), Some("type"));
+    assert_eq!(page_prefix("proc_macro", &json!({ "kind": "attr" })), Some("attr"));
+    assert_eq!(page_prefix("proc_macro", &json!({ "kind": "bang" })), Some("macro"));
+    assert_eq!(page_prefix("impl", &Value::Null), None);
+}
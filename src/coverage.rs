@@ -0,0 +1,121 @@
+//! Reporting the documentation coverage (`--coverage`).
+//!
+//! Without `-j`/`--json`, rustdoc prints a table per crate itself. With it, rustdoc reports the
+//! coverage of each file as JSON instead which we collect across all documented crates (e.g., the
+//! auxiliary ones) and render as a single table, sorted from the least to the most documented file.
+
+use serde_json::Value;
+use std::sync::Mutex;
+
+#[cfg(test)]
+mod test;
+
+static FILES: Mutex<Vec<File>> = Mutex::new(Vec::new());
+
+struct File {
+    crate_name: String,
+    path: String,
+    counts: Counts,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Counts {
+    total: u64,
+    with_docs: u64,
+    total_examples: u64,
+    with_examples: u64,
+}
+
+impl Counts {
+    fn add(&mut self, other: Self) {
+        self.total += other.total;
+        self.with_docs += other.with_docs;
+        self.total_examples += other.total_examples;
+        self.with_examples += other.with_examples;
+    }
+
+    fn documented(self) -> f64 {
+        percentage(self.with_docs, self.total)
+    }
+
+    fn examples(self) -> f64 {
+        percentage(self.with_examples, self.total_examples)
+    }
+}
+
+fn percentage(part: u64, total: u64) -> f64 {
+    // Like rustdoc, consider a file without any items to be undocumented.
+    if total == 0 { 0.0 } else { part as f64 * 100.0 / total as f64 }
+}
+
+/// Record the coverage of the given crate as reported by rustdoc in JSON.
+pub(crate) fn record(crate_name: &str, report: &Value) {
+    FILES.lock().unwrap().extend(parse(crate_name, report));
+}
+
+fn parse(crate_name: &str, report: &Value) -> Vec<File> {
+    let count = |file: &Value, key| file[key].as_u64().unwrap_or_default();
+
+    report
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(path, file)| File {
+            crate_name: crate_name.to_owned(),
+            path: path.clone(),
+            counts: Counts {
+                total: count(file, "total"),
+                with_docs: count(file, "with_docs"),
+                total_examples: count(file, "total_examples"),
+                with_examples: count(file, "with_examples"),
+            },
+        })
+        .collect()
+}
+
+/// Print the table of the coverage recorded so far and forget about it.
+pub(crate) fn report() {
+    let mut files = std::mem::take(&mut *FILES.lock().unwrap());
+    print!("{}", render(&mut files));
+}
+
+fn render(files: &mut [File]) -> String {
+    files.sort_by(|a, b| {
+        a.counts
+            .documented()
+            .total_cmp(&b.counts.documented())
+            .then_with(|| (&a.crate_name, &a.path).cmp(&(&b.crate_name, &b.path)))
+    });
+
+    let mut total = Counts::default();
+    for file in &*files {
+        total.add(file.counts);
+    }
+
+    let label = |file: &File| format!("{}: {}", file.crate_name, file.path);
+    let width = files.iter().map(|file| label(file).len()).chain(["File".len()]).max().unwrap();
+
+    let separator =
+        format!("+-{:-<width$}-+------------+------------+------------+------------+\n", "");
+    let row = |label: &str, counts: Counts| {
+        format!(
+            "| {label:<width$} | {:>10} | {:>9.1}% | {:>10} | {:>9.1}% |\n",
+            counts.with_docs,
+            counts.documented(),
+            counts.with_examples,
+            counts.examples(),
+        )
+    };
+
+    let mut table = separator.clone();
+    table +=
+        &format!("| {:<width$} | Documented | Percentage |   Examples | Percentage |\n", "File");
+    table += &separator;
+    for file in &*files {
+        table += &row(&label(file), file.counts);
+    }
+    table += &separator;
+    table += &row("Total", total);
+    table += &separator;
+    table
+}
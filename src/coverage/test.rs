@@ -0,0 +1,32 @@
+use super::{parse, render};
+use serde_json::json;
+
+#[test]
+fn table() {
+    let mut files = parse(
+        "lib",
+        &json!({
+            "lib.rs": { "total": 3, "with_docs": 2, "total_examples": 3, "with_examples": 1 },
+            "m.rs": { "total": 3, "with_docs": 1, "total_examples": 3, "with_examples": 0 },
+        }),
+    );
+    files.extend(parse(
+        "dep",
+        &json!({ "dep.rs": { "total": 2, "with_docs": 2, "total_examples": 0, "with_examples": 0 } }),
+    ));
+
+    assert_eq!(
+        render(&mut files),
+        "\
++-------------+------------+------------+------------+------------+
+| File        | Documented | Percentage |   Examples | Percentage |
++-------------+------------+------------+------------+------------+
+| lib: m.rs   |          1 |      33.3% |          0 |       0.0% |
+| lib: lib.rs |          2 |      66.7% |          1 |      33.3% |
+| dep: dep.rs |          2 |     100.0% |          0 |       0.0% |
++-------------+------------+------------+------------+------------+
+| Total       |          5 |      62.5% |          1 |      16.7% |
++-------------+------------+------------+------------+------------+
+"
+    );
+}
@@ -0,0 +1,397 @@
+//! An interactive terminal dashboard for batch runs (`--dashboard`).
+//!
+//! Each test is run in a child process of its own (by re-invoking ourselves with the same
+//! arguments, restricted to the test via the environment variable [`TEST_VARIABLE`]), so its
+//! output can be captured into a log instead of garbling the screen.
+//!
+//! The screen is drawn with `ratatui` on top of `crossterm` which also takes care of switching
+//! the terminal into raw mode, so this works on any platform `crossterm` supports.
+
+use crate::{
+    batch::{Outcome, Summary},
+    cli,
+    condition::Unmet,
+    utility::{scratch_dir, strip_ansi_escapes},
+};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Layout},
+    style::Stylize,
+    text::{Line, Span},
+    widgets::{List, ListState, Paragraph},
+};
+use std::{
+    fs::File,
+    io::{self, Stdout},
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, Instant},
+};
+
+#[cfg(test)]
+mod test;
+
+/// The environment variable that restricts a child process to a single test.
+pub(crate) const TEST_VARIABLE: &str = "RRUXWRY_DASHBOARD_TEST";
+
+/// Run the given tests while showing their status live and let the user inspect the results.
+pub(crate) fn run(paths: &[PathBuf], flags: &cli::ProgramFlags) -> crate::error::Result {
+    let directory = scratch_dir(flags)?.join(format!("rruxwry-dashboard-{}", process::id()));
+    std::fs::create_dir_all(&directory)?;
+
+    let mut dashboard = Dashboard {
+        tests: paths
+            .iter()
+            .enumerate()
+            .map(|(index, path)| Test {
+                path: path.clone(),
+                log: directory.join(format!("{index}.log")),
+                status: Status::Pending,
+            })
+            .collect(),
+        selected: 0,
+        failures_only: false,
+        filter: String::new(),
+    };
+
+    let mut terminal = Terminal::enter()?;
+    let result = dashboard.execute(&mut terminal);
+    drop(terminal);
+
+    let mut summary = Summary::default();
+    for test in dashboard.tests {
+        let outcome = match test.status {
+            Status::Passed(_) => Outcome::Success,
+            Status::Skipped(unmet) => Outcome::Skipped(unmet),
+            _ => Outcome::Failure,
+        };
+        summary.record(test.path.display().to_string(), outcome);
+    }
+    std::fs::remove_dir_all(&directory)?;
+
+    result?;
+    summary.publish();
+    Ok(summary.into_result(0)?)
+}
+
+struct Dashboard {
+    tests: Vec<Test>,
+    /// The index of the selected test among the visible ones.
+    selected: usize,
+    failures_only: bool,
+    filter: String,
+}
+
+struct Test {
+    path: PathBuf,
+    log: PathBuf,
+    status: Status,
+}
+
+enum Status {
+    Pending,
+    Running,
+    Passed(Duration),
+    Failed(Duration),
+    Skipped(Unmet),
+}
+
+enum Key {
+    Up,
+    Down,
+    Char(char),
+    Enter,
+    Backspace,
+    Escape,
+}
+
+impl Dashboard {
+    fn execute(&mut self, terminal: &mut Terminal) -> io::Result<()> {
+        for index in 0..self.tests.len() {
+            self.rerun(index, terminal)?;
+        }
+
+        loop {
+            self.draw(terminal, None)?;
+
+            let visible = self.visible();
+            let selected = visible.get(self.selected).copied();
+
+            match (terminal.read_key()?, selected) {
+                (Key::Up | Key::Char('k'), _) => self.selected = self.selected.saturating_sub(1),
+                (Key::Down | Key::Char('j'), _) => {
+                    self.selected = (self.selected + 1).min(visible.len().saturating_sub(1));
+                }
+                (Key::Char('r') | Key::Enter, Some(index)) => self.rerun(index, terminal)?,
+                (Key::Char('R'), _) => {
+                    let failed: Vec<_> = (0..self.tests.len())
+                        .filter(|&index| matches!(self.tests[index].status, Status::Failed(_)))
+                        .collect();
+                    for index in failed {
+                        self.rerun(index, terminal)?;
+                    }
+                }
+                (Key::Char('o'), Some(index)) => {
+                    terminal.suspend(|| page(&self.tests[index].log))?;
+                }
+                (Key::Char('f'), _) => {
+                    self.failures_only = !self.failures_only;
+                    self.selected = 0;
+                }
+                (Key::Char('/'), _) => {
+                    self.edit_filter(terminal)?;
+                    self.selected = 0;
+                }
+                (Key::Char('q') | Key::Escape, _) => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    fn rerun(&mut self, index: usize, terminal: &mut Terminal) -> io::Result<()> {
+        self.tests[index].status = Status::Running;
+        self.draw(terminal, None)?;
+        self.tests[index].status = execute(&self.tests[index])?;
+        Ok(())
+    }
+
+    fn edit_filter(&mut self, terminal: &mut Terminal) -> io::Result<()> {
+        loop {
+            let filter = self.filter.clone();
+            self.draw(terminal, Some(&filter))?;
+            match terminal.read_key()? {
+                Key::Enter => return Ok(()),
+                Key::Escape => {
+                    self.filter.clear();
+                    return Ok(());
+                }
+                Key::Backspace => {
+                    self.filter.pop();
+                }
+                Key::Char(char) => self.filter.push(char),
+                _ => {}
+            }
+        }
+    }
+
+    /// The indices of the tests matching the current filters.
+    fn visible(&self) -> Vec<usize> {
+        (0..self.tests.len())
+            .filter(|&index| {
+                let test = &self.tests[index];
+                (!self.failures_only || matches!(test.status, Status::Failed(_)))
+                    && test.path.to_string_lossy().contains(&self.filter)
+            })
+            .collect()
+    }
+
+    fn draw(&self, terminal: &mut Terminal, filter: Option<&str>) -> io::Result<()> {
+        let visible = self.visible();
+
+        let items: Vec<_> = visible
+            .iter()
+            .map(|&index| {
+                let test = &self.tests[index];
+                let mut line = status(&test.status);
+                line.push_span(format!(" {}", test.path.display()));
+                line
+            })
+            .collect();
+        let list = match items.is_empty() {
+            true => List::new(["  (no matching tests)"]),
+            false => List::new(items).highlight_symbol("> "),
+        };
+        let mut state = ListState::default().with_selected(Some(self.selected));
+
+        let footer = match filter {
+            Some(filter) => Line::raw(format!("filter: {filter}█")),
+            None => Line::raw(
+                "j/k: move  r: re-run  R: re-run failed  o: open output  \
+                 f: failures only  /: filter  q: quit",
+            )
+            .dim(),
+        };
+
+        terminal.screen.draw(|frame| {
+            // The header, the list and the footer, separated by blank lines.
+            let [header, _, body, _, bottom] = Layout::vertical([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Min(1),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
+            .areas(frame.area());
+
+            frame.render_widget(Paragraph::new(self.header()), header);
+            frame.render_stateful_widget(list, body, &mut state);
+            frame.render_widget(Paragraph::new(footer), bottom);
+        })?;
+        Ok(())
+    }
+
+    fn header(&self) -> Line<'static> {
+        let count = |predicate: fn(&Status) -> bool| {
+            self.tests.iter().filter(|test| predicate(&test.status)).count()
+        };
+        let passed = count(|status| matches!(status, Status::Passed(_)));
+        let failed = count(|status| matches!(status, Status::Failed(_)));
+        let skipped = count(|status| matches!(status, Status::Skipped(_)));
+        let pending = count(|status| matches!(status, Status::Pending | Status::Running));
+
+        let amount = self.tests.len();
+        let s = if amount == 1 { "" } else { "s" };
+        let mut rest = format!(" failed, {skipped} skipped");
+        if pending != 0 {
+            rest += &format!(", {pending} pending");
+        }
+        if self.failures_only {
+            rest += " (failures only)";
+        }
+        if !self.filter.is_empty() {
+            rest += &format!(" (matching `{}`)", self.filter);
+        }
+
+        Line::from(vec![
+            Span::raw(format!("{amount} test{s}: ")),
+            passed.to_string().green(),
+            Span::raw(" passed, "),
+            failed.to_string().red(),
+            Span::raw(rest),
+        ])
+        .bold()
+    }
+}
+
+/// The status column of a test, padded to a fixed width.
+fn status(status: &Status) -> Line<'static> {
+    match status {
+        Status::Pending => {
+            Line::from(vec![format!("{:<7}", "pending").dim(), " ".repeat(8).into()])
+        }
+        Status::Running => {
+            Line::from(vec![format!("{:<7}", "running").cyan(), " ".repeat(8).into()])
+        }
+        Status::Passed(duration) => {
+            Line::from(vec!["ok".green(), format!("      {duration:>7.2?}").into()])
+        }
+        Status::Failed(duration) => {
+            Line::from(vec!["FAILED".red(), format!("  {duration:>7.2?}").into()])
+        }
+        Status::Skipped(_) => {
+            Line::from(vec![format!("{:<7}", "skipped").yellow(), " ".repeat(8).into()])
+        }
+    }
+}
+
+/// Run the given test in a child process, capturing its output in its log.
+fn execute(test: &Test) -> io::Result<Status> {
+    let log = File::create(&test.log)?;
+    let start = Instant::now();
+    let status = process::Command::new(std::env::current_exe()?)
+        .args(std::env::args_os().skip(1))
+        .env(TEST_VARIABLE, &test.path)
+        .stdin(process::Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .status()?;
+    let duration = start.elapsed();
+
+    if !status.success() {
+        return Ok(Status::Failed(duration));
+    }
+
+    let output = std::fs::read_to_string(&test.log)?;
+    Ok(match skipped(&output, &test.path) {
+        Some(unmet) => Status::Skipped(unmet),
+        None => Status::Passed(duration),
+    })
+}
+
+/// Find out from the output of the child process whether it skipped the test.
+fn skipped(output: &str, path: &Path) -> Option<Unmet> {
+    let output = strip_ansi_escapes(output);
+    let message = format!("info: skipping `{}`", path.display());
+    let mut lines = output.lines().skip_while(|line| *line != message);
+    lines.next()?;
+    let note = lines.next()?.trim().strip_prefix("note: ")?;
+    let (directive, reason) = note.split_once(": ")?;
+    Some(Unmet { directive: directive.to_owned(), reason: reason.to_owned() })
+}
+
+/// Show the given log in the pager.
+fn page(log: &Path) -> io::Result<()> {
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less -R".to_owned());
+    let mut pager = shlex::split(&pager).unwrap_or_default().into_iter();
+    let program = pager.next().unwrap_or_else(|| "less".to_owned());
+    process::Command::new(program).args(pager).arg(log).status()?;
+    Ok(())
+}
+
+/// The terminal in raw mode showing the alternate screen.
+struct Terminal {
+    screen: ratatui::Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl Terminal {
+    fn enter() -> io::Result<Self> {
+        let screen = ratatui::Terminal::new(CrosstermBackend::new(io::stdout()))?;
+        let mut terminal = Self { screen };
+        terminal.resume()?;
+        Ok(terminal)
+    }
+
+    fn resume(&mut self) -> io::Result<()> {
+        terminal::enable_raw_mode()?;
+        crossterm::execute!(io::stdout(), EnterAlternateScreen, cursor::Hide)?;
+        // Whatever was shown in the meantime has to be overdrawn completely.
+        self.screen.clear()
+    }
+
+    /// Restore the terminal temporarily, e.g., for the pager.
+    fn suspend(&mut self, action: impl FnOnce() -> io::Result<()>) -> io::Result<()> {
+        self.restore()?;
+        let result = action();
+        self.resume()?;
+        result
+    }
+
+    fn restore(&self) -> io::Result<()> {
+        crossterm::execute!(io::stdout(), cursor::Show, LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()
+    }
+
+    fn read_key(&self) -> io::Result<Key> {
+        loop {
+            let Event::Key(key) = event::read()? else { continue };
+            // Some platforms also report the release of keys.
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            return Ok(match key.code {
+                // The terminal no longer turns it into a signal in raw mode.
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Key::Escape,
+                KeyCode::Char(char) => Key::Char(char),
+                KeyCode::Up => Key::Up,
+                KeyCode::Down => Key::Down,
+                KeyCode::Enter => Key::Enter,
+                KeyCode::Backspace => Key::Backspace,
+                KeyCode::Esc => Key::Escape,
+                _ => continue,
+            });
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        // There's nothing sensible we can do if this fails.
+        let _ = self.restore();
+    }
+}
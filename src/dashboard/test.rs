@@ -0,0 +1,16 @@
+use super::skipped;
+use std::path::Path;
+
+#[test]
+fn skipped_tests() {
+    let output = "info: parser: found 0 crate attributes\n\
+                  info: skipping `ui/a.rs`\n      \
+                  note: only-windows: the target OS is `linux`\n";
+
+    let unmet = skipped(output, Path::new("ui/a.rs")).unwrap();
+    assert_eq!(unmet.directive, "only-windows");
+    assert_eq!(unmet.reason, "the target OS is `linux`");
+
+    assert!(skipped(output, Path::new("ui/b.rs")).is_none());
+    assert!(skipped("info: skipping `ui/a.rs`\n", Path::new("ui/a.rs")).is_none());
+}
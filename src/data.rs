@@ -1,12 +1,19 @@
 use std::{borrow::Cow, fmt, path::Path, str::FromStr};
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg(test)]
+mod test;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub(crate) enum Edition {
     #[default]
     Edition2015,
     Edition2018,
     Edition2021,
     Edition2024,
+    /// The edition that is currently in development (if any).
+    ///
+    /// Its semantics depend on the toolchain. It's always unstable.
+    Future,
 }
 
 impl Edition {
@@ -23,12 +30,14 @@ impl Edition {
             Self::Edition2018 => "2018",
             Self::Edition2021 => "2021",
             Self::Edition2024 => "2024",
+            Self::Future => "future",
         }
     }
 
     // FIXME: Derive this.
     pub(crate) fn elements() -> impl Iterator<Item = Self> + Clone {
-        [Self::Edition2015, Self::Edition2018, Self::Edition2021, Self::Edition2024].into_iter()
+        [Self::Edition2015, Self::Edition2018, Self::Edition2021, Self::Edition2024, Self::Future]
+            .into_iter()
     }
 }
 
@@ -41,6 +50,7 @@ impl FromStr for Edition {
             "2018" => Self::Edition2018,
             "2021" => Self::Edition2021,
             "2024" => Self::Edition2024,
+            "future" => Self::Future,
             _ => return Err(()),
         })
     }
@@ -86,11 +96,88 @@ impl FromStr for CrateType {
     }
 }
 
+/// The level of `RUST_BACKTRACE`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Backtrace {
+    Off,
+    On,
+    Full,
+}
+
+impl Backtrace {
+    pub(crate) const fn to_str(self) -> &'static str {
+        match self {
+            Self::Off => "0",
+            Self::On => "1",
+            Self::Full => "full",
+        }
+    }
+
+    pub(crate) fn elements() -> impl Iterator<Item = Self> + Clone {
+        [Self::Off, Self::On, Self::Full].into_iter()
+    }
+}
+
+impl FromStr for Backtrace {
+    type Err = ();
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Ok(match source {
+            "0" => Self::Off,
+            "1" => Self::On,
+            "full" => Self::Full,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// The format of diagnostics (`--error-format`).
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ErrorFormat {
+    Human,
+    HumanUnicode,
+    Short,
+    Json,
+}
+
+impl ErrorFormat {
+    pub(crate) fn is_stable(self) -> bool {
+        self != Self::HumanUnicode
+    }
+
+    pub(crate) const fn to_str(self) -> &'static str {
+        match self {
+            Self::Human => "human",
+            Self::HumanUnicode => "human-unicode",
+            Self::Short => "short",
+            Self::Json => "json",
+        }
+    }
+
+    pub(crate) fn elements() -> impl Iterator<Item = Self> + Clone {
+        [Self::Human, Self::HumanUnicode, Self::Short, Self::Json].into_iter()
+    }
+}
+
+impl FromStr for ErrorFormat {
+    type Err = ();
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Ok(match source {
+            "human" => Self::Human,
+            "human-unicode" => Self::HumanUnicode,
+            "short" => Self::Short,
+            "json" => Self::Json,
+            _ => return Err(()),
+        })
+    }
+}
+
 pub(crate) type CrateNameBuf = CrateName<String>;
 pub(crate) type CrateNameRef<'a> = CrateName<&'a str>;
 pub(crate) type CrateNameCow<'a> = CrateName<Cow<'a, str>>;
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(test, derive(Debug))]
 pub(crate) struct CrateName<T: AsRef<str>>(T);
 
@@ -129,24 +216,65 @@ impl<'src> CrateNameRef<'src> {
 
 impl CrateNameBuf {
     pub(crate) fn adjust_and_parse_file_path(path: &Path) -> Result<Self, ()> {
-        path.file_stem()
-            .and_then(|name| name.to_str())
-            .ok_or(())
-            .and_then(|name| Self::adjust_and_parse(name))
+        path.file_stem().and_then(|name| name.to_str()).ok_or(()).and_then(Self::adjust_and_parse)
     }
 
+    /// Parse a crate name leniently, adjusting it where possible.
+    ///
+    /// Hyphens get replaced with underscores and the prefix of raw identifiers gets stripped.
+    ///
+    /// Apart from that, the name has to be accepted by rustc (see `CrateNameRef::parse`) *and*
+    /// it has to be a valid (potentially raw) identifier so it can be referred to from other
+    /// crates. We deviate from the latter by also permitting leading digits since rustc does so,
+    /// too (think of file names like `0.rs`).
     pub(crate) fn adjust_and_parse(source: &str) -> Result<Self, ()> {
-        // NB: See the comment over in `CrateNameRef::parse` for why this makes sense.
-        if !source.is_empty()
-            && source.chars().all(|char| char.is_alphanumeric() || char == '_' || char == '-')
+        let source = source.strip_prefix("r#").unwrap_or(source);
+
+        if source.is_empty()
+            || !source.chars().all(|char| {
+                char == '-'
+                    || char == '_'
+                    || (char.is_alphanumeric() && ra_ap_rustc_lexer::is_id_continue(char))
+            })
         {
-            Ok(Self::new_unchecked(source.replace('-', "_")))
-        } else {
-            Err(())
+            return Err(());
+        }
+
+        let source = source.replace('-', "_");
+
+        // Path segment keywords can't be raw identifiers and thus can't be crate names.
+        if let "_" | "crate" | "self" | "Self" | "super" = &*source {
+            return Err(());
         }
+
+        Ok(Self::new_unchecked(source))
     }
 }
 
+impl<T: AsRef<str>> CrateName<T> {
+    /// Whether the crate name is a keyword in any edition and thus must be written as a raw identifier.
+    pub(crate) fn is_keyword(&self) -> bool {
+        KEYWORDS.contains(&self.as_str())
+    }
+
+    /// Render the crate name as an identifier, i.e., as a raw identifier if it's a keyword.
+    pub(crate) fn to_ident(&self) -> Cow<'_, str> {
+        match self.is_keyword() {
+            true => format!("r#{}", self.as_str()).into(),
+            false => self.as_str().into(),
+        }
+    }
+}
+
+/// The strict and reserved keywords of all editions excluding path segment keywords.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
 impl<T: AsRef<str>> CrateName<T> {
     pub(crate) fn as_ref(&self) -> CrateNameRef<'_> {
         CrateName(self.0.as_ref())
@@ -0,0 +1,51 @@
+use super::{CrateName, CrateNameBuf};
+use std::path::Path;
+
+fn adjust(source: &str) -> Result<CrateNameBuf, ()> {
+    CrateNameBuf::adjust_and_parse(source)
+}
+
+#[test]
+fn crate_name_plain() {
+    assert_eq!(adjust("krate"), Ok(CrateName::new_unchecked("krate".into())));
+    assert_eq!(adjust("0"), Ok(CrateName::new_unchecked("0".into())));
+}
+
+#[test]
+fn crate_name_hyphens() {
+    assert_eq!(adjust("issue-12345"), Ok(CrateName::new_unchecked("issue_12345".into())));
+}
+
+#[test]
+fn crate_name_raw_keyword() {
+    let name = adjust("r#async").unwrap();
+    assert_eq!(name.as_str(), "async");
+    assert_eq!(name.to_ident(), "r#async");
+    assert_eq!(adjust("async").unwrap().to_ident(), "r#async");
+    assert_eq!(adjust("asynchronous").unwrap().to_ident(), "asynchronous");
+}
+
+#[test]
+fn crate_name_path_keywords() {
+    assert_eq!(adjust("self"), Err(()));
+    assert_eq!(adjust("r#crate"), Err(()));
+    assert_eq!(adjust("_"), Err(()));
+    // The keyword check applies to the adjusted name.
+    assert_eq!(adjust("-"), Err(()));
+    assert_eq!(CrateNameBuf::adjust_and_parse_file_path(Path::new("-.rs")), Err(()));
+}
+
+#[test]
+fn crate_name_non_ascii() {
+    assert_eq!(adjust("köln"), Ok(CrateName::new_unchecked("köln".into())));
+    assert_eq!(adjust("東京"), Ok(CrateName::new_unchecked("東京".into())));
+    assert_eq!(adjust("①"), Err(()));
+}
+
+#[test]
+fn crate_name_invalid() {
+    assert_eq!(adjust(""), Err(()));
+    assert_eq!(adjust("r#"), Err(()));
+    assert_eq!(adjust("a.b"), Err(()));
+    assert_eq!(adjust("a b"), Err(()));
+}
@@ -1,4 +1,4 @@
-use crate::utility::Str;
+use crate::{html::escape, utility::Str};
 use owo_colors::{AnsiColors, OwoColorize};
 use std::fmt;
 
@@ -38,6 +38,24 @@ impl Diagnostic {
     pub(crate) fn emit(self) {
         eprintln!("{self}");
     }
+
+    /// Render the diagnostic as an HTML fragment (without colors).
+    pub(crate) fn render_html(&self) -> String {
+        let mut html = format!(
+            "<div class=\"{0}\"><p><strong>{0}</strong>: {1}</p>",
+            self.severity.name(),
+            escape(&self.message)
+        );
+        if !self.notes.is_empty() {
+            html += "<ul>";
+            for note in &self.notes {
+                html += &format!("<li><strong>note</strong>: {}</li>", escape(note));
+            }
+            html += "</ul>";
+        }
+        html += "</div>";
+        html
+    }
 }
 
 impl fmt::Display for Diagnostic {
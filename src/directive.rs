@@ -2,14 +2,19 @@
 
 use crate::{
     builder::QueryMode,
-    command::{ExternCrate, VerbatimFlagsBuf},
+    command::{ExternCrate, Origin, VerbatimFlagsBuf},
     data::{CrateNameRef, Edition},
     diagnostic::warning,
+    jsonpath::JsonPath,
     parser,
-    utility::default,
+    query::{self, Operand, Query, QueryKind},
+    stderr::{Check, CheckKind},
+    utility::{self, default},
+    xpath::XPath,
 };
 use joinery::JoinableIterator;
 use ra_ap_rustc_lexer::TokenKind;
+use regex::Regex;
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::{
     fmt,
@@ -18,6 +23,9 @@ use std::{
     str::CharIndices,
 };
 
+#[cfg(test)]
+mod test;
+
 #[derive(Default)]
 pub(crate) struct Directives<'src> {
     instantiated: InstantiatedDirectives<'src>,
@@ -50,7 +58,6 @@ impl<'src> Directives<'src> {
     }
 
     /// Instantiate all directives that are conditional on a revision.
-    #[allow(dead_code)] // FIXME: use this when impl'ing `--all-revs`
     pub(crate) fn instantiated(&self, revs: &FxHashSet<&str>) -> Self {
         let mut instantiated =
             Self { instantiated: self.instantiated.clone(), uninstantiated: default() };
@@ -92,17 +99,28 @@ impl<'src> DerefMut for Directives<'src> {
 
 struct DirectivesParser<'src> {
     parser: parser::SourceFileParser<'src>,
-    #[allow(dead_code)] // FIXME: use this when impl'ing htmldocck & jsondocck queries.
     query: Option<QueryMode>,
     directives: Directives<'src>,
+    /// The path of the previous query which can be referred to via `-`.
+    previous_path: Option<String>,
+    /// The tags of the queries that follow, as set by the last `//@ tags` directive.
+    tags: Vec<String>,
+    /// The (one-based) line of the current token.
+    line: usize,
 }
 
 impl<'src> DirectivesParser<'src> {
     fn new(source: &'src str, query: Option<QueryMode>) -> Self {
-        Self { parser: parser::SourceFileParser::new(source), query, directives: default() }
+        Self {
+            parser: parser::SourceFileParser::new(source),
+            query,
+            directives: default(),
+            previous_path: None,
+            tags: Vec::new(),
+            line: 1,
+        }
     }
 
-    // FIXME: Parse htmldocck/jsondocck queries
     fn execute(mut self) -> Directives<'src> {
         let mut report = Report::default();
 
@@ -111,7 +129,13 @@ impl<'src> DirectivesParser<'src> {
                 && let comment = self.parser.source()
                 && let Some(directive) = comment.strip_prefix("//@")
             {
-                match DirectiveParser::new(directive).execute() {
+                let directive = DirectiveParser::new(directive, self.line, self.query)
+                    .execute()
+                    .and_then(|directive| self.resolve_previous_path(directive));
+                match directive {
+                    Ok(Directive { kind: DirectiveKind::Tags(tags), .. }) => {
+                        self.tags = tags.into_iter().map(ToOwned::to_owned).collect();
+                    }
                     Ok(directive) => self.directives.add(directive),
                     // Emit a single error containing all unknown directives to avoid terminal spam.
                     Err(Error { kind: ErrorKind::UnknownDirective(directive), .. }) => {
@@ -121,22 +145,51 @@ impl<'src> DirectivesParser<'src> {
                 };
             }
 
+            self.line += self.parser.source().matches('\n').count();
             self.parser.advance();
         }
 
         report.publish();
         self.directives
     }
+
+    /// Replace the path `-` of a query with the path of the previous query.
+    fn resolve_previous_path(
+        &mut self,
+        mut directive: Directive<'src>,
+    ) -> Result<Directive<'src>, Error<'src>> {
+        if let DirectiveKind::Query(query) = &mut directive.kind {
+            query.tags.clone_from(&self.tags);
+        }
+
+        if let DirectiveKind::Query(query) = &mut directive.kind
+            && let Some(path) = query.kind.path_mut()
+        {
+            if path == "-" {
+                let Some(previous) = &self.previous_path else {
+                    return Err(Error::new(ErrorKind::MissingPreviousPath));
+                };
+                path.clone_from(previous);
+            } else {
+                self.previous_path = Some(path.clone());
+            }
+        }
+        Ok(directive)
+    }
 }
 
 #[derive(Default, Clone)]
 pub(crate) struct InstantiatedDirectives<'src> {
     pub(crate) dependencies: Vec<ExternCrate<'src>>,
     pub(crate) build_aux_docs: bool,
+    pub(crate) check_fail: bool,
+    pub(crate) conditions: Vec<Condition<'src>>,
     pub(crate) edition: Option<Edition>,
     pub(crate) force_host: bool,
     pub(crate) no_prefer_dynamic: bool,
+    pub(crate) queries: Vec<Query>,
     pub(crate) revisions: FxHashSet<&'src str>,
+    pub(crate) stderr_checks: Vec<Check<'src>>,
     pub(crate) verbatim_flags: VerbatimFlagsBuf<'src>,
 }
 
@@ -146,22 +199,32 @@ impl<'src> InstantiatedDirectives<'src> {
             DirectiveKind::AuxBuild { path } => {
                 self.dependencies.push(ExternCrate::Unnamed { path });
             }
-            DirectiveKind::AuxCrate { name, path } => {
-                self.dependencies.push(ExternCrate::Named { name, path: path.map(Into::into) })
-            }
+            DirectiveKind::AuxCrate { name, version, path } => self
+                .dependencies
+                .push(ExternCrate::Named { name, version, path: path.map(Into::into) }),
             DirectiveKind::BuildAuxDocs => self.build_aux_docs = true,
+            DirectiveKind::CheckFail => self.check_fail = true,
+            DirectiveKind::Condition(condition) => self.conditions.push(condition),
             // These flags can indeed conflict with flags generated by us to implement other directives.
             // However, that's just how it is, they are treated verbatim by `compiletest`, so we do the same.
-            DirectiveKind::CompileFlags(flags) => self.verbatim_flags.arguments.extend(flags),
+            DirectiveKind::CompileFlags { arguments, line } => {
+                self.verbatim_flags.extend(arguments, Origin::Directive { line })
+            }
             // FIXME: Emit an error or warning if multiple `edition` directives were specified
             //        just like `compiletest` does.
             DirectiveKind::Edition(edition) => self.edition = Some(edition),
             DirectiveKind::ForceHost => self.force_host = true,
             DirectiveKind::NoPreferDynamic => self.no_prefer_dynamic = true,
+            DirectiveKind::ProcMacro { path } => {
+                self.dependencies.push(ExternCrate::ProcMacro { path });
+            }
+            DirectiveKind::Query(query) => self.queries.push(query),
             DirectiveKind::Revisions(_) => unreachable!(), // Already dealt with in `Self::add`.
             DirectiveKind::RustcEnv { key, value } => {
                 self.verbatim_flags.environment.push((key, Some(value)))
             }
+            DirectiveKind::StderrCheck(check) => self.stderr_checks.push(check),
+            DirectiveKind::Tags(_) => unreachable!(), // Already dealt with by the parser.
             DirectiveKind::UnsetRustcEnv(key) => self.verbatim_flags.environment.push((key, None)),
         }
     }
@@ -176,30 +239,97 @@ struct Directive<'src> {
 
 #[derive(Clone)]
 enum DirectiveKind<'src> {
-    AuxBuild { path: &'src str },
+    AuxBuild {
+        path: &'src str,
+    },
     // FIXME: Double-check that the path is indeed optional.
-    AuxCrate { name: CrateNameRef<'src>, path: Option<&'src str> },
+    AuxCrate {
+        name: CrateNameRef<'src>,
+        version: Option<&'src str>,
+        path: Option<&'src str>,
+    },
     // FIXME: This is relevant for rruxwry, right?
     BuildAuxDocs,
-    CompileFlags(Vec<&'src str>),
+    /// The test is expected to fail with errors, e.g., unresolved imports.
+    CheckFail,
+    CompileFlags {
+        arguments: Vec<&'src str>,
+        line: usize,
+    },
+    Condition(Condition<'src>),
     Edition(Edition),
     // FIXME: Is this actually relevant for rruxwry?
     ForceHost,
     // FIXME: Is this actually relevant for rruxwry?
     NoPreferDynamic,
+    ProcMacro {
+        path: &'src str,
+    },
+    Query(Query),
     Revisions(Vec<&'src str>),
-    RustcEnv { key: &'src str, value: &'src str },
+    RustcEnv {
+        key: &'src str,
+        value: &'src str,
+    },
+    StderrCheck(Check<'src>),
+    /// The tags of the subsequent queries for `--only-assertions`.
+    Tags(Vec<&'src str>),
     UnsetRustcEnv(&'src str),
 }
 
+/// A directive that determines whether the test gets run at all, e.g., `//@ only-linux`.
+#[derive(Clone)]
+pub(crate) struct Condition<'src> {
+    pub(crate) kind: ConditionKind,
+    pub(crate) name: &'src str,
+    /// The explanation given after the condition, if any.
+    pub(crate) comment: Option<&'src str>,
+}
+
+impl<'src> Condition<'src> {
+    fn parse(directive: &'src str) -> Option<Self> {
+        let (kind, name) = [ConditionKind::Ignore, ConditionKind::Only, ConditionKind::Needs]
+            .into_iter()
+            .find_map(|kind| Some((kind, directive.strip_prefix(kind.prefix())?)))?;
+        (!name.is_empty()).then_some(Self { kind, name, comment: None })
+    }
+}
+
+impl fmt::Display for Condition<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.kind.prefix(), self.name)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(Debug))]
+pub(crate) enum ConditionKind {
+    Ignore,
+    Only,
+    Needs,
+}
+
+impl ConditionKind {
+    const fn prefix(self) -> &'static str {
+        match self {
+            Self::Ignore => "ignore-",
+            Self::Only => "only-",
+            Self::Needs => "needs-",
+        }
+    }
+}
+
 struct DirectiveParser<'src> {
     chars: Peekable<CharIndices<'src>>,
     source: &'src str,
+    /// The line of the directive in the source file.
+    line: usize,
+    query: Option<QueryMode>,
 }
 
 impl<'src> DirectiveParser<'src> {
-    fn new(source: &'src str) -> Self {
-        Self { chars: source.char_indices().peekable(), source }
+    fn new(source: &'src str, line: usize, query: Option<QueryMode>) -> Self {
+        Self { chars: source.char_indices().peekable(), source, line, query }
     }
 
     fn execute(mut self) -> Result<Directive<'src>, Error<'src>> {
@@ -216,8 +346,45 @@ impl<'src> DirectiveParser<'src> {
 
         self.parse_whitespace();
 
-        let directive = self.take_while(|char| char == '-' || char.is_ascii_alphabetic());
+        let negated = self.consume(|char| char == '!');
+
+        // Conditions may contain digits and underscores, e.g., `only-x86_64`.
+        let directive =
+            self.take_while(|char| char == '-' || char == '_' || char.is_ascii_alphanumeric());
         let context = ErrorContext::Directive(directive);
+
+        let kind = match self.query {
+            Some(QueryMode::Html) => self.parse_html_query(directive).transpose(),
+            Some(QueryMode::Json) => self.parse_json_query(directive).transpose(),
+            None => None,
+        };
+        if let Some(kind) = kind {
+            let kind = kind.map_err(|error| error.context(context))?;
+            let source = format!("//@{}", self.source.trim_end());
+            return Ok(Directive {
+                revision,
+                kind: DirectiveKind::Query(Query { source, negated, kind, tags: Vec::new() }),
+            });
+        }
+
+        if let Some(kind) = self.parse_stderr_check(directive).transpose() {
+            let kind = kind.map_err(|error| error.context(context))?;
+            let check = Check { kind, negated, line: self.line };
+            return Ok(Directive { revision, kind: DirectiveKind::StderrCheck(check) });
+        }
+
+        if negated {
+            return Err(Error::new(ErrorKind::UnexpectedNegation).context(context));
+        }
+
+        if let Some(condition) = Condition::parse(directive) {
+            let comment = self.take_remaining_line().trim();
+            let comment = comment.strip_prefix(':').unwrap_or(comment).trim();
+            let condition =
+                Condition { comment: (!comment.is_empty()).then_some(comment), ..condition };
+            return Ok(Directive { revision, kind: DirectiveKind::Condition(condition) });
+        }
+
         let kind = match directive {
             "aux-build" => {
                 self.parse_separator(Padding::Yes).map_err(|error| error.context(context))?; // FIXME: audit AllowPadding
@@ -227,27 +394,46 @@ impl<'src> DirectiveParser<'src> {
             // `compiletest` doesn't support extern options like `priv`, `noprelude`, `nounused` or `force`
             // at the time of writing. Therefore, we don't need to deal with them here either.
             // Neither does it support optional paths (`//@ aux-crate:name`).
+            // Versions (`//@ aux-crate: name@version=path`) are specific to rruxwry.
             "aux-crate" => {
                 self.parse_separator(Padding::Yes).map_err(|error| error.context(context))?; // FIXME: audit AllowPadding
 
                 // We're doing this two-step process — (greedy) lexing followed by validation —
                 // to be able to provide a better error message.
-                let name = self.take_while(|char| char != '=' && !char.is_ascii_whitespace());
+                let name = self
+                    .take_while(|char| !matches!(char, '=' | '@') && !char.is_ascii_whitespace());
                 let Ok(name) = CrateNameRef::parse(name) else {
                     return Err(Error::new(ErrorKind::InvalidValue(name)).context(context));
                 };
 
+                let version = match self.consume(|char| char == '@') {
+                    true => {
+                        let version = self.take_while(|char| char != '=');
+                        let valid = |char: char| {
+                            char.is_ascii_alphanumeric() || matches!(char, '.' | '-' | '_')
+                        };
+                        if version.is_empty() || !version.chars().all(valid) {
+                            return Err(
+                                Error::new(ErrorKind::InvalidValue(version)).context(context)
+                            );
+                        }
+                        Some(version)
+                    }
+                    false => None,
+                };
+
                 let path = self.consume(|char| char == '=').then(|| self.take_remaining_line());
-                DirectiveKind::AuxCrate { name, path }
+                DirectiveKind::AuxCrate { name, version, path }
             }
             "build-aux-docs" => DirectiveKind::BuildAuxDocs,
+            "check-fail" => DirectiveKind::CheckFail,
             "compile-flags" => {
                 self.parse_separator(Padding::Yes).map_err(|error| error.context(context))?; // FIXME: audit AllowPadding (before)
 
                 // FIXME: Supported quotes arguments (they shouldn't be split in halves).
                 //        Use crate `shlex` for this.
                 let arguments = self.take_remaining_line().split_ascii_whitespace().collect();
-                DirectiveKind::CompileFlags(arguments)
+                DirectiveKind::CompileFlags { arguments, line: self.line }
             }
             "edition" => {
                 self.parse_separator(Padding::Yes).map_err(|error| error.context(context))?; // FIXME: audit AllowPadding (before)
@@ -263,12 +449,23 @@ impl<'src> DirectiveParser<'src> {
             }
             "force-host" => DirectiveKind::ForceHost,
             "no-prefer-dynamic" => DirectiveKind::NoPreferDynamic,
+            "proc-macro" => {
+                self.parse_separator(Padding::Yes).map_err(|error| error.context(context))?;
+                let path = self.take_remaining_line();
+                DirectiveKind::ProcMacro { path }
+            }
             "revisions" => {
                 self.parse_separator(Padding::Yes).map_err(|error| error.context(context))?; // FIXME: audit AllowPadding
                 let revisions = self.take_remaining_line().split_ascii_whitespace().collect();
                 DirectiveKind::Revisions(revisions)
             }
             // `compiletest` only supports a single environment variable per directive.
+            // Not supported by `compiletest`, it's specific to rruxwry.
+            "tags" => {
+                self.parse_separator(Padding::Yes).map_err(|error| error.context(context))?;
+                let tags = self.take_remaining_line().split(',').map(str::trim);
+                DirectiveKind::Tags(tags.filter(|tag| !tag.is_empty()).collect())
+            }
             "rustc-env" => {
                 self.parse_separator(Padding::No).map_err(|error| error.context(context))?;
                 let line = self.take_remaining_line();
@@ -291,6 +488,149 @@ impl<'src> DirectiveParser<'src> {
         Ok(Directive { revision, kind })
     }
 
+    /// Parse the argument of the stderr check `directive` if it is one.
+    fn parse_stderr_check(
+        &mut self,
+        directive: &str,
+    ) -> Result<Option<CheckKind<'src>>, Error<'src>> {
+        if !matches!(directive, "check-stderr-contains" | "check-stderr-regex") {
+            return Ok(None);
+        }
+
+        self.parse_separator(Padding::Yes)?;
+        let argument = self.take_remaining_line().trim();
+        if argument.is_empty() {
+            return Err(Error::new(ErrorKind::UnexpectedEndOfInput));
+        }
+
+        Ok(Some(match directive {
+            "check-stderr-contains" => CheckKind::Contains(argument),
+            _ => {
+                let regex = Regex::new(argument).map_err(|error| {
+                    let (argument, reason) = (argument.to_owned(), utility::regex_error(&error));
+                    Error::new(ErrorKind::InvalidArgument { argument, reason })
+                })?;
+                CheckKind::Regex { regex, pattern: argument }
+            }
+        }))
+    }
+
+    /// Parse the arguments of the `htmldocck` query `directive` if it is one.
+    fn parse_html_query(&mut self, directive: &str) -> Result<Option<QueryKind>, Error<'src>> {
+        if !matches!(
+            directive,
+            "files" | "has" | "hasraw" | "matches" | "matchesraw" | "count" | "snapshot"
+        ) {
+            return Ok(None);
+        }
+
+        let source = self.take_remaining_line();
+        let Some(arguments) = shlex::split(source) else {
+            return Err(Error::new(ErrorKind::InvalidValue(source)));
+        };
+        let invalid = || Error::new(ErrorKind::InvalidValue(source));
+        let xpath = |argument: &String| {
+            XPath::parse(argument).map_err(|error| {
+                let (argument, reason) = (argument.clone(), error.to_string());
+                Error::new(ErrorKind::InvalidArgument { argument, reason })
+            })
+        };
+        let pattern = |argument: &String| {
+            Regex::new(argument).map_err(|error| {
+                let (argument, reason) = (argument.clone(), utility::regex_error(&error));
+                Error::new(ErrorKind::InvalidArgument { argument, reason })
+            })
+        };
+        let count = |argument: &String| argument.parse().map_err(|_| invalid());
+
+        // The path `-` refers to the previous path and gets resolved by the caller.
+        Ok(Some(match (directive, &arguments[..]) {
+            ("files", [path, entries]) => {
+                let Ok(entries) = query::parse_string_list(entries) else { return Err(invalid()) };
+                QueryKind::Files { path: path.clone(), entries }
+            }
+            ("has", [path]) => QueryKind::Has { path: path.clone(), xpath: None, text: None },
+            ("has", [path, query]) => {
+                QueryKind::Has { path: path.clone(), xpath: Some(xpath(query)?), text: None }
+            }
+            ("has", [path, query, text]) => QueryKind::Has {
+                path: path.clone(),
+                xpath: Some(xpath(query)?),
+                text: Some(text.clone()),
+            },
+            ("hasraw", [path, text]) => {
+                QueryKind::HasRaw { path: path.clone(), text: text.clone() }
+            }
+            ("matches", [path, query, regex]) => QueryKind::Matches {
+                path: path.clone(),
+                xpath: xpath(query)?,
+                pattern: pattern(regex)?,
+            },
+            ("matchesraw", [path, regex]) => {
+                QueryKind::MatchesRaw { path: path.clone(), pattern: pattern(regex)? }
+            }
+            ("count", [path, query, amount]) => QueryKind::Count {
+                path: path.clone(),
+                xpath: xpath(query)?,
+                text: None,
+                count: count(amount)?,
+            },
+            ("count", [path, query, text, amount]) => QueryKind::Count {
+                path: path.clone(),
+                xpath: xpath(query)?,
+                text: Some(text.clone()),
+                count: count(amount)?,
+            },
+            ("snapshot", [name, path, query]) => {
+                QueryKind::Snapshot { name: name.clone(), path: path.clone(), xpath: xpath(query)? }
+            }
+            _ => return Err(invalid()),
+        }))
+    }
+
+    /// Parse the arguments of the `jsondocck` query `directive` if it is one.
+    fn parse_json_query(&mut self, directive: &str) -> Result<Option<QueryKind>, Error<'src>> {
+        if !matches!(directive, "has" | "is" | "count" | "set") {
+            return Ok(None);
+        }
+
+        let source = self.take_remaining_line();
+        let Some(arguments) = shlex::split(source) else {
+            return Err(Error::new(ErrorKind::InvalidValue(source)));
+        };
+        let invalid = || Error::new(ErrorKind::InvalidValue(source));
+        let path = |argument: &String| {
+            JsonPath::parse(argument).map_err(|error| {
+                let (argument, reason) = (argument.clone(), error.to_string());
+                Error::new(ErrorKind::InvalidArgument { argument, reason })
+            })
+        };
+        let operand = |argument: &String| {
+            Operand::parse(argument).map_err(|error| {
+                let (argument, reason) = (argument.clone(), error.to_string());
+                Error::new(ErrorKind::InvalidArgument { argument, reason })
+            })
+        };
+
+        Ok(Some(match (directive, &arguments[..]) {
+            ("has", [query]) => QueryKind::JsonHas { path: path(query)?, value: None },
+            ("has", [query, value]) => {
+                QueryKind::JsonHas { path: path(query)?, value: Some(operand(value)?) }
+            }
+            ("is", [query, value]) => {
+                QueryKind::JsonIs { path: path(query)?, value: operand(value)? }
+            }
+            ("count", [query, count]) => QueryKind::JsonCount {
+                path: path(query)?,
+                count: count.parse().map_err(|_| invalid())?,
+            },
+            ("set", [name, equals, query]) if equals == "=" => {
+                QueryKind::JsonSet { name: name.clone(), path: path(query)? }
+            }
+            _ => return Err(invalid()),
+        }))
+    }
+
     fn peek(&mut self) -> Option<char> {
         self.chars.peek().map(|&(_, char)| char)
     }
@@ -417,7 +757,10 @@ enum ErrorKind<'src> {
     UnknownDirective(&'src str),
     UnexpectedToken { found: char, expected: char },
     UnexpectedEndOfInput,
+    UnexpectedNegation,
     InvalidValue(&'src str),
+    InvalidArgument { argument: String, reason: String },
+    MissingPreviousPath,
 }
 
 impl fmt::Display for ErrorKind<'_> {
@@ -428,7 +771,14 @@ impl fmt::Display for ErrorKind<'_> {
                 write!(f, "found `{found}` but expected `{expected}`")
             }
             Self::UnexpectedEndOfInput => write!(f, "unexpected end of input"),
+            Self::UnexpectedNegation => write!(f, "unexpected negation"),
             Self::InvalidValue(value) => write!(f, "invalid value `{value}`"),
+            Self::InvalidArgument { argument, reason } => {
+                write!(f, "invalid argument `{argument}`: {reason}")
+            }
+            Self::MissingPreviousPath => {
+                write!(f, "the path `-` refers to the previous path but there is none")
+            }
         }
     }
 }
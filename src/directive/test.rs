@@ -0,0 +1,194 @@
+use super::{Directive, DirectiveKind, DirectiveParser, Directives};
+use crate::{
+    builder::QueryMode,
+    query::{Query, QueryKind},
+    stderr::{Check, CheckKind},
+};
+
+fn parse(directive: &str, query: Option<QueryMode>) -> Result<Directive<'_>, String> {
+    DirectiveParser::new(directive, 1, query).execute().map_err(|error| error.to_string())
+}
+
+fn query(directive: &str) -> Query {
+    match parse(directive, Some(QueryMode::Html)).unwrap().kind {
+        DirectiveKind::Query(query) => query,
+        _ => panic!("`{directive}` isn't a query"),
+    }
+}
+
+fn stderr_check(directive: &str) -> Check<'_> {
+    match parse(directive, None).unwrap().kind {
+        DirectiveKind::StderrCheck(check) => check,
+        _ => panic!("`{directive}` isn't a stderr check"),
+    }
+}
+
+fn error(directive: &str, query: Option<QueryMode>) -> String {
+    parse(directive, query).err().unwrap()
+}
+
+#[test]
+fn has() {
+    let Query { negated, kind, .. } = query(" has krate/index.html");
+    assert!(!negated);
+    let QueryKind::Has { path, xpath: None, text: None } = kind else { panic!() };
+    assert_eq!(path, "krate/index.html");
+
+    let Query { kind, .. } = query(" has krate/index.html '//h1'");
+    assert!(matches!(kind, QueryKind::Has { xpath: Some(_), text: None, .. }));
+
+    let Query { negated, kind, .. } = query(" !has krate/index.html '//h1' 'Crate krate'");
+    assert!(negated);
+    let QueryKind::Has { xpath: Some(_), text: Some(text), .. } = kind else { panic!() };
+    assert_eq!(text, "Crate krate");
+
+    let Query { kind, .. } = query(r#" has "krate/a b.html" '//h1'"#);
+    let QueryKind::Has { path, .. } = kind else { panic!() };
+    assert_eq!(path, "krate/a b.html");
+}
+
+#[test]
+fn hasraw() {
+    let Query { negated, kind, .. } = query(" !hasraw krate/index.html 'pub fn f'");
+    assert!(negated);
+    let QueryKind::HasRaw { path, text } = kind else { panic!() };
+    assert_eq!((&*path, &*text), ("krate/index.html", "pub fn f"));
+}
+
+#[test]
+fn matches() {
+    let Query { kind, .. } = query(r" matches krate/index.html '//h1' 'Crate \w+'");
+    let QueryKind::Matches { path, pattern, .. } = kind else { panic!() };
+    assert_eq!((&*path, pattern.as_str()), ("krate/index.html", r"Crate \w+"));
+
+    let Query { negated, kind, .. } = query(" !matchesraw krate/index.html '^<!DOC'");
+    assert!(negated);
+    let QueryKind::MatchesRaw { pattern, .. } = kind else { panic!() };
+    assert_eq!(pattern.as_str(), "^<!DOC");
+}
+
+#[test]
+fn count() {
+    let Query { kind, .. } = query(" count krate/index.html '//li' 3");
+    assert!(matches!(kind, QueryKind::Count { text: None, count: 3, .. }));
+
+    let Query { kind, .. } = query(" count krate/index.html '//li' 'fn' 0");
+    let QueryKind::Count { text: Some(text), count: 0, .. } = kind else { panic!() };
+    assert_eq!(text, "fn");
+}
+
+#[test]
+fn snapshot() {
+    let Query { kind, .. } = query(" snapshot decl krate/fn.f.html '//pre'");
+    let QueryKind::Snapshot { name, path, .. } = kind else { panic!() };
+    assert_eq!((&*name, &*path), ("decl", "krate/fn.f.html"));
+}
+
+#[test]
+fn files() {
+    let Query { negated, kind, .. } = query(r#" !files krate "['index.html', 'fn.f.html']""#);
+    assert!(negated);
+    let QueryKind::Files { path, entries } = kind else { panic!() };
+    assert_eq!(path, "krate");
+    assert_eq!(entries, ["index.html", "fn.f.html"]);
+}
+
+#[test]
+fn malformed_queries() {
+    let html = Some(QueryMode::Html);
+    assert_eq!(
+        error(" hasraw krate/index.html", html),
+        "invalid value ` krate/index.html` in directive `hasraw`"
+    );
+    assert_eq!(
+        error(" has 'krate/index.html", html),
+        "invalid value ` 'krate/index.html` in directive `has`"
+    );
+    assert_eq!(
+        error(" count krate/index.html '//li' many", html),
+        "invalid value ` krate/index.html '//li' many` in directive `count`"
+    );
+    assert_eq!(
+        error(" matchesraw krate/index.html '('", html),
+        "invalid argument `(`: unclosed group in directive `matchesraw`"
+    );
+    assert_eq!(
+        error(" files krate 'index.html'", html),
+        "invalid value ` krate 'index.html'` in directive `files`"
+    );
+    assert_eq!(
+        error(" snapshot krate/fn.f.html '//pre'", html),
+        "invalid value ` krate/fn.f.html '//pre'` in directive `snapshot`"
+    );
+
+    // Outside of query mode, queries are just unknown directives.
+    assert_eq!(error(" has krate/index.html", None), "unknown directive: `has`");
+}
+
+#[test]
+fn stderr_checks() {
+    let Check { kind, negated, .. } = stderr_check(" check-stderr-contains: unresolved link");
+    assert!(!negated);
+    assert!(matches!(kind, CheckKind::Contains("unresolved link")));
+
+    let Check { kind, negated, .. } = stderr_check(r" !check-stderr-regex: lint\.rs:\d+");
+    assert!(negated);
+    let CheckKind::Regex { regex, pattern } = kind else { panic!() };
+    assert_eq!((regex.as_str(), pattern), (r"lint\.rs:\d+", r"lint\.rs:\d+"));
+}
+
+#[test]
+fn malformed_stderr_checks() {
+    assert_eq!(
+        error(" check-stderr-contains:  ", None),
+        "unexpected end of input in directive `check-stderr-contains`"
+    );
+    assert_eq!(
+        error(" check-stderr-contains unresolved", None),
+        "found `u` but expected `:` in directive `check-stderr-contains`"
+    );
+    assert_eq!(
+        error(" check-stderr-regex: (", None),
+        "invalid argument `(`: unclosed group in directive `check-stderr-regex`"
+    );
+}
+
+#[test]
+fn tags() {
+    let source = "//@ has krate/index.html\n\
+                  //@ tags: sidebar, , search\n\
+                  //@ has krate/index.html '//nav'\n\
+                  //@ tags:\n\
+                  //@ has krate/index.html '//h1'\n";
+    let directives = Directives::parse(source, Some(QueryMode::Html));
+    let tags: Vec<_> = directives.queries.iter().map(|query| query.tags.clone()).collect();
+    assert_eq!(tags, [vec![], vec!["sidebar".to_owned(), "search".to_owned()], vec![]]);
+
+    assert_eq!(error(" !tags: sidebar", None), "unexpected negation in directive `tags`");
+    assert_eq!(error(" tags sidebar", None), "found `s` but expected `:` in directive `tags`");
+}
+
+#[test]
+fn previous_path() {
+    let source = "//@ has krate/index.html\n//@ hasraw - 'fn f'\n//@ count - '//li' 1\n";
+    let directives = Directives::parse(source, Some(QueryMode::Html));
+    let paths: Vec<_> = directives
+        .queries
+        .iter()
+        .map(|query| query.clone().kind.path_mut().cloned().unwrap())
+        .collect();
+    assert_eq!(paths, ["krate/index.html"; 3]);
+
+    // Without a previous path, the query gets reported and dropped.
+    let directives = Directives::parse("//@ hasraw - 'fn f'\n", Some(QueryMode::Html));
+    assert!(directives.queries.is_empty());
+}
+
+#[test]
+fn check_fail() {
+    let directives = Directives::parse("//@ check-stderr-contains: unresolved\n", None);
+    assert!(!directives.check_fail);
+    let directives = Directives::parse("//@ check-fail\n//@ check-stderr-contains: E0432\n", None);
+    assert!(directives.check_fail);
+    assert_eq!(directives.stderr_checks.len(), 1);
+}
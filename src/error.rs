@@ -1,4 +1,4 @@
-use crate::diagnostic::{Diagnostic, IntoDiagnostic, error};
+use crate::diagnostic::{Diagnostic, IntoDiagnostic, error, info};
 
 pub(crate) type Result<T = (), E = Error> = std::result::Result<T, E>;
 
@@ -6,6 +6,37 @@ pub(crate) enum Error {
     Io(std::io::Error),
     Process(std::process::ExitStatusError),
     Build(Box<crate::builder::Error>),
+    Batch(crate::batch::Error),
+    Command(crate::command::Error),
+    Toolchain(crate::toolchain::Error),
+    Query(crate::query::Error),
+    Consistency(crate::consistency::Error),
+    Audit(crate::audit::Error),
+    Impls(crate::impls::Error),
+    Trace(crate::trace::Error),
+    Snapshot(crate::snapshot::Error),
+    Stderr(crate::stderr::Error),
+    Config(crate::config::Error),
+    Budget(crate::budget::Error),
+    Layout(crate::layout::Error),
+    Lints(crate::lints::Error),
+    Search(crate::search::Error),
+    Bisect(crate::bisect::Error),
+    Regression(crate::regression::Error),
+    Cache(crate::cache::Error),
+    History(crate::history::Error),
+    Cargo(crate::cargo::Error),
+    Upload(crate::upload::Error),
+    Preset(crate::preset::Error),
+    Pipeline(crate::pipeline::Error),
+    Sysroot(crate::sysroot::Error),
+    Extract(crate::json::extract::Error),
+    /// An error that was already rendered as a diagnostic, e.g., for the failure report.
+    Reported(Diagnostic),
+    /// Not an error per se: A condition prevents the test from running.
+    Skipped(crate::condition::Unmet),
+    #[cfg(feature = "gui")]
+    Gui(crate::gui::Error),
 }
 
 impl From<std::io::Error> for Error {
@@ -26,12 +57,204 @@ impl From<crate::builder::Error> for Error {
     }
 }
 
+impl From<crate::batch::Error> for Error {
+    fn from(error: crate::batch::Error) -> Self {
+        Self::Batch(error)
+    }
+}
+
+impl From<crate::command::Error> for Error {
+    fn from(error: crate::command::Error) -> Self {
+        Self::Command(error)
+    }
+}
+
+impl From<crate::toolchain::Error> for Error {
+    fn from(error: crate::toolchain::Error) -> Self {
+        Self::Toolchain(error)
+    }
+}
+
+impl From<crate::query::Error> for Error {
+    fn from(error: crate::query::Error) -> Self {
+        Self::Query(error)
+    }
+}
+
+impl From<crate::consistency::Error> for Error {
+    fn from(error: crate::consistency::Error) -> Self {
+        Self::Consistency(error)
+    }
+}
+
+impl From<crate::audit::Error> for Error {
+    fn from(error: crate::audit::Error) -> Self {
+        Self::Audit(error)
+    }
+}
+
+impl From<crate::impls::Error> for Error {
+    fn from(error: crate::impls::Error) -> Self {
+        Self::Impls(error)
+    }
+}
+
+impl From<crate::trace::Error> for Error {
+    fn from(error: crate::trace::Error) -> Self {
+        Self::Trace(error)
+    }
+}
+
+impl From<crate::snapshot::Error> for Error {
+    fn from(error: crate::snapshot::Error) -> Self {
+        Self::Snapshot(error)
+    }
+}
+
+impl From<crate::stderr::Error> for Error {
+    fn from(error: crate::stderr::Error) -> Self {
+        Self::Stderr(error)
+    }
+}
+
+impl From<crate::lints::Error> for Error {
+    fn from(error: crate::lints::Error) -> Self {
+        Self::Lints(error)
+    }
+}
+
+impl From<crate::cargo::Error> for Error {
+    fn from(error: crate::cargo::Error) -> Self {
+        Self::Cargo(error)
+    }
+}
+
+impl From<crate::config::Error> for Error {
+    fn from(error: crate::config::Error) -> Self {
+        Self::Config(error)
+    }
+}
+
+impl From<crate::pipeline::Error> for Error {
+    fn from(error: crate::pipeline::Error) -> Self {
+        Self::Pipeline(error)
+    }
+}
+
+impl From<crate::json::extract::Error> for Error {
+    fn from(error: crate::json::extract::Error) -> Self {
+        Self::Extract(error)
+    }
+}
+
+impl From<crate::sysroot::Error> for Error {
+    fn from(error: crate::sysroot::Error) -> Self {
+        Self::Sysroot(error)
+    }
+}
+
+impl From<crate::preset::Error> for Error {
+    fn from(error: crate::preset::Error) -> Self {
+        Self::Preset(error)
+    }
+}
+
+impl From<crate::budget::Error> for Error {
+    fn from(error: crate::budget::Error) -> Self {
+        Self::Budget(error)
+    }
+}
+
+impl From<crate::layout::Error> for Error {
+    fn from(error: crate::layout::Error) -> Self {
+        Self::Layout(error)
+    }
+}
+
+impl From<crate::search::Error> for Error {
+    fn from(error: crate::search::Error) -> Self {
+        Self::Search(error)
+    }
+}
+
+impl From<crate::bisect::Error> for Error {
+    fn from(error: crate::bisect::Error) -> Self {
+        Self::Bisect(error)
+    }
+}
+
+impl From<crate::regression::Error> for Error {
+    fn from(error: crate::regression::Error) -> Self {
+        Self::Regression(error)
+    }
+}
+
+impl From<crate::cache::Error> for Error {
+    fn from(error: crate::cache::Error) -> Self {
+        Self::Cache(error)
+    }
+}
+
+impl From<crate::history::Error> for Error {
+    fn from(error: crate::history::Error) -> Self {
+        Self::History(error)
+    }
+}
+
+impl From<crate::condition::Unmet> for Error {
+    fn from(unmet: crate::condition::Unmet) -> Self {
+        Self::Skipped(unmet)
+    }
+}
+
+impl From<crate::upload::Error> for Error {
+    fn from(error: crate::upload::Error) -> Self {
+        Self::Upload(error)
+    }
+}
+
+#[cfg(feature = "gui")]
+impl From<crate::gui::Error> for Error {
+    fn from(error: crate::gui::Error) -> Self {
+        Self::Gui(error)
+    }
+}
+
 impl IntoDiagnostic for Error {
     fn into_diagnostic(self) -> Diagnostic {
         match self {
             Self::Io(error) => self::error(error.to_string()),
             Self::Process(error) => self::error(error.to_string()),
             Self::Build(error) => error.into_diagnostic(),
+            Self::Batch(error) => error.into_diagnostic(),
+            Self::Command(error) => error.into_diagnostic(),
+            Self::Toolchain(error) => error.into_diagnostic(),
+            Self::Query(error) => error.into_diagnostic(),
+            Self::Consistency(error) => error.into_diagnostic(),
+            Self::Audit(error) => error.into_diagnostic(),
+            Self::Impls(error) => error.into_diagnostic(),
+            Self::Trace(error) => error.into_diagnostic(),
+            Self::Snapshot(error) => error.into_diagnostic(),
+            Self::Stderr(error) => error.into_diagnostic(),
+            Self::Config(error) => error.into_diagnostic(),
+            Self::Budget(error) => error.into_diagnostic(),
+            Self::Layout(error) => error.into_diagnostic(),
+            Self::Lints(error) => error.into_diagnostic(),
+            Self::Search(error) => error.into_diagnostic(),
+            Self::Bisect(error) => error.into_diagnostic(),
+            Self::Regression(error) => error.into_diagnostic(),
+            Self::Cache(error) => error.into_diagnostic(),
+            Self::History(error) => error.into_diagnostic(),
+            Self::Cargo(error) => error.into_diagnostic(),
+            Self::Upload(error) => error.into_diagnostic(),
+            Self::Preset(error) => error.into_diagnostic(),
+            Self::Pipeline(error) => error.into_diagnostic(),
+            Self::Sysroot(error) => error.into_diagnostic(),
+            Self::Extract(error) => error.into_diagnostic(),
+            Self::Reported(diagnostic) => diagnostic,
+            Self::Skipped(unmet) => info(format!("skipped: {unmet}")),
+            #[cfg(feature = "gui")]
+            Self::Gui(error) => error.into_diagnostic(),
         }
     }
 }
@@ -0,0 +1,38 @@
+//! A rendered report of a failed run for `--open-on-failure`.
+//!
+//! It's written to `doc.failure/` next to the documentation root.
+
+use crate::{diagnostic::Diagnostic, html::escape};
+use std::{
+    fmt::Write,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Write the report of the failed run on the given source file and return the path to it.
+pub(crate) fn render(source: &Path, diagnostic: &Diagnostic) -> io::Result<PathBuf> {
+    let directory = std::env::current_dir()?.join("doc.failure");
+    let title = escape(&format!("rruxwry failed on `{}`", source.display())).into_owned();
+
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">")
+        .unwrap();
+    writeln!(html, "<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>").unwrap();
+    writeln!(html, "<h1>{title}</h1>\n{}", diagnostic.render_html()).unwrap();
+    // The diagnostics of `rust{c,doc}` themselves go straight to the terminal.
+    writeln!(html, "<p class=\"hint\">See the terminal for the output of the compiler.</p>")
+        .unwrap();
+    writeln!(html, "</body>\n</html>").unwrap();
+
+    std::fs::create_dir_all(&directory)?;
+    let index = directory.join("index.html");
+    std::fs::write(&index, html)?;
+    Ok(index)
+}
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 1em 2em; }
+.error strong { color: #c00; }
+.info strong { color: #08c; }
+li { font-family: monospace; white-space: pre-wrap; margin: 0.25em 0; }
+.hint { color: #888; }";
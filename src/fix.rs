@@ -0,0 +1,115 @@
+//! Applying machine-applicable suggestions found in the JSON diagnostics of `rust{c,doc}`.
+
+use crate::{cli, diagnostic::info};
+use serde_json::Value;
+use std::{io, path::Path};
+
+#[cfg(test)]
+mod test;
+
+/// Apply all machine-applicable suggestions that point into the file at `path`.
+pub(crate) fn apply_suggestions(
+    path: &Path,
+    diagnostics: &[Value],
+    flags: &cli::ProgramFlags,
+) -> io::Result<()> {
+    let mut suggestions = Vec::new();
+    for diagnostic in diagnostics {
+        collect_suggestions(diagnostic, path, &mut suggestions);
+    }
+
+    if suggestions.is_empty() {
+        return Ok(());
+    }
+
+    select_disjoint(&mut suggestions);
+
+    let source = std::fs::read_to_string(path)?;
+    let Some(source) = apply(source, &suggestions) else {
+        // The file has likely changed in the meantime.
+        return Err(io::Error::other(format!(
+            "failed to apply a suggestion to `{}`: invalid span",
+            path.display()
+        )));
+    };
+
+    let amount = suggestions.len();
+    let s = if amount == 1 { "" } else { "s" };
+    let verb = if !flags.dry_run { "applying" } else { "skipping" };
+    info(format!("{verb} {amount} suggestion{s} to `{}`", path.display())).emit();
+
+    if !flags.dry_run {
+        std::fs::write(path, source)?;
+    }
+
+    Ok(())
+}
+
+/// Sort the suggestions and drop the ones that overlap with a previous one.
+fn select_disjoint(suggestions: &mut Vec<Suggestion>) {
+    // Suggestions may overlap (e.g., if several diagnostics suggest the same fix).
+    // Like rustfix, we only apply the first one of a set of overlapping suggestions.
+    suggestions.sort_by_key(|suggestion| (suggestion.start, suggestion.end));
+    suggestions.dedup();
+    let mut end = 0;
+    suggestions.retain(|suggestion| {
+        let disjoint = suggestion.start >= end;
+        if disjoint {
+            end = suggestion.end;
+        }
+        disjoint
+    });
+}
+
+/// Apply the sorted and disjoint suggestions to the source or return `None` if a span is invalid.
+fn apply(mut source: String, suggestions: &[Suggestion]) -> Option<String> {
+    // Replace back to front so the spans of the remaining suggestions stay valid.
+    for suggestion in suggestions.iter().rev() {
+        if suggestion.start > suggestion.end
+            || suggestion.end > source.len()
+            || !source.is_char_boundary(suggestion.start)
+            || !source.is_char_boundary(suggestion.end)
+        {
+            return None;
+        }
+
+        source.replace_range(suggestion.start..suggestion.end, &suggestion.replacement);
+    }
+    Some(source)
+}
+
+#[derive(PartialEq, Eq)]
+#[cfg_attr(test, derive(Debug))]
+struct Suggestion {
+    start: usize,
+    end: usize,
+    replacement: String,
+}
+
+fn collect_suggestions(diagnostic: &Value, path: &Path, suggestions: &mut Vec<Suggestion>) {
+    for span in diagnostic["spans"].as_array().into_iter().flatten() {
+        if span["suggestion_applicability"] != "MachineApplicable"
+            || span["file_name"].as_str().map(Path::new) != Some(path)
+        {
+            continue;
+        }
+
+        let (Some(start), Some(end), Some(replacement)) = (
+            span["byte_start"].as_u64(),
+            span["byte_end"].as_u64(),
+            span["suggested_replacement"].as_str(),
+        ) else {
+            continue;
+        };
+
+        suggestions.push(Suggestion {
+            start: start as usize,
+            end: end as usize,
+            replacement: replacement.to_owned(),
+        });
+    }
+
+    for child in diagnostic["children"].as_array().into_iter().flatten() {
+        collect_suggestions(child, path, suggestions);
+    }
+}
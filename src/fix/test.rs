@@ -0,0 +1,71 @@
+use super::{Suggestion, apply, collect_suggestions, select_disjoint};
+use serde_json::{Value, json};
+use std::path::Path;
+
+fn span(file: &str, start: usize, end: usize, replacement: &str, applicability: &str) -> Value {
+    json!({
+        "file_name": file,
+        "byte_start": start,
+        "byte_end": end,
+        "suggested_replacement": replacement,
+        "suggestion_applicability": applicability,
+    })
+}
+
+fn suggestion(start: usize, end: usize, replacement: &str) -> Suggestion {
+    Suggestion { start, end, replacement: replacement.to_owned() }
+}
+
+#[test]
+fn collected_suggestions() {
+    let diagnostic = json!({
+        "spans": [span("lib.rs", 0, 1, "", "Unspecified")],
+        "children": [
+            {
+                "spans": [
+                    span("lib.rs", 4, 7, "pub", "MachineApplicable"),
+                    span("lib.rs", 10, 10, "&", "MachineApplicable"),
+                    span("other.rs", 0, 3, "pub", "MachineApplicable"),
+                ],
+                "children": [],
+            },
+            { "spans": [span("lib.rs", 20, 21, "_", "MaybeIncorrect")], "children": [] },
+        ],
+    });
+
+    let mut suggestions = Vec::new();
+    collect_suggestions(&diagnostic, Path::new("lib.rs"), &mut suggestions);
+    assert_eq!(suggestions, [suggestion(4, 7, "pub"), suggestion(10, 10, "&")]);
+}
+
+#[test]
+fn overlapping_suggestions() {
+    let mut suggestions = vec![
+        suggestion(8, 12, "b"),
+        suggestion(0, 4, "a"),
+        suggestion(2, 6, "x"),
+        suggestion(8, 12, "b"),
+        suggestion(12, 12, "c"),
+    ];
+    select_disjoint(&mut suggestions);
+    assert_eq!(suggestions, [
+        suggestion(0, 4, "a"),
+        suggestion(8, 12, "b"),
+        suggestion(12, 12, "c")
+    ]);
+}
+
+#[test]
+fn applied_suggestions() {
+    let source = "fn f(x: u8) -> u8 { x }".to_owned();
+    let suggestions =
+        [suggestion(0, 0, "pub "), suggestion(8, 10, "u16"), suggestion(15, 17, "u16")];
+    assert_eq!(
+        apply(source.clone(), &suggestions).as_deref(),
+        Some("pub fn f(x: u16) -> u16 { x }")
+    );
+
+    assert_eq!(apply(source.clone(), &[suggestion(20, 40, "")]), None);
+    assert_eq!(apply(source, &[suggestion(6, 5, "")]), None);
+    assert_eq!(apply("é".to_owned(), &[suggestion(1, 2, "e")]), None);
+}
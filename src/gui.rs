@@ -0,0 +1,61 @@
+//! Running GUI tests against the generated docs in a headless browser.
+//!
+//! The tests are written in the `.goml` format of [`browser-ui-test`] which is also used by the
+//! `tests/rustdoc-gui/` suite of rust-lang/rust. We merely shell out to it.
+//!
+//! [`browser-ui-test`]: https://github.com/GuillaumeGomez/browser-UI-test
+
+use crate::{
+    cli,
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info},
+};
+use std::{io, path::Path, process};
+
+/// Run the GUI tests found in the file at `tests`.
+pub(crate) fn run_tests(
+    tests: &Path,
+    root: &Path,
+    flags: &cli::ProgramFlags,
+) -> crate::error::Result {
+    let mut command = process::Command::new("npx");
+    command.arg("--no-install").arg("browser-ui-test");
+    command.arg("--test-file").arg(tests);
+    // `DOC_PATH` is the variable used by the tests of rust-lang/rust.
+    command.arg("--variable").arg("DOC_PATH").arg(root);
+    command.arg("--no-sandbox");
+
+    if flags.verbose {
+        let verb = if !flags.dry_run { "running" } else { "skipping" };
+        info(format!("{verb} the GUI tests in `{}`", tests.display())).emit();
+    }
+
+    if flags.dry_run {
+        return Ok(());
+    }
+
+    let status = command.status().map_err(|error| match error.kind() {
+        io::ErrorKind::NotFound => Error::MissingRunner.into(),
+        _ => crate::error::Error::from(error),
+    })?;
+
+    if !status.success() {
+        return Err(Error::Failed.into());
+    }
+
+    Ok(())
+}
+
+pub(crate) enum Error {
+    MissingRunner,
+    Failed,
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::MissingRunner => error("failed to find `npx`")
+                .note("running GUI tests requires Node.js and the npm package `browser-ui-test`"),
+            Self::Failed => error("the GUI tests failed"),
+        }
+    }
+}
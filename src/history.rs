@@ -0,0 +1,296 @@
+//! Recording summaries of runs (`--record`) and comparing them (`rruxwry history`).
+//!
+//! Each run gets stored as a JSON file in the `history` subdirectory of the cache directory,
+//! so `rruxwry cache gc` prunes old runs like any other cache entry.
+
+use crate::{
+    cache, cli,
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info},
+    provenance,
+    report::Report,
+    toolchain,
+};
+use joinery::JoinableIterator;
+use serde_json::{Value, json};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(test)]
+mod test;
+
+fn directory() -> Result<PathBuf, cache::Error> {
+    Ok(cache::directory()?.join("history"))
+}
+
+/// Store the summary of the runs recorded in the given report in the history.
+pub(crate) fn record(
+    report: &Report,
+    build_flags: &cli::BuildFlags,
+    program_flags: &cli::ProgramFlags,
+) -> crate::error::Result {
+    let version = toolchain::probe(build_flags, program_flags);
+    let id = provenance::run_id();
+
+    let record = json!({
+        "id": id,
+        "time": SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        "arguments": std::env::args().skip(1).collect::<Vec<_>>(),
+        "toolchain": {
+            "name": build_flags.toolchain,
+            "version": version.as_ref().map(ToString::to_string),
+        },
+        "runs": report.to_json()["runs"],
+    });
+
+    let directory = directory()?;
+    std::fs::create_dir_all(&directory)?;
+    let record = serde_json::to_string_pretty(&record).map_err(io::Error::from)? + "\n";
+    std::fs::write(directory.join(format!("{id}.json")), record)?;
+
+    if program_flags.verbose {
+        info(format!("recorded the run as `{id}`")).emit();
+    }
+
+    Ok(())
+}
+
+pub(crate) fn run(command: &cli::HistoryCommand) -> crate::error::Result {
+    let records = load(&directory()?)?;
+
+    match command {
+        cli::HistoryCommand::List => list(&records),
+        cli::HistoryCommand::Compare { baseline, current } => {
+            let current = match current {
+                Some(id) => find(&records, id)?,
+                None => records.last().ok_or(Error::Empty)?,
+            };
+            let baseline = match baseline {
+                Some(id) => find(&records, id)?,
+                None => records
+                    .iter()
+                    .rev()
+                    .filter(|record| record["time"].as_u64() <= current["time"].as_u64())
+                    .find(|record| {
+                        record["id"] != current["id"] && record["arguments"] == current["arguments"]
+                    })
+                    .ok_or(Error::NoBaseline)?,
+            };
+            compare(baseline, current);
+        }
+    }
+
+    Ok(())
+}
+
+/// The recorded runs from the oldest to the latest one.
+fn load(directory: &Path) -> io::Result<Vec<Value>> {
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+
+    let mut records = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "json") {
+            records.push(serde_json::from_slice::<Value>(&std::fs::read(&path)?)?);
+        }
+    }
+    records.sort_by_key(|record| record["time"].as_u64());
+
+    Ok(records)
+}
+
+/// Find the run whose ID starts with the given prefix.
+fn find<'a>(records: &'a [Value], id: &str) -> Result<&'a Value, Error> {
+    let mut candidates = records
+        .iter()
+        .filter(|record| record["id"].as_str().is_some_and(|candidate| candidate.starts_with(id)));
+
+    match (candidates.next(), candidates.count()) {
+        (Some(record), 0) => Ok(record),
+        (Some(_), rest) => Err(Error::AmbiguousRun(id.to_owned(), rest + 1)),
+        (None, _) => Err(Error::UnknownRun(id.to_owned())),
+    }
+}
+
+fn list(records: &[Value]) {
+    let amount = records.len();
+    let s = if amount == 1 { "" } else { "s" };
+    records
+        .iter()
+        .map(|record| {
+            let runs = runs(record);
+            let paths = runs.len();
+            let s = if paths == 1 { "" } else { "s" };
+            let failures = runs.iter().filter(|run| run["outcome"] == "failure").count();
+            let t = if failures == 1 { "" } else { "s" };
+            format!(
+                "{}: {paths} path{s} with {failures} failure{t}, {}, `{}`",
+                describe(record),
+                toolchain(record),
+                arguments(record)
+            )
+        })
+        .fold(info(format!("the history contains {amount} run{s}")), Diagnostic::note)
+        .emit();
+}
+
+fn compare(baseline: &Value, current: &Value) {
+    let headline =
+        format!("comparing the run {} with the run {}", describe(baseline), describe(current));
+
+    let mut differences = differences(baseline, current);
+    if differences.is_empty() {
+        differences.push("no outcome or output changed".into());
+    }
+    differences
+        .push(format!("in total: {}", change(total_duration(baseline), total_duration(current))));
+
+    differences.into_iter().fold(info(headline), Diagnostic::note).emit();
+}
+
+/// The differences between the two runs that are noteworthy.
+fn differences(baseline: &Value, current: &Value) -> Vec<String> {
+    let mut differences = Vec::new();
+
+    let (old, new) = (toolchain(baseline), toolchain(current));
+    if old != new {
+        differences.push(format!("toolchain: {old} → {new}"));
+    }
+    let (old, new) = (arguments(baseline), arguments(current));
+    if old != new {
+        differences.push(format!("arguments: `{old}` → `{new}`"));
+    }
+
+    let (old_runs, new_runs) = (runs(baseline), runs(current));
+    for &old in &old_runs {
+        let path = old["path"].as_str().unwrap_or_default();
+        let Some(new) = find_run(&new_runs, &old["path"]) else {
+            differences.push(format!("`{path}`: only part of the baseline"));
+            continue;
+        };
+
+        if old["outcome"] != new["outcome"] {
+            let outcome = |run: &Value| run["outcome"].as_str().unwrap_or("unknown").to_owned();
+            differences.push(format!("`{path}`: {} → {}", outcome(old), outcome(new)));
+        }
+        if old["output"].is_string() && new["output"].is_string() && old["output"] != new["output"]
+        {
+            differences.push(format!("`{path}`: the output changed"));
+        }
+        let (old, new) = (duration(old), duration(new));
+        if is_significant(old, new) {
+            differences.push(format!("`{path}`: {}", change(old, new)));
+        }
+    }
+
+    for &new in &new_runs {
+        if find_run(&old_runs, &new["path"]).is_none() {
+            let path = new["path"].as_str().unwrap_or_default();
+            differences.push(format!("`{path}`: only part of the current run"));
+        }
+    }
+
+    differences
+}
+
+fn runs(record: &Value) -> Vec<&Value> {
+    record["runs"].as_array().into_iter().flatten().collect()
+}
+
+fn find_run<'a>(runs: &[&'a Value], path: &Value) -> Option<&'a Value> {
+    runs.iter().copied().find(|run| run["path"] == *path)
+}
+
+fn duration(run: &Value) -> Duration {
+    let steps = run["steps"].as_array().into_iter().flatten();
+    steps.filter_map(|step| step["duration"].as_f64()).map(Duration::from_secs_f64).sum()
+}
+
+fn total_duration(record: &Value) -> Duration {
+    runs(record).into_iter().map(duration).sum()
+}
+
+/// Whether the change in duration exceeds the noise we usually observe between runs.
+fn is_significant(old: Duration, new: Duration) -> bool {
+    let (shorter, longer) = if old < new { (old, new) } else { (new, old) };
+    longer - shorter >= Duration::from_millis(50)
+        && (longer - shorter).as_secs_f64() >= shorter.as_secs_f64() * 0.1
+}
+
+fn change(old: Duration, new: Duration) -> String {
+    let mut change = format!("{old:.2?} → {new:.2?}");
+    if !old.is_zero() {
+        let percentage = (new.as_secs_f64() - old.as_secs_f64()) * 100.0 / old.as_secs_f64();
+        change += &format!(" ({percentage:+.0}%)");
+    }
+    change
+}
+
+fn toolchain(record: &Value) -> String {
+    let toolchain = &record["toolchain"];
+    match (toolchain["name"].as_str(), toolchain["version"].as_str()) {
+        (Some(name), Some(version)) => format!("`{name}` ({version})"),
+        (None, Some(version)) => version.to_owned(),
+        (Some(name), None) => format!("`{name}`"),
+        (None, None) => "an unknown toolchain".into(),
+    }
+}
+
+fn arguments(record: &Value) -> String {
+    record["arguments"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .join_with(" ")
+        .to_string()
+}
+
+/// The ID of the run alongside how long ago it happened.
+fn describe(record: &Value) -> String {
+    let id = record["id"].as_str().unwrap_or_default();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let age = now.saturating_sub(Duration::from_secs(record["time"].as_u64().unwrap_or_default()));
+    format!("`{id}` ({} ago)", format_age(age))
+}
+
+fn format_age(age: Duration) -> String {
+    const UNITS: &[(&str, u64)] =
+        &[("w", 7 * 24 * 60 * 60), ("d", 24 * 60 * 60), ("h", 60 * 60), ("m", 60), ("s", 1)];
+
+    let seconds = age.as_secs();
+    let &(unit, factor) =
+        UNITS.iter().find(|&&(_, factor)| seconds >= factor).unwrap_or(&UNITS[UNITS.len() - 1]);
+    format!("{}{unit}", seconds / factor)
+}
+
+pub(crate) enum Error {
+    Empty,
+    NoBaseline,
+    UnknownRun(String),
+    AmbiguousRun(String, usize),
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::Empty => error("the history doesn't contain any runs")
+                .note("pass `--record` to record a run"),
+            Self::NoBaseline => {
+                error("the history doesn't contain an earlier run with the same arguments")
+                    .note("pass the ID of the baseline explicitly (see `rruxwry history list`)")
+            }
+            Self::UnknownRun(id) => {
+                error(format!("the history doesn't contain a run with the ID `{id}`"))
+            }
+            Self::AmbiguousRun(id, amount) => error(format!("the ID `{id}` is ambiguous"))
+                .note(format!("it matches {amount} runs")),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use super::{differences, find, format_age};
+use serde_json::{Value, json};
+use std::time::Duration;
+
+fn record(id: &str, version: &str, runs: Value) -> Value {
+    json!({
+        "id": id,
+        "time": 0,
+        "arguments": ["-j", "lib.rs"],
+        "toolchain": { "name": "nightly", "version": version },
+        "runs": runs,
+    })
+}
+
+fn run(path: &str, outcome: &str, output: &str, duration: f64) -> Value {
+    json!({
+        "path": path,
+        "outcome": outcome,
+        "steps": [{ "name": "documenting `lib`", "duration": duration }],
+        "output": output,
+    })
+}
+
+#[test]
+fn changes() {
+    let baseline = record(
+        "a",
+        "rustdoc 1.83.0-nightly (fa724e5d8 2024-09-27)",
+        json!([
+            run("a.rs", "success", "00", 1.0),
+            run("b.rs", "success", "00", 1.0),
+            run("c.rs", "success", "00", 1.0),
+        ]),
+    );
+    let current = record(
+        "b",
+        "rustdoc 1.83.0-nightly (9e394f551 2024-09-28)",
+        json!([
+            run("a.rs", "failure", "00", 1.02),
+            run("b.rs", "success", "01", 2.0),
+            run("d.rs", "success", "00", 1.0),
+        ]),
+    );
+
+    assert_eq!(differences(&baseline, &current), [
+        "toolchain: `nightly` (rustdoc 1.83.0-nightly (fa724e5d8 2024-09-27)) → \
+         `nightly` (rustdoc 1.83.0-nightly (9e394f551 2024-09-28))",
+        "`a.rs`: success → failure",
+        "`b.rs`: the output changed",
+        "`b.rs`: 1.00s → 2.00s (+100%)",
+        "`c.rs`: only part of the baseline",
+        "`d.rs`: only part of the current run",
+    ]);
+    assert!(differences(&baseline, &baseline).is_empty());
+}
+
+#[test]
+fn prefixes() {
+    let records = [
+        record("19a-1", "", json!([])),
+        record("19b-1", "", json!([])),
+        record("19b-2", "", json!([])),
+    ];
+
+    assert_eq!(find(&records, "19a").ok().map(|record| &record["id"]), Some(&json!("19a-1")));
+    assert!(find(&records, "19b").is_err());
+    assert!(find(&records, "20").is_err());
+}
+
+#[test]
+fn ages() {
+    assert_eq!(format_age(Duration::ZERO), "0s");
+    assert_eq!(format_age(Duration::from_secs(90)), "1m");
+    assert_eq!(format_age(Duration::from_secs(3 * 24 * 60 * 60)), "3d");
+    assert_eq!(format_age(Duration::from_secs(15 * 24 * 60 * 60)), "2w");
+}
@@ -0,0 +1,227 @@
+//! A lenient tokenizer for the HTML output of rustdoc.
+//!
+//! It's by no means spec-compliant but it handles everything rustdoc is known to emit.
+
+use std::borrow::Cow;
+
+#[cfg(test)]
+mod test;
+
+pub(crate) enum Token<'a> {
+    StartTag(Tag<'a>),
+    EndTag(&'a str),
+    /// Raw text including the contents of `<script>` and `<style>` elements.
+    ///
+    /// Character references are *not* decoded, see [`decode`].
+    Text(&'a str),
+}
+
+pub(crate) struct Tag<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) attributes: Vec<(&'a str, Cow<'a, str>)>,
+    pub(crate) self_closing: bool,
+}
+
+impl Tag<'_> {
+    pub(crate) fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| *key == name).map(|(_, value)| &**value)
+    }
+}
+
+pub(crate) fn tokenize(html: &str) -> Tokens<'_> {
+    Tokens { rest: html, raw: None }
+}
+
+pub(crate) struct Tokens<'a> {
+    rest: &'a str,
+    /// The name of the raw text element we're currently inside of.
+    raw: Option<&'a str>,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(name) = self.raw.take() {
+            let end = self.rest.find(&format!("</{name}")).unwrap_or(self.rest.len());
+            let (text, rest) = self.rest.split_at(end);
+            self.rest = rest;
+            if !text.is_empty() {
+                return Some(Token::Text(text));
+            }
+        }
+
+        loop {
+            if self.rest.is_empty() {
+                return None;
+            }
+
+            let start = self.rest.find('<').unwrap_or(self.rest.len());
+            if start > 0 {
+                let (text, rest) = self.rest.split_at(start);
+                self.rest = rest;
+                return Some(Token::Text(text));
+            }
+
+            if let Some(rest) = self.rest.strip_prefix("<!--") {
+                self.rest = rest.find("-->").map_or("", |end| &rest[end + "-->".len()..]);
+                continue;
+            }
+
+            if self.rest.starts_with("<!") || self.rest.starts_with("<?") {
+                self.rest = self.rest.find('>').map_or("", |end| &self.rest[end + 1..]);
+                continue;
+            }
+
+            if let Some(rest) = self.rest.strip_prefix("</") {
+                let end = rest.find('>').unwrap_or(rest.len());
+                self.rest = rest.get(end + 1..).unwrap_or_default();
+                return Some(Token::EndTag(rest[..end].trim()));
+            }
+
+            if !self.rest[1..].starts_with(|char: char| char.is_ascii_alphabetic()) {
+                let (text, rest) = self.rest.split_at(1);
+                self.rest = rest;
+                return Some(Token::Text(text));
+            }
+
+            let tag = self.start_tag();
+            if matches!(tag.name, "script" | "style") && !tag.self_closing {
+                self.raw = Some(tag.name);
+            }
+            return Some(Token::StartTag(tag));
+        }
+    }
+}
+
+impl<'a> Tokens<'a> {
+    fn start_tag(&mut self) -> Tag<'a> {
+        let rest = &self.rest[1..];
+        let end = rest.find(|char: char| char.is_ascii_whitespace() || matches!(char, '/' | '>'));
+        let (name, mut rest) = rest.split_at(end.unwrap_or(rest.len()));
+
+        let mut attributes = Vec::new();
+        let mut self_closing = false;
+
+        loop {
+            rest = rest.trim_start();
+
+            if let Some(remainder) = rest.strip_prefix("/>") {
+                self_closing = true;
+                rest = remainder;
+                break;
+            }
+            if let Some(remainder) = rest.strip_prefix('>') {
+                rest = remainder;
+                break;
+            }
+            if let Some(remainder) = rest.strip_prefix('/') {
+                rest = remainder;
+                continue;
+            }
+            if rest.is_empty() {
+                break;
+            }
+
+            let end = rest
+                .find(|char: char| char.is_ascii_whitespace() || matches!(char, '=' | '>' | '/'));
+            let (key, remainder) = rest.split_at(end.unwrap_or(rest.len()));
+            rest = remainder.trim_start();
+
+            let Some(remainder) = rest.strip_prefix('=') else {
+                attributes.push((key, Cow::Borrowed("")));
+                continue;
+            };
+            rest = remainder.trim_start();
+
+            let value = match rest.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let end = rest[1..].find(quote).map_or(rest.len(), |end| end + 1);
+                    let value = &rest[1..end];
+                    rest = rest.get(end + 1..).unwrap_or_default();
+                    value
+                }
+                _ => {
+                    let end = rest.find(|char: char| char.is_ascii_whitespace() || char == '>');
+                    let (value, remainder) = rest.split_at(end.unwrap_or(rest.len()));
+                    rest = remainder;
+                    value
+                }
+            };
+
+            attributes.push((key, decode(value)));
+        }
+
+        self.rest = rest;
+        Tag { name, attributes, self_closing }
+    }
+}
+
+/// Decode the character references found in the given text.
+///
+/// Only numeric references and the named ones rustdoc emits are supported.
+pub(crate) fn decode(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let reference = rest[1..].find(';').map(|end| &rest[1..end + 1]);
+        let Some(char) = reference.and_then(decode_reference) else {
+            result.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+
+        result.push(char);
+        rest = &rest[reference.map_or(0, str::len) + 2..];
+    }
+
+    result.push_str(rest);
+    Cow::Owned(result)
+}
+
+/// Escape the given text for use in text content and attribute values.
+pub(crate) fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"']) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut result = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            char => result.push(char),
+        }
+    }
+    Cow::Owned(result)
+}
+
+fn decode_reference(reference: &str) -> Option<char> {
+    if let Some(number) = reference.strip_prefix('#') {
+        let number = match number.strip_prefix(['x', 'X']) {
+            Some(number) => u32::from_str_radix(number, 16),
+            None => number.parse(),
+        };
+        return number.ok().and_then(char::from_u32);
+    }
+
+    Some(match reference {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{A0}',
+        _ => return None,
+    })
+}
@@ -0,0 +1,47 @@
+use super::{Token, decode, tokenize};
+
+fn start_tags(html: &str) -> Vec<(&str, Vec<(&str, String)>)> {
+    tokenize(html)
+        .filter_map(|token| match token {
+            Token::StartTag(tag) => Some((
+                tag.name,
+                tag.attributes.into_iter().map(|(key, value)| (key, value.into_owned())).collect(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn attributes() {
+    assert_eq!(start_tags(r#"<a href="x.html" class='y' data-z=w hidden>"#), vec![("a", vec![
+        ("href", "x.html".to_owned()),
+        ("class", "y".to_owned()),
+        ("data-z", "w".to_owned()),
+        ("hidden", String::new()),
+    ])]);
+}
+
+#[test]
+fn self_closing_and_comments() {
+    let mut tokens = tokenize("<!DOCTYPE html><!-- <b> --><br/>text</p>");
+    assert!(
+        matches!(tokens.next(), Some(Token::StartTag(tag)) if tag.name == "br" && tag.self_closing)
+    );
+    assert!(matches!(tokens.next(), Some(Token::Text("text"))));
+    assert!(matches!(tokens.next(), Some(Token::EndTag("p"))));
+    assert!(tokens.next().is_none());
+}
+
+#[test]
+fn raw_text() {
+    let tokens: Vec<_> = tokenize(r#"<script>if (a<b) { x = "<i>"; }</script><i>"#).collect();
+    assert!(matches!(tokens[1], Token::Text(r#"if (a<b) { x = "<i>"; }"#)));
+    assert!(matches!(tokens[2], Token::EndTag("script")));
+    assert!(matches!(&tokens[3], Token::StartTag(tag) if tag.name == "i"));
+}
+
+#[test]
+fn character_references() {
+    assert_eq!(decode("a &amp; b &lt;&#60;&#x3C; &unknown; &"), "a & b <<< &unknown; &");
+}
@@ -0,0 +1,127 @@
+//! Re-running `rustdoc` on ICEs to obtain backtraces (`--rerun-ices`).
+//!
+//! The output of the crashing invocation gets suppressed since the reruns reproduce it anyway.
+
+use serde_json::{Value, json};
+use std::{fmt, num::NonZeroU32, sync::Mutex};
+
+#[cfg(test)]
+mod test;
+
+/// The exit code of rustc and rustdoc on ICEs.
+pub(crate) const EXIT_CODE: i32 = 101;
+
+static ICES: Mutex<Vec<Ice>> = Mutex::new(Vec::new());
+
+/// A way of re-running a crashing invocation.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(Debug))]
+pub(crate) enum Variant {
+    /// With `RUST_BACKTRACE=full`.
+    Backtrace,
+    /// With `RUST_BACKTRACE=full` and `-Ztreat-err-as-bug`, optionally turning the N-th error
+    /// into an ICE.
+    TreatErrAsBug(Option<NonZeroU32>),
+}
+
+impl Variant {
+    pub(crate) fn parse_cli_style(source: &str) -> Result<Self, &'static str> {
+        const EXPECTED: &str = "expected `backtrace` or `treat-err-as-bug[=N]` with `N` > 0";
+
+        match source.split_once('=') {
+            None if source == "backtrace" => Ok(Self::Backtrace),
+            None if source == "treat-err-as-bug" => Ok(Self::TreatErrAsBug(None)),
+            Some(("treat-err-as-bug", amount)) => {
+                Ok(Self::TreatErrAsBug(Some(amount.parse().map_err(|_| EXPECTED)?)))
+            }
+            _ => Err(EXPECTED),
+        }
+    }
+
+    /// The flag to pass to `rustdoc` in addition to setting `RUST_BACKTRACE`.
+    pub(crate) fn flag(self) -> Option<String> {
+        match self {
+            Self::Backtrace => None,
+            Self::TreatErrAsBug(None) => Some("-Ztreat-err-as-bug".into()),
+            Self::TreatErrAsBug(Some(amount)) => Some(format!("-Ztreat-err-as-bug={amount}")),
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.flag() {
+            Some(flag) => write!(f, "`RUST_BACKTRACE=full` and `{flag}`"),
+            None => write!(f, "`RUST_BACKTRACE=full`"),
+        }
+    }
+}
+
+struct Ice {
+    crate_name: String,
+    variant: Variant,
+    /// Where the compiler panicked alongside the panic message.
+    panic: Option<(String, String)>,
+    backtrace: Vec<String>,
+    query_stack: Vec<String>,
+}
+
+impl Ice {
+    fn parse(crate_name: &str, variant: Variant, stderr: &str) -> Self {
+        let mut ice = Self {
+            crate_name: crate_name.to_owned(),
+            variant,
+            panic: None,
+            backtrace: Vec::new(),
+            query_stack: Vec::new(),
+        };
+
+        let mut lines = stderr.lines();
+        while let Some(line) = lines.next() {
+            if let Some(location) =
+                line.split_once(" panicked at ").and_then(|(_, rest)| rest.strip_suffix(':'))
+                && ice.panic.is_none()
+            {
+                let message = lines.next().unwrap_or_default();
+                ice.panic = Some((location.to_owned(), message.to_owned()));
+            } else if line == "stack backtrace:" {
+                ice.backtrace = lines
+                    .by_ref()
+                    .take_while(|line| line.starts_with(' '))
+                    .map(|line| line.trim().to_owned())
+                    .collect();
+            } else if line == "query stack during panic:" {
+                ice.query_stack = lines
+                    .by_ref()
+                    .take_while(|line| *line != "end of query stack")
+                    .map(ToOwned::to_owned)
+                    .collect();
+            }
+        }
+
+        ice
+    }
+
+    fn to_json(&self) -> Value {
+        let mut ice = json!({
+            "crate": self.crate_name,
+            "variant": self.variant.flag(),
+            "backtrace": self.backtrace,
+            "query_stack": self.query_stack,
+        });
+        if let Some((location, message)) = &self.panic {
+            ice["panic"] = json!({ "location": location, "message": message });
+        }
+        ice
+    }
+}
+
+/// Record the ICE of the given crate found in the stderr of a rerun.
+pub(crate) fn record(crate_name: &str, variant: Variant, stderr: &str) {
+    ICES.lock().unwrap().push(Ice::parse(crate_name, variant, stderr));
+}
+
+/// Take all ICEs recorded so far in their JSON form.
+pub(crate) fn take() -> Vec<Value> {
+    std::mem::take(&mut *ICES.lock().unwrap()).iter().map(Ice::to_json).collect()
+}
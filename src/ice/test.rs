@@ -0,0 +1,58 @@
+use super::{Ice, Variant};
+use std::num::NonZeroU32;
+
+#[test]
+fn variants() {
+    assert_eq!(Variant::parse_cli_style("backtrace"), Ok(Variant::Backtrace));
+    assert_eq!(Variant::parse_cli_style("treat-err-as-bug"), Ok(Variant::TreatErrAsBug(None)));
+    assert_eq!(
+        Variant::parse_cli_style("treat-err-as-bug=2"),
+        Ok(Variant::TreatErrAsBug(NonZeroU32::new(2)))
+    );
+    assert!(Variant::parse_cli_style("treat-err-as-bug=0").is_err());
+    assert!(Variant::parse_cli_style("full").is_err());
+}
+
+#[test]
+fn output() {
+    let ice = Ice::parse(
+        "krate",
+        Variant::TreatErrAsBug(None),
+        "\
+error: internal compiler error[E0412]: cannot find type `Missing` in this scope
+ --> bad.rs:1:15
+
+thread 'rustc' panicked at compiler/rustc_errors/src/lib.rs:1763:17:
+aborting due to `-Z treat-err-as-bug=1`
+stack backtrace:
+   0:     0x7f3a60af57da - std::backtrace::print
+   1:     0x7f3a612037e6 - rustc_errors::DiagCtxtInner::emit_diagnostic
+                               at /rustc/compiler/rustc_errors/src/lib.rs:1763:17
+error: the compiler unexpectedly panicked. this is a bug.
+
+note: compiler flags: -Z treat-err-as-bug
+
+query stack during panic:
+#0 [resolver_for_lowering_raw] getting the resolver for lowering
+#1 [hir_crate] getting the crate HIR
+end of query stack
+",
+    );
+
+    assert_eq!(
+        ice.panic,
+        Some((
+            "compiler/rustc_errors/src/lib.rs:1763:17".to_owned(),
+            "aborting due to `-Z treat-err-as-bug=1`".to_owned()
+        ))
+    );
+    assert_eq!(ice.backtrace, [
+        "0:     0x7f3a60af57da - std::backtrace::print",
+        "1:     0x7f3a612037e6 - rustc_errors::DiagCtxtInner::emit_diagnostic",
+        "at /rustc/compiler/rustc_errors/src/lib.rs:1763:17",
+    ]);
+    assert_eq!(ice.query_stack, [
+        "#0 [resolver_for_lowering_raw] getting the resolver for lowering",
+        "#1 [hir_crate] getting the crate HIR",
+    ]);
+}
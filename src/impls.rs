@@ -0,0 +1,183 @@
+//! Reporting the trait implementations of the documented types.
+//!
+//! The implementations are taken from the JSON output and cross-referenced with the
+//! "Trait Implementations" sections (and the like) of the HTML output.
+
+use crate::{
+    data::CrateNameRef,
+    diagnostic::{Diagnostic, IntoDiagnostic, error},
+    html::{self, Token},
+    json::{id_to_key, item_kind},
+};
+use joinery::JoinableIterator;
+use serde_json::Value;
+use std::{collections::BTreeMap, io, path::Path};
+
+#[cfg(test)]
+mod test;
+
+/// Print the trait implementations of every type of the given crate found in `root`
+/// and check that they're rendered in the HTML output.
+pub(crate) fn report(root: &Path, crate_name: CrateNameRef<'_>) -> crate::error::Result {
+    let json = std::fs::read(root.join(format!("{crate_name}.json")))?;
+    let json: Value = serde_json::from_slice(&json).map_err(io::Error::from)?;
+
+    let mut types = BTreeMap::new();
+
+    for item in json["index"].as_object().into_iter().flat_map(|index| index.values()) {
+        let Some(kind @ ("struct" | "enum" | "union")) = item_kind(item) else { continue };
+        let Some(path) = json["paths"][id_to_key(&item["id"])]["path"].as_array() else {
+            continue;
+        };
+        let path: Vec<_> = path.iter().filter_map(Value::as_str).collect();
+
+        let mut impls: Vec<_> = item["inner"][kind]["impls"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|id| json["index"].get(id_to_key(id)))
+            .filter_map(|item| Impl::new(item, &json))
+            .collect();
+        impls.sort_by(|a, b| (a.origin.name(), &a.name).cmp(&(b.origin.name(), &b.name)));
+
+        types.insert(path, (kind, impls));
+    }
+
+    let mut missing = Vec::new();
+
+    for (path, (kind, impls)) in &types {
+        println!("{}", path.iter().join_with("::"));
+        for implementation in impls {
+            println!("    {:<8} {}", implementation.origin.name(), implementation.name);
+        }
+
+        let [modules @ .., name] = &path[..] else { continue };
+        let page = root.join(format!("{}/{kind}.{name}.html", modules.iter().join_with("/")));
+        let Ok(page) = std::fs::read_to_string(&page) else { continue };
+        let sections = impl_sections(&page);
+
+        let mut counts = BTreeMap::<_, usize>::new();
+        for implementation in impls {
+            *counts.entry(implementation.name.as_str()).or_default() += 1;
+        }
+
+        for (trait_name, expected) in counts {
+            let actual = sections.iter().filter(|id| renders(id, trait_name)).count();
+            if actual < expected {
+                missing.push(format!(
+                    "`{}`: `{trait_name}` (found {actual} of {expected} in the HTML output)",
+                    path.iter().join_with("::")
+                ));
+            }
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(Error { missing }.into());
+    }
+
+    Ok(())
+}
+
+struct Impl {
+    /// The name of the implemented trait.
+    name: String,
+    origin: Origin,
+}
+
+impl Impl {
+    fn new(item: &Value, json: &Value) -> Option<Self> {
+        let implementation = &item["inner"]["impl"];
+        let trait_ = &implementation["trait"];
+        // Inherent implementations.
+        if trait_.is_null() {
+            return None;
+        }
+
+        // Newer format versions use `path` instead of `name`.
+        let path = trait_["path"].as_str().or(trait_["name"].as_str())?;
+        let name = path.rsplit("::").next().unwrap_or(path).to_owned();
+
+        let origin = if implementation["is_synthetic"] == true {
+            Origin::Auto
+        } else if !implementation["blanket_impl"].is_null() {
+            Origin::Blanket
+        } else if is_derived(item) {
+            Origin::Derived
+        } else if json["index"].get(id_to_key(&trait_["id"])).is_some() {
+            Origin::Local
+        } else {
+            Origin::Foreign
+        };
+
+        Some(Self { name, origin })
+    }
+}
+
+fn is_derived(item: &Value) -> bool {
+    item["attrs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|attr| attr.as_str().is_some_and(|attr| attr.contains("automatically_derived")))
+}
+
+#[derive(Clone, Copy)]
+enum Origin {
+    /// A local trait implemented manually.
+    Local,
+    /// Implemented via a `derive` macro.
+    Derived,
+    /// Covered by a blanket implementation.
+    Blanket,
+    /// An auto trait implementation synthesized by rustdoc.
+    Auto,
+    /// A trait from another crate implemented manually.
+    Foreign,
+}
+
+impl Origin {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::Derived => "derived",
+            Self::Blanket => "blanket",
+            Self::Auto => "auto",
+            Self::Foreign => "foreign",
+        }
+    }
+}
+
+/// The IDs of the sections of trait implementations found on the given page.
+fn impl_sections(page: &str) -> Vec<String> {
+    html::tokenize(page)
+        .filter_map(|token| match token {
+            Token::StartTag(tag) if tag.name == "section" => {
+                tag.attribute("id").filter(|id| id.starts_with("impl-")).map(ToOwned::to_owned)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether the section with the given ID renders an implementation of the given trait.
+fn renders(id: &str, trait_name: &str) -> bool {
+    let Some(rest) = id.strip_prefix("impl-") else { return false };
+    let rest = rest.strip_prefix('!').unwrap_or(rest);
+    rest.strip_prefix(trait_name)
+        .is_some_and(|rest| rest.starts_with("-for-") || rest.starts_with("%3C"))
+}
+
+pub(crate) struct Error {
+    missing: Vec<String>,
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        let amount = self.missing.len();
+        let s = if amount == 1 { "" } else { "s" };
+        let diagnostic =
+            error(format!("found {amount} trait implementation{s} missing from the HTML output"));
+        self.missing.into_iter().fold(diagnostic, Diagnostic::note)
+    }
+}
@@ -0,0 +1,56 @@
+use super::{Impl, impl_sections, renders};
+use serde_json::{Value, json};
+
+fn origin(item: Value) -> Option<(String, &'static str)> {
+    let json = json!({ "index": { "1": { "id": 1, "name": "T" } } });
+    Impl::new(&item, &json)
+        .map(|implementation| (implementation.name, implementation.origin.name()))
+}
+
+#[test]
+fn origins() {
+    let local = json!({ "inner": { "impl": { "trait": { "id": 1, "path": "T" } } } });
+    assert_eq!(origin(local), Some(("T".to_owned(), "local")));
+
+    let foreign = json!({ "inner": { "impl": { "trait": { "id": 2, "name": "fmt::Debug" } } } });
+    assert_eq!(origin(foreign), Some(("Debug".to_owned(), "foreign")));
+
+    let derived = json!({
+        "attrs": ["#[automatically_derived]"],
+        "inner": { "impl": { "trait": { "id": 3, "path": "Clone" } } },
+    });
+    assert_eq!(origin(derived), Some(("Clone".to_owned(), "derived")));
+
+    let blanket = json!({
+        "inner": { "impl": { "trait": { "id": 4, "path": "From" }, "blanket_impl": { "generic": "T" } } },
+    });
+    assert_eq!(origin(blanket), Some(("From".to_owned(), "blanket")));
+
+    let auto = json!({
+        "inner": { "impl": { "trait": { "id": 5, "path": "Send" }, "is_synthetic": true } },
+    });
+    assert_eq!(origin(auto), Some(("Send".to_owned(), "auto")));
+
+    let inherent = json!({ "inner": { "impl": { "trait": null } } });
+    assert_eq!(origin(inherent), None);
+}
+
+#[test]
+fn sections() {
+    let page = r#"<section id="impl-S"></section><section id="impl-Clone-for-S"></section>
+        <div id="impl-Debug-for-S"></div><section id="method.f"></section>
+        <section id="impl-!Send-for-S"></section><section id="impl-From%3CT%3E-for-S"></section>"#;
+    let sections = impl_sections(page);
+    assert_eq!(sections, [
+        "impl-S",
+        "impl-Clone-for-S",
+        "impl-!Send-for-S",
+        "impl-From%3CT%3E-for-S"
+    ]);
+
+    assert!(renders("impl-Clone-for-S", "Clone"));
+    assert!(renders("impl-!Send-for-S", "Send"));
+    assert!(renders("impl-From%3CT%3E-for-S", "From"));
+    assert!(!renders("impl-S", "S"));
+    assert!(!renders("impl-CloneFrom-for-S", "Clone"));
+}
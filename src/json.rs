@@ -0,0 +1,89 @@
+//! Utilities for dealing with rustdoc's JSON output.
+
+use owo_colors::{AnsiColors, OwoColorize};
+use serde_json::Value;
+use std::io::{self, Write};
+
+pub(crate) mod extract;
+pub(crate) mod filter;
+
+/// Pretty-print the given JSON value, optionally colorizing it.
+pub(crate) fn print_pretty(
+    value: &Value,
+    colored: bool,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    if colored {
+        write_colored(value, 0, output)?;
+        writeln!(output)
+    } else {
+        serde_json::to_writer_pretty(&mut *output, value)?;
+        writeln!(output)
+    }
+}
+
+/// The kind of the item, i.e., the sole key of its `inner` object.
+pub(crate) fn item_kind(item: &Value) -> Option<&str> {
+    item["inner"].as_object()?.keys().next().map(String::as_str)
+}
+
+/// Item IDs are strings in older format versions and integers in newer ones.
+pub(crate) fn id_to_key(id: &Value) -> String {
+    match id {
+        Value::String(id) => id.clone(),
+        id => id.to_string(),
+    }
+}
+
+fn write_colored(value: &Value, depth: usize, output: &mut impl Write) -> io::Result<()> {
+    const INDENTATION: &str = "  ";
+
+    match value {
+        Value::Null => write!(output, "{}", "null".color(palette::LITERAL)),
+        Value::Bool(value) => write!(output, "{}", value.color(palette::LITERAL)),
+        Value::Number(value) => write!(output, "{}", value.color(palette::NUMBER)),
+        Value::String(value) => {
+            write!(output, "{}", Value::from(value.as_str()).color(palette::STRING))
+        }
+        Value::Array(values) if values.is_empty() => write!(output, "[]"),
+        Value::Array(values) => {
+            writeln!(output, "[")?;
+            for (index, value) in values.iter().enumerate() {
+                write!(output, "{}", INDENTATION.repeat(depth + 1))?;
+                write_colored(value, depth + 1, output)?;
+                if index + 1 != values.len() {
+                    write!(output, ",")?;
+                }
+                writeln!(output)?;
+            }
+            write!(output, "{}]", INDENTATION.repeat(depth))
+        }
+        Value::Object(entries) if entries.is_empty() => write!(output, "{{}}"),
+        Value::Object(entries) => {
+            writeln!(output, "{{")?;
+            for (index, (key, value)) in entries.iter().enumerate() {
+                write!(
+                    output,
+                    "{}{}: ",
+                    INDENTATION.repeat(depth + 1),
+                    Value::from(key.as_str()).color(palette::KEY).bold()
+                )?;
+                write_colored(value, depth + 1, output)?;
+                if index + 1 != entries.len() {
+                    write!(output, ",")?;
+                }
+                writeln!(output)?;
+            }
+            write!(output, "{}}}", INDENTATION.repeat(depth))
+        }
+    }
+}
+
+mod palette {
+    use super::AnsiColors;
+
+    pub(super) const KEY: AnsiColors = AnsiColors::Blue;
+    pub(super) const STRING: AnsiColors = AnsiColors::Green;
+    pub(super) const NUMBER: AnsiColors = AnsiColors::Cyan;
+    pub(super) const LITERAL: AnsiColors = AnsiColors::Magenta;
+}
@@ -0,0 +1,108 @@
+//! Printing an excerpt of the JSON output (`rruxwry extract`).
+//!
+//! The excerpt is selected either by a JSON pointer (RFC 6901) like `/index/123/inner` or by the
+//! path of a local item like `krate::Type::method`, in which case it's the entry of the item in
+//! the index. This way, issue reports can include precisely the relevant part of the output.
+
+use super::{
+    filter::{apply, item_paths},
+    print_pretty,
+};
+use crate::{
+    builder::{self, BuildMode},
+    cli,
+    command::{self, Flags},
+    data::{CrateNameRef, CrateType, Edition},
+    diagnostic::{Diagnostic, IntoDiagnostic, error},
+};
+use joinery::JoinableIterator;
+use serde_json::Value;
+use std::io::{self, IsTerminal};
+
+#[cfg(test)]
+mod test;
+
+pub(crate) enum Selector<'a> {
+    Pointer(&'a str),
+    Item(&'a str),
+}
+
+pub(crate) fn run(
+    selector: Selector<'_>,
+    build_mode: BuildMode,
+    path: &std::path::Path,
+    crate_name: CrateNameRef<'_>,
+    crate_type: CrateType,
+    edition: Edition,
+    flags: Flags<'_>,
+) -> crate::error::Result {
+    let build_flags = cli::BuildFlags { json: true, ..flags.build.clone() };
+    let flags = Flags { build: &build_flags, ..flags };
+    let documented = builder::build(build_mode, path, crate_name, crate_type, edition, flags)?;
+
+    if flags.program.dry_run {
+        return Ok(());
+    }
+
+    let json = std::fs::read(command::entry_point(documented[0].as_ref(), flags.build)?)?;
+    let mut json: Value = serde_json::from_slice(&json).map_err(io::Error::from)?;
+    // E.g., for stripping the spans of the excerpt.
+    apply(&mut json, &flags.program.json_filter);
+    let excerpt = select(&json, &selector)?;
+
+    let stdout = io::stdout();
+    let colored = stdout.is_terminal();
+    print_pretty(excerpt, colored, &mut stdout.lock())?;
+    Ok(())
+}
+
+fn select<'v>(json: &'v Value, selector: &Selector<'_>) -> Result<&'v Value, Error> {
+    match *selector {
+        Selector::Pointer(pointer) => {
+            json.pointer(pointer).ok_or_else(|| Error::UnknownPointer(pointer.to_owned()))
+        }
+        Selector::Item(path) => {
+            let mut ids: Vec<_> = item_paths(json)
+                .into_iter()
+                .filter(|(id, candidate)| {
+                    // Impls are considered to be located under the type they're for.
+                    candidate == path && !json["index"][id]["name"].is_null()
+                })
+                .map(|(id, _)| id)
+                .collect();
+            ids.sort();
+            match &ids[..] {
+                [id] => Ok(&json["index"][id]),
+                [] => Err(Error::UnknownItem(path.to_owned())),
+                _ => Err(Error::AmbiguousItem { path: path.to_owned(), ids }),
+            }
+        }
+    }
+}
+
+#[cfg_attr(test, derive(Debug))]
+pub(crate) enum Error {
+    UnknownPointer(String),
+    UnknownItem(String),
+    AmbiguousItem { path: String, ids: Vec<String> },
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::UnknownPointer(pointer) => {
+                error(format!("the JSON output doesn't contain a value at `{pointer}`"))
+                    .note("JSON pointers have the form `/key/…`, e.g., `/index/0/inner`")
+            }
+            Self::UnknownItem(path) => {
+                error(format!("the JSON output doesn't contain a local item at `{path}`"))
+            }
+            Self::AmbiguousItem { path, ids } => {
+                error(format!("the path `{path}` refers to several items")).note(format!(
+                    "select one of them via `--pointer`: {}",
+                    ids.iter().map(|id| format!("`/index/{id}`")).join_with(", ")
+                ))
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use super::{Error, Selector, select};
+use serde_json::{Value, json};
+
+fn output() -> Value {
+    json!({
+        "root": 0,
+        "index": {
+            "0": { "name": "krate", "inner": { "module": { "items": [1, 4, 6] } } },
+            "1": { "name": "S", "inner": { "struct": { "kind": { "unit": null }, "impls": [3] } } },
+            "3": { "name": null, "inner": { "impl": { "items": [5] } } },
+            "4": { "name": "f", "inner": { "function": {} } },
+            "5": { "name": "new", "inner": { "function": {} } },
+            "6": { "name": "f", "inner": { "macro": "macro_rules! f {}" } },
+        },
+        "paths": {
+            "0": { "crate_id": 0, "path": ["krate"], "kind": "module" },
+            "1": { "crate_id": 0, "path": ["krate", "S"], "kind": "struct" },
+            "4": { "crate_id": 0, "path": ["krate", "f"], "kind": "function" },
+            "6": { "crate_id": 0, "path": ["krate", "f"], "kind": "macro" },
+        },
+    })
+}
+
+#[test]
+fn pointers() {
+    let json = output();
+    let selected = select(&json, &Selector::Pointer("/index/5/inner")).unwrap();
+    assert_eq!(*selected, json!({ "function": {} }));
+    assert!(matches!(
+        select(&json, &Selector::Pointer("/index/7")),
+        Err(Error::UnknownPointer(pointer)) if pointer == "/index/7"
+    ));
+}
+
+#[test]
+fn items() {
+    let json = output();
+    // The impl shares the path of `S` but it isn't `S`.
+    assert_eq!(select(&json, &Selector::Item("krate::S")).unwrap()["name"], "S");
+    assert_eq!(select(&json, &Selector::Item("krate::S::new")).unwrap()["name"], "new");
+    assert!(matches!(
+        select(&json, &Selector::Item("krate::T")),
+        Err(Error::UnknownItem(path)) if path == "krate::T"
+    ));
+    assert!(matches!(
+        select(&json, &Selector::Item("krate::f")),
+        Err(Error::AmbiguousItem { ids, .. }) if ids == ["4", "6"]
+    ));
+}
@@ -0,0 +1,154 @@
+//! Post-processing the JSON output to make it tractable for eyeballing and diffing (`--json-filter`).
+//!
+//! Items that don't have a path of their own (e.g., impls, fields and associated items) are
+//! considered to be located under the item that contains them, so filtering by the path
+//! `krate::Type` retains the impls of `Type` and their associated items, too.
+
+use super::{id_to_key, item_kind};
+use joinery::JoinableIterator;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde_json::Value;
+use std::{io, path::Path};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Clone)]
+pub(crate) enum Filter {
+    /// Only retain the items of the given kind, e.g., `struct` or `impl`.
+    Kind(String),
+    /// Only retain the items under the given path, e.g., `krate::module`.
+    Path(String),
+    StripSpans,
+}
+
+impl Filter {
+    pub(crate) fn parse_cli_style(source: &str) -> Result<Self, &'static str> {
+        const EXPECTED: &str = "expected `kind=KIND`, `path=PREFIX` or `strip-spans`";
+
+        match source.split_once('=') {
+            Some(("kind", kind)) if !kind.is_empty() => Ok(Self::Kind(kind.to_owned())),
+            Some(("path", path)) if !path.is_empty() => Ok(Self::Path(path.to_owned())),
+            None if source == "strip-spans" => Ok(Self::StripSpans),
+            _ => Err(EXPECTED),
+        }
+    }
+}
+
+/// Apply the given filters to the JSON output at the given path and pretty-print it in place.
+pub(crate) fn rewrite(path: &Path, filters: &[Filter]) -> io::Result<()> {
+    let json = std::fs::read(path)?;
+    let mut json: Value = serde_json::from_slice(&json)?;
+    apply(&mut json, filters);
+    std::fs::write(path, serde_json::to_string_pretty(&json)? + "\n")
+}
+
+/// Apply the given filters to the JSON output.
+pub(crate) fn apply(json: &mut Value, filters: &[Filter]) {
+    let kinds: Vec<_> = filters
+        .iter()
+        .filter_map(|filter| match filter {
+            Filter::Kind(kind) => Some(kind.as_str()),
+            _ => None,
+        })
+        .collect();
+    let prefixes: Vec<_> = filters
+        .iter()
+        .filter_map(|filter| match filter {
+            Filter::Path(path) => Some(path.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    if !kinds.is_empty() || !prefixes.is_empty() {
+        let paths = item_paths(json);
+        let retained: FxHashSet<String> = json["index"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(_, item)| {
+                kinds.is_empty() || item_kind(item).is_some_and(|kind| kinds.contains(&kind))
+            })
+            .filter(|(id, _)| {
+                prefixes.is_empty()
+                    || paths
+                        .get(*id)
+                        .is_some_and(|path| prefixes.iter().any(|prefix| is_under(path, prefix)))
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for key in ["index", "paths"] {
+            if let Some(entries) = json[key].as_object_mut() {
+                entries.retain(|id, _| retained.contains(id));
+            }
+        }
+    }
+
+    if filters.iter().any(|filter| matches!(filter, Filter::StripSpans)) {
+        for item in json["index"].as_object_mut().into_iter().flat_map(|index| index.values_mut()) {
+            if let Some(item) = item.as_object_mut() {
+                item.remove("span");
+            }
+        }
+    }
+}
+
+/// Whether the given path is the given prefix or located under it.
+fn is_under(path: &str, prefix: &str) -> bool {
+    path.strip_prefix(prefix).is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+/// The paths of the local items, including the ones inherited from the containing item.
+pub(super) fn item_paths(json: &Value) -> FxHashMap<String, String> {
+    let mut paths = FxHashMap::default();
+    let mut queue = Vec::new();
+
+    for (id, summary) in json["paths"].as_object().into_iter().flatten() {
+        if summary["crate_id"] != 0 {
+            continue;
+        }
+        let Some(path) = summary["path"].as_array() else { continue };
+        paths.insert(id.clone(), path.iter().filter_map(Value::as_str).join_with("::").to_string());
+        queue.push(id.clone());
+    }
+
+    while let Some(id) = queue.pop() {
+        let Some(item) = json["index"].get(&id) else { continue };
+        let parent = paths[&id].clone();
+        let mut children = Vec::new();
+        collect_children(&item["inner"], &mut children);
+
+        for child in children {
+            if paths.contains_key(&child) {
+                continue;
+            }
+            let path = match json["index"][&child]["name"].as_str() {
+                Some(name) => format!("{parent}::{name}"),
+                // E.g., impls.
+                None => parent.clone(),
+            };
+            paths.insert(child.clone(), path);
+            queue.push(child);
+        }
+    }
+
+    paths
+}
+
+/// The IDs of the items contained in an item (e.g., the items of a module or impl, the impls of
+/// a type or the fields and variants of an ADT), given its `inner` object.
+fn collect_children(value: &Value, children: &mut Vec<String>) {
+    let Some(object) = value.as_object() else { return };
+
+    for (key, value) in object {
+        match (key.as_str(), value) {
+            ("items" | "impls" | "fields" | "variants", Value::Array(ids)) => {
+                children.extend(ids.iter().filter(|id| !id.is_null()).map(id_to_key));
+            }
+            // Types may refer to other items but they don't contain them.
+            ("generics" | "type" | "for" | "trait" | "sig" | "decl", _) => {}
+            _ => collect_children(value, children),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use super::{Filter, apply};
+use serde_json::{Value, json};
+
+fn output() -> Value {
+    json!({
+        "root": 0,
+        "index": {
+            "0": { "name": "krate", "span": {}, "inner": { "module": { "items": [1, 4] } } },
+            "1": { "name": "S", "span": {}, "inner": { "struct": { "kind": { "plain": { "fields": [2] } }, "impls": [3] } } },
+            "2": { "name": "field", "inner": { "struct_field": {} } },
+            "3": { "name": null, "inner": { "impl": { "items": [5], "for": { "resolved_path": { "id": 4 } } } } },
+            "4": { "name": "T", "inner": { "trait": { "items": [] } } },
+            "5": { "name": "new", "inner": { "function": {} } },
+        },
+        "paths": {
+            "0": { "crate_id": 0, "path": ["krate"], "kind": "module" },
+            "1": { "crate_id": 0, "path": ["krate", "S"], "kind": "struct" },
+            "4": { "crate_id": 0, "path": ["krate", "T"], "kind": "trait" },
+            "9": { "crate_id": 1, "path": ["core", "clone", "Clone"], "kind": "trait" },
+        },
+    })
+}
+
+fn ids(json: &Value, key: &str) -> Vec<String> {
+    json[key].as_object().unwrap().keys().cloned().collect()
+}
+
+#[test]
+fn paths() {
+    let mut json = output();
+    apply(&mut json, &[Filter::Path("krate::S".into())]);
+    // The impl is contained in `S` even though its self type refers to `T`.
+    assert_eq!(ids(&json, "index"), ["1", "2", "3", "5"]);
+    assert_eq!(ids(&json, "paths"), ["1"]);
+
+    let mut json = output();
+    apply(&mut json, &[Filter::Path("krate::S::new".into())]);
+    assert_eq!(ids(&json, "index"), ["5"]);
+
+    let mut json = output();
+    apply(&mut json, &[Filter::Path("krate::Sx".into())]);
+    assert!(ids(&json, "index").is_empty());
+}
+
+#[test]
+fn kinds() {
+    let mut json = output();
+    let filters = [Filter::Kind("impl".into()), Filter::Kind("trait".into()), Filter::StripSpans];
+    apply(&mut json, &filters);
+    assert_eq!(ids(&json, "index"), ["3", "4"]);
+    assert_eq!(json["root"], 0);
+
+    let mut json = output();
+    apply(&mut json, &[Filter::StripSpans]);
+    assert_eq!(ids(&json, "index").len(), 6);
+    assert!(json["index"]["0"].get("span").is_none());
+}
+
+#[test]
+fn parse() {
+    assert!(
+        matches!(Filter::parse_cli_style("kind=struct"), Ok(Filter::Kind(kind)) if kind == "struct")
+    );
+    assert!(matches!(Filter::parse_cli_style("strip-spans"), Ok(Filter::StripSpans)));
+    assert!(Filter::parse_cli_style("path=").is_err());
+    assert!(Filter::parse_cli_style("spans").is_err());
+}
@@ -0,0 +1,349 @@
+//! An evaluator of the JSONPath expressions used by `jsondocck`.
+//!
+//! We support the subset found in `tests/rustdoc-json/`: the root `$` followed by the segments
+//! `.name`, `['name']`, `[N]`, `.*`, `[*]`, `..name` and filters `[?(…)]`. A filter consists of
+//! relative paths `@.name…` (which test for existence) and comparisons against literals via `==`
+//! and `!=`, joined by `&&` and `||`.
+//!
+//! Filters applied to an object test both the object itself and its members. The former is the
+//! behavior of the `jsonpath_lib` crate `jsondocck` used to be based on, so older tests write
+//! `$.index[*][?(…)]` while newer ones write `$.index[?(…)]`.
+
+use serde_json::Value;
+use std::fmt;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Clone)]
+pub(crate) struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone)]
+enum Segment {
+    Child(Selector),
+    Descendant(String),
+}
+
+#[derive(Clone)]
+enum Selector {
+    Name(String),
+    Index(usize),
+    Wildcard,
+    Filter(Filter),
+}
+
+#[derive(Clone)]
+enum Filter {
+    Exists(Vec<Key>),
+    Compare { path: Vec<Key>, negated: bool, value: Value },
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+#[derive(Clone)]
+enum Key {
+    Name(String),
+    Index(usize),
+}
+
+impl JsonPath {
+    pub(crate) fn parse(source: &str) -> Result<Self, Error> {
+        let mut parser = Parser { chars: source.chars().collect(), index: 0 };
+        parser.skip_whitespace();
+        parser.expect('$')?;
+
+        let mut segments = Vec::new();
+        loop {
+            parser.skip_whitespace();
+            if parser.consume_str("..") {
+                segments.push(Segment::Descendant(parser.name()?));
+            } else if parser.consume('.') {
+                let selector = match parser.consume('*') {
+                    true => Selector::Wildcard,
+                    false => Selector::Name(parser.name()?),
+                };
+                segments.push(Segment::Child(selector));
+            } else if parser.consume('[') {
+                parser.skip_whitespace();
+                let selector = if parser.consume('*') {
+                    Selector::Wildcard
+                } else if parser.consume('?') {
+                    parser.skip_whitespace();
+                    parser.expect('(')?;
+                    let filter = parser.disjunction()?;
+                    parser.skip_whitespace();
+                    parser.expect(')')?;
+                    Selector::Filter(filter)
+                } else {
+                    match parser.key()? {
+                        Key::Name(name) => Selector::Name(name),
+                        Key::Index(index) => Selector::Index(index),
+                    }
+                };
+                parser.skip_whitespace();
+                parser.expect(']')?;
+                segments.push(Segment::Child(selector));
+            } else {
+                return match parser.peek() {
+                    Some(char) => Err(Error::UnexpectedChar(char)),
+                    None => Ok(Self { segments }),
+                };
+            }
+        }
+    }
+
+    /// The values selected by the path in the given document.
+    pub(crate) fn select<'v>(&self, document: &'v Value) -> Vec<&'v Value> {
+        let mut selected = vec![document];
+
+        for segment in &self.segments {
+            let mut next = Vec::new();
+            for value in selected {
+                match segment {
+                    Segment::Child(selector) => selector.apply(value, &mut next),
+                    Segment::Descendant(name) => descendants(value, name, &mut next),
+                }
+            }
+            selected = next;
+        }
+
+        selected
+    }
+}
+
+impl Selector {
+    fn apply<'v>(&self, value: &'v Value, selected: &mut Vec<&'v Value>) {
+        match self {
+            Self::Name(name) => selected.extend(value.get(name)),
+            Self::Index(index) => selected.extend(value.get(index)),
+            Self::Wildcard => selected.extend(children(value)),
+            Self::Filter(filter) => {
+                if value.is_object() && filter.holds(value) {
+                    selected.push(value);
+                }
+                selected.extend(children(value).filter(|child| filter.holds(child)));
+            }
+        }
+    }
+}
+
+impl Filter {
+    fn holds(&self, value: &Value) -> bool {
+        match self {
+            Self::Exists(path) => resolve(value, path).is_some(),
+            Self::Compare { path, negated, value: expected } => {
+                (resolve(value, path) == Some(expected)) != *negated
+            }
+            Self::And(left, right) => left.holds(value) && right.holds(value),
+            Self::Or(left, right) => left.holds(value) || right.holds(value),
+        }
+    }
+}
+
+fn children(value: &Value) -> Box<dyn Iterator<Item = &Value> + '_> {
+    match value {
+        Value::Array(elements) => Box::new(elements.iter()),
+        Value::Object(members) => Box::new(members.values()),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+fn descendants<'v>(value: &'v Value, name: &str, selected: &mut Vec<&'v Value>) {
+    selected.extend(value.as_object().and_then(|members| members.get(name)));
+    for child in children(value) {
+        descendants(child, name, selected);
+    }
+}
+
+fn resolve<'v>(value: &'v Value, path: &[Key]) -> Option<&'v Value> {
+    path.iter().try_fold(value, |value, key| match key {
+        Key::Name(name) => value.get(name),
+        Key::Index(index) => value.get(index),
+    })
+}
+
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn unexpected(&self) -> Error {
+        self.peek().map_or(Error::UnexpectedEnd, Error::UnexpectedChar)
+    }
+
+    fn consume(&mut self, expected: char) -> bool {
+        let consumed = self.peek() == Some(expected);
+        if consumed {
+            self.index += 1;
+        }
+        consumed
+    }
+
+    fn consume_str(&mut self, expected: &str) -> bool {
+        let consumed =
+            self.chars[self.index..].iter().copied().take(expected.len()).eq(expected.chars());
+        if consumed {
+            self.index += expected.len();
+        }
+        consumed
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        match self.consume(expected) {
+            true => Ok(()),
+            false => Err(self.unexpected()),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.index += 1;
+        }
+    }
+
+    fn name(&mut self) -> Result<String, Error> {
+        let start = self.index;
+        while self.peek().is_some_and(|char| char.is_alphanumeric() || matches!(char, '_' | '-')) {
+            self.index += 1;
+        }
+        match start == self.index {
+            true => Err(self.unexpected()),
+            false => Ok(self.chars[start..self.index].iter().collect()),
+        }
+    }
+
+    /// Parse the contents of a bracketed segment, i.e., a quoted name or an index.
+    fn key(&mut self) -> Result<Key, Error> {
+        if let Some('\'' | '"') = self.peek() {
+            return self.string().map(Key::Name);
+        }
+
+        let start = self.index;
+        while self.peek().is_some_and(|char| char.is_ascii_digit()) {
+            self.index += 1;
+        }
+        let index: String = self.chars[start..self.index].iter().collect();
+        index.parse().map(Key::Index).map_err(|_| self.unexpected())
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let Some(quote @ ('\'' | '"')) = self.peek() else { return Err(self.unexpected()) };
+        self.index += 1;
+
+        let mut string = String::new();
+        loop {
+            match self.peek() {
+                Some(char) if char == quote => break,
+                Some('\\') => {
+                    self.index += 1;
+                    string.extend(self.peek());
+                }
+                Some(char) => string.push(char),
+                None => return Err(Error::UnexpectedEnd),
+            }
+            self.index += 1;
+        }
+        self.index += 1;
+        Ok(string)
+    }
+
+    fn disjunction(&mut self) -> Result<Filter, Error> {
+        let mut filter = self.conjunction()?;
+        loop {
+            self.skip_whitespace();
+            if !self.consume_str("||") {
+                return Ok(filter);
+            }
+            filter = Filter::Or(Box::new(filter), Box::new(self.conjunction()?));
+        }
+    }
+
+    fn conjunction(&mut self) -> Result<Filter, Error> {
+        let mut filter = self.comparison()?;
+        loop {
+            self.skip_whitespace();
+            if !self.consume_str("&&") {
+                return Ok(filter);
+            }
+            filter = Filter::And(Box::new(filter), Box::new(self.comparison()?));
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Filter, Error> {
+        self.skip_whitespace();
+        if self.consume('(') {
+            let filter = self.disjunction()?;
+            self.skip_whitespace();
+            self.expect(')')?;
+            return Ok(filter);
+        }
+
+        self.expect('@')?;
+        let mut path = Vec::new();
+        loop {
+            if self.consume('.') {
+                path.push(Key::Name(self.name()?));
+            } else if self.consume('[') {
+                self.skip_whitespace();
+                path.push(self.key()?);
+                self.skip_whitespace();
+                self.expect(']')?;
+            } else {
+                break;
+            }
+        }
+
+        self.skip_whitespace();
+        let negated = if self.consume_str("==") {
+            false
+        } else if self.consume_str("!=") {
+            true
+        } else {
+            return Ok(Filter::Exists(path));
+        };
+
+        self.skip_whitespace();
+        let value = self.literal()?;
+        Ok(Filter::Compare { path, negated, value })
+    }
+
+    fn literal(&mut self) -> Result<Value, Error> {
+        if let Some('\'' | '"') = self.peek() {
+            return self.string().map(Value::String);
+        }
+
+        let start = self.index;
+        while self.peek().is_some_and(|char| char.is_alphanumeric() || matches!(char, '-' | '.')) {
+            self.index += 1;
+        }
+        let literal: String = self.chars[start..self.index].iter().collect();
+        serde_json::from_str(&literal).map_err(|_| match literal.is_empty() {
+            true => self.unexpected(),
+            false => Error::InvalidLiteral(literal),
+        })
+    }
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub(crate) enum Error {
+    UnexpectedEnd,
+    UnexpectedChar(char),
+    InvalidLiteral(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd => write!(f, "unexpected end of path"),
+            Self::UnexpectedChar(char) => write!(f, "unexpected `{char}`"),
+            Self::InvalidLiteral(literal) => write!(f, "invalid literal `{literal}`"),
+        }
+    }
+}
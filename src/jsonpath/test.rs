@@ -0,0 +1,39 @@
+use super::{Error, JsonPath};
+use serde_json::{Value, json};
+
+fn select(path: &str, document: &Value) -> Vec<Value> {
+    JsonPath::parse(path).ok().unwrap().select(document).into_iter().cloned().collect()
+}
+
+#[test]
+fn selection() {
+    let document = json!({
+        "index": {
+            "0": { "name": "f", "visibility": "public", "inner": { "function": { "params": [] } } },
+            "1": { "name": "S", "visibility": "default", "inner": { "struct": {} } },
+        },
+        "paths": [{ "path": ["k", "S"] }],
+    });
+
+    assert_eq!(select("$.index['0'].name", &document), [json!("f")]);
+    assert_eq!(select("$.paths[0].path[1]", &document), [json!("S")]);
+    assert_eq!(select("$.index[*].name", &document), [json!("f"), json!("S")]);
+    assert_eq!(select("$.index[?(@.name=='S')].visibility", &document), [json!("default")]);
+    assert_eq!(select(r#"$.index[*][?(@.name == "f")].visibility"#, &document), [json!("public")]);
+    assert_eq!(select("$.index[?(@.inner.struct)].name", &document), [json!("S")]);
+    assert_eq!(
+        select("$.index[?(@.name!='f' && @.visibility=='public' || @.name=='f')].name", &document),
+        [json!("f")]
+    );
+    assert_eq!(select("$..params", &document), [json!([])]);
+    assert!(select("$.index.missing", &document).is_empty());
+}
+
+#[test]
+fn errors() {
+    let error = |source| JsonPath::parse(source).err().unwrap();
+
+    assert_eq!(error("index"), Error::UnexpectedChar('i'));
+    assert_eq!(error("$.index[?(@.name=='f')"), Error::UnexpectedEnd);
+    assert_eq!(error("$.index[?(@.name==f)]"), Error::InvalidLiteral("f".to_owned()));
+}
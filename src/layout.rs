@@ -0,0 +1,206 @@
+//! Verifying the type layout sections rendered by `--show-type-layout`.
+//!
+//! Optionally, the sizes get cross-checked against the ones reported by `rustc -Zprint-type-sizes`.
+
+use crate::{
+    diagnostic::{Diagnostic, IntoDiagnostic, error},
+    html::{self, Token},
+    utility::files,
+};
+use rustc_hash::FxHashMap;
+use std::path::Path;
+
+#[cfg(test)]
+mod test;
+
+/// Check the layout sections on the pages of the given crate found in `root`.
+///
+/// If `type_sizes` (i.e., the output of `-Zprint-type-sizes`) is provided, the sizes are
+/// compared against it.
+pub(crate) fn check(
+    root: &Path,
+    crate_name: &str,
+    type_sizes: Option<&str>,
+) -> crate::error::Result {
+    let crate_root = root.join(crate_name);
+    let type_sizes = type_sizes.map(parse_type_sizes);
+    let mut findings = Vec::new();
+
+    for page in files(&crate_root, &["html"])? {
+        let Some((kind, path)) = item_path(&crate_root, &page) else { continue };
+        let html = std::fs::read_to_string(&page)?;
+        let Some(section) = layout_section(&html) else { continue };
+
+        let Some(layout) = parse_layout(&section) else {
+            findings.push(format!("the layout section of `{path}` lacks a size"));
+            continue;
+        };
+
+        if let Size::Bytes(size) = layout.size {
+            for (variant, variant_size) in &layout.variants {
+                if *variant_size > size {
+                    findings.push(format!(
+                        "`{path}`: variant `{variant}` is larger than the type itself \
+                         ({variant_size} > {size} bytes)"
+                    ));
+                }
+            }
+        }
+
+        let Some(type_sizes) = &type_sizes else { continue };
+        let Size::Bytes(size) = layout.size else { continue };
+        let Some(expected) = type_sizes.get(path.as_str()) else { continue };
+
+        if expected.size != size {
+            findings.push(format!(
+                "`{path}`: rendered size of {size} bytes but rustc reports {} bytes",
+                expected.size
+            ));
+        }
+
+        // Rustc also reports "variants" for structs and unions.
+        if kind != "enum" {
+            continue;
+        }
+        for (variant, size) in &layout.variants {
+            if let Some(&expected) = expected.variants.get(variant.as_str())
+                && expected != *size
+            {
+                findings.push(format!(
+                    "`{path}`: rendered size of {size} bytes for variant `{variant}` \
+                     but rustc reports {expected} bytes"
+                ));
+            }
+        }
+    }
+
+    if !findings.is_empty() {
+        return Err(Error { findings }.into());
+    }
+
+    Ok(())
+}
+
+/// The kind and the path (relative to the crate root) of the type whose page is at `page`.
+fn item_path(crate_root: &Path, page: &Path) -> Option<(&'static str, String)> {
+    let relative = page.strip_prefix(crate_root).ok()?;
+    let name = relative.file_name()?.to_str()?;
+    let (kind, name) = name.strip_suffix(".html")?.split_once('.')?;
+    let kind = ["struct", "enum", "union"].into_iter().find(|candidate| *candidate == kind)?;
+
+    let mut path: Vec<_> =
+        relative.parent()?.iter().map(|module| module.to_string_lossy().into_owned()).collect();
+    path.push(name.to_owned());
+    Some((kind, path.join("::")))
+}
+
+/// The text of the layout section of the given page with one line per paragraph or list item.
+fn layout_section(html: &str) -> Option<String> {
+    let start = html.find("id=\"layout\"")?;
+    let html = &html[start..];
+    let html = &html[..html.find("</section>").unwrap_or(html.len())];
+
+    let mut text = String::new();
+    // Skip the remainder of the start tag.
+    for token in html::tokenize(&html[html.find('>')? + 1..]) {
+        match token {
+            Token::StartTag(tag) if matches!(tag.name, "p" | "li") => text.push('\n'),
+            Token::Text(fragment) => text.push_str(&html::decode(fragment)),
+            _ => {}
+        }
+    }
+    Some(text)
+}
+
+#[derive(Debug, PartialEq)]
+struct Layout {
+    size: Size,
+    variants: Vec<(String, u64)>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Size {
+    Bytes(u64),
+    Unsized,
+    /// The layout of generic types can't be computed.
+    Unknown,
+}
+
+fn parse_layout(section: &str) -> Option<Layout> {
+    let mut size = None;
+    let mut variants = Vec::new();
+
+    for line in section.lines().map(str::trim) {
+        if line.starts_with("Note: Unable to compute type layout") {
+            size = Some(Size::Unknown);
+        } else if let Some(rest) = line.strip_prefix("Size:") {
+            let rest = rest.trim();
+            size = Some(match rest.strip_prefix("(unsized)") {
+                Some(_) => Size::Unsized,
+                None => Size::Bytes(parse_bytes(rest)?),
+            });
+        } else if size.is_some()
+            && let Some((variant, rest)) = line.split_once(": ")
+            && let Some(bytes) = parse_bytes(rest)
+        {
+            variants.push((variant.to_owned(), bytes));
+        }
+    }
+
+    Some(Layout { size: size?, variants })
+}
+
+/// Parse strings like `8 bytes` or `1 byte (uninhabited)`.
+fn parse_bytes(text: &str) -> Option<u64> {
+    let (amount, rest) = text.split_once(' ')?;
+    rest.starts_with("byte").then_some(())?;
+    amount.parse().ok()
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct TypeSize<'a> {
+    size: u64,
+    variants: FxHashMap<&'a str, u64>,
+}
+
+/// Parse the output of `-Zprint-type-sizes`.
+fn parse_type_sizes(output: &str) -> FxHashMap<&str, TypeSize<'_>> {
+    let mut sizes = FxHashMap::default();
+    let mut current = None;
+
+    for line in output.lines() {
+        let Some(line) = line.strip_prefix("print-type-size ") else { continue };
+
+        if let Some(rest) = line.strip_prefix("type: `") {
+            current = None;
+            let Some((name, rest)) = rest.split_once("`: ") else { continue };
+            let Some(size) = parse_bytes(rest.split(',').next().unwrap_or_default()) else {
+                continue;
+            };
+            sizes.insert(name, TypeSize { size, variants: FxHashMap::default() });
+            current = Some(name);
+        } else if let Some(rest) = line.trim_start().strip_prefix("variant `")
+            && let Some(name) = current
+            && let Some((variant, rest)) = rest.split_once("`: ")
+            && let Some(size) = parse_bytes(rest)
+            && let Some(entry) = sizes.get_mut(name)
+        {
+            entry.variants.insert(variant, size);
+        }
+    }
+
+    sizes
+}
+
+pub(crate) struct Error {
+    findings: Vec<String>,
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        let amount = self.findings.len();
+        let s = if amount == 1 { "" } else { "s" };
+        let diagnostic = error(format!("found {amount} issue{s} with the type layout sections"));
+        self.findings.into_iter().fold(diagnostic, Diagnostic::note)
+    }
+}
@@ -0,0 +1,38 @@
+use super::{Layout, Size, TypeSize, parse_layout, parse_type_sizes};
+
+#[test]
+fn layout() {
+    assert_eq!(
+        parse_layout(
+            "\nNote: Most layout information is unstable.\nSize: 16 bytes\nSize for each variant:\nA: 8 bytes\nB: 0 bytes"
+        ),
+        Some(Layout { size: Size::Bytes(16), variants: vec![("A".into(), 8), ("B".into(), 0)] })
+    );
+    assert_eq!(
+        parse_layout("\nSize: 1 byte (uninhabited)"),
+        Some(Layout { size: Size::Bytes(1), variants: Vec::new() })
+    );
+    assert_eq!(
+        parse_layout("\nSize: (unsized)"),
+        Some(Layout { size: Size::Unsized, variants: Vec::new() })
+    );
+    assert_eq!(
+        parse_layout("\nNote: Unable to compute type layout, possibly due to generic parameters."),
+        Some(Layout { size: Size::Unknown, variants: Vec::new() })
+    );
+    assert_eq!(parse_layout("\nNote: Encountered an error during type layout."), None);
+}
+
+#[test]
+fn type_sizes() {
+    let sizes = parse_type_sizes(
+        "print-type-size type: `E`: 16 bytes, alignment: 8 bytes\n\
+         print-type-size     discriminant: 8 bytes\n\
+         print-type-size     variant `A`: 8 bytes\n\
+         print-type-size         field `.0`: 8 bytes\n\
+         print-type-size type: `inner::S`: 8 bytes, alignment: 4 bytes\n\
+         print-type-size     field `.b`: 4 bytes\n",
+    );
+    assert_eq!(sizes["E"], TypeSize { size: 16, variants: [("A", 8)].into_iter().collect() });
+    assert_eq!(sizes["inner::S"], TypeSize { size: 8, variants: Default::default() });
+}
@@ -0,0 +1,147 @@
+//! Aggregating the diagnostics emitted across a run for suite-wide lint triage (`--lint-summary`).
+//!
+//! The summary groups the diagnostics by lint and by file:
+//!
+//! ```json
+//! { "total": 3, "lints": { "rustdoc::broken_intra_doc_links": { "total": 3, "files": { "a.rs": 3 } } } }
+//! ```
+//!
+//! Diagnostics that don't belong to a lint are grouped by their level, e.g., `error`.
+
+use crate::diagnostic::{Diagnostic, IntoDiagnostic, error, info};
+use serde_json::{Value, json};
+use std::{collections::BTreeMap, path::Path, sync::Mutex};
+
+#[cfg(test)]
+mod test;
+
+static OCCURRENCES: Mutex<Vec<Occurrence>> = Mutex::new(Vec::new());
+
+struct Occurrence {
+    lint: String,
+    file: String,
+}
+
+/// Record the given JSON diagnostics of `rust{c,doc}`.
+pub(crate) fn record(diagnostics: &[Value]) {
+    let mut occurrences = OCCURRENCES.lock().unwrap();
+    occurrences.extend(diagnostics.iter().filter_map(occurrence));
+}
+
+fn occurrence(diagnostic: &Value) -> Option<Occurrence> {
+    let level = diagnostic["level"].as_str()?;
+    if !matches!(level, "error" | "warning") {
+        return None;
+    }
+
+    // This skips summaries like `aborting due to 1 previous error` which don't have any spans.
+    let span = diagnostic["spans"]
+        .as_array()?
+        .iter()
+        .find(|span| span["is_primary"].as_bool() == Some(true))?;
+    let file = span["file_name"].as_str()?.to_owned();
+
+    let lint = diagnostic["code"]["code"].as_str().unwrap_or(level).to_owned();
+    Some(Occurrence { lint, file })
+}
+
+/// Write the summary of all diagnostics recorded so far to `path`, comparing it against the
+/// summary found at `baseline` if available.
+pub(crate) fn write(path: &Path, baseline: Option<&Path>) -> crate::error::Result {
+    let summary = summarize(&OCCURRENCES.lock().unwrap());
+
+    let baseline = match baseline {
+        Some(path) => {
+            let baseline = std::fs::read_to_string(path)?;
+            let baseline = serde_json::from_str(&baseline)
+                .map_err(|error| Error::InvalidBaseline { path: path.to_owned(), error })?;
+            Some(baseline)
+        }
+        None => None,
+    };
+
+    let summary = match &baseline {
+        Some(baseline) => compare(summary, baseline),
+        None => summary,
+    };
+
+    let rendered = serde_json::to_string_pretty(&summary).map_err(std::io::Error::from)?;
+    std::fs::write(path, rendered + "\n")?;
+    report(&summary, baseline.is_some()).emit();
+    Ok(())
+}
+
+fn summarize(occurrences: &[Occurrence]) -> Value {
+    let mut lints = BTreeMap::<_, BTreeMap<_, u64>>::new();
+    for occurrence in occurrences {
+        *lints.entry(&occurrence.lint).or_default().entry(&occurrence.file).or_default() += 1;
+    }
+
+    let lints: serde_json::Map<_, _> = lints
+        .into_iter()
+        .map(|(lint, files)| {
+            let total: u64 = files.values().sum();
+            (lint.clone(), json!({ "total": total, "files": files }))
+        })
+        .collect();
+
+    json!({ "total": occurrences.len(), "lints": lints })
+}
+
+/// Add the change in the number of diagnostics since the `baseline` to every lint.
+///
+/// Lints that no longer occur are kept with a total of zero, so the improvement shows up, too.
+fn compare(mut summary: Value, baseline: &Value) -> Value {
+    let total = |summary: &Value| summary["total"].as_i64().unwrap_or_default();
+
+    summary["change"] = json!(total(&summary) - total(baseline));
+
+    let lints = summary["lints"].as_object_mut().unwrap();
+    for (lint, entry) in lints.iter_mut() {
+        entry["change"] = json!(total(entry) - total(&baseline["lints"][lint]));
+    }
+    for (lint, entry) in baseline["lints"].as_object().into_iter().flatten() {
+        if !lints.contains_key(lint) {
+            lints.insert(lint.clone(), json!({ "total": 0, "files": {}, "change": -total(entry) }));
+        }
+    }
+
+    summary
+}
+
+fn report(summary: &Value, compared: bool) -> Diagnostic {
+    let change = |entry: &Value| match entry["change"].as_i64() {
+        Some(change) if compared => format!(" ({change:+})"),
+        _ => String::new(),
+    };
+
+    let total = summary["total"].as_u64().unwrap_or_default();
+    let s = if total == 1 { "" } else { "s" };
+    let diagnostic = info(format!("emitted {total} diagnostic{s}{}", change(summary)));
+
+    summary["lints"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(lint, entry)| {
+            let files = entry["files"].as_object().map_or(0, |files| files.len());
+            let s = if files == 1 { "" } else { "s" };
+            format!("`{lint}`: {} in {files} file{s}{}", entry["total"], change(entry))
+        })
+        .fold(diagnostic, Diagnostic::note)
+}
+
+pub(crate) enum Error {
+    InvalidBaseline { path: std::path::PathBuf, error: serde_json::Error },
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::InvalidBaseline { path, error: cause } => {
+                error(format!("failed to parse the lint summary `{}`", path.display()))
+                    .note(cause.to_string())
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use super::{Occurrence, compare, occurrence, summarize};
+use serde_json::json;
+
+fn occurrences(entries: &[(&str, &str)]) -> Vec<Occurrence> {
+    entries
+        .iter()
+        .map(|&(lint, file)| Occurrence { lint: lint.to_owned(), file: file.to_owned() })
+        .collect()
+}
+
+#[test]
+fn occurrences_of_diagnostics() {
+    let lint = json!({
+        "level": "warning",
+        "code": { "code": "rustdoc::broken_intra_doc_links" },
+        "spans": [{ "file_name": "a.rs", "is_primary": true }],
+    });
+    let found = occurrence(&lint).unwrap();
+    assert_eq!((&*found.lint, &*found.file), ("rustdoc::broken_intra_doc_links", "a.rs"));
+
+    let error = json!({ "level": "error", "code": null, "spans": [{ "file_name": "b.rs", "is_primary": true }] });
+    assert_eq!(occurrence(&error).unwrap().lint, "error");
+
+    let summary = json!({ "level": "error", "code": null, "spans": [] });
+    assert!(occurrence(&summary).is_none());
+    assert!(occurrence(&json!({ "level": "failure-note", "spans": [] })).is_none());
+}
+
+#[test]
+fn summary() {
+    let summary =
+        summarize(&occurrences(&[("x", "a.rs"), ("x", "b.rs"), ("x", "a.rs"), ("y", "a.rs")]));
+    assert_eq!(
+        summary,
+        json!({
+            "total": 4,
+            "lints": {
+                "x": { "total": 3, "files": { "a.rs": 2, "b.rs": 1 } },
+                "y": { "total": 1, "files": { "a.rs": 1 } },
+            },
+        })
+    );
+}
+
+#[test]
+fn comparison() {
+    let baseline = summarize(&occurrences(&[("x", "a.rs"), ("z", "a.rs"), ("z", "b.rs")]));
+    let summary = summarize(&occurrences(&[("x", "a.rs"), ("x", "b.rs"), ("y", "a.rs")]));
+    let summary = compare(summary, &baseline);
+
+    assert_eq!(summary["change"], 0);
+    assert_eq!(summary["lints"]["x"]["change"], 1);
+    assert_eq!(summary["lints"]["y"]["change"], 1);
+    assert_eq!(summary["lints"]["z"], json!({ "total": 0, "files": {}, "change": -2 }));
+}
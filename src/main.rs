@@ -5,21 +5,81 @@ use attribute::Attributes;
 use builder::{BuildMode, QueryMode};
 use data::{CrateNameBuf, CrateNameCow, CrateType, Edition};
 use diagnostic::IntoDiagnostic;
-use std::{path::Path, process::ExitCode};
+use rustc_hash::FxHashSet;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Instant,
+};
 
+mod api;
 mod attribute;
+mod audit;
+mod backend;
+mod batch;
+mod bisect;
+mod budget;
 mod builder;
+mod cache;
+mod cargo;
 mod cli;
+mod clobber;
 mod command;
+mod completions;
+mod condition;
+mod config;
+mod consistency;
+mod coverage;
+#[cfg(feature = "tui")]
+mod dashboard;
 mod data;
 mod diagnostic;
 mod directive;
 mod error;
+mod failure;
+mod fix;
+#[cfg(feature = "gui")]
+mod gui;
+mod history;
+mod html;
+mod ice;
+mod impls;
+mod json;
+mod jsonpath;
+mod layout;
+mod lints;
+mod module;
+mod output;
+mod overlay;
 mod parser;
+mod pipeline;
+mod plan;
+mod preset;
+mod probe;
+mod provenance;
+mod query;
+mod regression;
+mod render;
+mod report;
+mod reproducible;
+mod search;
+mod serve;
+mod signatures;
+mod snapshot;
+mod stderr;
+mod sysroot;
+mod targets;
+mod timing;
+mod toolchain;
+mod trace;
+mod upload;
 mod utility;
+mod viewer;
+mod wrap;
+mod xpath;
 
 // FIXME: respect `compile-flags: --test`
-// FIXME: Add `--all-revs`.
 
 fn main() -> ExitCode {
     let result = try_main();
@@ -34,31 +94,389 @@ fn main() -> ExitCode {
 }
 
 fn try_main() -> error::Result {
+    let arguments: Vec<_> = std::env::args_os().collect();
+    // We need to know this before parsing the arguments since presets affect parsing.
+    let no_config = arguments
+        .iter()
+        .take_while(|argument| *argument != "--")
+        .any(|argument| argument == "--no-config");
+    let presets = if no_config { Vec::new() } else { config::load_presets()? };
+    let arguments = preset::expand(arguments, &presets)?;
+
+    let matches = <cli::Arguments as clap::CommandFactory>::command().get_matches_from(arguments);
+    let mut arguments = <cli::Arguments as clap::FromArgMatches>::from_arg_matches(&matches)
+        .unwrap_or_else(|error| error.exit());
+    arguments.build_flags.order_environment(&matches);
+
+    // We only report this once the arguments are known to be valid to avoid noise.
+    if let Some(crate_name) = &arguments.crate_name
+        && let Some(source) = matches.get_raw("crate_name").into_iter().flatten().next()
+        && let source = source.to_string_lossy()
+        && source.contains('-')
+    {
+        diagnostic::info(format!("adjusted the crate name `{source}` to `{crate_name}`"))
+            .note("crate names may not contain hyphens, they get replaced with underscores")
+            .emit();
+    }
+
+    match arguments.color {
+        clap::ColorChoice::Always => owo_colors::set_override(true),
+        clap::ColorChoice::Never => owo_colors::set_override(false),
+        clap::ColorChoice::Auto => {}
+    }
+
+    if !arguments.program_flags.no_config {
+        config::load_defaults()?.apply(&mut arguments, &matches);
+    }
+
+    let paths = match &arguments.command {
+        Some(cli::Command::Cache(command)) => return cache::run(command, &arguments.program_flags),
+        Some(cli::Command::History(command)) => return history::run(command),
+        Some(cli::Command::Toolchain(cli::ToolchainCommand::Info)) => {
+            return toolchain::report(&arguments.build_flags, &arguments.program_flags);
+        }
+        Some(cli::Command::Print(command)) => {
+            return toolchain::print(*command, &arguments.build_flags, &arguments.program_flags);
+        }
+        Some(cli::Command::Completions { shell }) => {
+            completions::print(*shell);
+            return Ok(());
+        }
+        Some(cli::Command::Run { pipeline, paths }) => {
+            return pipeline::run(pipeline, paths, &arguments);
+        }
+        Some(cli::Command::Diff { old, .. }) => std::slice::from_ref(old),
+        Some(cli::Command::Bisect { path, .. }) => std::slice::from_ref(path),
+        Some(cli::Command::Extract { path, .. }) => std::slice::from_ref(path),
+        Some(cli::Command::Prebuild { paths }) => paths.as_slice(),
+        None => arguments.paths.as_slice(),
+    };
+
+    // The path `-` stands for stdin.
+    let stdin = match paths.iter().any(|path| path == Path::new("-")) {
+        true => Some(utility::stdin_source(&arguments.program_flags)?),
+        false => None,
+    };
+    let paths: Vec<_> = paths
+        .iter()
+        .map(|path| match &stdin {
+            Some(source) if path == Path::new("-") => source.clone(),
+            _ => path.clone(),
+        })
+        .collect();
+    let paths = batch::collect(&paths)?;
+    // We're a child process of the dashboard, responsible for a single test.
+    #[cfg(feature = "tui")]
+    let paths = match std::env::var_os(dashboard::TEST_VARIABLE) {
+        Some(path) => {
+            arguments.program_flags.dashboard = false;
+            vec![path.into()]
+        }
+        None => paths,
+    };
+    let paths = &paths;
+
+    let mut report = report::Report::default();
+    report.record_lints(&arguments.build_flags);
+
+    let result = match paths.as_slice() {
+        #[cfg(feature = "tui")]
+        _ if arguments.program_flags.dashboard => dashboard::run(paths, &arguments.program_flags),
+        [path] if arguments.program_flags.watch => watch(path, &arguments, &mut report),
+        [path] => run_recorded(path, &arguments, &mut report, &mut None).map(drop),
+        _ => run_batch(paths, &arguments, &mut report),
+    };
+
+    if let Some(directory) = stdin.as_deref().and_then(Path::parent) {
+        std::fs::remove_dir_all(directory)?;
+    }
+
+    if let Some(path) = &arguments.program_flags.lint_summary
+        && !arguments.program_flags.dry_run
+    {
+        lints::write(path, arguments.program_flags.lint_baseline.as_deref())?;
+    }
+
+    if let Some(path) = &arguments.program_flags.report {
+        report.write(path)?;
+
+        if let Some(destination) = &arguments.program_flags.upload
+            && result.is_ok()
+        {
+            upload::upload(path, destination, &arguments.program_flags)?;
+        }
+    }
+
+    if arguments.program_flags.record && !arguments.program_flags.dry_run {
+        history::record(&report, &arguments.build_flags, &arguments.program_flags)?;
+    }
+
+    result
+}
+
+fn run_batch(
+    paths: &[PathBuf],
+    arguments: &cli::Arguments,
+    report: &mut report::Report,
+) -> error::Result {
+    if arguments.program_flags.watch {
+        diagnostic::warning("`--watch` only supports a single path").note("ignoring it").emit();
+    }
+
+    let mut summary = batch::Summary::default();
+
+    for (index, path) in paths.iter().enumerate() {
+        let outcome = match run_recorded(path, arguments, report, &mut None) {
+            Ok(outcome) => outcome,
+            Err(error) => {
+                error.into_diagnostic().emit();
+                batch::Outcome::Failure
+            }
+        };
+
+        let failed = matches!(outcome, batch::Outcome::Failure);
+        summary.record(path.display().to_string(), outcome);
+
+        if failed && !arguments.program_flags.keep_going {
+            summary.publish();
+            return Ok(summary.into_result(paths.len() - index - 1)?);
+        }
+    }
+
+    summary.publish();
+    Ok(summary.into_result(0)?)
+}
+
+/// Rebuild on the given path whenever one of the files in its build plan changes.
+///
+/// The plan gets reused across rebuilds unless the root file of one of its crates changes since
+/// only their directives can alter the set of crates.
+fn watch(path: &Path, arguments: &cli::Arguments, report: &mut report::Report) -> error::Result {
+    let mut plan = None;
+
+    loop {
+        if let Err(error) = run_recorded(path, arguments, report, &mut plan) {
+            error.into_diagnostic().emit();
+        }
+
+        let files = watched_files(path, arguments, plan.as_ref());
+        let s = if files.len() == 1 { "" } else { "s" };
+        diagnostic::info(format!("watching {} file{s} for changes", files.len()))
+            .note("press Ctrl+C to stop")
+            .emit();
+
+        let changed = utility::wait_for_change(&files);
+        let mut rebuilding =
+            diagnostic::info(format!("`{}` changed, rebuilding", changed.display()));
+        if config::paths().contains(&changed) {
+            rebuilding = rebuilding
+                .note("changes to `defaults`, `presets` and `lints` take effect after a restart");
+        }
+        rebuilding.emit();
+
+        // The directives of the crates might have changed and with them the plan.
+        if plan.as_ref().is_some_and(|plan| plan.has_root(&changed)) {
+            plan = None;
+        }
+    }
+}
+
+/// The files whose changes trigger a rebuild in watch mode.
+fn watched_files(
+    path: &Path,
+    arguments: &cli::Arguments,
+    plan: Option<&plan::Node>,
+) -> Vec<PathBuf> {
+    // If we failed before computing the plan, we can at least watch the root file.
+    let mut files = plan.map_or_else(|| vec![path.to_owned()], plan::Node::files);
+    files.extend(cargo::manifest(path));
+    if arguments.compiletest {
+        files.extend(expectation_files(path));
+    }
+    files.extend(arguments.program_flags.search_tests.clone());
+    #[cfg(feature = "gui")]
+    files.extend(arguments.program_flags.gui_tests.clone());
+    if !arguments.program_flags.no_config {
+        files.extend(config::paths());
+    }
+
+    let mut seen = FxHashSet::default();
+    files.retain(|file| seen.insert(file.clone()));
+    files
+}
+
+/// The `.stderr` files and the snapshots of the queries next to the given test.
+fn expectation_files(test: &Path) -> Vec<PathBuf> {
+    let stem = test.file_stem().unwrap_or_default().to_string_lossy();
+    let prefix = format!("{stem}.");
+    let directory = match test.parent() {
+        Some(directory) if !directory.as_os_str().is_empty() => directory,
+        _ => Path::new("."),
+    };
+    let Ok(entries) = std::fs::read_dir(directory) else { return Vec::new() };
+
+    let mut files: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|file| {
+            file.file_name().is_some_and(|name| name.to_string_lossy().starts_with(&prefix))
+                && file
+                    .extension()
+                    .is_some_and(|extension| extension == "stderr" || extension == "html")
+        })
+        .map(|file| test.with_file_name(file.file_name().unwrap()))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Run on the given path and record the outcome as well as the timings.
+///
+/// Tests that got skipped due to unmet conditions don't count as failures.
+fn run_recorded(
+    path: &Path,
+    arguments: &cli::Arguments,
+    report: &mut report::Report,
+    plan: &mut Option<plan::Node>,
+) -> error::Result<batch::Outcome> {
+    clobber::forget();
+    let result = run(path, arguments, plan);
+    let steps = timing::take();
+
+    if arguments.program_flags.verbose {
+        timing::publish(&steps);
+    }
+
+    let result = match result {
+        Ok(()) => Ok(batch::Outcome::Success),
+        Err(error::Error::Skipped(unmet)) => {
+            diagnostic::info(format!("skipping `{}`", path.display()))
+                .note(unmet.to_string())
+                .emit();
+            Ok(batch::Outcome::Skipped(unmet))
+        }
+        Err(error) if arguments.open_on_failure && !arguments.program_flags.dry_run => {
+            let diagnostic = error.into_diagnostic();
+            let opened = failure::render(path, &diagnostic)
+                .map_err(Into::into)
+                .and_then(|page| command::open(&page, &arguments.program_flags));
+            // Don't let this shadow the actual failure.
+            if let Err(error) = opened {
+                error.into_diagnostic().emit();
+            }
+            Err(error::Error::Reported(diagnostic))
+        }
+        Err(error) => Err(error),
+    };
+    let outcome = match &result {
+        Ok(outcome) => outcome,
+        Err(_) => &batch::Outcome::Failure,
+    };
+    let output = match arguments.program_flags.record
+        && !arguments.program_flags.dry_run
+        && !arguments.build_flags.stdout
+    {
+        true => output_digest(&arguments.build_flags),
+        false => None,
+    };
+    report.record(path, outcome, &steps, output, ice::take());
+
+    result
+}
+
+/// The digest of the output directory if there is one.
+fn output_digest(flags: &cli::BuildFlags) -> Option<u64> {
+    let root = utility::doc_root(flags).ok()?;
+    Some(output::Fingerprint::new(&root).ok()?.digest())
+}
+
+/// Run on the given path.
+///
+/// The build plan gets computed if it's needed and not already provided.
+fn run(path: &Path, arguments: &cli::Arguments, plan: &mut Option<plan::Node>) -> error::Result {
     let cli::Arguments {
-        path,
-        verbatim_flags,
+        command,
+        paths: _,
+        verbatim_flags: verbatim_arguments,
+        config_verbatim_flags,
         open,
+        view,
+        open_on_failure: _,
+        print_paths,
         crate_name,
         crate_type,
         edition,
+        wrap,
         build_flags,
         cross_crate,
         compiletest,
         query,
         program_flags,
-        color,
-    } = clap::Parser::parse();
+        color: _,
+    } = arguments;
 
-    match color {
-        clap::ColorChoice::Always => owo_colors::set_override(true),
-        clap::ColorChoice::Never => owo_colors::set_override(false),
-        clap::ColorChoice::Auto => {}
+    // The path as given, e.g., the one of a Cargo package instead of its root source file.
+    let given_path = path;
+
+    // For Cargo packages, the root source file and the crate metadata come from Cargo.
+    let package = match cargo::manifest(path) {
+        Some(manifest) => Some(timing::time("loading the package", || {
+            cargo::load(&manifest, build_flags, program_flags)
+        })?),
+        None => None,
+    };
+    let mut package_build_flags = None;
+    let (path, crate_name, crate_type, edition, build_flags) = match &package {
+        Some(package) => (
+            package.path.as_path(),
+            crate_name.clone().or_else(|| Some(package.crate_name.clone())),
+            crate_type.or(Some(package.crate_type)),
+            edition.or(Some(package.edition)),
+            &*package_build_flags.insert(cli::BuildFlags {
+                cargo_features: package.features.clone(),
+                ..build_flags.clone()
+            }),
+        ),
+        None => (path, crate_name.clone(), *crate_type, *edition, build_flags),
+    };
+
+    // The wrapped snippet keeps the crate name derived from the path of the snippet.
+    let wrapped = match wrap {
+        Some(template) => Some(wrap::write(path, *template, program_flags)?),
+        None => None,
+    };
+    let crate_name = match &wrapped {
+        Some(_) => crate_name.or_else(|| CrateNameBuf::adjust_and_parse_file_path(path).ok()),
+        None => crate_name,
+    };
+    let path = wrapped.as_deref().unwrap_or(path);
+
+    timing::time("probing the toolchain", || {
+        toolchain::check_requirements(edition, build_flags, program_flags)
+    })?;
+    // A custom sysroot doesn't need to contain the standard library of the target.
+    if build_flags.sysroot.is_none() && build_flags.build_sysroot.is_none() {
+        for target in build_flags.target.iter().chain(&program_flags.compare_targets) {
+            timing::time("checking the target", || {
+                toolchain::check_target(target, build_flags, program_flags)
+            })?;
+        }
     }
 
+    let mut sysroot_build_flags = None;
+    let build_flags = match &build_flags.build_sysroot {
+        Some(checkout) => {
+            let sysroot = sysroot::build(checkout, build_flags, program_flags)?;
+            &*sysroot_build_flags
+                .insert(cli::BuildFlags { sysroot: Some(sysroot), ..build_flags.clone() })
+        }
+        None => build_flags,
+    };
+
     // FIXME: eagerly lower `-f`s to `--cfg`s here, so we properly support them in `compiletest`+command
 
-    let query_mode = compute_query_mode(query, build_flags.json);
-    let build_mode = compute_build_mode(cross_crate, compiletest, query_mode);
+    let query_mode = compute_query_mode(*query, build_flags.json);
+    let build_mode = compute_build_mode(*cross_crate, *compiletest, query_mode);
 
     let edition = edition.unwrap_or_else(|| match build_mode {
         BuildMode::Default | BuildMode::CrossCrate => Edition::LATEST_STABLE,
@@ -69,34 +487,985 @@ fn try_main() -> error::Result {
     let (crate_name, crate_type) = compute_crate_name_and_type(
         crate_name,
         crate_type,
-        build_mode,
-        &path,
-        edition,
+        Input { build_mode, path, edition },
         &build_flags.cfgs,
-        &program_flags,
+        program_flags,
         &mut source,
     )?;
 
-    let verbatim_flags = command::VerbatimFlagsBuf {
-        arguments: verbatim_flags.iter().map(String::as_str).collect(),
-        environment: Vec::new(),
+    let mut verbatim_flags = command::VerbatimFlagsBuf {
+        environment: build_flags
+            .environment
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_deref()))
+            .collect(),
+        ..utility::default()
     };
+    verbatim_flags.extend(
+        package.iter().flat_map(|package| &package.arguments).map(String::as_str),
+        command::Origin::Cargo,
+    );
+    verbatim_flags
+        .extend(config_verbatim_flags.iter().map(String::as_str), command::Origin::Config);
+    verbatim_flags
+        .extend(verbatim_arguments.iter().map(String::as_str), command::Origin::CommandLine);
+    command::warn_about_confusable_verbatim_flags(verbatim_flags.as_ref());
+
     let flags = command::Flags {
-        build: &build_flags,
+        build: build_flags,
         verbatim: verbatim_flags.as_ref(),
-        program: &program_flags,
+        program: program_flags,
+        scope: command::Scope::Root,
+        source: command::Source::Written,
+        redirect_stderr: None,
+        capture_stderr: None,
     };
 
-    let crate_name =
-        builder::build(build_mode, &path, crate_name.as_ref(), crate_type, edition, flags)?;
+    if let Some(cli::Command::Prebuild { .. }) = command {
+        return timing::time("prebuilding the auxiliary crates", || {
+            builder::prebuild(build_mode, path, flags)
+        });
+    }
+
+    if let Some(cli::Command::Diff { new, new_toolchain, new_args, .. }) = command {
+        return timing::time("comparing the public APIs", || {
+            let new = (new.as_deref().unwrap_or(path), new_toolchain.as_deref(), &**new_args);
+            diff(new, build_mode, path, crate_name.as_ref(), crate_type, edition, flags)
+        });
+    }
+
+    if let Some(cli::Command::Bisect { start, end, predicate, .. }) = command {
+        return timing::time("bisecting the nightlies", || {
+            let range = (*start, *end, *predicate);
+            regression::bisect(
+                range,
+                build_mode,
+                path,
+                crate_name.as_ref(),
+                crate_type,
+                edition,
+                flags,
+            )
+        });
+    }
+
+    if let Some(cli::Command::Extract { pointer, item, .. }) = command {
+        let selector = match (pointer, item) {
+            (Some(pointer), _) => json::extract::Selector::Pointer(pointer),
+            (None, Some(item)) => json::extract::Selector::Item(item),
+            (None, None) => unreachable!(), // Enforced by clap.
+        };
+        return timing::time("extracting from the JSON output", || {
+            json::extract::run(
+                selector,
+                build_mode,
+                path,
+                crate_name.as_ref(),
+                crate_type,
+                edition,
+                flags,
+            )
+        });
+    }
+
+    if (program_flags.print.is_some() || program_flags.watch) && plan.is_none() {
+        *plan =
+            Some(plan::compute(build_mode, path, crate_name.as_ref(), crate_type, edition, flags)?);
+    }
+
+    if let Some(print) = program_flags.print
+        && let Some(plan) = plan
+    {
+        match print {
+            cli::Print::Tree => print!("{}", plan.render_tree()),
+            cli::Print::Dot => print!("{}", plan.render_dot()),
+            cli::Print::Json => print!("{}", plan.render_json()),
+            cli::Print::WatchList => {
+                for file in watched_files(given_path, arguments, Some(plan)) {
+                    println!("{}", file.display());
+                }
+            }
+        }
+    }
+
+    if program_flags.bisect_ice {
+        return timing::time("bisecting the ICE", || {
+            bisect::bisect(path, crate_name.as_ref(), crate_type, edition, flags)
+        });
+    }
+
+    if program_flags.all_revisions_query {
+        return timing::time("querying the revisions", || {
+            query_revisions(build_mode, path, crate_name.as_ref(), crate_type, edition, flags)
+        });
+    }
+
+    if program_flags.all_revisions {
+        return timing::time("building the revisions", || {
+            build_revisions(build_mode, path, crate_name.as_ref(), crate_type, edition, flags)
+        });
+    }
+
+    if !program_flags.compare_targets.is_empty() {
+        return timing::time("comparing the targets", || {
+            compare_targets(build_mode, path, crate_name.as_ref(), crate_type, edition, flags)
+        });
+    }
+
+    // There are no docs to post-process.
+    if build_flags.doctest || build_flags.coverage {
+        builder::build(build_mode, path, crate_name.as_ref(), crate_type, edition, flags)?;
+        if build_flags.coverage && build_flags.json && !build_flags.stdout && !program_flags.dry_run
+        {
+            coverage::report();
+        }
+        return Ok(());
+    }
+
+    let documented =
+        builder::build(build_mode, path, crate_name.as_ref(), crate_type, edition, flags)?;
+
+    if !program_flags.json_filter.is_empty() && !program_flags.dry_run && !build_flags.stdout {
+        let path = command::entry_point(documented[0].as_ref(), build_flags)?;
+        timing::time("filtering the JSON output", || {
+            json::filter::rewrite(&path, &program_flags.json_filter)
+        })?;
+    }
+
+    if !program_flags.dry_run && !build_flags.stdout {
+        let root = utility::doc_root(build_flags)?;
+        provenance::stamp(
+            &root,
+            path,
+            crate_name.as_ref(),
+            crate_type,
+            edition,
+            build_mode,
+            flags,
+        )?;
+    }
+
+    if build_flags.reproducible && !program_flags.dry_run && !build_flags.stdout {
+        let root = utility::doc_root(build_flags)?;
+        timing::time("checking the reproducibility", || {
+            reproducible::check(&root, || {
+                // Restoring the libraries from the cache would hide any nondeterminism in them.
+                let program_flags = cli::ProgramFlags { no_cache: true, ..program_flags.clone() };
+                let flags = command::Flags { program: &program_flags, ..flags };
+                build_beside(
+                    "doc.rebuilt",
+                    build_mode,
+                    path,
+                    crate_name.as_ref(),
+                    crate_type,
+                    edition,
+                    flags,
+                )
+            })
+        })?;
+    }
+
+    if program_flags.debug_overlay && !program_flags.dry_run {
+        let root = utility::doc_root(build_flags)?;
+        let amount = timing::time("injecting the debug overlay", || overlay::inject_all(&root))?;
+        if program_flags.verbose {
+            let s = if amount == 1 { "" } else { "s" };
+            diagnostic::info(format!("injected the debug overlay into {amount} page{s}")).emit();
+        }
+    }
 
-    if open {
-        command::open(crate_name.as_ref(), &program_flags)?;
+    // Some checks require both the HTML and the JSON output.
+    if program_flags.check_consistency || program_flags.trait_impls || program_flags.trace.is_some()
+    {
+        let build_flags = cli::BuildFlags { json: true, ..build_flags.clone() };
+        let flags = command::Flags { build: &build_flags, ..flags };
+        builder::build(build_mode, path, crate_name.as_ref(), crate_type, edition, flags)?;
+    }
+
+    if program_flags.check_consistency && !program_flags.dry_run {
+        let root = utility::doc_root(build_flags)?;
+        timing::time("checking the consistency", || {
+            consistency::check(&root, documented[0].as_ref())
+        })?;
+    }
+
+    if program_flags.trait_impls && !program_flags.dry_run {
+        let root = utility::doc_root(build_flags)?;
+        timing::time("reporting the trait implementations", || {
+            impls::report(&root, documented[0].as_ref())
+        })?;
+    }
+
+    if program_flags.compare_normalize {
+        let root = utility::doc_root(build_flags)?;
+        let before = match program_flags.dry_run {
+            false => signatures::collect(&root, documented[0].as_str())?,
+            true => utility::default(),
+        };
+
+        // The later steps like the checks and `--open` apply to the docs that weren't normalized.
+        let build_flags = cli::BuildFlags { normalize: true, ..build_flags.clone() };
+        let flags = command::Flags { build: &build_flags, ..flags };
+        let normalized = build_beside(
+            "doc.normalized",
+            build_mode,
+            path,
+            crate_name.as_ref(),
+            crate_type,
+            edition,
+            flags,
+        )?;
+
+        if !program_flags.dry_run {
+            let after = signatures::collect(&normalized, documented[0].as_str())?;
+            let amount = signatures::print_differences(&before, &after);
+            let s = if amount == 1 { "" } else { "s" };
+            diagnostic::info(format!("normalization changed {amount} signature{s}")).emit();
+        }
+
+        if program_flags.diff_viewer && !program_flags.dry_run {
+            viewer::preserve_as_baseline(&root, &normalized)?;
+            let baseline = output::Fingerprint::new(&root)?;
+            let after = output::Fingerprint::new(&normalized)?;
+            let differences = baseline.differences(&after);
+            let index = viewer::generate(
+                &normalized,
+                &differences,
+                "Docs built without vs. with --normalize",
+            )?;
+            command::open(&index, program_flags)?;
+        }
+    }
+
+    if !program_flags.compare_threads.is_empty() {
+        compare_threads(build_mode, path, crate_name.as_ref(), crate_type, edition, flags)?;
+    }
+
+    if let Some(toolchain) = &program_flags.compare_toolchain {
+        timing::time("comparing the toolchains", || {
+            compare_toolchain(
+                toolchain,
+                build_mode,
+                path,
+                crate_name.as_ref(),
+                crate_type,
+                edition,
+                flags,
+            )
+        })?;
+    }
+
+    if program_flags.all_revisions_diff {
+        timing::time("comparing the revisions", || {
+            compare_revisions(build_mode, path, crate_name.as_ref(), crate_type, edition, flags)
+        })?;
+    }
+
+    if let Some(path) = &program_flags.trace
+        && !program_flags.dry_run
+    {
+        let root = utility::doc_root(build_flags)?;
+        timing::time("tracing the item", || trace::trace(&root, documented[0].as_ref(), path))?;
+    }
+
+    if !program_flags.query_expr.is_empty() && !program_flags.dry_run {
+        let root = utility::doc_root(build_flags)?;
+        timing::time("evaluating the XPath expressions", || {
+            probe::run(&root, documented[0].as_str(), &program_flags.query_expr)
+        })?;
+    }
+
+    if program_flags.check_assets && !program_flags.dry_run {
+        let root = utility::doc_root(build_flags)?;
+        timing::time("checking the static assets", || audit::assets::check(&root))?;
+    }
+
+    if program_flags.check_anchors && !program_flags.dry_run {
+        let root = utility::doc_root(build_flags)?;
+        timing::time("checking the anchors", || audit::anchors::check(&root))?;
+    }
+
+    if program_flags.check_links && !program_flags.dry_run {
+        let root = utility::doc_root(build_flags)?;
+        let fragments = !program_flags.check_anchors;
+        timing::time("checking the links", || audit::links::check(&root, fragments))?;
+    }
+
+    if program_flags.check_definitions && !program_flags.dry_run {
+        let root = utility::doc_root(build_flags)?;
+        timing::time("checking the links to definitions", || audit::definitions::check(&root))?;
+    }
+
+    if program_flags.check_layout {
+        let type_sizes = match program_flags.cross_check_layout {
+            true => Some(command::print_type_sizes(
+                path,
+                crate_name.as_ref(),
+                crate_type,
+                edition,
+                flags,
+            )?),
+            false => None,
+        };
+
+        if !program_flags.dry_run {
+            let root = utility::doc_root(build_flags)?;
+            timing::time("checking the type layout sections", || {
+                layout::check(&root, documented[0].as_str(), type_sizes.as_deref())
+            })?;
+        }
+    }
+
+    if program_flags.check_accessibility && !program_flags.dry_run {
+        let root = utility::doc_root(build_flags)?;
+        timing::time("checking the accessibility", || {
+            audit::accessibility::check(&root, &build_flags.theme)
+        })?;
+    }
+
+    if !program_flags.snapshots.is_empty() && !program_flags.dry_run {
+        let root = utility::doc_root(build_flags)?;
+        let config = config::load(program_flags.no_config)?;
+        timing::time("checking the snapshots", || {
+            snapshot::check(
+                &root,
+                path,
+                &program_flags.snapshots,
+                &config.normalizer,
+                program_flags.bless,
+            )
+        })?;
+    }
+
+    if !program_flags.dry_run && !build_flags.stdout {
+        let config = config::load(program_flags.no_config)?;
+        if !config.budgets.is_empty() {
+            let root = utility::doc_root(build_flags)?;
+            timing::time("checking the size budgets", || budget::check(&root, &config.budgets))?;
+        }
+    }
+
+    if let Some(tests) = &program_flags.search_tests {
+        timing::time("running the search tests", || {
+            search::run_tests(tests, &utility::doc_root(build_flags)?, program_flags)
+        })?;
+    }
+
+    #[cfg(feature = "gui")]
+    if let Some(tests) = &program_flags.gui_tests {
+        timing::time("running the GUI tests", || {
+            gui::run_tests(tests, &utility::doc_root(build_flags)?, program_flags)
+        })?;
+    }
+
+    if let Some(destination) = &program_flags.upload {
+        let root = utility::doc_root(build_flags)?;
+        timing::time("uploading the docs", || upload::upload(&root, destination, program_flags))?;
+    }
+
+    if let Some(address) = &program_flags.serve {
+        let root = utility::doc_root(build_flags)?;
+        let baseline = match &program_flags.serve_baseline {
+            Some(toolchain) => Some(build_baseline(
+                toolchain,
+                build_mode,
+                path,
+                crate_name.as_ref(),
+                crate_type,
+                edition,
+                flags,
+            )?),
+            None => None,
+        };
+
+        let target = open.as_ref().and_then(Option::as_deref);
+        let (entry_point, anchor) = command::resolve_open_target(target, &documented, build_flags)?;
+        let entry_point = entry_point.strip_prefix(&root).unwrap_or(&entry_point);
+        let mut entry_point = entry_point.to_string_lossy().replace('\\', "/");
+        if let Some(anchor) = anchor {
+            entry_point += &format!("#{anchor}");
+        }
+
+        return serve::serve(
+            &root,
+            baseline.as_deref(),
+            address,
+            &entry_point,
+            open.is_some(),
+            program_flags,
+        );
+    }
+
+    if *print_paths && !program_flags.dry_run {
+        for crate_name in &documented {
+            println!("{}", command::entry_point(crate_name.as_ref(), build_flags)?.display());
+        }
+    }
+
+    if let Some(target) = view
+        && !program_flags.dry_run
+    {
+        let (page, anchor) =
+            command::resolve_open_target(target.as_deref(), &documented, build_flags)?;
+        render::view(&page, anchor.as_deref())?;
+    }
+
+    if let Some(target) = open {
+        let (page, anchor) =
+            command::resolve_open_target(target.as_deref(), &documented, build_flags)?;
+        command::open_at(&page, anchor.as_deref(), program_flags)?;
+    }
+
+    Ok(())
+}
+
+/// Build the docs with the given toolchain into `doc.baseline/` next to the documentation root.
+fn build_baseline(
+    toolchain: &str,
+    build_mode: BuildMode,
+    path: &Path,
+    crate_name: data::CrateNameRef<'_>,
+    crate_type: CrateType,
+    edition: Edition,
+    flags: command::Flags<'_>,
+) -> error::Result<PathBuf> {
+    let build_flags =
+        cli::BuildFlags { toolchain: Some(toolchain.to_owned()), ..flags.build.clone() };
+    let flags = command::Flags { build: &build_flags, ..flags };
+    build_beside("doc.baseline", build_mode, path, crate_name, crate_type, edition, flags)
+}
+
+/// Build the docs into the given directory next to the documentation root, leaving the latter
+/// untouched.
+fn build_beside(
+    directory: &str,
+    build_mode: BuildMode,
+    path: &Path,
+    crate_name: data::CrateNameRef<'_>,
+    crate_type: CrateType,
+    edition: Edition,
+    flags: command::Flags<'_>,
+) -> error::Result<PathBuf> {
+    let root = utility::doc_root(flags.build)?;
+    let destination = root.with_file_name(directory);
+
+    if flags.program.dry_run {
+        builder::build(build_mode, path, crate_name, crate_type, edition, flags)?;
+        return Ok(destination);
+    }
+
+    // Move the actual docs out of the way since rustdoc would merge new docs into them.
+    let actual = root.with_file_name("doc.actual");
+    for directory in [&actual, &destination] {
+        if directory.exists() {
+            std::fs::remove_dir_all(directory)?;
+        }
+    }
+    std::fs::rename(&root, &actual)?;
+    // The documentation of the auxiliary crates has to be rebuilt into the empty root.
+    builder::forget_auxiliary_docs();
+
+    let result = builder::build(build_mode, path, crate_name, crate_type, edition, flags);
+
+    if root.exists() {
+        std::fs::rename(&root, &destination)?;
+    }
+    std::fs::rename(&actual, &root)?;
+    builder::forget_auxiliary_docs();
+    result?;
+
+    Ok(destination)
+}
+
+/// Build the docs with each of the requested numbers of rustdoc threads and compare the
+/// outputs and the durations.
+fn compare_threads(
+    build_mode: BuildMode,
+    path: &Path,
+    crate_name: data::CrateNameRef<'_>,
+    crate_type: CrateType,
+    edition: Edition,
+    flags: command::Flags<'_>,
+) -> error::Result {
+    let root = utility::doc_root(flags.build)?;
+    let mut baseline = None;
+    let mut latest = None;
+
+    for &threads in &flags.program.compare_threads {
+        let build_flags = cli::BuildFlags { rustdoc_threads: Some(threads), ..flags.build.clone() };
+        let flags = command::Flags { build: &build_flags, ..flags };
+
+        let start = Instant::now();
+        builder::build(build_mode, path, crate_name, crate_type, edition, flags)?;
+        let duration = start.elapsed();
+
+        if flags.program.dry_run {
+            continue;
+        }
+
+        let fingerprint = output::Fingerprint::new(&root)?;
+        let s = if threads.get() == 1 { "" } else { "s" };
+        let diagnostic =
+            diagnostic::info(format!("built with {threads} thread{s} in {duration:.2?}"));
+
+        let Some((baseline_threads, baseline)) = &baseline else {
+            diagnostic.emit();
+            if flags.program.diff_viewer {
+                viewer::preserve_baseline(&root)?;
+            }
+            baseline = Some((threads, fingerprint));
+            continue;
+        };
+
+        let differences = baseline.differences(&fingerprint);
+        let s = if baseline_threads.get() == 1 { "" } else { "s" };
+        let baseline = format!("the output built with {baseline_threads} thread{s}");
+        let diagnostic = match differences.len() {
+            0 => diagnostic.note(format!("the output is identical to {baseline}")),
+            _ => {
+                let summary = output::summarize(&differences);
+                let diagnostic = diagnostic.note(format!("{summary} from {baseline}"));
+                differences
+                    .iter()
+                    .map(ToString::to_string)
+                    .fold(diagnostic, diagnostic::Diagnostic::note)
+            }
+        };
+        diagnostic.emit();
+        latest = Some((threads, fingerprint));
+    }
+
+    if flags.program.diff_viewer
+        && let Some((baseline_threads, baseline)) = &baseline
+        && let Some((threads, latest)) = &latest
+    {
+        let differences = baseline.differences(latest);
+        let title = format!("Docs built with {baseline_threads} vs. {threads} rustdoc threads");
+        let index = viewer::generate(&root, &differences, &title)?;
+        command::open(&index, flags.program)?;
+    }
+
+    Ok(())
+}
+
+/// Build the docs with the given toolchain, too, and compare them against the docs built with the
+/// selected one after normalizing them.
+fn compare_toolchain(
+    toolchain: &str,
+    build_mode: BuildMode,
+    path: &Path,
+    crate_name: data::CrateNameRef<'_>,
+    crate_type: CrateType,
+    edition: Edition,
+    flags: command::Flags<'_>,
+) -> error::Result {
+    let baseline =
+        build_baseline(toolchain, build_mode, path, crate_name, crate_type, edition, flags)?;
+
+    if flags.program.dry_run {
+        return Ok(());
+    }
+
+    let root = utility::doc_root(flags.build)?;
+    let config = config::load(flags.program.no_config)?;
+    let before = output::Fingerprint::normalized(&baseline, &config.normalizer)?;
+    let after = output::Fingerprint::normalized(&root, &config.normalizer)?;
+    let differences = before.differences(&after);
+
+    let current = match &flags.build.toolchain {
+        Some(current) => format!("`{current}`"),
+        None => "the default toolchain".to_owned(),
+    };
+    let diagnostic = diagnostic::info(format!("built with `{toolchain}` and {current}"));
+    let diagnostic = match differences.len() {
+        0 => diagnostic.note("the (normalized) outputs are identical"),
+        _ => {
+            let summary = output::summarize(&differences);
+            differences.iter().map(ToString::to_string).fold(
+                diagnostic.note(format!("{summary} after normalization")),
+                diagnostic::Diagnostic::note,
+            )
+        }
+    };
+    diagnostic.emit();
+
+    if flags.program.diff_viewer {
+        let title = format!("Docs built with `{toolchain}` vs. {current}");
+        let index = viewer::generate(&root, &differences, &title)?;
+        command::open(&index, flags.program)?;
+    }
+
+    Ok(())
+}
+
+/// Document the crate and its counterpart as JSON under the same crate name and report the
+/// differences between their public APIs.
+fn diff(
+    (new, new_toolchain, new_args): (&Path, Option<&str>, &[String]),
+    build_mode: BuildMode,
+    path: &Path,
+    crate_name: data::CrateNameRef<'_>,
+    crate_type: CrateType,
+    edition: Edition,
+    flags: command::Flags<'_>,
+) -> error::Result {
+    let out = utility::out_path(flags.build, "out").join(crate_name.as_str());
+    let old_build_flags = cli::BuildFlags { json: true, ..flags.build.clone() };
+    let new_build_flags = cli::BuildFlags {
+        toolchain: new_toolchain.map(ToOwned::to_owned).or_else(|| flags.build.toolchain.clone()),
+        ..old_build_flags.clone()
+    };
+    let mut verbatim = command::VerbatimFlagsBuf::default().extended(flags.verbatim);
+    verbatim.extend(new_args.iter().map(String::as_str), command::Origin::Implied("`--new-arg`"));
+
+    let sides = [
+        ("old", path, command::Flags { build: &old_build_flags, ..flags }),
+        ("new", new, command::Flags {
+            build: &new_build_flags,
+            verbatim: verbatim.as_ref(),
+            ..flags
+        }),
+    ];
+    let mut apis = Vec::new();
+
+    for (side, path, flags) in sides {
+        let documented = builder::build(build_mode, path, crate_name, crate_type, edition, flags)?;
+
+        if flags.program.dry_run {
+            continue;
+        }
+
+        // Keep the output of each side around for closer inspection.
+        let json = command::entry_point(documented[0].as_ref(), flags.build)?;
+        std::fs::create_dir_all(&out)?;
+        std::fs::copy(&json, out.join(format!("{side}.json")))?;
+
+        let json = std::fs::read(&json)?;
+        let json: serde_json::Value =
+            serde_json::from_slice(&json).map_err(std::io::Error::from)?;
+        apis.push(api::collect(&json));
+    }
+
+    if let [old_api, new_api] = apis.as_slice() {
+        let (old_toolchain, new_toolchain) =
+            (&old_build_flags.toolchain, &new_build_flags.toolchain);
+        let describe = |path: &Path, toolchain: &Option<String>| {
+            let path = path.display();
+            match toolchain {
+                _ if old_toolchain == new_toolchain => format!("`{path}`"),
+                Some(toolchain) => format!("`{path}` (`{toolchain}`)"),
+                None => format!("`{path}` (the default toolchain)"),
+            }
+        };
+        let description = match path == new && old_toolchain == new_toolchain {
+            true => format!("`{}` without and with the `--new-arg`s", path.display()),
+            false => {
+                format!("{} and {}", describe(path, old_toolchain), describe(new, new_toolchain))
+            }
+        };
+        api::report(old_api, new_api, &description);
+    }
+
+    Ok(())
+}
+
+fn compare_revisions(
+    build_mode: BuildMode,
+    path: &Path,
+    crate_name: data::CrateNameRef<'_>,
+    crate_type: CrateType,
+    edition: Edition,
+    flags: command::Flags<'_>,
+) -> error::Result {
+    let source = std::fs::read_to_string(path)?;
+    let revisions = sorted_revisions(&directive::Directives::parse(&source, None));
+
+    if revisions.len() < 2 {
+        diagnostic::warning("there are no revisions to compare")
+            .note(format!("`{}` declares fewer than two revisions", path.display()))
+            .emit();
+        return Ok(());
+    }
+
+    let root = utility::doc_root(flags.build)?;
+    let mut baseline = None;
+    let mut changed = BTreeSet::new();
+    let input = Input { build_mode, path, edition };
+
+    for revision in &revisions {
+        build_revision(revision, &root, input, crate_name, crate_type, flags)?;
+
+        if flags.program.dry_run {
+            continue;
+        }
+
+        let fingerprint = output::Fingerprint::new(&root)?;
+        let diagnostic = diagnostic::info(format!("built revision `{revision}`"));
+
+        let Some((baseline_revision, baseline)) = &baseline else {
+            diagnostic.emit();
+            if flags.program.diff_viewer {
+                viewer::preserve_baseline(&root)?;
+            }
+            baseline = Some((revision, fingerprint));
+            continue;
+        };
+
+        let differences = baseline.differences(&fingerprint);
+        changed.extend(differences.iter().map(|difference| difference.path().to_owned()));
+        let baseline_name = format!("the output of revision `{baseline_revision}`");
+        let diagnostic = match differences.len() {
+            0 => diagnostic.note(format!("the output is identical to {baseline_name}")),
+            _ => {
+                let summary = output::summarize(&differences);
+                let diagnostic = diagnostic.note(format!("{summary} from {baseline_name}"));
+                differences
+                    .iter()
+                    .map(ToString::to_string)
+                    .fold(diagnostic, diagnostic::Diagnostic::note)
+            }
+        };
+        diagnostic.emit();
+
+        // The viewer compares the last revision against the first one.
+        if flags.program.diff_viewer && Some(revision) == revisions.last() {
+            let title = format!("Docs of revision `{baseline_revision}` vs. `{revision}`");
+            let index = viewer::generate(&root, &differences, &title)?;
+            command::open(&index, flags.program)?;
+        }
+    }
+
+    if !flags.program.dry_run {
+        let amount = changed.len();
+        let (s, verb) = if amount == 1 { ("", "differs") } else { ("s", "differ") };
+        let revisions = revisions.len();
+        diagnostic::info(format!("{amount} file{s} {verb} across the {revisions} revisions"))
+            .emit();
+    }
+
+    Ok(())
+}
+
+fn compare_targets(
+    build_mode: BuildMode,
+    path: &Path,
+    crate_name: data::CrateNameRef<'_>,
+    crate_type: CrateType,
+    edition: Edition,
+    flags: command::Flags<'_>,
+) -> error::Result {
+    let targets = &flags.program.compare_targets;
+    let targets: Vec<_> = targets
+        .iter()
+        .enumerate()
+        .filter(|&(index, target)| !targets[..index].contains(target))
+        .map(|(_, target)| target)
+        .collect();
+
+    if targets.len() < 2 {
+        diagnostic::warning("there are no targets to compare")
+            .note("pass at least two distinct targets to `--compare-targets`")
+            .emit();
+        return Ok(());
+    }
+
+    let out = utility::out_path(flags.build, "out").join(crate_name.as_str());
+    let mut items = Vec::new();
+
+    for target in targets {
+        let build_flags =
+            cli::BuildFlags { json: true, target: Some(target.clone()), ..flags.build.clone() };
+        let flags = command::Flags { build: &build_flags, ..flags };
+        builder::build(build_mode, path, crate_name, crate_type, edition, flags)?;
+
+        if flags.program.dry_run {
+            continue;
+        }
+
+        // Keep the output of each target around for closer inspection.
+        let json = command::entry_point(crate_name, &build_flags)?;
+        std::fs::create_dir_all(&out)?;
+        std::fs::copy(&json, out.join(format!("{target}.json")))?;
+
+        let json = std::fs::read(&json)?;
+        let json: serde_json::Value =
+            serde_json::from_slice(&json).map_err(std::io::Error::from)?;
+        items.push((target.clone(), targets::collect(&json)));
+    }
+
+    if !flags.program.dry_run {
+        targets::report(&items);
     }
 
     Ok(())
 }
 
+fn query_revisions(
+    build_mode: BuildMode,
+    path: &Path,
+    crate_name: data::CrateNameRef<'_>,
+    crate_type: CrateType,
+    edition: Edition,
+    flags: command::Flags<'_>,
+) -> error::Result {
+    let BuildMode::Compiletest { query: query_mode @ Some(_) } = build_mode else {
+        unreachable!() // clap ensures `--all-revisions-query` implies `--query`
+    };
+
+    let source = std::fs::read_to_string(path)?;
+    let directives = directive::Directives::parse(&source, query_mode);
+    let revisions = sorted_revisions(&directives);
+
+    if revisions.is_empty() {
+        diagnostic::warning("there are no revisions to query")
+            .note(format!("`{}` doesn't declare any revisions", path.display()))
+            .emit();
+        return Ok(());
+    }
+
+    let root = utility::doc_root(flags.build)?;
+    let mut results = Vec::new();
+    let input = Input { build_mode, path, edition };
+
+    for revision in &revisions {
+        build_revision(revision, &root, input, crate_name, crate_type, flags)?;
+
+        if flags.program.dry_run {
+            continue;
+        }
+
+        let enabled = flags.build.cfgs.iter().map(String::as_str).chain([revision.as_str()]);
+        let queries = std::mem::take(&mut directives.instantiated(&enabled.collect()).queries);
+        let queries = builder::selected_queries(&queries, flags.program).into_owned();
+        let mut context =
+            query::Context::new(&root, path, crate_name.as_str(), flags.program.bless);
+        let queries = queries
+            .into_iter()
+            .map(|query| {
+                let result = query.check(&mut context);
+                (query, result)
+            })
+            .collect();
+        results.push((revision.clone(), queries));
+    }
+
+    if flags.program.dry_run {
+        return Ok(());
+    }
+
+    let (table, result) = query::tabulate(&results);
+    print!("{table}");
+    Ok(result?)
+}
+
+/// Build each revision of the compiletest test and keep its docs in `out/<crate>/<revision>/`.
+///
+/// With `--keep-going`, a failing revision doesn't prevent the remaining ones from getting built.
+fn build_revisions(
+    build_mode: BuildMode,
+    path: &Path,
+    crate_name: data::CrateNameRef<'_>,
+    crate_type: CrateType,
+    edition: Edition,
+    flags: command::Flags<'_>,
+) -> error::Result {
+    let source = std::fs::read_to_string(path)?;
+    let revisions = sorted_revisions(&directive::Directives::parse(&source, None));
+
+    if revisions.is_empty() {
+        diagnostic::warning("there are no revisions to build")
+            .note(format!("`{}` doesn't declare any revisions", path.display()))
+            .emit();
+        return Ok(());
+    }
+
+    let root = utility::doc_root(flags.build)?;
+    let out = std::env::current_dir()?.join(utility::out_path(flags.build, "out"));
+    let mut summary = batch::Summary::default();
+    let input = Input { build_mode, path, edition };
+
+    for (index, revision) in revisions.iter().enumerate() {
+        let built =
+            build_revision(revision, &root, input, crate_name, crate_type, flags).and_then(|()| {
+                if flags.program.dry_run {
+                    return Ok(());
+                }
+                let output = out.join(crate_name.as_str()).join(revision);
+                if output.exists() {
+                    std::fs::remove_dir_all(&output)?;
+                }
+                utility::copy_dir(&root, &output)?;
+                if flags.program.verbose {
+                    diagnostic::info(format!(
+                        "stored the docs of revision `{revision}` in `{}`",
+                        output.display()
+                    ))
+                    .emit();
+                }
+                Ok(())
+            });
+
+        let outcome = match built {
+            Ok(()) => batch::Outcome::Success,
+            Err(error::Error::Skipped(unmet)) => batch::Outcome::Skipped(unmet),
+            Err(error) => {
+                error.into_diagnostic().emit();
+                batch::Outcome::Failure
+            }
+        };
+        let failed = matches!(outcome, batch::Outcome::Failure);
+        summary.record(format!("revision `{revision}`"), outcome);
+
+        if failed && !flags.program.keep_going {
+            summary.publish();
+            return Ok(summary.into_result(revisions.len() - index - 1)?);
+        }
+    }
+
+    summary.publish();
+    Ok(summary.into_result(0)?)
+}
+
+/// The revisions declared by the given directives in a deterministic order.
+fn sorted_revisions(directives: &directive::Directives<'_>) -> Vec<String> {
+    let mut revisions: Vec<_> = directives.revisions.iter().map(ToString::to_string).collect();
+    revisions.sort_unstable();
+    revisions
+}
+
+/// The root source file together with how it gets built.
+#[derive(Clone, Copy)]
+struct Input<'a> {
+    build_mode: BuildMode,
+    path: &'a Path,
+    edition: Edition,
+}
+
+/// Build the given revision of the compiletest test from scratch.
+fn build_revision(
+    revision: &str,
+    root: &Path,
+    input: Input<'_>,
+    crate_name: data::CrateNameRef<'_>,
+    crate_type: CrateType,
+    flags: command::Flags<'_>,
+) -> error::Result {
+    // Start from scratch since rustdoc doesn't remove the pages of items that got cfg'ed out.
+    if !flags.program.dry_run && root.exists() {
+        std::fs::remove_dir_all(root)?;
+        builder::forget_auxiliary_docs();
+    }
+
+    // Like compiletest, we also enable the revision as a `cfg`.
+    let cfgs = flags.build.cfgs.iter().cloned().chain([revision.to_owned()]).collect();
+    let build_flags =
+        cli::BuildFlags { revisions: vec![revision.to_owned()], cfgs, ..flags.build.clone() };
+    let flags = command::Flags { build: &build_flags, ..flags };
+    builder::build(input.build_mode, input.path, crate_name, crate_type, input.edition, flags)?;
+
+    Ok(())
+}
+
 fn compute_query_mode(query: bool, json: bool) -> Option<QueryMode> {
     match (query, json) {
         (true, false) => Some(QueryMode::Html),
@@ -117,26 +1486,28 @@ fn compute_build_mode(cross_crate: bool, compiletest: bool, query: Option<QueryM
 fn compute_crate_name_and_type<'src>(
     crate_name: Option<CrateNameBuf>,
     crate_type: Option<CrateType>,
-    build_mode: BuildMode,
-    path: &Path,
-    edition: Edition,
+    input: Input<'_>,
     cfgs: &[String],
     program_flags: &cli::ProgramFlags,
     source: &'src mut String,
 ) -> error::Result<(CrateNameCow<'src>, CrateType)> {
+    let Input { build_mode, path, edition } = input;
+
     Ok(match (crate_name, crate_type) {
         (Some(crate_name), Some(crate_type)) => (crate_name.into(), crate_type),
         (crate_name, crate_type) => {
             let (crate_name, crate_type): (Option<CrateNameCow<'_>>, _) = match build_mode {
                 BuildMode::Default | BuildMode::CrossCrate => {
                     *source = std::fs::read_to_string(path)?;
-                    let attributes = Attributes::parse(
-                        source,
-                        // FIXME: doesn't contain `-f`s; eagerly expand them into `--cfg`s in main
-                        cfgs,
-                        edition,
-                        program_flags.verbose,
-                    );
+                    let attributes = timing::time("parsing crate attributes", || {
+                        Attributes::parse(
+                            source,
+                            // FIXME: doesn't contain `-f`s; eagerly expand them into `--cfg`s in main
+                            cfgs,
+                            edition,
+                            program_flags.verbose,
+                        )
+                    });
 
                     let crate_name: Option<CrateNameCow<'_>> = crate_name
                         .map(Into::into)
@@ -0,0 +1,188 @@
+//! Resolving the source files that constitute a crate by following its `mod foo;` declarations.
+//!
+//! We follow the rules of rustc for locating module files incl. `#[path]` attributes and inline
+//! modules, but we don't evaluate `cfg`s or expand macros.
+
+use crate::parser::{At, SourceFileParser};
+use ra_ap_rustc_lexer::{LiteralKind, TokenKind};
+use rustc_hash::FxHashSet;
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod test;
+
+/// The source files of the crate with the given root, starting with the root itself.
+///
+/// Modules whose file doesn't exist are skipped, rustc is going to report them anyway.
+pub(crate) fn files(root: &Path) -> Vec<PathBuf> {
+    let mut files = vec![root.to_owned()];
+    let mut visited = FxHashSet::default();
+    // The file alongside the directory its non-inline modules are relative to.
+    let mut queue = vec![(root.to_owned(), root.parent().unwrap_or(Path::new("")).to_owned())];
+
+    while let Some((file, directory)) = queue.pop() {
+        if !visited.insert(file.canonicalize().unwrap_or_else(|_| file.clone())) {
+            continue;
+        }
+        let Ok(source) = std::fs::read_to_string(&file) else { continue };
+        let base = file.parent().unwrap_or(Path::new(""));
+
+        for module in modules(&source, base, &directory) {
+            if module.file.exists() && !files.contains(&module.file) {
+                files.push(module.file.clone());
+            }
+            queue.push((module.file, module.directory));
+        }
+    }
+
+    files
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+struct Module {
+    file: PathBuf,
+    /// The directory the modules declared in the file are relative to.
+    directory: PathBuf,
+}
+
+/// The out-of-line modules declared in the given source file.
+///
+/// Modules with a `#[path]` attribute are relative to the `base` directory containing the file
+/// (unless nested in an inline module), all others are relative to the given `directory`.
+fn modules(source: &str, base: &Path, directory: &Path) -> Vec<Module> {
+    let tokens = tokens(source);
+    let mut modules = Vec::new();
+    // The directories of the enclosing inline modules alongside their brace depth.
+    let mut inline: Vec<(usize, PathBuf)> = Vec::new();
+    let mut depth = 0;
+    let mut path = None;
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let directory = inline.last().map_or(directory, |(_, directory)| directory);
+
+        match tokens[index..] {
+            [
+                (TokenKind::Pound, _),
+                (TokenKind::OpenBracket, _),
+                (TokenKind::Ident, "path"),
+                (TokenKind::Eq, _),
+                (
+                    TokenKind::Literal {
+                        kind: LiteralKind::Str { .. } | LiteralKind::RawStr { .. },
+                        ..
+                    },
+                    literal,
+                ),
+                (TokenKind::CloseBracket, _),
+                ..,
+            ] => {
+                path = Some(unquote(literal));
+                index += 6;
+            }
+            // Other attributes and visibilities may come between the `#[path]` and the `mod`.
+            [(TokenKind::Pound, _), (TokenKind::OpenBracket, _), ..] => {
+                index += 2;
+                let mut brackets = 1;
+                while brackets != 0 && index < tokens.len() {
+                    match tokens[index].0 {
+                        TokenKind::OpenBracket => brackets += 1,
+                        TokenKind::CloseBracket => brackets -= 1,
+                        _ => {}
+                    }
+                    index += 1;
+                }
+            }
+            [(TokenKind::Ident, "pub"), (TokenKind::OpenParen, _), ..] => {
+                let length =
+                    tokens[index..].iter().position(|(kind, _)| *kind == TokenKind::CloseParen);
+                index += length.map_or(tokens.len(), |length| length + 1);
+            }
+            [(TokenKind::Ident, "pub" | "unsafe"), ..] => index += 1,
+            [(TokenKind::Ident, "mod"), (TokenKind::Ident, name), (TokenKind::Semi, _), ..] => {
+                let module = match path.take() {
+                    // The module directory of a file loaded via `#[path]` is its parent directory,
+                    // as if it were a `mod.rs` file.
+                    Some(path) => {
+                        let file =
+                            inline.last().map_or(base, |(_, directory)| directory).join(path);
+                        let directory = file.parent().unwrap_or(Path::new("")).to_owned();
+                        Module { file, directory }
+                    }
+                    None => {
+                        let file = directory.join(format!("{name}.rs"));
+                        match file.exists() {
+                            true => Module { file, directory: directory.join(name) },
+                            false => {
+                                let directory = directory.join(name);
+                                Module { file: directory.join("mod.rs"), directory }
+                            }
+                        }
+                    }
+                };
+                modules.push(module);
+                index += 3;
+            }
+            [
+                (TokenKind::Ident, "mod"),
+                (TokenKind::Ident, name),
+                (TokenKind::OpenBrace, _),
+                ..,
+            ] => {
+                let nested = directory.join(path.take().unwrap_or_else(|| name.to_owned()));
+                depth += 1;
+                inline.push((depth, nested));
+                index += 3;
+            }
+            [(TokenKind::OpenBrace, _), ..] => {
+                depth += 1;
+                path = None;
+                index += 1;
+            }
+            [(TokenKind::CloseBrace, _), ..] => {
+                if inline.last().is_some_and(|&(level, _)| level == depth) {
+                    inline.pop();
+                }
+                depth = depth.saturating_sub(1);
+                path = None;
+                index += 1;
+            }
+            _ => {
+                path = None;
+                index += 1;
+            }
+        }
+    }
+
+    modules
+}
+
+/// The tokens of the given source file excluding whitespace and comments.
+fn tokens(source: &str) -> Vec<(TokenKind, &str)> {
+    let mut parser = SourceFileParser::new(source);
+    let mut tokens = Vec::new();
+
+    while let Some(token) = parser.peek() {
+        let kind = token.kind;
+        if !matches!(
+            kind,
+            TokenKind::Whitespace | TokenKind::LineComment { .. } | TokenKind::BlockComment { .. }
+        ) {
+            tokens.push((kind, source.at(parser.span())));
+        }
+        parser.advance();
+    }
+
+    tokens
+}
+
+/// The contents of the given (raw) string literal.
+fn unquote(literal: &str) -> String {
+    let literal = literal.strip_prefix('r').unwrap_or(literal);
+    let literal = literal.trim_matches('#');
+    literal
+        .strip_prefix('"')
+        .and_then(|literal| literal.strip_suffix('"'))
+        .unwrap_or(literal)
+        .to_owned()
+}
@@ -0,0 +1,48 @@
+use super::{Module, modules, unquote};
+use std::path::Path;
+
+fn module(file: &str, directory: &str) -> Module {
+    Module { file: file.into(), directory: directory.into() }
+}
+
+#[test]
+fn outline_modules() {
+    let source = "mod a;\npub mod b;\npub(crate) mod c { mod d; }\nfn f() { mod e {} }\n";
+    assert_eq!(modules(source, Path::new("src"), Path::new("src")), [
+        module("src/a/mod.rs", "src/a"),
+        module("src/b/mod.rs", "src/b"),
+        module("src/c/d/mod.rs", "src/c/d"),
+    ]);
+}
+
+#[test]
+fn path_attributes() {
+    let source = "#[path = \"x/y.rs\"]\n#[cfg(unix)]\npub mod a;\n\
+                  #[path = r#\"z\"#]\nmod b {\n    #[path = \"w.rs\"]\n    mod c;\n}\n";
+    assert_eq!(modules(source, Path::new("src"), Path::new("src/m")), [
+        module("src/x/y.rs", "src/x"),
+        module("src/m/z/w.rs", "src/m/z")
+    ]);
+}
+
+#[test]
+fn path_attribute_ends_at_item() {
+    let source = "#[path = \"x.rs\"]\nfn f() {}\nmod a;\n";
+    assert_eq!(modules(source, Path::new("src"), Path::new("src")), [module(
+        "src/a/mod.rs",
+        "src/a"
+    )]);
+}
+
+#[test]
+fn comments_and_strings() {
+    let source = "// mod a;\n/* mod b; */\nconst _: &str = \"mod c;\";\n";
+    assert_eq!(modules(source, Path::new("src"), Path::new("src")), []);
+}
+
+#[test]
+fn string_literals() {
+    assert_eq!(unquote("\"a.rs\""), "a.rs");
+    assert_eq!(unquote("r\"a.rs\""), "a.rs");
+    assert_eq!(unquote("r##\"a.rs\"##"), "a.rs");
+}
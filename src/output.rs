@@ -0,0 +1,117 @@
+//! Fingerprinting and comparing entire output directories.
+
+use crate::{provenance, snapshot::Normalizer};
+use std::{
+    collections::BTreeMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+};
+
+/// The content hashes of all files found in an output directory keyed by their relative path.
+#[derive(Default)]
+pub(crate) struct Fingerprint(BTreeMap<PathBuf, u64>);
+
+impl Fingerprint {
+    pub(crate) fn new(root: &Path) -> io::Result<Self> {
+        Self::compute(root, None)
+    }
+
+    /// Like [`Self::new`] but normalize the contents of text files beforehand to ignore
+    /// details that vary between toolchains.
+    pub(crate) fn normalized(root: &Path, normalizer: &Normalizer) -> io::Result<Self> {
+        Self::compute(root, Some(normalizer))
+    }
+
+    fn compute(root: &Path, normalizer: Option<&Normalizer>) -> io::Result<Self> {
+        let mut fingerprint = Self::default();
+        let mut directories = vec![root.to_owned()];
+
+        while let Some(directory) = directories.pop() {
+            for entry in std::fs::read_dir(directory)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    directories.push(path);
+                    continue;
+                }
+
+                // It differs between any two runs.
+                if path == root.join(provenance::FILE_NAME) {
+                    continue;
+                }
+
+                let mut hasher = DefaultHasher::new();
+                let contents = std::fs::read(&path)?;
+                match (normalizer, std::str::from_utf8(&contents)) {
+                    (Some(normalizer), Ok(text)) => normalizer.normalize(text).hash(&mut hasher),
+                    _ => contents.hash(&mut hasher),
+                }
+                let path = path.strip_prefix(root).unwrap_or(&path).to_owned();
+                fingerprint.0.insert(path, hasher.finish());
+            }
+        }
+
+        Ok(fingerprint)
+    }
+
+    /// A single hash summarizing the contents of the entire directory.
+    pub(crate) fn digest(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.0.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The files that differ between the two directories.
+    pub(crate) fn differences<'a>(&'a self, other: &'a Self) -> Vec<Difference<'a>> {
+        let mut differences = Vec::new();
+
+        for (path, hash) in &self.0 {
+            match other.0.get(path) {
+                Some(other) if other == hash => {}
+                Some(_) => differences.push(Difference::Changed(path)),
+                None => differences.push(Difference::Removed(path)),
+            }
+        }
+
+        for path in other.0.keys() {
+            if !self.0.contains_key(path) {
+                differences.push(Difference::Added(path));
+            }
+        }
+
+        differences.sort_by_key(|difference| difference.path());
+        differences
+    }
+}
+
+/// How many files differ, e.g., `1 file differs` or `3 files differ`.
+pub(crate) fn summarize(differences: &[Difference<'_>]) -> String {
+    let amount = differences.len();
+    let (s, verb) = if amount == 1 { ("", "differs") } else { ("s", "differ") };
+    format!("{amount} file{s} {verb}")
+}
+
+pub(crate) enum Difference<'a> {
+    Added(&'a Path),
+    Removed(&'a Path),
+    Changed(&'a Path),
+}
+
+impl<'a> Difference<'a> {
+    pub(crate) fn path(&self) -> &'a Path {
+        match self {
+            Self::Added(path) | Self::Removed(path) | Self::Changed(path) => path,
+        }
+    }
+}
+
+impl std::fmt::Display for Difference<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (marker, path) = match self {
+            Self::Added(path) => ('+', path),
+            Self::Removed(path) => ('-', path),
+            Self::Changed(path) => ('~', path),
+        };
+        write!(f, "{marker} {}", path.display())
+    }
+}
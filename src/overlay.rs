@@ -0,0 +1,130 @@
+//! The debug overlay injected into the generated pages by `--debug-overlay`.
+//!
+//! On hover, it shows the IDs, anchors and the DOM outline of the element under the cursor
+//! alongside an XPath that selects it, which helps with writing `htmldocck` queries.
+
+use crate::utility::files;
+use std::{io, path::Path};
+
+#[cfg(test)]
+mod test;
+
+/// Marks pages that already contain the overlay since rustdoc doesn't regenerate all of them.
+const MARKER: &str = "<!-- rruxwry: debug overlay -->";
+
+/// Inject the overlay into all pages found in the documentation root `root`.
+///
+/// Returns the number of pages that were modified.
+pub(crate) fn inject_all(root: &Path) -> io::Result<usize> {
+    let mut amount = 0;
+
+    for path in files(root, &["html"])? {
+        let page = std::fs::read_to_string(&path)?;
+        if let Some(page) = inject(&page) {
+            std::fs::write(&path, page)?;
+            amount += 1;
+        }
+    }
+
+    Ok(amount)
+}
+
+/// Inject the overlay into the given page unless it already contains it.
+fn inject(page: &str) -> Option<String> {
+    if page.contains(MARKER) {
+        return None;
+    }
+
+    let overlay = format!("{MARKER}<style>{STYLE}</style><script>{SCRIPT}</script>");
+    Some(match page.rfind("</body>") {
+        Some(index) => format!("{}{overlay}{}", &page[..index], &page[index..]),
+        None => format!("{page}{overlay}"),
+    })
+}
+
+const STYLE: &str = "\
+#rruxwry-overlay { position: fixed; z-index: 1000; max-width: 40em; padding: 0.5em 0.75em; \
+pointer-events: none; background: #222; color: #eee; border-radius: 4px; \
+font: 12px/1.4 monospace; white-space: pre-wrap; word-break: break-all; }
+#rruxwry-overlay b { color: #8cf; }
+.rruxwry-hovered { outline: 2px dashed #f80 !important; }";
+
+/// Toggle the overlay with the backtick key.
+const SCRIPT: &str = r##"(() => {
+    const overlay = document.createElement("div");
+    overlay.id = "rruxwry-overlay";
+    overlay.hidden = true;
+    document.body.appendChild(overlay);
+    let enabled = true;
+    let hovered = null;
+
+    const describe = element => {
+        let description = element.localName;
+        if (element.id) description += "#" + element.id;
+        for (const name of element.classList) {
+            if (!name.startsWith("rruxwry-")) description += "." + name;
+        }
+        return description;
+    };
+    // Anchor the path at the closest ancestor with an ID, like queries usually do.
+    const xpath = element => {
+        const steps = [];
+        for (let node = element; node && node.nodeType === Node.ELEMENT_NODE; node = node.parentNode) {
+            if (node.id) {
+                steps.unshift(`//*[@id="${node.id}"]`);
+                return steps.join("/");
+            }
+            const siblings = [...node.parentNode?.children ?? []]
+                .filter(sibling => sibling.localName === node.localName);
+            const index = siblings.length > 1 ? `[${siblings.indexOf(node) + 1}]` : "";
+            steps.unshift(node.localName + index);
+        }
+        return "/" + steps.join("/");
+    };
+    const outline = element => {
+        const chain = [];
+        for (let node = element; node && node !== document.body; node = node.parentElement) {
+            chain.unshift(describe(node));
+        }
+        return chain.join(" > ");
+    };
+    const anchors = element => [...new Set(
+        [element.closest("[id]")?.id, element.closest("a[href]")?.getAttribute("href")]
+            .filter(Boolean)
+    )];
+
+    const line = (label, value) => {
+        const entry = document.createElement("div");
+        const name = document.createElement("b");
+        name.textContent = label + ": ";
+        entry.append(name, value);
+        return entry;
+    };
+
+    addEventListener("mouseover", event => {
+        if (!enabled || event.target === overlay) return;
+        hovered?.classList.remove("rruxwry-hovered");
+        hovered = event.target;
+        hovered.classList.add("rruxwry-hovered");
+        overlay.replaceChildren(
+            line("element", describe(hovered)),
+            line("anchors", anchors(hovered).join(", ") || "none"),
+            line("outline", outline(hovered)),
+            line("xpath", xpath(hovered)),
+        );
+        overlay.hidden = false;
+    });
+    addEventListener("mousemove", event => {
+        const x = Math.min(event.clientX + 16, innerWidth - overlay.offsetWidth - 8);
+        const y = Math.min(event.clientY + 16, innerHeight - overlay.offsetHeight - 8);
+        overlay.style.left = Math.max(x, 8) + "px";
+        overlay.style.top = Math.max(y, 8) + "px";
+    });
+    addEventListener("keydown", event => {
+        if (event.key !== "`" || event.target instanceof HTMLInputElement) return;
+        enabled = !enabled;
+        overlay.hidden = !enabled;
+        hovered?.classList.remove("rruxwry-hovered");
+        hovered = null;
+    });
+})();"##;
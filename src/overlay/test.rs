@@ -0,0 +1,14 @@
+use super::{MARKER, inject};
+
+#[test]
+fn injection() {
+    let page = inject("<html><body><p>x</p></body></html>").unwrap();
+    assert!(page.starts_with(&format!("<html><body><p>x</p>{MARKER}<style>")));
+    assert!(page.ends_with("</script></body></html>"));
+}
+
+#[test]
+fn idempotence() {
+    let page = inject("<html><body></body></html>").unwrap();
+    assert_eq!(inject(&page), None);
+}
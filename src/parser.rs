@@ -43,7 +43,7 @@ mod lexer {
 
     pub(super) type Tokens<'src> = impl Iterator<Item = Token>;
 
-    pub(super) fn lex<'src>(source: &'src str) -> (usize, PeekableTokens<'src>) {
+    pub(super) fn lex(source: &str) -> (usize, PeekableTokens<'_>) {
         let index = strip_shebang(source).unwrap_or_default();
         let tokens = ra_ap_rustc_lexer::tokenize(&source[index..]).peekable();
         (index, tokens)
@@ -56,6 +56,16 @@ pub(crate) struct Span {
     length: u32,
 }
 
+impl Span {
+    pub(crate) fn start(self) -> usize {
+        self.start as _
+    }
+
+    pub(crate) fn end(self) -> usize {
+        (self.start + self.length) as _
+    }
+}
+
 pub(crate) trait At<'src> {
     type Output;
 
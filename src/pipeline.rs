@@ -0,0 +1,129 @@
+//! Named sequences of runs defined in the configuration files (`rruxwry run`).
+//!
+//! Each step is a separate invocation of rruxwry with the arguments of the step followed by the
+//! paths passed to `run`. The steps are run one after the other and the pipeline stops at the
+//! first one that fails.
+
+use crate::{
+    cli, config,
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info},
+};
+use clap::{ColorChoice, ValueEnum};
+use joinery::JoinableIterator;
+use std::{ffi::OsString, path::PathBuf, process};
+
+#[cfg(test)]
+mod test;
+
+pub(crate) fn run(
+    name: &str,
+    paths: &[PathBuf],
+    arguments: &cli::Arguments,
+) -> crate::error::Result {
+    let pipelines = config::load_pipelines()?;
+    let Some((_, steps)) = pipelines.iter().rfind(|(candidate, _)| candidate == name) else {
+        let mut available: Vec<_> = pipelines.iter().map(|(name, _)| name.clone()).collect();
+        available.sort();
+        available.dedup();
+        return Err(Error::Unknown { unknown: name.to_owned(), available }.into());
+    };
+
+    let forwarded = forwarded(arguments);
+    let amount = steps.len();
+
+    for (index, step) in steps.iter().enumerate() {
+        let step_arguments = step_arguments(step, &forwarded, paths);
+        let command_line = command_line(&step_arguments);
+        let step = index + 1;
+        info(format!("running step {step}/{amount} of the pipeline `{name}`"))
+            .note(format!("`{command_line}`"))
+            .emit();
+
+        let mut command = process::Command::new(std::env::current_exe()?);
+        command.args(step_arguments);
+        // Steps that select a toolchain themselves take precedence.
+        if let Some(toolchain) = &arguments.build_flags.toolchain {
+            command.env("RUSTUP_TOOLCHAIN", toolchain);
+        }
+        let status = command.status()?;
+        if !status.success() {
+            return Err(
+                Error::Failed { pipeline: name.to_owned(), step, amount, command_line }.into()
+            );
+        }
+    }
+
+    let s = if amount == 1 { "" } else { "s" };
+    info(format!("all {amount} step{s} of the pipeline `{name}` succeeded")).emit();
+    Ok(())
+}
+
+/// The flags of the invocation of `run` that also apply to each step.
+fn forwarded(arguments: &cli::Arguments) -> Vec<OsString> {
+    let mut forwarded = Vec::new();
+    if arguments.color != ColorChoice::Auto
+        && let Some(color) = arguments.color.to_possible_value()
+    {
+        forwarded.push(format!("--color={}", color.get_name()).into());
+    }
+    let flags = &arguments.program_flags;
+    for (enabled, flag) in
+        [(flags.no_config, "--no-config"), (flags.dry_run, "-0"), (flags.verbose, "-V")]
+    {
+        if enabled {
+            forwarded.push(flag.into());
+        }
+    }
+    forwarded
+}
+
+/// The arguments of the given step, placing the paths before the verbatim flags if there are any.
+fn step_arguments(step: &[String], forwarded: &[OsString], paths: &[PathBuf]) -> Vec<OsString> {
+    let verbatim = step.iter().position(|argument| argument == "--").unwrap_or(step.len());
+    let (step, verbatim) = step.split_at(verbatim);
+
+    forwarded
+        .iter()
+        .cloned()
+        .chain(step.iter().map(OsString::from))
+        .chain(paths.iter().map(|path| path.as_os_str().to_owned()))
+        .chain(verbatim.iter().map(OsString::from))
+        .collect()
+}
+
+fn command_line(arguments: &[OsString]) -> String {
+    let quote = |argument: &OsString| {
+        let argument = argument.to_string_lossy();
+        shlex::try_quote(&argument).map_or_else(|_| argument.to_string(), Into::into)
+    };
+    ["rruxwry".to_owned()].into_iter().chain(arguments.iter().map(quote)).join_with(' ').to_string()
+}
+
+pub(crate) enum Error {
+    Unknown { unknown: String, available: Vec<String> },
+    Failed { pipeline: String, step: usize, amount: usize, command_line: String },
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::Unknown { unknown, available } => {
+                let diagnostic = error(format!("unknown pipeline `{unknown}`"));
+                match available.is_empty() {
+                    true => diagnostic.note(format!(
+                        "pipelines are defined under `pipelines` in `{}`",
+                        config::FILE_NAME
+                    )),
+                    false => diagnostic.note(format!(
+                        "available pipelines: {}",
+                        available.iter().map(|name| format!("`{name}`")).join_with(", ")
+                    )),
+                }
+            }
+            Self::Failed { pipeline, step, amount, command_line } => {
+                error(format!("step {step}/{amount} of the pipeline `{pipeline}` failed"))
+                    .note(format!("`{command_line}`"))
+            }
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use super::{command_line, step_arguments};
+use std::{ffi::OsString, path::PathBuf};
+
+#[test]
+fn arguments() {
+    let step = ["-T".to_owned(), "--".to_owned(), "-Zunstable-options".to_owned()];
+    let paths = [PathBuf::from("a b.rs"), PathBuf::from("c.rs")];
+    let arguments = step_arguments(&step, &[OsString::from("-0")], &paths);
+    assert_eq!(arguments, ["-0", "-T", "a b.rs", "c.rs", "--", "-Zunstable-options"]);
+    assert_eq!(command_line(&arguments), "rruxwry -0 -T 'a b.rs' c.rs -- -Zunstable-options");
+
+    let step = ["diff".to_owned(), "--new-toolchain".to_owned(), "beta".to_owned()];
+    let arguments = step_arguments(&step, &[], &[PathBuf::from("lib.rs")]);
+    assert_eq!(arguments, ["diff", "--new-toolchain", "beta", "lib.rs"]);
+}
@@ -0,0 +1,435 @@
+//! The build plan, i.e., the crates that are about to be built and how they depend on each other.
+//!
+//! The plan is computed without executing anything, so it can be printed upfront for
+//! sanity-checking complex multi-crate invocations.
+
+use crate::{
+    builder::{self, BuildMode},
+    command::{ExternCrate, Flags},
+    data::{CrateName, CrateNameRef, CrateType, Edition},
+    directive::Directives,
+    module,
+    utility::out_path,
+};
+use joinery::JoinableIterator;
+use rustc_hash::FxHashSet;
+use serde_json::json;
+use std::{
+    env::consts::{DLL_PREFIX, DLL_SUFFIX, EXE_SUFFIX},
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+#[cfg(test)]
+mod test;
+
+pub(crate) struct Node {
+    crate_name: String,
+    path: PathBuf,
+    crate_type: CrateType,
+    role: Role,
+    action: Action,
+    edition: Edition,
+    /// The available revisions and the selected ones.
+    revisions: Option<(Vec<String>, Vec<String>)>,
+    /// The `cfg` specs incl. the ones derived from Cargo-like features.
+    cfgs: Vec<String>,
+    /// The flags passed verbatim incl. the ones from `//@ compile-flags`.
+    flags: Vec<String>,
+    /// The files written by `rust{c,doc}` (relative to the current directory).
+    artifacts: Vec<PathBuf>,
+    /// The name the dependent crate passes to `--extern` if any.
+    extern_name: Option<String>,
+    dependencies: Vec<Node>,
+}
+
+#[derive(Clone, Copy)]
+enum Role {
+    Root,
+    Auxiliary,
+    /// The synthesized crate that re-exports the root crate in cross-crate mode.
+    Wrapper,
+}
+
+impl Role {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Root => "root",
+            Self::Auxiliary => "auxiliary",
+            Self::Wrapper => "wrapper",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Action {
+    Compile,
+    Document,
+    CompileAndDocument,
+}
+
+impl Action {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Compile => "compile",
+            Self::Document => "document",
+            Self::CompileAndDocument => "compile & document",
+        }
+    }
+
+    fn steps(self) -> &'static [&'static str] {
+        match self {
+            Self::Compile => &["compile"],
+            Self::Document => &["document"],
+            Self::CompileAndDocument => &["compile", "document"],
+        }
+    }
+}
+
+/// Compute the plan for building the given crate.
+pub(crate) fn compute(
+    mode: BuildMode,
+    path: &Path,
+    crate_name: CrateNameRef<'_>,
+    crate_type: CrateType,
+    edition: Edition,
+    flags: Flags<'_>,
+) -> crate::error::Result<Node> {
+    let cfgs: Vec<_> = flags
+        .build
+        .cfgs
+        .iter()
+        .cloned()
+        .chain(flags.build.cargo_features.iter().map(|feature| format!("feature=\"{feature}\"")))
+        .collect();
+    let verbatim: Vec<_> = flags.verbatim.arguments.iter().map(|&flag| flag.to_owned()).collect();
+
+    let node =
+        |crate_name: String, path: PathBuf, crate_type, role, action: Action, edition| Node {
+            artifacts: artifacts(&crate_name, crate_type, action, flags),
+            crate_name,
+            path,
+            crate_type,
+            role,
+            action,
+            edition,
+            revisions: None,
+            cfgs: cfgs.clone(),
+            flags: verbatim.clone(),
+            extern_name: None,
+            dependencies: Vec::new(),
+        };
+
+    Ok(match mode {
+        BuildMode::Default => node(
+            crate_name.to_string(),
+            path.to_owned(),
+            crate_type,
+            Role::Root,
+            Action::Document,
+            edition,
+        ),
+        BuildMode::CrossCrate => {
+            let wrapper_name = CrateName::new_unchecked(format!("u_{crate_name}"));
+            let mut wrapper = node(
+                wrapper_name.to_string(),
+                builder::dependent_crate_path(path, wrapper_name.as_ref(), flags)?,
+                CrateType::default(),
+                Role::Wrapper,
+                Action::Document,
+                edition,
+            );
+            wrapper.dependencies.push(Node {
+                extern_name: Some(crate_name.to_string()),
+                ..node(
+                    crate_name.to_string(),
+                    path.to_owned(),
+                    crate_type.to_non_executable(),
+                    Role::Root,
+                    Action::Compile,
+                    edition,
+                )
+            });
+            wrapper
+        }
+        BuildMode::Compiletest { .. } => {
+            let source = std::fs::read_to_string(path)?;
+            let directives = Directives::parse(&source, None);
+
+            let mut available: Vec<_> =
+                directives.revisions.iter().map(ToString::to_string).collect();
+            available.sort_unstable();
+            let selected = flags.build.revisions.clone();
+
+            let revisions: FxHashSet<_> =
+                flags.build.revisions.iter().chain(&flags.build.cfgs).map(String::as_str).collect();
+            let directives = directives.into_instantiated(&revisions);
+
+            let base_path = path.parent().unwrap_or(Path::new("")).join("auxiliary");
+            let action = match directives.build_aux_docs {
+                true => Action::CompileAndDocument,
+                false => Action::Compile,
+            };
+            let auxiliary = Auxiliary {
+                base_path: &base_path,
+                action,
+                verbatim: &verbatim,
+                cfgs: &flags.build.cfgs,
+                node: &node,
+            };
+            let dependencies = directives
+                .dependencies
+                .iter()
+                .map(|dependency| auxiliary.node(dependency, &mut Vec::new()))
+                .collect();
+
+            Node {
+                revisions: (!available.is_empty()).then_some((available, selected)),
+                flags: own(&directives.verbatim_flags.arguments, &verbatim),
+                dependencies,
+                ..node(
+                    crate_name.to_string(),
+                    path.to_owned(),
+                    // FIXME: Respect `//@ compile-flags: --crate-type` once the builder does.
+                    CrateType::default(),
+                    Role::Root,
+                    Action::Document,
+                    directives.edition.unwrap_or_default(),
+                )
+            }
+        }
+    })
+}
+
+/// The context for computing the nodes of auxiliary crates.
+struct Auxiliary<'a> {
+    base_path: &'a Path,
+    action: Action,
+    verbatim: &'a [String],
+    cfgs: &'a [String],
+    node: &'a dyn Fn(String, PathBuf, CrateType, Role, Action, Edition) -> Node,
+}
+
+impl Auxiliary<'_> {
+    /// Compute the node of the given auxiliary crate incl. its own auxiliary crates.
+    ///
+    /// The `stack` contains the dependents. We don't descend into cycles, the builder reports them.
+    fn node(&self, dependency: &ExternCrate<'_>, stack: &mut Vec<PathBuf>) -> Node {
+        // Nested auxiliary crates are resolved relative to the directory of the test, too.
+        let path = builder::auxiliary_path(dependency, self.base_path);
+        let version = match *dependency {
+            ExternCrate::Named { version, .. } => version,
+            ExternCrate::Unnamed { .. } | ExternCrate::ProcMacro { .. } => None,
+        };
+        let crate_name = match (dependency, version) {
+            (ExternCrate::Named { name, .. }, Some(_)) => name.to_string(),
+            _ => match CrateName::adjust_and_parse_file_path(&path) {
+                Ok(crate_name) => crate_name.to_string(),
+                Err(()) => path.display().to_string(),
+            },
+        };
+        let source = std::fs::read_to_string(&path).unwrap_or_default();
+        let directives = Directives::parse(&source, None);
+        let extern_name = match dependency {
+            ExternCrate::Named { name, .. } => Some(name.to_string()),
+            ExternCrate::ProcMacro { .. } => Some(crate_name.clone()),
+            ExternCrate::Unnamed { .. } => None,
+        };
+        let edition = directives.edition.unwrap_or_default();
+        let crate_type = builder::auxiliary_crate_type(
+            dependency,
+            &source,
+            directives.verbatim_flags.as_ref().value_of("--crate-type"),
+            edition,
+            self.cfgs,
+        );
+
+        let dependencies = match stack.contains(&path) {
+            true => Vec::new(),
+            false => {
+                stack.push(path.clone());
+                let dependencies = directives
+                    .dependencies
+                    .iter()
+                    .map(|dependency| self.node(dependency, stack))
+                    .collect();
+                stack.pop();
+                dependencies
+            }
+        };
+
+        let mut flags = own(&directives.verbatim_flags.arguments, self.verbatim);
+        let mut node =
+            (self.node)(crate_name, path, crate_type, Role::Auxiliary, self.action, edition);
+        if let Some(version) = version {
+            flags.extend(builder::version_flags(version));
+            let library = format!("lib{}.rlib", node.crate_name);
+            for artifact in &mut node.artifacts {
+                if artifact.file_name().is_some_and(|name| *name == *library) {
+                    artifact.set_file_name(format!("lib{}-{version}.rlib", node.crate_name));
+                }
+            }
+        }
+
+        Node { flags, extern_name, dependencies, ..node }
+    }
+}
+
+/// The flags from `//@ compile-flags` followed by the ones from the command line.
+fn own(directive_flags: &[&str], verbatim: &[String]) -> Vec<String> {
+    directive_flags.iter().map(|&flag| flag.to_owned()).chain(verbatim.iter().cloned()).collect()
+}
+
+fn artifacts(
+    crate_name: &str,
+    crate_type: CrateType,
+    action: Action,
+    flags: Flags<'_>,
+) -> Vec<PathBuf> {
+    let mut artifacts = Vec::new();
+
+    if let Action::Compile | Action::CompileAndDocument = action {
+        artifacts.push(out_path(flags.build, match crate_type {
+            CrateType::Bin => format!("{crate_name}{EXE_SUFFIX}"),
+            CrateType::Lib => format!("lib{crate_name}.rlib"),
+            CrateType::ProcMacro => format!("{DLL_PREFIX}{crate_name}{DLL_SUFFIX}"),
+        }));
+    }
+
+    if let Action::Document | Action::CompileAndDocument = action
+        && !flags.build.stdout
+    {
+        let root = out_path(flags.build, "doc");
+        artifacts.push(match (flags.build.json, &flags.build.output_format) {
+            (true, _) => root.join(format!("{crate_name}.json")),
+            (false, Some(_)) => root.to_owned(),
+            (false, None) => root.join(crate_name).join("index.html"),
+        });
+    }
+
+    artifacts
+}
+
+impl Node {
+    fn label(&self) -> String {
+        let mut label = format!(
+            "{} {} `{}` ({} `{}`, edition {})",
+            self.role.name(),
+            self.crate_type.to_str(),
+            self.crate_name,
+            self.action.name(),
+            self.path.display(),
+            self.edition.to_str(),
+        );
+        if let Some((available, selected)) = &self.revisions {
+            let list = |revisions: &[String]| match revisions {
+                [] => "none".to_owned(),
+                revisions => revisions
+                    .iter()
+                    .map(|revision| format!("`{revision}`"))
+                    .join_with(", ")
+                    .to_string(),
+            };
+            write!(label, " [revisions: {}; selected: {}]", list(available), list(selected))
+                .unwrap();
+        }
+        label
+    }
+
+    /// The source files the plan consists of, excluding synthesized ones.
+    pub(crate) fn files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        if !matches!(self.role, Role::Wrapper) {
+            files.extend(module::files(&self.path));
+        }
+        for dependency in &self.dependencies {
+            files.extend(dependency.files());
+        }
+        files
+    }
+
+    /// Whether the given file is the root source file of one of the crates of the plan.
+    pub(crate) fn has_root(&self, path: &Path) -> bool {
+        self.path == path || self.dependencies.iter().any(|dependency| dependency.has_root(path))
+    }
+
+    /// Render the plan as an indented tree, dependencies below their dependents.
+    pub(crate) fn render_tree(&self) -> String {
+        fn render(node: &Node, prefix: &str, output: &mut String) {
+            let amount = node.dependencies.len();
+            for (index, dependency) in node.dependencies.iter().enumerate() {
+                let last = index + 1 == amount;
+                let (branch, continuation) =
+                    if last { ("└── ", "    ") } else { ("├── ", "│   ") };
+                writeln!(output, "{prefix}{branch}{}", dependency.label()).unwrap();
+                render(dependency, &format!("{prefix}{continuation}"), output);
+            }
+        }
+
+        let mut output = format!("{}\n", self.label());
+        render(self, "", &mut output);
+        output
+    }
+
+    /// Render the plan as a graph in the DOT language.
+    pub(crate) fn render_dot(&self) -> String {
+        fn render(node: &Node, id: &mut usize, output: &mut String) -> usize {
+            let this = *id;
+            *id += 1;
+            let label = node.label().replace('\\', "\\\\").replace('"', "\\\"");
+            let shape = match node.role {
+                Role::Root => "box",
+                Role::Auxiliary | Role::Wrapper => "ellipse",
+            };
+            writeln!(output, "    n{this} [label=\"{label}\", shape={shape}];").unwrap();
+            for dependency in &node.dependencies {
+                let other = render(dependency, id, output);
+                writeln!(output, "    n{this} -> n{other};").unwrap();
+            }
+            this
+        }
+
+        let mut output = String::from("digraph plan {\n");
+        render(self, &mut 0, &mut output);
+        output.push_str("}\n");
+        output
+    }
+
+    /// Render the plan as JSON: a list of crates and a list of edges pointing from dependents to
+    /// their dependencies by index.
+    pub(crate) fn render_json(&self) -> String {
+        fn render(
+            node: &Node,
+            crates: &mut Vec<serde_json::Value>,
+            edges: &mut Vec<serde_json::Value>,
+        ) -> usize {
+            let this = crates.len();
+            crates.push(json!({
+                "name": node.crate_name,
+                "path": node.path,
+                "files": module::files(&node.path),
+                "crate_type": node.crate_type.to_str(),
+                "role": node.role.name(),
+                "actions": node.action.steps(),
+                "edition": node.edition.to_str(),
+                "revisions": node.revisions.as_ref().map(|(available, selected)| json!({
+                    "available": available,
+                    "selected": selected,
+                })),
+                "cfgs": node.cfgs,
+                "flags": node.flags,
+                "artifacts": node.artifacts,
+            }));
+            for dependency in &node.dependencies {
+                let other = render(dependency, crates, edges);
+                edges.push(json!({ "from": this, "to": other, "extern": dependency.extern_name }));
+            }
+            this
+        }
+
+        let (mut crates, mut edges) = (Vec::new(), Vec::new());
+        render(self, &mut crates, &mut edges);
+        let plan = json!({ "crates": crates, "edges": edges });
+        serde_json::to_string_pretty(&plan).unwrap() + "\n"
+    }
+}
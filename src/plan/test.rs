@@ -0,0 +1,106 @@
+use super::{Action, Node, Role};
+use crate::data::{CrateType, Edition};
+use std::path::Path;
+
+fn plan() -> Node {
+    let auxiliary = |crate_name: &str| Node {
+        crate_name: crate_name.to_owned(),
+        path: format!("auxiliary/{crate_name}.rs").into(),
+        crate_type: CrateType::Lib,
+        role: Role::Auxiliary,
+        action: Action::Compile,
+        edition: Edition::Edition2015,
+        revisions: None,
+        cfgs: Vec::new(),
+        flags: vec!["-Cdebuginfo=0".to_owned()],
+        artifacts: vec![format!("lib{crate_name}.rlib").into()],
+        extern_name: Some(crate_name.to_owned()),
+        dependencies: Vec::new(),
+    };
+
+    Node {
+        crate_name: "root".to_owned(),
+        path: "root.rs".into(),
+        crate_type: CrateType::Bin,
+        role: Role::Root,
+        action: Action::Document,
+        edition: Edition::Edition2021,
+        revisions: Some((vec!["a".to_owned(), "b".to_owned()], vec!["b".to_owned()])),
+        cfgs: vec!["b".to_owned()],
+        flags: Vec::new(),
+        artifacts: vec!["doc/root/index.html".into()],
+        extern_name: None,
+        dependencies: vec![auxiliary("first")],
+    }
+}
+
+#[test]
+fn tree() {
+    assert_eq!(
+        plan().render_tree(),
+        "\
+root bin `root` (document `root.rs`, edition 2021) [revisions: `a`, `b`; selected: `b`]
+└── auxiliary lib `first` (compile `auxiliary/first.rs`, edition 2015)
+"
+    );
+}
+
+#[test]
+fn dot() {
+    assert_eq!(
+        plan().render_dot(),
+        r#"digraph plan {
+    n0 [label="root bin `root` (document `root.rs`, edition 2021) [revisions: `a`, `b`; selected: `b`]", shape=box];
+    n1 [label="auxiliary lib `first` (compile `auxiliary/first.rs`, edition 2015)", shape=ellipse];
+    n0 -> n1;
+}
+"#
+    );
+}
+
+#[test]
+fn json() {
+    let plan: serde_json::Value = serde_json::from_str(&plan().render_json()).unwrap();
+    assert_eq!(
+        plan,
+        serde_json::json!({
+            "crates": [
+                {
+                    "name": "root",
+                    "path": "root.rs",
+                    "files": ["root.rs"],
+                    "crate_type": "bin",
+                    "role": "root",
+                    "actions": ["document"],
+                    "edition": "2021",
+                    "revisions": { "available": ["a", "b"], "selected": ["b"] },
+                    "cfgs": ["b"],
+                    "flags": [],
+                    "artifacts": ["doc/root/index.html"],
+                },
+                {
+                    "name": "first",
+                    "path": "auxiliary/first.rs",
+                    "files": ["auxiliary/first.rs"],
+                    "crate_type": "lib",
+                    "role": "auxiliary",
+                    "actions": ["compile"],
+                    "edition": "2015",
+                    "revisions": null,
+                    "cfgs": [],
+                    "flags": ["-Cdebuginfo=0"],
+                    "artifacts": ["libfirst.rlib"],
+                },
+            ],
+            "edges": [{ "from": 0, "to": 1, "extern": "first" }],
+        })
+    );
+}
+
+#[test]
+fn roots() {
+    let plan = plan();
+    assert!(plan.has_root(Path::new("root.rs")));
+    assert!(plan.has_root(Path::new("auxiliary/first.rs")));
+    assert!(!plan.has_root(Path::new("module.rs")));
+}
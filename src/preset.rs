@@ -0,0 +1,109 @@
+//! Named sets of flags (`--preset`).
+//!
+//! Presets get expanded before the command line is parsed. The `--preset` flag itself is kept
+//! and the flags of the preset are inserted right after it, so flags passed later still take
+//! precedence (where clap allows overriding them).
+
+use crate::diagnostic::{Diagnostic, IntoDiagnostic, error};
+use joinery::JoinableIterator;
+use std::ffi::{OsStr, OsString};
+
+#[cfg(test)]
+mod test;
+
+/// The built-in presets matching common workflows.
+const BUILTINS: &[(&str, &[&str])] = &[
+    // Like tests in `tests/rustdoc/`.
+    ("rustdoc-test", &["-T", "-Q"]),
+    // Like tests in `tests/rustdoc-json/`.
+    ("rustdoc-json-test", &["-T", "-Q", "-j"]),
+    ("xcrate", &["-X", "-P", "-H", "-D"]),
+];
+
+/// Expand all occurrences of `--preset` in the arguments up to `--`.
+///
+/// User-defined presets take precedence over built-in ones and later ones over earlier ones.
+pub(crate) fn expand(
+    arguments: impl IntoIterator<Item = OsString>,
+    presets: &[(String, Vec<String>)],
+) -> Result<Vec<OsString>, Error> {
+    let mut arguments = arguments.into_iter();
+    let mut expanded = Vec::new();
+
+    while let Some(argument) = arguments.next() {
+        if argument == "--" {
+            expanded.push(argument);
+            expanded.extend(arguments);
+            break;
+        }
+
+        let rest = argument.to_str().and_then(|argument| argument.strip_prefix("--preset"));
+        let name = match rest.map(|rest| rest.strip_prefix('=').ok_or(rest)) {
+            Some(Err("")) => {
+                expanded.push(argument);
+                // A missing name gets reported by clap.
+                let Some(name) = arguments.next() else { break };
+                expanded.push(name.clone());
+                name
+            }
+            Some(Ok(name)) => {
+                let name = OsString::from(name);
+                expanded.push(argument);
+                name
+            }
+            _ => {
+                expanded.push(argument);
+                continue;
+            }
+        };
+
+        expanded.extend(lookup(&name, presets)?.into_iter().map(OsString::from));
+    }
+
+    Ok(expanded)
+}
+
+fn lookup(name: &OsStr, presets: &[(String, Vec<String>)]) -> Result<Vec<String>, Error> {
+    let flags = match presets.iter().rfind(|(candidate, _)| name == candidate.as_str()) {
+        Some((_, flags)) => flags.clone(),
+        None => match BUILTINS.iter().find(|&&(candidate, _)| name == candidate) {
+            Some((_, flags)) => flags.iter().map(|&flag| flag.to_owned()).collect(),
+            None => {
+                let mut available: Vec<_> = BUILTINS
+                    .iter()
+                    .map(|&(name, _)| name.to_owned())
+                    .chain(presets.iter().map(|(name, _)| name.clone()))
+                    .collect();
+                available.sort();
+                available.dedup();
+                let unknown = name.to_string_lossy().into_owned();
+                return Err(Error::Unknown { unknown, available });
+            }
+        },
+    };
+
+    if flags.iter().any(|flag| flag.starts_with("--preset")) {
+        return Err(Error::Nested(name.to_string_lossy().into_owned()));
+    }
+
+    Ok(flags)
+}
+
+pub(crate) enum Error {
+    Unknown { unknown: String, available: Vec<String> },
+    Nested(String),
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::Unknown { unknown, available } => {
+                let available = available.iter().map(|name| format!("`{name}`")).join_with(", ");
+                error(format!("unknown preset `{unknown}`"))
+                    .note(format!("available presets: {available}"))
+            }
+            Self::Nested(name) => error(format!("the preset `{name}` refers to another preset"))
+                .note("presets can't be nested"),
+        }
+    }
+}
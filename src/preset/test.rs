@@ -0,0 +1,63 @@
+use super::{Error, expand};
+use std::ffi::OsString;
+
+fn arguments(arguments: &[&str]) -> Vec<OsString> {
+    arguments.iter().map(OsString::from).collect()
+}
+
+#[test]
+fn builtin_presets() {
+    let expanded = expand(arguments(&["rruxwry", "--preset", "xcrate", "lib.rs"]), &[]);
+    assert_eq!(
+        expanded.ok().unwrap(),
+        arguments(&["rruxwry", "--preset", "xcrate", "-X", "-P", "-H", "-D", "lib.rs"])
+    );
+    let expanded = expand(arguments(&["rruxwry", "--preset=rustdoc-json-test", "lib.rs"]), &[]);
+    assert_eq!(
+        expanded.ok().unwrap(),
+        arguments(&["rruxwry", "--preset=rustdoc-json-test", "-T", "-Q", "-j", "lib.rs"])
+    );
+}
+
+#[test]
+fn user_presets() {
+    let presets = [
+        ("xcrate".to_owned(), vec!["-X".to_owned()]),
+        ("ui".to_owned(), vec!["-T".to_owned()]),
+        ("ui".to_owned(), vec!["-T".to_owned(), "--rev".to_owned(), "next".to_owned()]),
+    ];
+    let expanded = expand(
+        arguments(&["rruxwry", "--preset=xcrate", "--preset", "ui", "lib.rs", "--", "--preset=ui"]),
+        &presets,
+    );
+    assert_eq!(
+        expanded.ok().unwrap(),
+        arguments(&[
+            "rruxwry",
+            "--preset=xcrate",
+            "-X",
+            "--preset",
+            "ui",
+            "-T",
+            "--rev",
+            "next",
+            "lib.rs",
+            "--",
+            "--preset=ui"
+        ])
+    );
+}
+
+#[test]
+fn invalid_presets() {
+    let presets = [("outer".to_owned(), vec!["--preset=xcrate".to_owned()])];
+    assert!(matches!(
+        expand(arguments(&["rruxwry", "--preset", "outer"]), &presets),
+        Err(Error::Nested(name)) if name == "outer"
+    ));
+    assert!(matches!(
+        expand(arguments(&["rruxwry", "--preset", "unknown"]), &presets),
+        Err(Error::Unknown { unknown, available })
+            if unknown == "unknown" && available == ["outer", "rustdoc-json-test", "rustdoc-test", "xcrate"]
+    ));
+}
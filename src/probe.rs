@@ -0,0 +1,82 @@
+//! Evaluating ad hoc XPath expressions against the generated docs (`--query-expr`).
+//!
+//! Unlike `htmldocck` queries, these don't assert anything and don't require a compiletest
+//! test. They merely print what they match, so the output can be probed before annotating the
+//! source file.
+
+use crate::{
+    diagnostic::{info, warning},
+    utility::files,
+    xpath::{Document, Target, XPath},
+};
+use std::{io, path::Path};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Clone)]
+pub(crate) struct Expr {
+    source: String,
+    xpath: XPath,
+}
+
+impl Expr {
+    pub(crate) fn parse_cli_style(source: &str) -> Result<Self, String> {
+        let xpath = XPath::parse(source).map_err(|error| error.to_string())?;
+        Ok(Self { source: source.to_owned(), xpath })
+    }
+}
+
+/// Print the nodes matched by the given expressions in the pages of the given crate in `root`.
+pub(crate) fn run(root: &Path, crate_name: &str, exprs: &[Expr]) -> io::Result<()> {
+    let mut pages = Vec::new();
+    for page in files(&root.join(crate_name), &["html"])? {
+        let html = std::fs::read_to_string(&page)?;
+        let page = page.strip_prefix(root).unwrap_or(&page).display().to_string();
+        pages.push((page, Document::parse(&html)));
+    }
+
+    for expr in exprs {
+        let mut amount = 0;
+        let mut matching_pages = 0;
+
+        for (page, document) in &pages {
+            let matches = matches(document, &expr.xpath);
+            if matches.is_empty() {
+                continue;
+            }
+
+            println!("`{page}`");
+            for match_ in &matches {
+                println!("    {}", match_.replace('\n', "\n    "));
+            }
+            amount += matches.len();
+            matching_pages += 1;
+        }
+
+        let source = &expr.source;
+        match amount {
+            0 => warning(format!("the XPath `{source}` didn't match anything")).emit(),
+            _ => {
+                let s = if amount == 1 { "" } else { "s" };
+                let pages_s = if matching_pages == 1 { "" } else { "s" };
+                info(format!(
+                    "the XPath `{source}` matched {amount} node{s} on {matching_pages} page{pages_s}"
+                ))
+                .emit();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The selected elements serialized as HTML or the selected attribute values or texts.
+fn matches(document: &Document, xpath: &XPath) -> Vec<String> {
+    match xpath.target {
+        Target::Elements => {
+            document.select(xpath).into_iter().map(|node| document.serialize(node)).collect()
+        }
+        Target::Attribute(_) | Target::Text => document.values(xpath),
+    }
+}
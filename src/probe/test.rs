@@ -0,0 +1,21 @@
+use super::{Expr, matches};
+use crate::xpath::Document;
+
+fn probe(html: &str, source: &str) -> Vec<String> {
+    let expr = Expr::parse_cli_style(source).unwrap();
+    matches(&Document::parse(html), &expr.xpath)
+}
+
+#[test]
+fn elements_attributes_and_texts() {
+    let html = r#"<div id="main"><h1>Struct <span class="struct">S</span></h1><a href="struct.S.html">S</a></div>"#;
+    assert_eq!(probe(html, "//h1/span"), [r#"<span class="struct">S</span>"#]);
+    assert_eq!(probe(html, "//a/@href"), ["struct.S.html"]);
+    assert_eq!(probe(html, "//h1/text()"), ["Struct S"]);
+    assert!(probe(html, "//p").is_empty());
+}
+
+#[test]
+fn invalid() {
+    assert!(Expr::parse_cli_style("h1").is_err());
+}
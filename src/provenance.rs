@@ -0,0 +1,124 @@
+//! Stamping the output directory with the provenance of its contents.
+//!
+//! This allows tracing stale artifacts back to the invocation that produced them.
+
+use crate::{
+    builder::BuildMode,
+    command::Flags,
+    data::{CrateNameRef, CrateType, Edition},
+    module, toolchain,
+    utility::files,
+};
+use serde_json::{Map, Value, json};
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
+    path::Path,
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The name of the file stored in the documentation root.
+pub(crate) const FILE_NAME: &str = "run.json";
+
+/// Write the provenance of the docs of the given crate to the documentation root `root`.
+pub(crate) fn stamp(
+    root: &Path,
+    path: &Path,
+    crate_name: CrateNameRef<'_>,
+    crate_type: CrateType,
+    edition: Edition,
+    build_mode: BuildMode,
+    flags: Flags<'_>,
+) -> io::Result<()> {
+    let build = flags.build;
+
+    let mode = match build_mode {
+        BuildMode::Default => "default",
+        BuildMode::CrossCrate => "cross-crate",
+        BuildMode::Compiletest { .. } => "compiletest",
+    };
+    let environment: Map<_, _> = flags
+        .verbatim
+        .environment
+        .iter()
+        .map(|&(key, value)| (key.to_owned(), value.map_or(Value::Null, Into::into)))
+        .collect();
+
+    let version = toolchain::probe(build, flags.program);
+
+    let mut inputs = module::files(path);
+    // The auxiliary crates of compiletest tests reside in a sibling directory.
+    if let BuildMode::Compiletest { .. } = build_mode
+        && let Some(auxiliary) = path.parent().map(|parent| parent.join("auxiliary"))
+        && auxiliary.is_dir()
+    {
+        inputs.extend(files(&auxiliary, &["rs"])?);
+    }
+    let inputs = inputs
+        .iter()
+        .map(|input| Ok((input.display().to_string(), hash(input)?.into())))
+        .collect::<io::Result<Map<_, _>>>()?;
+
+    let run = json!({
+        "id": run_id(),
+        "rruxwry": env!("CARGO_PKG_VERSION"),
+        "arguments": std::env::args().skip(1).collect::<Vec<_>>(),
+        "configuration": {
+            "mode": mode,
+            "presets": flags.program.preset,
+            "crate_name": crate_name.as_str(),
+            "crate_type": crate_type.to_str(),
+            "edition": edition.to_str(),
+            "cfgs": build.cfgs,
+            "revisions": build.revisions,
+            "cargo_features": build.cargo_features,
+            "rustc_features": build.rustc_features,
+            "json": build.json,
+            "output_format": build.output_format,
+            "private": build.private,
+            "hidden": build.hidden,
+            "layout": build.layout,
+            "link_to_definition": build.link_to_definition,
+            "normalize": build.normalize,
+            "reproducible": build.reproducible,
+            "crate_version": build.crate_version,
+            "dep_crate_versions": build
+                .dep_crate_versions
+                .iter()
+                .map(|(name, version)| (name.to_string(), version.as_str().into()))
+                .collect::<Map<_, _>>(),
+            "theme": build.theme,
+            "cap_lints": build.cap_lints,
+            "cap_dep_lints": build.cap_dep_lints,
+            "config_lints": build.config_lints,
+            "lints": build.lints,
+            "dep_lints": build.dep_lints,
+            "verbatim_flags": flags.verbatim.arguments,
+            "environment": environment,
+        },
+        "toolchain": {
+            "name": build.toolchain,
+            "version": version.as_ref().map(ToString::to_string),
+            "commit_hash": version.and_then(|version| version.commit_hash),
+            "container_image": flags.program.in_container,
+            "ssh_host": flags.program.ssh,
+        },
+        "inputs": inputs,
+    });
+
+    std::fs::create_dir_all(root)?;
+    std::fs::write(root.join(FILE_NAME), serde_json::to_string_pretty(&run)? + "\n")
+}
+
+/// An identifier that's unique enough to tell runs apart.
+pub(crate) fn run_id() -> String {
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!("{:x}-{:x}", time.as_millis(), process::id())
+}
+
+fn hash(path: &Path) -> io::Result<String> {
+    let mut hasher = DefaultHasher::new();
+    std::fs::read(path)?.hash(&mut hasher);
+    Ok(format!("{:016x}", hasher.finish()))
+}
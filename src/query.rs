@@ -0,0 +1,538 @@
+//! The evaluator of `htmldocck` and `jsondocck` queries.
+//!
+//! The queries themselves are parsed in [`crate::directive`].
+
+use crate::{
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info},
+    jsonpath::JsonPath,
+    utility::Str,
+    xpath::{Document, Target, XPath},
+};
+use joinery::JoinableIterator;
+use regex::Regex;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde_json::Value;
+use std::path::Path;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Clone)]
+pub(crate) struct Query {
+    /// The verbatim source of the query for use in diagnostics.
+    pub(crate) source: String,
+    pub(crate) negated: bool,
+    pub(crate) kind: QueryKind,
+    /// The labels given via `//@ tags` for running a subset of the queries.
+    pub(crate) tags: Vec<String>,
+}
+
+#[derive(Clone)]
+pub(crate) enum QueryKind {
+    /// Check that the directory at `path` contains exactly the given `entries`.
+    Files { path: String, entries: Vec<String> },
+    /// Check that the file at `path` exists, that the XPath selects anything in it or that the
+    /// text of one of the selected nodes contains `text` (modulo whitespace).
+    Has { path: String, xpath: Option<XPath>, text: Option<String> },
+    /// Check that the file at `path` contains `text` (modulo whitespace).
+    HasRaw { path: String, text: String },
+    /// Check that the text of one of the nodes selected by the XPath matches the pattern.
+    Matches { path: String, xpath: XPath, pattern: Regex },
+    /// Check that the contents of the file at `path` match the pattern.
+    MatchesRaw { path: String, pattern: Regex },
+    /// Check that the XPath selects exactly `count` nodes (whose text contains `text` if given).
+    Count { path: String, xpath: XPath, text: Option<String>, count: usize },
+    /// Check that the single node selected by the XPath is identical to the snapshot `name`.
+    Snapshot { name: String, path: String, xpath: XPath },
+    /// Check that the JSONPath selects anything or a value equal to `value` if given.
+    JsonHas { path: JsonPath, value: Option<Operand> },
+    /// Check that the JSONPath selects exactly one value which is equal to `value`.
+    JsonIs { path: JsonPath, value: Operand },
+    /// Check that the JSONPath selects exactly `count` values.
+    JsonCount { path: JsonPath, count: usize },
+    /// Bind the single value selected by the JSONPath to the variable `name`.
+    JsonSet { name: String, path: JsonPath },
+}
+
+impl QueryKind {
+    /// The path of the file (relative to the documentation root) the query applies to if any.
+    ///
+    /// `jsondocck` queries always apply to the JSON output of the root crate.
+    pub(crate) fn path_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Self::Files { path, .. }
+            | Self::Has { path, .. }
+            | Self::HasRaw { path, .. }
+            | Self::Matches { path, .. }
+            | Self::MatchesRaw { path, .. }
+            | Self::Count { path, .. }
+            | Self::Snapshot { path, .. } => Some(path),
+            Self::JsonHas { .. }
+            | Self::JsonIs { .. }
+            | Self::JsonCount { .. }
+            | Self::JsonSet { .. } => None,
+        }
+    }
+}
+
+/// The value a `jsondocck` query compares against.
+#[derive(Clone)]
+pub(crate) enum Operand {
+    Literal(Value),
+    /// A variable bound by a preceding `set` query.
+    Variable(String),
+}
+
+impl Operand {
+    pub(crate) fn parse(source: &str) -> Result<Self, serde_json::Error> {
+        if let Some(name) = source.strip_prefix('$')
+            && !name.is_empty()
+            && name.chars().all(|char| char.is_alphanumeric() || char == '_')
+        {
+            return Ok(Self::Variable(name.to_owned()));
+        }
+        serde_json::from_str(source).map(Self::Literal)
+    }
+}
+
+/// The environment the queries get evaluated in.
+pub(crate) struct Context<'a> {
+    root: &'a Path,
+    /// The test file which determines the location of the snapshots.
+    test: &'a Path,
+    /// Whether to update the snapshots instead of comparing against them.
+    bless: bool,
+    /// The name of the root crate whose JSON output the `jsondocck` queries apply to.
+    crate_name: &'a str,
+    /// The pages parsed so far by path relative to the root.
+    documents: FxHashMap<String, Document>,
+    json: Option<Value>,
+    /// The variables bound by `set` queries.
+    variables: FxHashMap<String, Value>,
+}
+
+impl<'a> Context<'a> {
+    pub(crate) fn new(root: &'a Path, test: &'a Path, crate_name: &'a str, bless: bool) -> Self {
+        Self {
+            root,
+            test,
+            bless,
+            crate_name,
+            documents: FxHashMap::default(),
+            json: None,
+            variables: FxHashMap::default(),
+        }
+    }
+
+    fn read(&self, path: &str) -> Result<String, Str> {
+        std::fs::read_to_string(self.root.join(path))
+            .map_err(|error| format!("failed to read `{path}`: {error}").into())
+    }
+
+    fn document(&mut self, path: &str) -> Result<&Document, Str> {
+        if !self.documents.contains_key(path) {
+            let document = Document::parse(&self.read(path)?);
+            self.documents.insert(path.to_owned(), document);
+        }
+        Ok(&self.documents[path])
+    }
+
+    fn json(&mut self) -> Result<&Value, Str> {
+        if self.json.is_none() {
+            let path = format!("{}.json", self.crate_name);
+            let json = serde_json::from_str(&self.read(&path)?)
+                .map_err(|error| format!("failed to parse `{path}`: {error}"))?;
+            self.json = Some(json);
+        }
+        Ok(self.json.as_ref().unwrap())
+    }
+
+    fn resolve(&self, operand: &Operand) -> Result<Value, Str> {
+        match operand {
+            Operand::Literal(value) => Ok(value.clone()),
+            Operand::Variable(name) => self
+                .variables
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("unknown variable `${name}`").into()),
+        }
+    }
+}
+
+/// Evaluate the given queries against the documentation found in the root of the context.
+pub(crate) fn evaluate(queries: &[Query], context: &mut Context<'_>) -> Result<(), Error> {
+    let failures: Vec<_> = queries
+        .iter()
+        .filter_map(|query| {
+            let reason = query.check(context).err()?;
+            Some(Failure { query, reason })
+        })
+        .map(|failure| format!("`{}`: {}", failure.query.source, failure.reason))
+        .collect();
+
+    if !failures.is_empty() {
+        return Err(Error { failures, total: queries.len() });
+    }
+
+    Ok(())
+}
+
+/// Keep only the queries carrying any of the given tags (`--only-assertions`).
+///
+/// Also returns the given tags that no query carries.
+pub(crate) fn select<'t>(queries: &[Query], tags: &'t [String]) -> (Vec<Query>, Vec<&'t str>) {
+    let selected =
+        queries.iter().filter(|query| query.tags.iter().any(|tag| tags.contains(tag))).cloned();
+    let unknown = tags
+        .iter()
+        .filter(|&tag| queries.iter().all(|query| !query.tags.contains(tag)))
+        .map(String::as_str);
+    (selected.collect(), unknown.collect())
+}
+
+/// The queries of a revision alongside their results.
+pub(crate) type Results = Vec<(Query, Result<(), Str>)>;
+
+/// Tabulate the results of the queries of each revision, one row per query.
+///
+/// Fails if any query failed in any revision but the table is still worth printing in that case.
+pub(crate) fn tabulate(revisions: &[(String, Results)]) -> (String, Result<(), Error>) {
+    let mut rows: Vec<(&str, Vec<Status>)> = Vec::new();
+    let mut failures = Vec::new();
+    let mut total = 0;
+
+    for (index, (revision, results)) in revisions.iter().enumerate() {
+        for (query, result) in results {
+            let row = match rows.iter().position(|(source, _)| *source == query.source) {
+                Some(row) => row,
+                None => {
+                    rows.push((&query.source, vec![Status::Inactive; revisions.len()]));
+                    rows.len() - 1
+                }
+            };
+            total += 1;
+            rows[row].1[index] = match result {
+                Ok(()) => Status::Passed,
+                Err(reason) => {
+                    failures.push(format!("[{revision}] `{}`: {reason}", query.source));
+                    Status::Failed
+                }
+            };
+        }
+    }
+
+    let names: Vec<_> = revisions.iter().map(|(revision, _)| revision.as_str()).collect();
+    let table = render_table(&names, &rows);
+
+    match failures.is_empty() {
+        true => (table, Ok(())),
+        false => (table, Err(Error { failures, total })),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Status {
+    Passed,
+    Failed,
+    /// The query doesn't apply to the revision.
+    Inactive,
+}
+
+impl Status {
+    fn to_str(self) -> &'static str {
+        match self {
+            Self::Passed => "ok",
+            Self::Failed => "FAILED",
+            Self::Inactive => "-",
+        }
+    }
+}
+
+fn render_table(revisions: &[&str], rows: &[(&str, Vec<Status>)]) -> String {
+    let widths: Vec<_> = revisions
+        .iter()
+        .map(|revision| revision.len().max(Status::Failed.to_str().len()))
+        .collect();
+    let width = rows.iter().map(|(source, _)| source.chars().count()).max().unwrap_or_default();
+
+    let mut table = format!("{:width$}", "");
+    for (revision, width) in revisions.iter().zip(&widths) {
+        table += &format!("  {revision:width$}");
+    }
+    table = table.trim_end().to_owned() + "\n";
+
+    for (source, statuses) in rows {
+        let mut line = format!("{source:width$}");
+        for (status, width) in statuses.iter().zip(&widths) {
+            line += &format!("  {:width$}", status.to_str());
+        }
+        table += line.trim_end();
+        table.push('\n');
+    }
+
+    table
+}
+
+impl Query {
+    /// Check the query against the documentation respecting its negation.
+    ///
+    /// Only a query that didn't match gets inverted by the negation, not one that couldn't be
+    /// evaluated in the first place, e.g., due to a missing file.
+    pub(crate) fn check(&self, context: &mut Context<'_>) -> Result<(), Str> {
+        match (self.kind.evaluate(context)?, self.negated) {
+            (Ok(()), false) | (Err(_), true) => Ok(()),
+            (Ok(()), true) => Err("the negated query matched".into()),
+            (Err(reason), false) => Err(reason),
+        }
+    }
+}
+
+/// Whether a query matched and if not, why not.
+type Outcome = Result<(), Str>;
+
+impl QueryKind {
+    /// Evaluate the query, failing if it can't be evaluated at all.
+    fn evaluate(&self, context: &mut Context<'_>) -> Result<Outcome, Str> {
+        Ok(match self {
+            Self::Files { path, entries } => {
+                let actual: FxHashSet<String> = std::fs::read_dir(context.root.join(path))
+                    .map_err(|error| format!("failed to read directory `{path}`: {error}"))?
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                    .collect();
+                let expected: FxHashSet<String> = entries.iter().cloned().collect();
+
+                let mut missing: Vec<_> = expected.difference(&actual).collect();
+                let mut unexpected: Vec<_> = actual.difference(&expected).collect();
+
+                if missing.is_empty() && unexpected.is_empty() {
+                    return Ok(Ok(()));
+                }
+
+                missing.sort();
+                unexpected.sort();
+
+                let render = |entries: Vec<&String>| {
+                    entries
+                        .into_iter()
+                        .map(|entry| format!("`{entry}`"))
+                        .join_with(", ")
+                        .to_string()
+                };
+
+                let mut reason = String::new();
+                if !missing.is_empty() {
+                    reason += &format!("missing {}", render(missing));
+                }
+                if !unexpected.is_empty() {
+                    if !reason.is_empty() {
+                        reason += "; ";
+                    }
+                    reason += &format!("unexpected {}", render(unexpected));
+                }
+
+                Err(reason.into())
+            }
+            Self::Has { path, xpath: None, .. } => match context.root.join(path).exists() {
+                true => Ok(()),
+                false => Err(format!("`{path}` doesn't exist").into()),
+            },
+            Self::Has { path, xpath: Some(xpath), text } => {
+                let values = context.document(path)?.values(xpath);
+                let Some(text) = text else {
+                    return Ok(match values.is_empty() {
+                        true => Err("the XPath doesn't select anything".into()),
+                        false => Ok(()),
+                    });
+                };
+                let text = normalize_whitespace(text);
+                match values.iter().any(|value| normalize_whitespace(value).contains(&text)) {
+                    true => Ok(()),
+                    false => Err(none_of(values.len(), &format!("contains `{text}`"))),
+                }
+            }
+            Self::HasRaw { path, text } => {
+                let text = normalize_whitespace(text);
+                match normalize_whitespace(&context.read(path)?).contains(&text) {
+                    true => Ok(()),
+                    false => Err(format!("`{path}` doesn't contain `{text}`").into()),
+                }
+            }
+            Self::Matches { path, xpath, pattern } => {
+                let values = context.document(path)?.values(xpath);
+                match values.iter().any(|value| pattern.is_match(value)) {
+                    true => Ok(()),
+                    false => Err(none_of(values.len(), "matches the pattern")),
+                }
+            }
+            Self::MatchesRaw { path, pattern } => match pattern.is_match(&context.read(path)?) {
+                true => Ok(()),
+                false => Err(format!("`{path}` doesn't match the pattern").into()),
+            },
+            Self::Count { path, xpath, text, count } => {
+                let values = context.document(path)?.values(xpath);
+                let actual = match text {
+                    Some(text) => {
+                        let text = normalize_whitespace(text);
+                        values
+                            .iter()
+                            .filter(|value| normalize_whitespace(value).contains(&text))
+                            .count()
+                    }
+                    None => values.len(),
+                };
+                match actual == *count {
+                    true => Ok(()),
+                    false => Err(format!("expected {count} matches but found {actual}").into()),
+                }
+            }
+            Self::Snapshot { name, path, xpath } => {
+                let (test, bless) = (context.test, context.bless);
+                let document = context.document(path)?;
+                let nodes = document.select(xpath);
+                let [node] = nodes[..] else {
+                    let amount = nodes.len();
+                    return Ok(Err(format!(
+                        "the XPath has to select exactly one node but it selected {amount}"
+                    )
+                    .into()));
+                };
+                let actual = match &xpath.target {
+                    Target::Elements => document.serialize(node),
+                    Target::Attribute(_) | Target::Text => {
+                        document.values(xpath).concat().trim().to_owned()
+                    }
+                };
+
+                // Like `htmldocck`, we store the snapshots next to the test file.
+                let stem = test.file_stem().unwrap_or_default().to_string_lossy();
+                let snapshot = test.with_file_name(format!("{stem}.{name}.html"));
+
+                if bless {
+                    std::fs::write(&snapshot, &actual).map_err(|error| {
+                        format!("failed to write `{}`: {error}", snapshot.display())
+                    })?;
+                    info(format!("updated the snapshot `{}`", snapshot.display())).emit();
+                    return Ok(Ok(()));
+                }
+
+                let display = snapshot.display();
+                let Ok(expected) = std::fs::read_to_string(&snapshot) else {
+                    return Err(format!(
+                        "missing snapshot `{display}` (rerun with `--bless` to create it)"
+                    )
+                    .into());
+                };
+                match expected.trim() == actual.trim() {
+                    true => Ok(()),
+                    false => Err(format!("the node differs from the snapshot `{display}`").into()),
+                }
+            }
+            Self::JsonHas { path, value } => {
+                let expected = value.as_ref().map(|value| context.resolve(value)).transpose()?;
+                let selected = path.select(context.json()?);
+                match expected {
+                    _ if selected.is_empty() => Err("the path doesn't select anything".into()),
+                    Some(expected) if !selected.contains(&&expected) => Err(format!(
+                        "no selected value equals `{expected}` (out of {})",
+                        selected.len()
+                    )
+                    .into()),
+                    _ => Ok(()),
+                }
+            }
+            Self::JsonIs { path, value } => {
+                let expected = context.resolve(value)?;
+                match path.select(context.json()?)[..] {
+                    [actual] if *actual == expected => Ok(()),
+                    [actual] => Err(format!("expected `{expected}` but found `{actual}`").into()),
+                    ref selected => Err(exactly_one(selected.len())),
+                }
+            }
+            Self::JsonCount { path, count } => {
+                let actual = path.select(context.json()?).len();
+                match actual == *count {
+                    true => Ok(()),
+                    false => Err(format!("expected {count} matches but found {actual}").into()),
+                }
+            }
+            Self::JsonSet { name, path } => {
+                let value = match path.select(context.json()?)[..] {
+                    [value] => value.clone(),
+                    ref selected => return Ok(Err(exactly_one(selected.len()))),
+                };
+                context.variables.insert(name.clone(), value);
+                Ok(())
+            }
+        })
+    }
+}
+
+fn exactly_one(amount: usize) -> Str {
+    format!("expected the path to select exactly one value but it selected {amount}").into()
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().join_with(' ').to_string()
+}
+
+fn none_of(amount: usize, predicate: &str) -> Str {
+    match amount {
+        0 => "the XPath doesn't select anything".into(),
+        _ => format!("no selected node {predicate} (out of {amount})").into(),
+    }
+}
+
+/// Parse a Python-style list of string literals, e.g., `['a.html', "b"]`.
+pub(crate) fn parse_string_list(source: &str) -> Result<Vec<String>, ()> {
+    let source = source.trim().strip_prefix('[').ok_or(())?.strip_suffix(']').ok_or(())?;
+    let mut entries = Vec::new();
+    let mut chars = source.chars().peekable();
+
+    loop {
+        while chars.next_if(|char| char.is_whitespace()).is_some() {}
+
+        let Some(quote) = chars.next() else { break };
+        if quote != '\'' && quote != '"' {
+            return Err(());
+        }
+
+        let mut entry = String::new();
+        loop {
+            match chars.next().ok_or(())? {
+                char if char == quote => break,
+                '\\' => entry.push(chars.next().ok_or(())?),
+                char => entry.push(char),
+            }
+        }
+        entries.push(entry);
+
+        while chars.next_if(|char| char.is_whitespace()).is_some() {}
+
+        match chars.next() {
+            Some(',') => {}
+            None => break,
+            Some(_) => return Err(()),
+        }
+    }
+
+    Ok(entries)
+}
+
+struct Failure<'a> {
+    query: &'a Query,
+    reason: Str,
+}
+
+pub(crate) struct Error {
+    failures: Vec<String>,
+    total: usize,
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        let s = if self.total == 1 { "y" } else { "ies" };
+        let diagnostic = error(format!("{} of {} quer{s} failed", self.failures.len(), self.total));
+        self.failures.into_iter().fold(diagnostic, Diagnostic::note)
+    }
+}
@@ -0,0 +1,99 @@
+use super::{Context, Operand, Query, QueryKind, parse_string_list, select, tabulate};
+use crate::jsonpath::JsonPath;
+use std::path::Path;
+
+#[test]
+fn string_list() {
+    assert_eq!(
+        parse_string_list(r#"['index.html', "struct.Foo.html"]"#),
+        Ok(vec!["index.html".to_owned(), "struct.Foo.html".to_owned()])
+    );
+}
+
+#[test]
+fn string_list_empty() {
+    assert_eq!(parse_string_list(" [ ] "), Ok(Vec::new()));
+}
+
+#[test]
+fn string_list_trailing_comma_escapes() {
+    assert_eq!(parse_string_list(r"['it\'s',]"), Ok(vec!["it's".to_owned()]));
+}
+
+#[test]
+fn string_list_malformed() {
+    assert_eq!(parse_string_list("['unterminated]"), Err(()));
+    assert_eq!(parse_string_list("[bare]"), Err(()));
+    assert_eq!(parse_string_list("'no brackets'"), Err(()));
+}
+
+#[test]
+fn table() {
+    let query = |source: &str| Query {
+        source: source.to_owned(),
+        negated: false,
+        kind: QueryKind::Files { path: String::new(), entries: Vec::new() },
+        tags: Vec::new(),
+    };
+    let revisions = [
+        ("a".to_owned(), vec![(query("//@ common"), Ok(())), (query("//@[a] only"), Ok(()))]),
+        ("b".to_owned(), vec![(query("//@ common"), Err("missing `x`".into()))]),
+    ];
+
+    let (table, result) = tabulate(&revisions);
+    assert_eq!(
+        table,
+        "             a       b
+//@ common   ok      FAILED
+//@[a] only  ok      -
+"
+    );
+    assert_eq!(result.err().unwrap().failures, ["[b] `//@ common`: missing `x`"]);
+}
+
+#[test]
+fn selection() {
+    let query = |source: &str, tags: &[&str]| Query {
+        source: source.to_owned(),
+        negated: false,
+        kind: QueryKind::Files { path: String::new(), entries: Vec::new() },
+        tags: tags.iter().map(|&tag| tag.to_owned()).collect(),
+    };
+    let queries = [
+        query("//@ untagged", &[]),
+        query("//@ search", &["search"]),
+        query("//@ both", &["search", "layout"]),
+        query("//@ layout", &["layout"]),
+    ];
+
+    let tags = ["search".to_owned(), "typo".to_owned()];
+    let (selected, unknown) = select(&queries, &tags);
+    let selected: Vec<_> = selected.iter().map(|query| query.source.as_str()).collect();
+    assert_eq!(selected, ["//@ search", "//@ both"]);
+    assert_eq!(unknown, ["typo"]);
+}
+
+#[test]
+fn negation() {
+    let negated = |kind| Query { source: String::new(), negated: true, kind, tags: Vec::new() };
+    let mut context = Context::new(Path::new("missing"), Path::new("test.rs"), "krate", false);
+
+    // A file that doesn't exist is exactly what `!has` checks for.
+    let query = negated(QueryKind::Has { path: "index.html".into(), xpath: None, text: None });
+    assert!(query.check(&mut context).is_ok());
+
+    // Other queries can't be evaluated without the file, so the negation doesn't apply.
+    let query = negated(QueryKind::HasRaw { path: "index.html".into(), text: "fn".into() });
+    let reason = query.check(&mut context).unwrap_err();
+    assert!(reason.starts_with("failed to read `index.html`"), "{reason}");
+
+    let query = negated(QueryKind::Files { path: "krate".into(), entries: Vec::new() });
+    let reason = query.check(&mut context).unwrap_err();
+    assert!(reason.starts_with("failed to read directory `krate`"), "{reason}");
+
+    // Neither can a query with an unbound variable.
+    context.json = Some(serde_json::json!({ "index": {} }));
+    let path = JsonPath::parse("$.index").ok().unwrap();
+    let query = negated(QueryKind::JsonIs { path, value: Operand::Variable("id".into()) });
+    assert_eq!(&*query.check(&mut context).unwrap_err(), "unknown variable `$id`");
+}
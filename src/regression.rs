@@ -0,0 +1,226 @@
+//! Finding the first nightly for which the build of a crate fulfills a predicate (`rruxwry bisect`).
+//!
+//! The dated nightlies get installed via rustup as needed. Dates for which there's no nightly
+//! (e.g., because the release process failed) are skipped.
+
+use crate::{
+    builder::{self, BuildMode},
+    cli::{self, Predicate},
+    command::{Flags, VerbatimFlagsBuf},
+    data::{CrateNameRef, CrateType, Edition},
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info, warning},
+    ice,
+    toolchain::{self, Date, Version},
+    utility::{default, scratch_dir},
+};
+use rustc_hash::FxHashMap;
+use std::{fmt, path::Path, process};
+
+#[cfg(test)]
+mod test;
+
+pub(crate) fn bisect(
+    (start, end, predicate): (Date, Date, Predicate),
+    build_mode: BuildMode,
+    path: &Path,
+    crate_name: CrateNameRef<'_>,
+    crate_type: CrateType,
+    edition: Edition,
+    flags: Flags<'_>,
+) -> crate::error::Result {
+    if start >= end {
+        return Err(Error::EmptyRange { start, end }.into());
+    }
+
+    let log = scratch_dir(flags.program)?.join(format!("rruxwry-{crate_name}-nightly.log"));
+    // Don't litter the working directory with ICE reports.
+    let verbatim_flags =
+        VerbatimFlagsBuf { environment: vec![("RUSTC_ICE", Some("0"))], ..default() }
+            .extended(flags.verbatim);
+    let flags = Flags { verbatim: verbatim_flags.as_ref(), redirect_stderr: Some(&log), ..flags };
+    let build_flags = |date| cli::BuildFlags {
+        toolchain: Some(format!("nightly-{date}")),
+        ..flags.build.clone()
+    };
+
+    // Without running anything, every nightly would be good.
+    if flags.program.dry_run {
+        let build_flags = build_flags(start);
+        let flags = Flags { build: &build_flags, ..flags };
+        builder::build(build_mode, path, crate_name, crate_type, edition, flags)?;
+        return Ok(());
+    }
+
+    let mut dates = vec![start];
+    while let Some(&date) = dates.last()
+        && date < end
+    {
+        dates.push(date.next());
+    }
+
+    let mut versions = FxHashMap::default();
+    let result = search(dates, |date| {
+        let build_flags = build_flags(date);
+        let toolchain = build_flags.toolchain.as_deref().unwrap_or_default();
+        let Some(version) = install(&build_flags, flags.program) else {
+            warning(format!("`{toolchain}` isn't available, skipping it")).emit();
+            return Ok(None);
+        };
+        versions.insert(date, version);
+
+        let flags = Flags { build: &build_flags, ..flags };
+        let outcome = match builder::build(build_mode, path, crate_name, crate_type, edition, flags)
+        {
+            Ok(_) => Outcome::Success,
+            Err(crate::error::Error::Process(error)) if error.code() == Some(ice::EXIT_CODE) => {
+                Outcome::Ice
+            }
+            Err(_) => Outcome::Failure,
+        };
+        let bad = predicate.holds(outcome);
+        let verdict = if bad { "bad" } else { "good" };
+        info(format!("`{toolchain}` is {verdict}: {outcome}")).emit();
+        Ok::<_, crate::error::Error>(Some(bad))
+    });
+    let _ = std::fs::remove_file(&log);
+    let (good, bad) = result?;
+
+    let mut diagnostic =
+        info(format!("`nightly-{bad}` is the first nightly for which {}", predicate.outcome()))
+            .note(format!("the previous available nightly is `nightly-{good}`"));
+    if let (Some(good), Some(bad)) = (commit(&versions, good), commit(&versions, bad)) {
+        diagnostic = diagnostic.note(format!(
+            "the changes in between: https://github.com/rust-lang/rust/compare/{good}...{bad}"
+        ));
+    }
+    diagnostic.emit();
+
+    Ok(())
+}
+
+/// Install the given toolchain unless it's already installed and probe its version.
+fn install(build_flags: &cli::BuildFlags, program_flags: &cli::ProgramFlags) -> Option<Version> {
+    if let Some(version) = toolchain::probe(build_flags, program_flags) {
+        return Some(version);
+    }
+    let toolchain = build_flags.toolchain.as_deref()?;
+
+    info(format!("installing `{toolchain}`")).emit();
+    let status = process::Command::new("rustup")
+        .args(["toolchain", "install", "--profile", "minimal", "--no-self-update", toolchain])
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .status()
+        .ok()?;
+    if !status.success() {
+        return None;
+    }
+
+    toolchain::probe(build_flags, program_flags)
+}
+
+fn commit(versions: &FxHashMap<Date, Version>, date: Date) -> Option<&str> {
+    versions.get(&date)?.commit_hash.as_deref()
+}
+
+/// Find the adjacent available dates where the earlier one is good and the later one is bad
+/// via binary search, given that the first available date is good and the last one is bad.
+///
+/// The predicate returns `None` if there's no nightly for the given date.
+fn search<E: From<Error>>(
+    mut dates: Vec<Date>,
+    mut is_bad: impl FnMut(Date) -> Result<Option<bool>, E>,
+) -> Result<(Date, Date), E> {
+    loop {
+        let &first = dates.first().ok_or(Error::Unavailable)?;
+        match is_bad(first)? {
+            Some(false) => break,
+            Some(true) => return Err(Error::StartBad(first).into()),
+            None => _ = dates.remove(0),
+        }
+    }
+    loop {
+        let &last = dates.last().ok_or(Error::Unavailable)?;
+        match is_bad(last)? {
+            Some(true) => break,
+            Some(false) => return Err(Error::EndGood(last).into()),
+            None => _ = dates.pop(),
+        }
+    }
+
+    let (mut good, mut bad) = (0, dates.len() - 1);
+    while bad - good > 1 {
+        let middle = (good + bad) / 2;
+        match is_bad(dates[middle])? {
+            Some(true) => bad = middle,
+            Some(false) => good = middle,
+            None => {
+                dates.remove(middle);
+                bad -= 1;
+            }
+        }
+    }
+
+    Ok((dates[good], dates[bad]))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Success,
+    Failure,
+    Ice,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Success => "the build succeeds",
+            Self::Failure => "the build fails",
+            Self::Ice => "`rustdoc` crashes",
+        })
+    }
+}
+
+impl Predicate {
+    fn holds(self, outcome: Outcome) -> bool {
+        match self {
+            Self::Failure => outcome != Outcome::Success,
+            Self::Ice => outcome == Outcome::Ice,
+            Self::Success => outcome == Outcome::Success,
+        }
+    }
+
+    /// The outcome that makes a nightly bad.
+    fn outcome(self) -> Outcome {
+        match self {
+            Self::Failure => Outcome::Failure,
+            Self::Ice => Outcome::Ice,
+            Self::Success => Outcome::Success,
+        }
+    }
+}
+
+#[cfg_attr(test, derive(Debug))]
+pub(crate) enum Error {
+    EmptyRange { start: Date, end: Date },
+    StartBad(Date),
+    EndGood(Date),
+    Unavailable,
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::EmptyRange { start, end } => {
+                error(format!("the start date {start} isn't before the end date {end}"))
+            }
+            Self::StartBad(date) => {
+                error(format!("the start of the range `nightly-{date}` is already bad"))
+                    .note("pick an earlier start date via `--start`")
+            }
+            Self::EndGood(date) => error(format!("the end of the range `nightly-{date}` is good"))
+                .note("pick a later end date via `--end` or a different `--predicate`"),
+            Self::Unavailable => error("there are no nightlies available in the given range"),
+        }
+    }
+}
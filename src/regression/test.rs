@@ -0,0 +1,38 @@
+use super::{Error, search};
+use crate::toolchain::Date;
+
+fn dates(amount: u8) -> Vec<Date> {
+    (1..=amount).map(|day| Date::new(2024, 9, day)).collect()
+}
+
+#[test]
+fn first_bad() {
+    let mut tested = Vec::new();
+    let range = search::<Error>(dates(20), |date| {
+        tested.push(date);
+        Ok(Some(date >= Date::new(2024, 9, 13)))
+    });
+    assert_eq!(range.unwrap(), (Date::new(2024, 9, 12), Date::new(2024, 9, 13)));
+    assert!(tested.len() <= 7);
+}
+
+#[test]
+fn unavailable() {
+    let missing = [Date::new(2024, 9, 1), Date::new(2024, 9, 5), Date::new(2024, 9, 6)];
+    let range = search::<Error>(dates(10), |date| match missing.contains(&date) {
+        true => Ok(None),
+        false => Ok(Some(date >= Date::new(2024, 9, 6))),
+    });
+    // The regression happened in 5 or 6 but those aren't available.
+    assert_eq!(range.unwrap(), (Date::new(2024, 9, 4), Date::new(2024, 9, 7)));
+}
+
+#[test]
+fn invalid_range() {
+    let start_bad = search::<Error>(dates(5), |_| Ok(Some(true)));
+    assert!(matches!(start_bad, Err(Error::StartBad(date)) if date == Date::new(2024, 9, 1)));
+    let end_good = search::<Error>(dates(5), |_| Ok(Some(false)));
+    assert!(matches!(end_good, Err(Error::EndGood(date)) if date == Date::new(2024, 9, 5)));
+    let unavailable = search::<Error>(dates(5), |_| Ok(None));
+    assert!(matches!(unavailable, Err(Error::Unavailable)));
+}
@@ -0,0 +1,255 @@
+//! Rendering the generated docs as formatted text for the terminal (`--view`).
+//!
+//! Only the main content of a page gets rendered, or just the section of an item if the page
+//! is the one of its parent. The chrome of the page like buttons, source links and anchors is
+//! left out. Lines don't get wrapped, that's left to the terminal.
+
+use crate::xpath::{Document, NodeId, ROOT};
+use owo_colors::OwoColorize;
+use std::{io, path::Path};
+
+#[cfg(test)]
+mod test;
+
+/// The classes of elements that only make sense in a browser.
+const CHROME: &[&str] = &["anchor", "doc-anchor", "hideme", "out-of-band", "rightside", "src"];
+
+const INDENT: &str = "    ";
+
+/// Print the given page, only the section of the item with the given anchor if there is one.
+pub(crate) fn view(page: &Path, anchor: Option<&str>) -> io::Result<()> {
+    let html = std::fs::read_to_string(page)?;
+    print!("{}", render(&html, anchor));
+    Ok(())
+}
+
+fn render(html: &str, anchor: Option<&str>) -> String {
+    let document = Document::parse(html);
+    let node = match anchor.and_then(|anchor| find(&document, ROOT, anchor)) {
+        Some(node) => section(&document, node),
+        None => find(&document, ROOT, "main-content").unwrap_or(ROOT),
+    };
+
+    let mut renderer =
+        Renderer { document: &document, output: String::new(), space: false, lists: Vec::new() };
+    renderer.node(node, Style::default());
+    let mut output = renderer.output.trim_end().to_owned();
+    output.push('\n');
+    output
+}
+
+/// The element with the given ID.
+fn find(document: &Document, node: NodeId, id: &str) -> Option<NodeId> {
+    if document.attribute(node, "id") == Some(id) {
+        return Some(node);
+    }
+    document.child_nodes(node).iter().find_map(|&child| find(document, child, id))
+}
+
+/// The section of the item with the given heading, incl. its docs.
+///
+/// The heading of an associated item is the summary of a toggle that contains its docs.
+fn section(document: &Document, heading: NodeId) -> NodeId {
+    let summary = document.parent(heading);
+    let details = document.parent(summary);
+    match (document.name(summary), document.name(details)) {
+        (Some("summary"), Some("details")) => details,
+        _ => heading,
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Style {
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    code: bool,
+    preformatted: bool,
+}
+
+impl Style {
+    fn apply(self, text: &str) -> String {
+        let mut text = text.to_owned();
+        if self.code {
+            text = text.cyan().to_string();
+        }
+        if self.italic {
+            text = text.italic().to_string();
+        }
+        if self.underline {
+            text = text.underline().to_string();
+        }
+        if self.bold {
+            text = text.bold().to_string();
+        }
+        text
+    }
+}
+
+struct Renderer<'a> {
+    document: &'a Document,
+    output: String,
+    /// Whether there's whitespace between the previous word and the next one.
+    space: bool,
+    /// The number of the current item of each enclosing list, `None` for unordered lists.
+    lists: Vec<Option<usize>>,
+}
+
+impl Renderer<'_> {
+    fn node(&mut self, node: NodeId, mut style: Style) {
+        if let Some(text) = self.document.contents(node) {
+            self.text(text, style);
+            return;
+        }
+        let Some(name) = self.document.name(node) else { return };
+        let has_class = |wanted: &[&str]| {
+            self.document.attribute(node, "class").is_some_and(|classes| {
+                classes.split_ascii_whitespace().any(|class| wanted.contains(&class))
+            })
+        };
+        if has_class(CHROME)
+            || matches!(
+                name,
+                "script" | "style" | "button" | "nav" | "noscript" | "rustdoc-toolbar"
+            )
+        {
+            return;
+        }
+
+        // Whether the element is separated from its siblings by a line or an empty line.
+        let block = match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                style.bold = true;
+                style.underline |= name == "h1";
+                Some(true)
+            }
+            "p" | "blockquote" | "table" | "hr" => Some(true),
+            "pre" => {
+                style.code = true;
+                style.preformatted = true;
+                Some(true)
+            }
+            "ul" | "ol" => {
+                self.lists.push((name == "ol").then_some(0));
+                Some(true)
+            }
+            // The entries of the item tables of modules.
+            "div" if has_class(&["item-name"]) => None,
+            "div" if has_class(&["docblock-short"]) => {
+                if !self.at_line_start() {
+                    self.output.push_str(" — ");
+                }
+                self.space = false;
+                None
+            }
+            "div" | "section" | "details" | "summary" | "dl" | "dt" | "dd" | "tr" => Some(false),
+            "li" => {
+                self.break_line(false);
+                self.item();
+                None
+            }
+            "br" => {
+                self.output.push('\n');
+                self.space = false;
+                None
+            }
+            "td" | "th" => {
+                if !self.at_line_start() {
+                    self.output.push_str("  ");
+                }
+                self.space = false;
+                style.bold |= name == "th";
+                None
+            }
+            "code" => {
+                style.code = true;
+                None
+            }
+            "a" => {
+                style.underline = !style.code;
+                None
+            }
+            "em" | "i" => {
+                style.italic = true;
+                None
+            }
+            "strong" | "b" => {
+                style.bold = true;
+                None
+            }
+            _ => None,
+        };
+
+        if let Some(blank) = block {
+            self.break_line(blank);
+        }
+        for &child in self.document.child_nodes(node) {
+            self.node(child, style);
+        }
+        if let Some(blank) = block {
+            self.break_line(blank);
+        }
+        if let "ul" | "ol" = name {
+            self.lists.pop();
+        }
+    }
+
+    fn text(&mut self, text: &str, style: Style) {
+        if style.preformatted {
+            for (index, line) in text.split('\n').enumerate() {
+                if index > 0 {
+                    self.output.push('\n');
+                }
+                if line.is_empty() {
+                    continue;
+                }
+                if self.at_line_start() {
+                    self.output.push_str(INDENT);
+                }
+                self.output.push_str(&style.apply(line));
+            }
+            return;
+        }
+
+        self.space |= text.starts_with(char::is_whitespace);
+        for word in text.split_whitespace() {
+            if self.space && !self.at_line_start() && !self.output.ends_with(' ') {
+                self.output.push(' ');
+            }
+            self.output.push_str(&style.apply(word));
+            self.space = true;
+        }
+        self.space &= text.ends_with(char::is_whitespace);
+    }
+
+    /// Start the next item of the innermost list.
+    fn item(&mut self) {
+        let depth = self.lists.len().saturating_sub(1);
+        self.output.push_str(&"  ".repeat(depth));
+        match self.lists.last_mut() {
+            Some(Some(number)) => {
+                *number += 1;
+                self.output.push_str(&format!("{number}. "));
+            }
+            _ => self.output.push_str("• "),
+        }
+        self.space = false;
+    }
+
+    /// End the current line, followed by an empty line if `blank` is set.
+    fn break_line(&mut self, blank: bool) {
+        self.space = false;
+        if self.output.is_empty() {
+            return;
+        }
+        let present = self.output.len() - self.output.trim_end_matches('\n').len();
+        let wanted = if blank { 2 } else { 1 };
+        for _ in present..wanted {
+            self.output.push('\n');
+        }
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.output.is_empty() || self.output.ends_with('\n')
+    }
+}
@@ -0,0 +1,40 @@
+use super::render;
+use crate::utility::strip_ansi_escapes;
+
+fn plain(html: &str, anchor: Option<&str>) -> String {
+    strip_ansi_escapes(&render(html, anchor))
+}
+
+#[test]
+fn main_content() {
+    let html = r##"<nav class="sidebar">Sidebar</nav>
+<section id="main-content" class="content">
+<div class="main-heading"><h1>Struct <span class="struct">S</span><button id="copy-path">Copy item path</button></h1>
+<span class="sub-heading"><a class="src" href="#">source</a></span></div>
+<pre class="rust item-decl"><code>pub struct S {
+    pub field: u8,
+}</code></pre>
+<details class="toggle top-doc" open><summary class="hideme"><span>Expand description</span></summary>
+<div class="docblock"><p>A  <em>struct</em>
+with <code>docs</code>.</p><ol><li>First.</li><li>Second.</li></ol></div></details>
+<h2 id="implementations" class="section-header">Implementations<a href="#implementations" class="anchor">§</a></h2>
+</section>"##;
+    assert_eq!(
+        plain(html, None),
+        "Struct S\n\n    pub struct S {\n        pub field: u8,\n    }\n\n\
+         A struct with docs.\n\n1. First.\n2. Second.\n\nImplementations\n",
+    );
+}
+
+#[test]
+fn item_tables() {
+    let html = r#"<section id="main-content"><ul class="item-table"><li><div class="item-name"><a href="struct.S.html">S</a></div><div class="desc docblock-short">A struct.</div></li><li><div class="item-name"><a href="fn.f.html">f</a></div></li></ul></section>"#;
+    assert_eq!(plain(html, None), "• S — A struct.\n• f\n");
+}
+
+#[test]
+fn anchor() {
+    let html = r##"<section id="main-content"><details class="toggle method-toggle" open><summary><section id="method.new" class="method"><a href="#" class="src rightside">source</a><h4 class="code-header">pub fn <a href="#method.new" class="fn">new</a>() -&gt; Self</h4></section></summary><div class="docblock"><p>Create one.</p></div></details><p>Unrelated.</p></section>"##;
+    assert_eq!(plain(html, Some("method.new")), "pub fn new() -> Self\n\nCreate one.\n");
+    assert_eq!(plain(html, Some("missing")), "pub fn new() -> Self\n\nCreate one.\n\nUnrelated.\n");
+}
@@ -0,0 +1,63 @@
+//! The machine-readable report of a run (`--report`).
+
+use crate::{batch::Outcome, cli, timing::Step};
+use serde_json::{Value, json};
+use std::path::Path;
+
+#[derive(Default)]
+pub(crate) struct Report {
+    runs: Vec<Value>,
+    lints: Vec<Value>,
+}
+
+impl Report {
+    /// Record the lint levels that apply to the root crates in the order they get passed in.
+    pub(crate) fn record_lints(&mut self, flags: &cli::BuildFlags) {
+        let config = flags.config_lints.iter().map(|lint| (lint, "config"));
+        let command_line = flags.lints.iter().map(|lint| (lint, "command-line"));
+        self.lints = config
+            .chain(command_line)
+            .map(
+                |((name, level), origin)| json!({ "name": name, "level": level, "origin": origin }),
+            )
+            .collect();
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        path: &Path,
+        outcome: &Outcome,
+        steps: &[Step],
+        output: Option<u64>,
+        ices: Vec<Value>,
+    ) {
+        let steps: Vec<_> = steps
+            .iter()
+            .map(|step| json!({ "name": step.name, "duration": step.duration.as_secs_f64() }))
+            .collect();
+
+        let mut run = json!({
+            "path": path.display().to_string(),
+            "outcome": outcome.to_str(),
+            "steps": steps,
+        });
+        if let Outcome::Skipped(unmet) = outcome {
+            run["skip"] = json!({ "directive": unmet.directive, "reason": unmet.reason });
+        }
+        if let Some(output) = output {
+            run["output"] = format!("{output:016x}").into();
+        }
+        if !ices.is_empty() {
+            run["ices"] = ices.into();
+        }
+        self.runs.push(run);
+    }
+
+    pub(crate) fn to_json(&self) -> Value {
+        json!({ "runs": self.runs, "lints": self.lints })
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(&self.to_json())? + "\n")
+    }
+}
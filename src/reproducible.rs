@@ -0,0 +1,148 @@
+//! Pinning the output of rustdoc for reproducible builds (`--reproducible`).
+//!
+//! The flags passed to rustdoc (path remapping and a fixed `SOURCE_DATE_EPOCH`) are set in
+//! [`crate::command`]. Here, we canonicalize the JSON output and check the result by building
+//! a second time from scratch into a separate directory and by looking for paths that are
+//! specific to the current machine.
+//!
+//! We don't need to pin the resource suffix: The names of rustdoc's static files already
+//! contain a hash of their contents.
+
+use crate::{
+    diagnostic::{Diagnostic, info, warning},
+    output::Fingerprint,
+};
+use serde_json::Value;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(test)]
+mod test;
+
+/// Canonicalize the output, rebuild it and report any remaining nondeterminism.
+///
+/// The `rebuild` is expected to start from scratch and to return the root it built into.
+pub(crate) fn check(
+    root: &Path,
+    rebuild: impl FnOnce() -> crate::error::Result<PathBuf>,
+) -> crate::error::Result {
+    canonicalize(root)?;
+    let first = Fingerprint::new(root)?;
+
+    let rebuilt = rebuild()?;
+    canonicalize(&rebuilt)?;
+    let second = Fingerprint::new(&rebuilt)?;
+
+    let differences = first.differences(&second);
+    let leaks = leaks(root)?;
+
+    if differences.is_empty() && leaks.is_empty() {
+        info("the output is reproducible")
+            .note("two independent builds produced byte-identical output")
+            .emit();
+        return Ok(());
+    }
+
+    if !differences.is_empty() {
+        let amount = differences.len();
+        let s = if amount == 1 { "" } else { "s" };
+        differences
+            .iter()
+            .map(ToString::to_string)
+            .fold(
+                warning(format!("{amount} file{s} changed between two independent builds")),
+                Diagnostic::note,
+            )
+            .emit();
+    }
+
+    if !leaks.is_empty() {
+        let amount = leaks.len();
+        let s = if amount == 1 { "" } else { "s" };
+        leaks
+            .iter()
+            .map(|(path, needle)| format!("`{}` mentions `{needle}`", path.display()))
+            .fold(
+                warning(format!("found machine-specific paths in {amount} file{s}")),
+                Diagnostic::note,
+            )
+            .emit();
+    }
+
+    Ok(())
+}
+
+/// Rewrite the JSON output with sorted object keys.
+fn canonicalize(root: &Path) -> io::Result<()> {
+    for entry in std::fs::read_dir(root)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "json")
+            || path == root.join(crate::provenance::FILE_NAME)
+        {
+            continue;
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        if let Some(sorted) = sort(&contents) {
+            std::fs::write(&path, sorted)?;
+        }
+    }
+    Ok(())
+}
+
+/// Re-serialize the given JSON with sorted object keys.
+///
+/// Returns `None` if it isn't valid JSON.
+fn sort(json: &str) -> Option<String> {
+    // Without the `preserve_order` feature, the maps of `serde_json` are ordered by key.
+    let value: Value = serde_json::from_str(json).ok()?;
+    Some(value.to_string())
+}
+
+/// The text files in the output that mention the current directory or the home directory.
+fn leaks(root: &Path) -> io::Result<Vec<(PathBuf, String)>> {
+    let needles: Vec<_> =
+        [std::env::current_dir().ok(), std::env::var_os("HOME").map(PathBuf::from)]
+            .into_iter()
+            .flatten()
+            .map(|path| path.display().to_string())
+            // Guard against a degenerate home directory like `/`.
+            .filter(|needle| needle.len() > 1)
+            .collect();
+
+    let mut leaks = Vec::new();
+    let mut directories = vec![root.to_owned()];
+
+    while let Some(directory) = directories.pop() {
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                directories.push(path);
+                continue;
+            }
+            if path == root.join(crate::provenance::FILE_NAME) {
+                continue;
+            }
+
+            let Ok(contents) = std::fs::read_to_string(&path) else { continue };
+            if let Some(needle) = find_needle(&contents, &needles) {
+                let path = path.strip_prefix(root).unwrap_or(&path).to_owned();
+                leaks.push((path, needle.to_owned()));
+            }
+        }
+    }
+
+    leaks.sort();
+    Ok(leaks)
+}
+
+/// The longest of the given needles contained in the text.
+fn find_needle<'a>(text: &str, needles: &'a [String]) -> Option<&'a str> {
+    needles
+        .iter()
+        .filter(|needle| text.contains(needle.as_str()))
+        .max_by_key(|needle| needle.len())
+        .map(String::as_str)
+}
@@ -0,0 +1,18 @@
+use super::{find_needle, sort};
+
+#[test]
+fn sorted_keys() {
+    assert_eq!(
+        sort("{\"b\": [{\"d\": 1, \"c\": 2}], \"a\": null}").unwrap(),
+        r#"{"a":null,"b":[{"c":2,"d":1}]}"#
+    );
+    assert_eq!(sort("{\"a\":"), None);
+}
+
+#[test]
+fn longest_needle() {
+    let needles = ["/home/user".to_owned(), "/home/user/krate".to_owned()];
+    assert_eq!(find_needle("src: /home/user/krate/lib.rs", &needles), Some("/home/user/krate"));
+    assert_eq!(find_needle("src: /home/user/lib.rs", &needles), Some("/home/user"));
+    assert_eq!(find_needle("src: ./lib.rs", &needles), None);
+}
@@ -0,0 +1,115 @@
+//! Smoke-testing rustdoc's search functionality.
+//!
+//! The tests are written in the format of the `tests/rustdoc-js/` suite of rust-lang/rust and
+//! get executed by Node.js against the search index of the generated docs.
+
+use crate::{
+    cli,
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info},
+};
+use serde_json::Value;
+use std::{io, path::Path, process};
+
+#[cfg(test)]
+mod test;
+
+const DRIVER: &str = include_str!("search/driver.js");
+
+/// Run the search queries found in the test file at `tests` and check the results.
+pub(crate) fn run_tests(
+    tests: &Path,
+    root: &Path,
+    flags: &cli::ProgramFlags,
+) -> crate::error::Result {
+    if flags.verbose {
+        let verb = if !flags.dry_run { "running" } else { "skipping" };
+        info(format!("{verb} the search tests in `{}`", tests.display())).emit();
+    }
+
+    if flags.dry_run {
+        return Ok(());
+    }
+
+    let output = process::Command::new("node")
+        .arg("-e")
+        .arg(DRIVER)
+        .arg(root)
+        .arg(tests)
+        .stderr(process::Stdio::inherit())
+        .output()
+        .map_err(|error| match error.kind() {
+            io::ErrorKind::NotFound => Error::MissingNode.into(),
+            _ => crate::error::Error::from(error),
+        })?;
+    output.status.exit_ok()?;
+
+    let outcomes: Vec<Value> = serde_json::from_slice(&output.stdout).map_err(io::Error::from)?;
+
+    let failures: Vec<_> = outcomes.iter().filter_map(check).collect();
+
+    if !failures.is_empty() {
+        return Err(Error::Failures { failures, total: outcomes.len() }.into());
+    }
+
+    Ok(())
+}
+
+/// Check the results of a single query against the expectations.
+///
+/// The expected entries have to be found in the given order but other entries may come
+/// in between. An empty list of expected entries means that there shouldn't be any results.
+fn check(outcome: &Value) -> Option<String> {
+    let query = outcome["query"].as_str().unwrap_or_default();
+    let expected = outcome["expected"].as_object()?;
+
+    for (key, expected) in expected {
+        let (Some(expected), Some(actual)) =
+            (expected.as_array(), outcome["actual"][key].as_array())
+        else {
+            if outcome["actual"][key] != *expected {
+                return Some(format!("`{query}`: expected `{key}` to be `{expected}`"));
+            }
+            continue;
+        };
+
+        if expected.is_empty() && !actual.is_empty() {
+            let amount = actual.len();
+            let s = if amount == 1 { "" } else { "s" };
+            return Some(format!("`{query}`: expected no `{key}` but found {amount} result{s}"));
+        }
+
+        let mut actual = actual.iter();
+        for entry in expected {
+            if !actual.any(|result| matches(entry, result)) {
+                return Some(format!("`{query}`: failed to find `{entry}` in `{key}` in order"));
+            }
+        }
+    }
+
+    None
+}
+
+fn matches(expected: &Value, actual: &Value) -> bool {
+    let Some(expected) = expected.as_object() else { return false };
+    expected.iter().all(|(key, expected)| actual[key] == *expected)
+}
+
+pub(crate) enum Error {
+    Failures { failures: Vec<String>, total: usize },
+    MissingNode,
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::Failures { failures, total } => {
+                let s = if total == 1 { "y" } else { "ies" };
+                let diagnostic =
+                    error(format!("{} of {total} search quer{s} failed", failures.len()));
+                failures.into_iter().fold(diagnostic, Diagnostic::note)
+            }
+            Self::MissingNode => error("failed to find `node`")
+                .note("running search tests requires Node.js to be installed"),
+        }
+    }
+}
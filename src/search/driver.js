@@ -0,0 +1,118 @@
+// Runs the search queries of a rustdoc-js-style test file against the search index of the
+// generated docs and prints the expectations alongside the actual results as JSON.
+//
+// Heavily inspired by `src/tools/rustdoc-js/tester.js` of rust-lang/rust.
+
+"use strict";
+
+const fs = require("fs");
+const path = require("path");
+
+const [docRoot, testsPath] = process.argv.slice(1);
+
+function loadTests() {
+    const source = fs.readFileSync(testsPath, "utf8");
+    const exports = {};
+    new Function("exports", `${source}
+        exports.QUERY = typeof QUERY !== "undefined" ? QUERY : undefined;
+        exports.EXPECTED = typeof EXPECTED !== "undefined" ? EXPECTED : undefined;
+        exports.FILTER_CRATE = typeof FILTER_CRATE !== "undefined" ? FILTER_CRATE : null;
+    `)(exports);
+
+    let expected = exports.EXPECTED;
+    if (expected === undefined) {
+        throw new Error("the test file doesn't define `EXPECTED`");
+    }
+    if (!Array.isArray(expected)) {
+        expected = [expected];
+    }
+    // In older test files, the queries are defined separately.
+    if (exports.QUERY !== undefined) {
+        const queries = Array.isArray(exports.QUERY) ? exports.QUERY : [exports.QUERY];
+        expected = expected.map((entry, index) => ({ query: queries[index], ...entry }));
+    }
+
+    return { tests: expected, filterCrate: exports.FILTER_CRATE };
+}
+
+// FIXME: Support the newer search index format (i.e., the `search.index/` directory).
+function loadSearch() {
+    globalThis.searchState = {
+        descShards: new Map(),
+        loadDesc: async function({ descShard, descIndex }) {
+            if (descShard.promise === null) {
+                descShard.promise = new Promise((resolve, reject) => {
+                    descShard.resolve = resolve;
+                    const file = path.join(
+                        docRoot, "search.desc", descShard.crate,
+                        `${descShard.crate}-desc-${descShard.shard}-.js`,
+                    );
+                    fs.readFile(file, (error, data) => {
+                        if (error) {
+                            reject(error);
+                        } else {
+                            eval(data.toString("utf8"));
+                        }
+                    });
+                });
+            }
+            const list = await descShard.promise;
+            return list[descIndex];
+        },
+        loadedDescShard: function(crate, shard, data) {
+            this.descShards.get(crate)[shard].resolve(data.split("\n"));
+        },
+    };
+
+    const searchIndex = require(path.join(docRoot, "search-index.js"));
+    const staticFiles = path.join(docRoot, "static.files");
+    const searchJs = fs.readdirSync(staticFiles).find(file => /^search-.*\.js$/.test(file));
+    const searchModule = require(path.join(staticFiles, searchJs));
+    searchModule.initSearch(searchIndex.searchIndex);
+
+    return async (query, filterCrate) => {
+        const parsedQuery = searchModule.parseQuery(query);
+        return await searchModule.docSearch.execQuery(parsedQuery, filterCrate, undefined);
+    };
+}
+
+// Only keep the primitive fields of the results, they may contain cyclic references otherwise.
+function simplify(entries) {
+    return entries.map(entry => {
+        const simplified = Object.fromEntries(
+            Object.entries(entry).filter(([_, value]) => value === null || typeof value !== "object"),
+        );
+        // The `path` of associated items doesn't include their parent, the full path does.
+        if (typeof simplified.fullPath === "string") {
+            const fullPath = simplified.fullPath.split("|")[0];
+            const index = fullPath.lastIndexOf("::");
+            if (index !== -1) {
+                simplified.path = fullPath.slice(0, index);
+            }
+        }
+        return simplified;
+    });
+}
+
+async function main() {
+    const { tests, filterCrate } = loadTests();
+    const search = loadSearch();
+    const output = [];
+
+    for (const { query, ...expected } of tests) {
+        const results = await search(query, filterCrate);
+        const actual = {};
+        for (const key of Object.keys(expected)) {
+            const entries = await results[key];
+            actual[key] = Array.isArray(entries) ? simplify(entries) : entries;
+        }
+        output.push({ query, expected, actual });
+    }
+
+    process.stdout.write(JSON.stringify(output));
+}
+
+main().catch(error => {
+    process.stderr.write(`${error.stack ?? error}\n`);
+    process.exit(1);
+});
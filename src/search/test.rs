@@ -0,0 +1,56 @@
+use super::check;
+use serde_json::json;
+
+#[test]
+fn results_in_order() {
+    let outcome = json!({
+        "query": "vec",
+        "expected": {
+            "others": [{ "path": "std::vec", "name": "Vec" }, { "name": "vec", "ty": "macro" }],
+        },
+        "actual": {
+            "others": [
+                { "path": "std::vec", "name": "Vec", "ty": "struct" },
+                { "path": "std::vec", "name": "IntoIter", "ty": "struct" },
+                { "path": "std", "name": "vec", "ty": "macro" },
+            ],
+        },
+    });
+    assert_eq!(check(&outcome), None);
+
+    let outcome = json!({
+        "query": "vec",
+        "expected": { "others": [{ "name": "vec" }, { "name": "Vec" }] },
+        "actual": { "others": [{ "name": "Vec" }, { "name": "vec" }] },
+    });
+    assert_eq!(
+        check(&outcome).as_deref(),
+        Some(r#"`vec`: failed to find `{"name":"Vec"}` in `others` in order"#)
+    );
+}
+
+#[test]
+fn no_results() {
+    let outcome = json!({
+        "query": "missing",
+        "expected": { "others": [], "returned": [] },
+        "actual": { "others": [], "returned": [{ "name": "f" }, { "name": "g" }] },
+    });
+    assert_eq!(
+        check(&outcome).as_deref(),
+        Some("`missing`: expected no `returned` but found 2 results")
+    );
+}
+
+#[test]
+fn non_list_expectations() {
+    let outcome = json!({
+        "query": "u8 ->",
+        "expected": { "error": null },
+        "actual": { "error": "unexpected `->`", "others": [] },
+    });
+    assert_eq!(check(&outcome).as_deref(), Some("`u8 ->`: expected `error` to be `null`"));
+
+    // Outcomes of queries without expectations always pass.
+    assert_eq!(check(&json!({ "query": "f", "actual": { "others": [] } })), None);
+}
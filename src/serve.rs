@@ -0,0 +1,201 @@
+//! Serving the generated docs over HTTP.
+//!
+//! Optionally, a baseline build gets served alongside under `/a/` (with the actual build
+//! under `/b/`) and a toggle overlay is injected into the pages for switching between both.
+
+use crate::{
+    cli,
+    diagnostic::{info, warning},
+};
+use std::{
+    borrow::Cow,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::{Component, Path, PathBuf},
+};
+
+#[cfg(test)]
+mod test;
+
+/// The prefixes under which the baseline and the actual build get served in comparison mode.
+const PREFIXES: [&str; 2] = ["/a/", "/b/"];
+
+/// Serve the documentation root `root` (and the baseline if provided) until interrupted.
+pub(crate) fn serve(
+    root: &Path,
+    baseline: Option<&Path>,
+    address: &str,
+    entry_point: &str,
+    open: bool,
+    flags: &cli::ProgramFlags,
+) -> crate::error::Result {
+    let url = match baseline {
+        Some(_) => format!("http://{address}{}{entry_point}", PREFIXES[1]),
+        None => format!("http://{address}/{entry_point}"),
+    };
+
+    if flags.dry_run {
+        if flags.verbose {
+            info(format!("skipping serving the docs at {url}")).emit();
+        }
+        return Ok(());
+    }
+
+    let listener = TcpListener::bind(address)?;
+    let diagnostic = info(format!("serving the docs at {url}"));
+    let diagnostic = match baseline {
+        Some(_) => diagnostic.note("press the toggle in the corner or `\\` to switch builds"),
+        None => diagnostic,
+    };
+    diagnostic.note("press Ctrl+C to stop").emit();
+
+    if open {
+        open::that(&url)?;
+    }
+
+    for stream in listener.incoming() {
+        let result = stream.and_then(|stream| respond(stream, root, baseline));
+        if let Err(error) = result {
+            warning(format!("failed to respond to a request: {error}")).emit();
+        }
+    }
+
+    Ok(())
+}
+
+fn respond(mut stream: TcpStream, root: &Path, baseline: Option<&Path>) -> io::Result<()> {
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+
+    let mut parts = request.split_ascii_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return write_response(&mut stream, "400 Bad Request", "text/plain", b"bad request");
+    };
+    if method != "GET" && method != "HEAD" {
+        return write_response(&mut stream, "405 Method Not Allowed", "text/plain", b"");
+    }
+
+    let Some((path, overlay)) = resolve(target, root, baseline) else {
+        return write_response(&mut stream, "404 Not Found", "text/plain", b"not found");
+    };
+    let path = if path.is_dir() { path.join("index.html") } else { path };
+    let Ok(contents) = std::fs::read(&path) else {
+        return write_response(&mut stream, "404 Not Found", "text/plain", b"not found");
+    };
+
+    let content_type = content_type(&path);
+    let contents = match overlay && content_type.starts_with("text/html") {
+        true => Cow::Owned(inject_overlay(&String::from_utf8_lossy(&contents)).into_bytes()),
+        false => Cow::Borrowed(&contents[..]),
+    };
+    let body = if method == "HEAD" { &[][..] } else { &contents[..] };
+    write_response(&mut stream, "200 OK", content_type, body)
+}
+
+/// The file denoted by the request target and whether it belongs to a comparison.
+fn resolve(target: &str, root: &Path, baseline: Option<&Path>) -> Option<(PathBuf, bool)> {
+    let path = target.split(['?', '#']).next()?;
+    let path = percent_decode(path)?;
+
+    let (directory, path) = match baseline {
+        Some(baseline) => {
+            if let Some(path) = path.strip_prefix(PREFIXES[0]) {
+                (baseline, path.to_owned())
+            } else {
+                (root, path.strip_prefix(PREFIXES[1])?.to_owned())
+            }
+        }
+        None => (root, path.strip_prefix('/')?.to_owned()),
+    };
+
+    let path = Path::new(&path);
+    // Don't serve anything outside of the documentation roots.
+    if !path.components().all(|component| matches!(component, Component::Normal(_))) {
+        return None;
+    }
+
+    Some((directory.join(path), baseline.is_some()))
+}
+
+fn percent_decode(path: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css",
+        Some("js") => "text/javascript",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("woff2") => "font/woff2",
+        Some("txt" | "md") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Cache-Control: no-store\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)
+}
+
+fn inject_overlay(page: &str) -> String {
+    let overlay = format!("<script>{OVERLAY}</script>");
+    match page.rfind("</body>") {
+        Some(index) => format!("{}{overlay}{}", &page[..index], &page[index..]),
+        None => format!("{page}{overlay}"),
+    }
+}
+
+/// Switch to the same page of the other build while preserving the scroll position.
+const OVERLAY: &str = r#"(() => {
+    const [a, b] = ["/a/", "/b/"];
+    const current = location.pathname.startsWith(a) ? a : b;
+    const other = current === a ? b : a;
+    const key = "rruxwry-scroll";
+    const saved = sessionStorage.getItem(key);
+    if (saved !== null) {
+        sessionStorage.removeItem(key);
+        addEventListener("load", () => scrollTo(0, Number(saved)));
+    }
+    const toggle = () => {
+        sessionStorage.setItem(key, String(scrollY));
+        location.pathname = other + location.pathname.slice(current.length);
+    };
+    const button = document.createElement("button");
+    button.textContent = current === a ? "A (baseline)" : "B";
+    button.title = "Switch to the other build (\\)";
+    button.style.cssText = "position:fixed;bottom:1em;right:1em;z-index:1000;padding:0.5em 1em;"
+        + "font:bold 14px sans-serif;border-radius:4px;cursor:pointer;"
+        + (current === a ? "background:#c33;color:#fff;" : "background:#36c;color:#fff;");
+    button.addEventListener("click", toggle);
+    addEventListener("keydown", event => {
+        if (event.key === "\\" && !(event.target instanceof HTMLInputElement)) toggle();
+    });
+    document.body.appendChild(button);
+})();"#;
@@ -0,0 +1,36 @@
+use super::{inject_overlay, percent_decode, resolve};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn resolve_targets() {
+    let (root, baseline) = (Path::new("doc"), Path::new("doc.baseline"));
+
+    assert_eq!(
+        resolve("/krate/index.html?search=x", root, None),
+        Some((PathBuf::from("doc/krate/index.html"), false))
+    );
+    assert_eq!(
+        resolve("/a/krate/struct.S.html", root, Some(baseline)),
+        Some((PathBuf::from("doc.baseline/krate/struct.S.html"), true))
+    );
+    assert_eq!(
+        resolve("/b/krate/", root, Some(baseline)),
+        Some((PathBuf::from("doc/krate/"), true))
+    );
+    assert_eq!(resolve("/krate/index.html", root, Some(baseline)), None);
+    assert_eq!(resolve("/../secret", root, None), None);
+    assert_eq!(resolve("/%2E%2E/secret", root, None), None);
+}
+
+#[test]
+fn decode_percent_encoding() {
+    assert_eq!(percent_decode("/a%20b/%C3%A4"), Some("/a b/ä".to_owned()));
+    assert_eq!(percent_decode("/%zz"), None);
+}
+
+#[test]
+fn overlay_before_end_of_body() {
+    let page = inject_overlay("<html><body><p>x</p></body></html>");
+    assert!(page.starts_with("<html><body><p>x</p><script>"));
+    assert!(page.ends_with("</script></body></html>"));
+}
@@ -0,0 +1,108 @@
+//! Comparing the rendered signatures of two builds of the docs.
+
+use crate::{
+    html::{self, Token},
+    utility::files,
+};
+use std::{collections::BTreeMap, io, path::Path};
+
+#[cfg(test)]
+mod test;
+
+/// The rendered signatures of the docs in `root` keyed by page and anchor.
+pub(crate) type Signatures = BTreeMap<String, String>;
+
+/// Collect the rendered signatures of the docs of the given crate found in `root`.
+pub(crate) fn collect(root: &Path, crate_name: &str) -> io::Result<Signatures> {
+    let mut signatures = Signatures::new();
+
+    for page in files(&root.join(crate_name), &["html"])? {
+        let html = std::fs::read_to_string(&page)?;
+        let page = page.strip_prefix(root).unwrap_or(&page).display().to_string();
+        for (anchor, signature) in extract(&html) {
+            signatures.insert(format!("{page}#{anchor}"), signature);
+        }
+    }
+
+    Ok(signatures)
+}
+
+/// Print the signatures that differ between the two builds.
+pub(crate) fn print_differences(before: &Signatures, after: &Signatures) -> usize {
+    let mut amount = 0;
+
+    for (location, signature) in before {
+        let Some(other) = after.get(location) else { continue };
+        if signature == other {
+            continue;
+        }
+
+        println!("`{location}`");
+        println!("    - {signature}");
+        println!("    + {other}");
+        amount += 1;
+    }
+
+    amount
+}
+
+/// Extract the item declaration and the code headers (of associated items and implementations)
+/// alongside the ID of the section they're found in.
+fn extract(page: &str) -> Vec<(String, String)> {
+    let mut signatures = Vec::new();
+    let mut section = None;
+    // The element whose text we're currently collecting alongside its nesting depth.
+    let mut current: Option<(&str, usize, String)> = None;
+
+    for token in html::tokenize(page) {
+        match token {
+            Token::StartTag(tag) => {
+                if let Some((name, depth, _)) = &mut current {
+                    if tag.name == *name {
+                        *depth += 1;
+                    }
+                    continue;
+                }
+
+                if tag.name == "section"
+                    && let Some(id) = tag.attribute("id")
+                {
+                    section = Some(id.to_owned());
+                }
+
+                let classes = tag.attribute("class").unwrap_or_default();
+                let has_class = |class| classes.split_ascii_whitespace().any(|name| name == class);
+
+                if tag.name == "pre" && has_class("item-decl") {
+                    section = Some("declaration".to_owned());
+                    current = Some((tag.name, 0, String::new()));
+                } else if matches!(tag.name, "h3" | "h4") && has_class("code-header") {
+                    current = Some((tag.name, 0, String::new()));
+                }
+            }
+            Token::EndTag(name) => {
+                let Some((current_name, depth, text)) = &mut current else { continue };
+                if name != *current_name {
+                    continue;
+                }
+                if *depth > 0 {
+                    *depth -= 1;
+                    continue;
+                }
+
+                let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                if let Some(section) = &section {
+                    signatures.push((section.clone(), text));
+                }
+                current = None;
+            }
+            Token::Text(fragment) => {
+                if let Some((_, _, text)) = &mut current {
+                    text.push_str(&html::decode(fragment));
+                }
+            }
+        }
+    }
+
+    signatures
+}
@@ -0,0 +1,15 @@
+use super::extract;
+
+#[test]
+fn signatures() {
+    let page = r##"<pre class="rust item-decl"><code>pub struct <b>S</b>;</code></pre>
+        <section id="impl-S" class="impl"><h3 class="code-header">impl S</h3></section>
+        <section id="method.f" class="method"><a class="src" href="x">source</a>
+        <h4 class="code-header">pub fn <a href="#method.f">f</a>(self) -&gt;
+            u8</h4></section>"##;
+    assert_eq!(extract(page), [
+        ("declaration".to_owned(), "pub struct S;".to_owned()),
+        ("impl-S".to_owned(), "impl S".to_owned()),
+        ("method.f".to_owned(), "pub fn f(self) -> u8".to_owned()),
+    ]);
+}
@@ -0,0 +1,266 @@
+//! Snapshot testing of individual pages of the HTML output.
+//!
+//! The normalized pages get stored as golden files in the directory `⟨stem⟩.snapshots/`
+//! next to the source file.
+
+use crate::{
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info},
+    utility::files,
+};
+use regex::Regex;
+use std::path::Path;
+
+#[cfg(test)]
+mod test;
+
+/// Compare the pages of the docs in `root` selected by the given patterns against their
+/// snapshots or update the snapshots if `bless` holds.
+///
+/// A pattern is either an item path like `krate::module::Item` or a glob like `krate/struct.*.html`.
+pub(crate) fn check(
+    root: &Path,
+    source: &Path,
+    patterns: &[String],
+    normalizer: &Normalizer,
+    bless: bool,
+) -> crate::error::Result {
+    let directory = source.with_extension("snapshots");
+    let pages = files(root, &["html"])?;
+
+    let mut failures = Vec::new();
+    let mut blessed = 0;
+
+    for pattern in patterns {
+        let globs = globs(pattern);
+        let selected: Vec<_> = pages
+            .iter()
+            .filter_map(|page| page.strip_prefix(root).ok())
+            .filter(|page| {
+                page.to_str().is_some_and(|page| globs.iter().any(|glob| matches(glob, page)))
+            })
+            .collect();
+
+        if selected.is_empty() {
+            failures.push(format!("no page matches `{pattern}`"));
+        }
+
+        for page in selected {
+            let actual = normalizer.normalize(&std::fs::read_to_string(root.join(page))?);
+            let snapshot = directory.join(page);
+
+            if bless {
+                if let Some(parent) = snapshot.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&snapshot, actual)?;
+                blessed += 1;
+                continue;
+            }
+
+            let Ok(expected) = std::fs::read_to_string(&snapshot) else {
+                failures.push(format!("missing snapshot for `{}`", page.display()));
+                continue;
+            };
+
+            if let Some((line, expected, actual)) = first_difference(&expected, &actual) {
+                failures.push(format!(
+                    "`{}` differs from its snapshot on line {line}: expected `{expected}`, found `{actual}`",
+                    page.display()
+                ));
+            }
+        }
+    }
+
+    if bless {
+        let s = if blessed == 1 { "" } else { "s" };
+        info(format!("updated {blessed} snapshot{s} in `{}`", directory.display())).emit();
+    }
+
+    if !failures.is_empty() {
+        return Err(Error { failures }.into());
+    }
+
+    Ok(())
+}
+
+/// The globs (relative to the documentation root) denoted by the given pattern.
+fn globs(pattern: &str) -> Vec<String> {
+    if !pattern.contains("::") {
+        return vec![pattern.to_owned()];
+    }
+
+    let segments: Vec<_> = pattern.split("::").collect();
+    let (name, modules) = segments.split_last().unwrap();
+    let modules = modules.join("/");
+    // We don't know the kind of the item (nor do we care).
+    vec![format!("{modules}/*.{name}.html"), format!("{modules}/{name}/index.html")]
+}
+
+/// Match the path against the glob where `*` matches anything but `/`
+/// and `**` matches anything.
+pub(crate) fn matches(glob: &str, path: &str) -> bool {
+    if let Some(glob) = glob.strip_prefix("**") {
+        return (0..=path.len())
+            .filter(|&index| path.is_char_boundary(index))
+            .any(|index| matches(glob, &path[index..]));
+    }
+
+    if let Some(glob) = glob.strip_prefix('*') {
+        let end = path.find('/').unwrap_or(path.len());
+        return (0..=end)
+            .filter(|&index| path.is_char_boundary(index))
+            .any(|index| matches(glob, &path[index..]));
+    }
+
+    let mut glob_chars = glob.chars();
+    let mut path_chars = path.chars();
+    match (glob_chars.next(), path_chars.next()) {
+        (None, None) => true,
+        (Some('?'), Some(char)) if char != '/' => matches(glob_chars.as_str(), path_chars.as_str()),
+        (Some(expected), Some(actual)) if expected == actual => {
+            matches(glob_chars.as_str(), path_chars.as_str())
+        }
+        _ => false,
+    }
+}
+
+/// The pipeline of normalizations that remove details from pages which vary between
+/// toolchains and builds.
+///
+/// The enabled built-in normalizations run first, followed by the user-defined rules in order.
+pub(crate) struct Normalizer {
+    pub(crate) builtins: Vec<Builtin>,
+    pub(crate) rules: Vec<Rule>,
+}
+
+impl Normalizer {
+    pub(crate) fn normalize(&self, page: &str) -> String {
+        let page = self.builtins.iter().fold(page.to_owned(), |page, builtin| builtin.apply(&page));
+        self.rules.iter().fold(page, |page, rule| {
+            rule.pattern.replace_all(&page, &rule.replacement).into_owned()
+        })
+    }
+}
+
+impl Default for Normalizer {
+    fn default() -> Self {
+        Self { builtins: Builtin::ALL.to_vec(), rules: Vec::new() }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Builtin {
+    LineEndings,
+    RustdocVersion,
+    ResourceHashes,
+}
+
+impl Builtin {
+    pub(crate) const ALL: [Self; 3] =
+        [Self::LineEndings, Self::RustdocVersion, Self::ResourceHashes];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::LineEndings => "line-endings",
+            Self::RustdocVersion => "rustdoc-version",
+            Self::ResourceHashes => "resource-hashes",
+        }
+    }
+
+    fn apply(self, page: &str) -> String {
+        match self {
+            Self::LineEndings => page.replace("\r\n", "\n"),
+            Self::RustdocVersion => replace_attribute(page, "data-rustdoc-version", "$VERSION"),
+            Self::ResourceHashes => remove_resource_hashes(page),
+        }
+    }
+}
+
+/// A user-defined normalization replacing all matches of the pattern.
+pub(crate) struct Rule {
+    pub(crate) pattern: Regex,
+    /// May refer to capture groups via `$N`.
+    pub(crate) replacement: String,
+}
+
+fn replace_attribute(page: &str, name: &str, replacement: &str) -> String {
+    let prefix = format!("{name}=\"");
+    let mut result = String::with_capacity(page.len());
+    let mut rest = page;
+
+    while let Some(start) = rest.find(&prefix) {
+        let start = start + prefix.len();
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        result.push_str(replacement);
+        rest = &rest[rest.find('"').unwrap_or(rest.len())..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Replace the hashes found in the file names of static files like `rustdoc-0123456789abcdef.css`.
+fn remove_resource_hashes(page: &str) -> String {
+    const LENGTH: usize = 16;
+
+    let mut result = String::with_capacity(page.len());
+    let mut rest = page;
+
+    while let Some(start) = rest.find('-') {
+        result.push_str(&rest[..=start]);
+        rest = &rest[start + 1..];
+
+        let is_hash = rest
+            .get(..LENGTH)
+            .is_some_and(|hash| hash.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f')))
+            && rest[LENGTH..].starts_with('.');
+
+        if is_hash {
+            result.push_str("$HASH");
+            rest = &rest[LENGTH..];
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// The line of the first difference alongside excerpts of both texts starting shortly before it.
+///
+/// We don't print entire lines since rustdoc emits rather long ones.
+pub(crate) fn first_difference(expected: &str, actual: &str) -> Option<(usize, String, String)> {
+    const CONTEXT: usize = 20;
+    const EXCERPT: usize = 60;
+
+    let start = expected
+        .char_indices()
+        .zip(actual.chars())
+        .find(|((_, expected), actual)| expected != actual)
+        .map(|((index, _), _)| index)
+        .or_else(|| (expected.len() != actual.len()).then(|| expected.len().min(actual.len())))?;
+
+    let line = expected[..start].matches('\n').count() + 1;
+    let excerpt = |text: &str| {
+        let prefix = &text[..start];
+        let offset = prefix.char_indices().rev().nth(CONTEXT - 1).map_or(0, |(index, _)| index);
+        let excerpt: String = text[offset..].chars().take(EXCERPT).collect();
+        if excerpt.is_empty() { "⟨EOF⟩".to_owned() } else { excerpt.replace('\n', "⏎") }
+    };
+
+    Some((line, excerpt(expected), excerpt(actual)))
+}
+
+pub(crate) struct Error {
+    failures: Vec<String>,
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        let amount = self.failures.len();
+        let s = if amount == 1 { "" } else { "s" };
+        let diagnostic = error(format!("{amount} snapshot check{s} failed"))
+            .note("rerun with `--bless` to update the snapshots");
+        self.failures.into_iter().fold(diagnostic, Diagnostic::note)
+    }
+}
@@ -0,0 +1,51 @@
+use super::{Builtin, Normalizer, Rule, first_difference, globs, matches};
+use regex::Regex;
+
+#[test]
+fn glob() {
+    assert!(matches("k/struct.*.html", "k/struct.S.html"));
+    assert!(!matches("k/struct.*.html", "k/inner/struct.S.html"));
+    assert!(matches("k/**.html", "k/inner/struct.S.html"));
+    assert!(matches("k/?n.*", "k/fn.f.html"));
+    assert!(!matches("k/index.html", "k/index.htm"));
+}
+
+#[test]
+fn item_path() {
+    assert_eq!(globs("k::inner::S"), ["k/inner/*.S.html", "k/inner/S/index.html"]);
+    assert_eq!(globs("k/*.html"), ["k/*.html"]);
+}
+
+#[test]
+fn normalization() {
+    assert_eq!(
+        Normalizer::default().normalize(
+            "<link href=\"../static.files/rustdoc-b778ab399e080a4b.css\">\r\n\
+             <meta data-rustdoc-version=\"1.83.0-nightly\" data-x=\"a-b\">"
+        ),
+        "<link href=\"../static.files/rustdoc-$HASH.css\">\n\
+         <meta data-rustdoc-version=\"$VERSION\" data-x=\"a-b\">"
+    );
+}
+
+#[test]
+fn custom_normalization() {
+    let normalizer = Normalizer {
+        builtins: vec![Builtin::LineEndings],
+        rules: vec![Rule {
+            pattern: Regex::new(r#"id="(\w+)-\d+""#).unwrap(),
+            replacement: "id=\"$1-$$N\"".to_owned(),
+        }],
+    };
+    assert_eq!(
+        normalizer.normalize("<a id=\"impl-12\">\r\n<meta data-rustdoc-version=\"1.83.0\">"),
+        "<a id=\"impl-$N\">\n<meta data-rustdoc-version=\"1.83.0\">"
+    );
+}
+
+#[test]
+fn difference() {
+    assert_eq!(first_difference("a\nb", "a\nb"), None);
+    assert_eq!(first_difference("a\nbc", "a\nbd"), Some((2, "a⏎bc".to_owned(), "a⏎bd".to_owned())));
+    assert_eq!(first_difference("a", "ab"), Some((1, "a".to_owned(), "ab".to_owned())));
+}
@@ -0,0 +1,203 @@
+//! Comparing the diagnostics of `rustdoc` against the `.stderr` files of rustdoc-ui tests.
+//!
+//! Like `compiletest`, we store them next to the test as `⟨stem⟩.stderr` or, for a single
+//! revision, as `⟨stem⟩.⟨revision⟩.stderr`.
+//!
+//! For small tests, the directives `//@ check-stderr-contains: ⟨TEXT⟩` and
+//! `//@ check-stderr-regex: ⟨REGEX⟩` are a lightweight alternative. They can be negated
+//! with `!`. Unlike a `.stderr` file, they don't permit errors unless the test is declared to
+//! fail via `//@ check-fail`.
+
+use crate::{
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info},
+    snapshot::first_difference,
+};
+use regex::Regex;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+#[cfg(test)]
+mod test;
+
+/// The path to the expected stderr of the given test.
+pub(crate) fn path(test: &Path, revision: Option<&str>) -> PathBuf {
+    let stem = test.file_stem().unwrap_or_default().to_string_lossy();
+    match revision {
+        Some(revision) => test.with_file_name(format!("{stem}.{revision}.stderr")),
+        None => test.with_file_name(format!("{stem}.stderr")),
+    }
+}
+
+/// Compare the normalized stderr against the expected one or update it if `bless` holds.
+///
+/// Just like `compiletest`, a missing file stands for an empty stderr.
+pub(crate) fn check(path: &Path, actual: &str, bless: bool) -> crate::error::Result {
+    if bless {
+        if actual.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(path)?;
+                info(format!("removed `{}` since the stderr is empty", path.display())).emit();
+            }
+        } else {
+            std::fs::write(path, actual)?;
+            info(format!("updated `{}`", path.display())).emit();
+        }
+        return Ok(());
+    }
+
+    let expected = match std::fs::read_to_string(path) {
+        Ok(expected) => expected.replace("\r\n", "\n"),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(error.into()),
+    };
+
+    if let Some((line, expected, actual)) = first_difference(&expected, actual) {
+        return Err(Error::Mismatch { path: path.to_owned(), line, expected, actual }.into());
+    }
+
+    Ok(())
+}
+
+/// An assertion on the (normalized) stderr given via a directive.
+#[derive(Clone)]
+pub(crate) struct Check<'src> {
+    pub(crate) kind: CheckKind<'src>,
+    pub(crate) negated: bool,
+    /// The line of the directive in the source file.
+    pub(crate) line: usize,
+}
+
+#[derive(Clone)]
+pub(crate) enum CheckKind<'src> {
+    Contains(&'src str),
+    Regex { regex: Regex, pattern: &'src str },
+}
+
+impl Check<'_> {
+    fn holds(&self, stderr: &str) -> bool {
+        let found = match &self.kind {
+            CheckKind::Contains(text) => stderr.contains(text),
+            CheckKind::Regex { regex, .. } => regex.is_match(stderr),
+        };
+        found != self.negated
+    }
+}
+
+impl fmt::Display for Check<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let negation = if self.negated { "!" } else { "" };
+        let (name, argument) = match self.kind {
+            CheckKind::Contains(text) => ("contains", text),
+            CheckKind::Regex { pattern, .. } => ("regex", pattern),
+        };
+        write!(f, "`//@ {negation}check-stderr-{name}: {argument}` on line {}", self.line)
+    }
+}
+
+/// Check that the normalized stderr satisfies all the given checks.
+pub(crate) fn check_contents(
+    path: &Path,
+    checks: &[Check<'_>],
+    actual: &str,
+) -> crate::error::Result {
+    let failures: Vec<_> =
+        checks.iter().filter(|check| !check.holds(actual)).map(ToString::to_string).collect();
+
+    if !failures.is_empty() {
+        return Err(Error::Unsatisfied { path: path.to_owned(), failures }.into());
+    }
+
+    Ok(())
+}
+
+/// Normalize the stderr of `rustdoc` like `compiletest` does.
+///
+/// Paths inside the `directory` of the test get replaced with `$DIR` and paths into the
+/// sources of the standard library with `$SRC_DIR`. Relative paths in locations are resolved
+/// against the `base` directory `rustdoc` ran in first.
+pub(crate) fn normalize(stderr: &str, directory: &Path, base: &Path) -> String {
+    let directory = directory.to_string_lossy();
+    let mut normalized = String::with_capacity(stderr.len());
+
+    for line in stderr.replace("\r\n", "\n").split_inclusive('\n') {
+        let line = absolutize_location(line, base);
+        normalized.push_str(&line.replace(&*directory, "$DIR"));
+    }
+
+    remove_source_paths(&normalized)
+}
+
+/// Replace the paths like `/rustc/⟨commit hash⟩/library` the standard library is remapped to.
+fn remove_source_paths(stderr: &str) -> String {
+    const PREFIX: &str = "/rustc/";
+    const LENGTH: usize = 40;
+
+    let mut result = String::with_capacity(stderr.len());
+    let mut rest = stderr;
+
+    while let Some(start) = rest.find(PREFIX) {
+        result.push_str(&rest[..start]);
+        rest = &rest[start + PREFIX.len()..];
+
+        let is_source_path = rest
+            .get(..LENGTH)
+            .is_some_and(|hash| hash.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f')))
+            && rest[LENGTH..].starts_with("/library");
+
+        match is_source_path {
+            true => {
+                result.push_str("$SRC_DIR");
+                rest = &rest[LENGTH + "/library".len()..];
+            }
+            false => result.push_str(PREFIX),
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Make the path of the location in lines like `  --> file.rs:1:1` absolute.
+fn absolutize_location(line: &str, base: &Path) -> String {
+    let Some(start) = ["--> ", "::: "].into_iter().find_map(|marker| {
+        let start = line.find(marker)? + marker.len();
+        line[..start - marker.len()].trim().is_empty().then_some(start)
+    }) else {
+        return line.to_owned();
+    };
+
+    let (prefix, location) = line.split_at(start);
+    if location.starts_with('/') || location.starts_with('$') {
+        return line.to_owned();
+    }
+    let location = location.strip_prefix("./").unwrap_or(location);
+    format!("{prefix}{}", base.join(location).display())
+}
+
+pub(crate) enum Error {
+    Mismatch { path: PathBuf, line: usize, expected: String, actual: String },
+    Unsatisfied { path: PathBuf, failures: Vec<String> },
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::Mismatch { path, line, expected, actual } => {
+                error(format!("the stderr differs from `{}` on line {line}", path.display()))
+                    .note(format!("expected `{expected}`"))
+                    .note(format!("found `{actual}`"))
+                    .note("rerun with `--bless` to update it")
+            }
+            Self::Unsatisfied { path, failures } => {
+                let amount = failures.len();
+                let s = if amount == 1 { "" } else { "s" };
+                failures.into_iter().fold(
+                    error(format!("{amount} stderr check{s} of `{}` failed", path.display())),
+                    Diagnostic::note,
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use super::{Check, CheckKind, check_contents, normalize, path};
+use regex::Regex;
+use std::path::Path;
+
+#[test]
+fn expected_path() {
+    assert_eq!(path(Path::new("ui/lint.rs"), None), Path::new("ui/lint.stderr"));
+    assert_eq!(path(Path::new("ui/lint.rs"), Some("a")), Path::new("ui/lint.a.stderr"));
+}
+
+#[test]
+fn normalization() {
+    let stderr = "error: unresolved link to `X`\r\n \
+                  --> ui/lint.rs:1:6\n  \
+                  ::: /rustc/fa724e5d8cbbdfbd1e53c4c656121af01b694406/library/core/src/lib.rs:2:1\n";
+    assert_eq!(
+        normalize(stderr, Path::new("/work/ui"), Path::new("/work")),
+        "error: unresolved link to `X`\n \
+         --> $DIR/lint.rs:1:6\n  \
+         ::: $SRC_DIR/core/src/lib.rs:2:1\n"
+    );
+}
+
+#[test]
+fn unrelated_arrows() {
+    let stderr = "note: `a --> b` isn't a location\n";
+    assert_eq!(normalize(stderr, Path::new("/work"), Path::new("/work")), stderr);
+}
+
+#[test]
+fn checks() {
+    let check = |kind, negated| Check { kind, negated, line: 1 };
+    let regex = |pattern| CheckKind::Regex { regex: Regex::new(pattern).ok().unwrap(), pattern };
+    let stderr = "warning: unresolved link to `X`\n --> $DIR/lint.rs:1:6\n";
+    let path = Path::new("lint.rs");
+
+    assert!(
+        check_contents(path, &[check(CheckKind::Contains("link to `X`"), false)], stderr).is_ok()
+    );
+    assert!(
+        check_contents(path, &[check(CheckKind::Contains("link to `Y`"), true)], stderr).is_ok()
+    );
+    assert!(check_contents(path, &[check(regex(r"lint\.rs:\d+:\d+"), false)], stderr).is_ok());
+    assert!(check_contents(path, &[check(regex(r"^error"), false)], stderr).is_err());
+    assert!(check_contents(path, &[check(CheckKind::Contains("warning"), true)], stderr).is_err());
+}
@@ -0,0 +1,135 @@
+//! Building `core` and `alloc` from a Rust checkout for use as the sysroot (`--build-sysroot`).
+//!
+//! This is meant for documenting `#![no_std]` and `#![no_core]` crates against a locally modified
+//! standard library. `compiler_builtins` isn't part of older checkouts (it comes from crates.io),
+//! so it gets replaced by an empty stub. That's fine for building docs but the resulting sysroot
+//! can't be used for linking executables.
+
+use crate::{
+    cli, command,
+    data::{CrateNameRef, Edition},
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info},
+    toolchain,
+    utility::{out_path, scratch_dir},
+};
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
+
+#[cfg(test)]
+mod test;
+
+/// Records what the sysroot was built from, so it only gets rebuilt if that changes.
+const STAMP: &str = ".rruxwry-stamp";
+
+const COMPILER_BUILTINS_STUB: &str = "\
+#![feature(compiler_builtins)]
+#![allow(internal_features)]
+#![compiler_builtins]
+#![no_builtins]
+#![no_std]
+";
+
+/// Build the sysroot from the given checkout unless it's up to date and return its path.
+pub(crate) fn build(
+    checkout: &Path,
+    build_flags: &cli::BuildFlags,
+    program_flags: &cli::ProgramFlags,
+) -> crate::error::Result<PathBuf> {
+    let library = checkout.join("library");
+    if !library.join("core/src/lib.rs").is_file() {
+        return Err(Error::NotACheckout(checkout.to_owned()).into());
+    }
+
+    let target = match &build_flags.target {
+        Some(target) => target.clone(),
+        None => {
+            let version = query(&["-vV"], build_flags, program_flags)?;
+            let host = version.lines().find_map(|line| line.strip_prefix("host: "));
+            host.unwrap_or_default().to_owned()
+        }
+    };
+    let sysroot = out_path(build_flags, "sysroot");
+    let libdir = sysroot.join("lib/rustlib").join(&target).join("lib");
+
+    let version = query(&["--version"], build_flags, program_flags)?;
+    let stamp = format!("{version}\n{target}\n{}\n", checkout.canonicalize()?.display());
+    if std::fs::read_to_string(libdir.join(STAMP)).is_ok_and(|existing| existing == stamp) {
+        if program_flags.verbose {
+            info(format!("reusing the sysroot at `{}`", sysroot.display()))
+                .note(format!("delete it to rebuild it from `{}`", checkout.display()))
+                .emit();
+        }
+        return Ok(sysroot);
+    }
+
+    if !program_flags.dry_run {
+        std::fs::create_dir_all(&libdir)?;
+    }
+    let stub = scratch_dir(program_flags)?.join("rruxwry-compiler-builtins.rs");
+    std::fs::write(&stub, COMPILER_BUILTINS_STUB)?;
+
+    // In dependency order.
+    let crates = [
+        ("core", library.join("core/src/lib.rs"), manifest_edition(&library.join("core"))),
+        ("compiler_builtins", stub, Edition::Edition2021),
+        ("alloc", library.join("alloc/src/lib.rs"), manifest_edition(&library.join("alloc"))),
+    ];
+    for (crate_name, path, edition) in crates {
+        let output = libdir.join(format!("lib{crate_name}.rlib"));
+        command::compile_sysroot_crate(
+            &path,
+            CrateNameRef::new_unchecked(crate_name),
+            edition,
+            &sysroot,
+            &output,
+            build_flags,
+            program_flags,
+        )?;
+    }
+
+    if !program_flags.dry_run {
+        std::fs::write(libdir.join(STAMP), stamp)?;
+    }
+
+    Ok(sysroot)
+}
+
+fn query(
+    arguments: &[&str],
+    build_flags: &cli::BuildFlags,
+    program_flags: &cli::ProgramFlags,
+) -> crate::error::Result<String> {
+    toolchain::query("rustc", arguments, process::Stdio::inherit(), build_flags, program_flags)
+}
+
+/// The edition declared in the manifest of the given package of the standard library.
+fn manifest_edition(package: &Path) -> Edition {
+    std::fs::read_to_string(package.join("Cargo.toml"))
+        .ok()
+        .and_then(|manifest| parse_edition(&manifest))
+        .unwrap_or(Edition::Edition2021)
+}
+
+fn parse_edition(manifest: &str) -> Option<Edition> {
+    manifest.lines().find_map(|line| {
+        let value = line.strip_prefix("edition")?.trim_start().strip_prefix('=')?;
+        value.trim().strip_prefix('"')?.strip_suffix('"')?.parse().ok()
+    })
+}
+
+pub(crate) enum Error {
+    NotACheckout(PathBuf),
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::NotACheckout(path) => {
+                error(format!("`{}` isn't a checkout of the Rust repository", path.display()))
+                    .note("it has to contain `library/core/src/lib.rs`")
+            }
+        }
+    }
+}
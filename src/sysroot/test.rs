@@ -0,0 +1,11 @@
+use super::parse_edition;
+use crate::data::Edition;
+
+#[test]
+fn edition() {
+    let manifest = "[package]\nname = \"core\"\n# The edition.\nedition = \"2021\"\n";
+    assert!(parse_edition(manifest) == Some(Edition::Edition2021));
+    assert!(parse_edition("edition=\"2024\"") == Some(Edition::Edition2024));
+    assert!(parse_edition("[package]\nname = \"alloc\"\n").is_none());
+    assert!(parse_edition("edition.workspace = true").is_none());
+}
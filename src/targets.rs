@@ -0,0 +1,144 @@
+//! Comparing the documented items across targets (`--compare-targets`).
+//!
+//! The items are taken from the JSON output of each target and keyed by their path, so the
+//! unstable item IDs don't get in the way. Besides the items listed in `paths`, we also consider
+//! the associated items of inherent impls and traits since those are often cfg-gated, too.
+
+use crate::{
+    diagnostic::{Diagnostic, info},
+    json::{id_to_key, item_kind},
+};
+use joinery::JoinableIterator;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod test;
+
+/// The items of a crate keyed by path.
+pub(crate) type Items = BTreeMap<String, Item>;
+
+pub(crate) struct Item {
+    kind: String,
+    /// The `#[cfg]` attributes of the item.
+    cfgs: Vec<String>,
+}
+
+/// Collect the items of the local crate from the given JSON output.
+pub(crate) fn collect(json: &Value) -> Items {
+    let mut items = Items::new();
+
+    for (id, summary) in json["paths"].as_object().into_iter().flatten() {
+        if summary["crate_id"] != 0 {
+            continue;
+        }
+        let Some(path) = summary["path"].as_array() else { continue };
+        let path = path.iter().filter_map(Value::as_str).join_with("::").to_string();
+        let kind = summary["kind"].as_str().unwrap_or("item").to_owned();
+        let item = &json["index"][id];
+
+        for (name, associated) in associated_items(json, item) {
+            let kind = item_kind(associated).unwrap_or("item").to_owned();
+            items.insert(format!("{path}::{name}"), Item { kind, cfgs: cfgs(associated) });
+        }
+        items.insert(path, Item { kind, cfgs: cfgs(item) });
+    }
+
+    items
+}
+
+/// The associated items of the given type (from its inherent impls) or trait.
+fn associated_items<'a>(json: &'a Value, item: &'a Value) -> Vec<(&'a str, &'a Value)> {
+    let lookup = |ids: &'a Value| {
+        ids.as_array().into_iter().flatten().filter_map(|id| json["index"].get(id_to_key(id)))
+    };
+
+    let associated: Vec<_> = match item_kind(item) {
+        Some(kind @ ("struct" | "enum" | "union")) => lookup(&item["inner"][kind]["impls"])
+            .filter(|implementation| implementation["inner"]["impl"]["trait"].is_null())
+            .flat_map(|implementation| lookup(&implementation["inner"]["impl"]["items"]))
+            .collect(),
+        Some("trait") => lookup(&item["inner"]["trait"]["items"]).collect(),
+        _ => Vec::new(),
+    };
+
+    associated.into_iter().filter_map(|item| Some((item["name"].as_str()?, item))).collect()
+}
+
+fn cfgs(item: &Value) -> Vec<String> {
+    item["attrs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .filter(|attr| attr.starts_with("#[cfg("))
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// An item that only exists on some of the targets.
+pub(crate) struct Difference<'a> {
+    path: &'a str,
+    kind: &'a str,
+    /// The `#[cfg]` attributes of the item on the targets it exists on.
+    cfgs: Vec<&'a str>,
+    targets: Vec<&'a str>,
+}
+
+/// The items that don't exist on all of the given targets.
+pub(crate) fn differences(targets: &[(String, Items)]) -> Vec<Difference<'_>> {
+    let mut presence: BTreeMap<&str, Vec<(&str, &Item)>> = BTreeMap::new();
+    for (target, items) in targets {
+        for (path, item) in items {
+            presence.entry(path).or_default().push((target, item));
+        }
+    }
+
+    presence
+        .into_iter()
+        .filter(|(_, present)| present.len() != targets.len())
+        .map(|(path, present)| {
+            let mut cfgs: Vec<_> =
+                present.iter().flat_map(|(_, item)| &item.cfgs).map(String::as_str).collect();
+            cfgs.sort_unstable();
+            cfgs.dedup();
+            Difference {
+                path,
+                kind: &present[0].1.kind,
+                cfgs,
+                targets: present.iter().map(|&(target, _)| target).collect(),
+            }
+        })
+        .collect()
+}
+
+/// Report the items that only exist on some of the given targets.
+pub(crate) fn report(targets: &[(String, Items)]) {
+    let differences = differences(targets);
+    let amount = targets.len();
+
+    if differences.is_empty() {
+        let items = targets.first().map_or(0, |(_, items)| items.len());
+        let s = if items == 1 { "" } else { "s" };
+        info(format!("all {amount} targets document the same {items} item{s}")).emit();
+        return;
+    }
+
+    let items = differences.len();
+    let (s, verb) = if items == 1 { ("", "exists") } else { ("s", "exist") };
+    differences
+        .iter()
+        .map(|difference| {
+            let targets = difference.targets.iter().map(|target| format!("`{target}`"));
+            let mut note = format!("`{}` ({})", difference.path, difference.kind);
+            if !difference.cfgs.is_empty() {
+                note += &format!(" gated by `{}`", difference.cfgs.iter().join_with(" "));
+            }
+            note + &format!(": only on {}", targets.join_with(", "))
+        })
+        .fold(
+            info(format!("{items} item{s} only {verb} on some of the {amount} targets")),
+            Diagnostic::note,
+        )
+        .emit();
+}
@@ -0,0 +1,59 @@
+use super::{Items, collect, differences};
+use serde_json::json;
+
+fn items(json: serde_json::Value) -> Items {
+    collect(&json)
+}
+
+#[test]
+fn associated_items() {
+    let items = items(json!({
+        "index": {
+            "0": { "name": "S", "attrs": [], "inner": { "struct": { "impls": [1, 3] } } },
+            "1": { "attrs": [], "inner": { "impl": { "trait": null, "items": [2] } } },
+            "2": {
+                "name": "new",
+                "attrs": ["#[cfg(unix)]", "#[inline]"],
+                "inner": { "function": {} }
+            },
+            "3": { "attrs": [], "inner": { "impl": { "trait": { "name": "Clone" }, "items": [4] } } },
+            "4": { "name": "clone", "attrs": [], "inner": { "function": {} } },
+        },
+        "paths": {
+            "0": { "crate_id": 0, "path": ["krate", "S"], "kind": "struct" },
+            "5": { "crate_id": 1, "path": ["core", "clone", "Clone"], "kind": "trait" },
+        },
+    }));
+
+    let paths: Vec<_> = items.keys().map(String::as_str).collect();
+    assert_eq!(paths, ["krate::S", "krate::S::new"]);
+    assert_eq!(items["krate::S::new"].kind, "function");
+    assert_eq!(items["krate::S::new"].cfgs, ["#[cfg(unix)]"]);
+}
+
+#[test]
+fn presence() {
+    let linux = items(json!({
+        "index": {
+            "0": { "attrs": [], "inner": { "function": {} } },
+            "1": { "attrs": ["#[cfg(target_os = \"linux\")]"], "inner": { "function": {} } },
+        },
+        "paths": {
+            "0": { "crate_id": 0, "path": ["krate", "everywhere"], "kind": "function" },
+            "1": { "crate_id": 0, "path": ["krate", "linux"], "kind": "function" },
+        },
+    }));
+    let windows = items(json!({
+        "index": { "7": { "attrs": [], "inner": { "function": {} } } },
+        "paths": { "7": { "crate_id": 0, "path": ["krate", "everywhere"], "kind": "function" } },
+    }));
+
+    let targets = [("linux".to_owned(), linux), ("windows".to_owned(), windows)];
+    let differences = differences(&targets);
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].path, "krate::linux");
+    assert_eq!(differences[0].cfgs, ["#[cfg(target_os = \"linux\")]"]);
+    assert_eq!(differences[0].targets, ["linux"]);
+
+    assert!(super::differences(&targets[..1]).is_empty());
+}
@@ -0,0 +1,40 @@
+//! Measuring the duration of the individual steps of a run.
+
+use crate::{diagnostic::info, utility::Str};
+use std::{
+    mem,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+static STEPS: Mutex<Vec<Step>> = Mutex::new(Vec::new());
+
+pub(crate) struct Step {
+    pub(crate) name: Str,
+    pub(crate) duration: Duration,
+}
+
+/// Execute the given step and record how long it took.
+pub(crate) fn time<T>(name: impl Into<Str>, step: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = step();
+    let duration = start.elapsed();
+    STEPS.lock().unwrap().push(Step { name: name.into(), duration });
+    result
+}
+
+/// Take all steps recorded so far.
+pub(crate) fn take() -> Vec<Step> {
+    mem::take(&mut *STEPS.lock().unwrap())
+}
+
+pub(crate) fn publish(steps: &[Step]) {
+    let total: Duration = steps.iter().map(|step| step.duration).sum();
+
+    steps
+        .iter()
+        .fold(info(format!("took {:.2?} in total", total)), |diagnostic, step| {
+            diagnostic.note(format!("{:.2?}: {}", step.duration, step.name))
+        })
+        .emit();
+}
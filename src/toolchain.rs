@@ -0,0 +1,340 @@
+//! Probing the toolchain and checking whether it supports the requested features.
+
+use crate::{
+    backend::Backend,
+    cli,
+    data::{Edition, ErrorFormat},
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info, warning},
+    utility::SmallVec,
+};
+use std::{fmt, path::Path, process, str::FromStr};
+
+#[cfg(test)]
+mod test;
+
+/// Check that the selected `rustdoc` is new enough for the requested features.
+///
+/// This only probes the toolchain if any of the requested features has a requirement.
+pub(crate) fn check_requirements(
+    edition: Option<Edition>,
+    build_flags: &cli::BuildFlags,
+    program_flags: &cli::ProgramFlags,
+) -> Result<(), Error> {
+    let requirements = requirements(edition, build_flags);
+    if requirements.is_empty() {
+        return Ok(());
+    }
+
+    let Some(version) = probe(build_flags, program_flags) else {
+        // We couldn't figure out the version. Let the actual build fail instead if applicable.
+        return Ok(());
+    };
+    let Some(date) = version.commit_date else {
+        return Ok(());
+    };
+
+    // All of these features are unstable, so we also require a nightly toolchain.
+    let nightly = version.is_nightly();
+
+    for requirement in requirements {
+        if !nightly || date < requirement.date {
+            return Err(Error::UnsupportedFeature { requirement, found: version });
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that the standard library is installed for the given target.
+///
+/// Crates that don't depend on it (e.g., `#![no_core]` ones) don't need it, so this only warns.
+pub(crate) fn check_target(
+    target: &str,
+    build_flags: &cli::BuildFlags,
+    program_flags: &cli::ProgramFlags,
+) -> crate::error::Result {
+    let arguments = ["--print", "target-libdir", "--target", target];
+    let libdir = query("rustc", &arguments, process::Stdio::inherit(), build_flags, program_flags)?;
+
+    if !Path::new(&libdir).is_dir() {
+        let toolchain = match &build_flags.toolchain {
+            Some(toolchain) => format!(" --toolchain {toolchain}"),
+            None => String::new(),
+        };
+        warning(format!("the standard library isn't installed for the target `{target}`"))
+            .note(format!("you can install it via `rustup target add {target}{toolchain}`"))
+            .emit();
+    }
+
+    Ok(())
+}
+
+/// The features that need a `rustdoc` newer than a certain date.
+///
+/// The dates are the ones on which the PRs that introduced the features were merged.
+fn requirements(edition: Option<Edition>, flags: &cli::BuildFlags) -> SmallVec<Requirement, 4> {
+    let mut requirements = SmallVec::new();
+
+    if flags.stdout {
+        // That's when rustdoc learned to interpret `-o -` as stdout for JSON output.
+        requirements.push(Requirement { feature: "--stdout", date: Date::new(2024, 4, 3) });
+    }
+    if flags.error_format == Some(ErrorFormat::HumanUnicode) {
+        // It's definitely not available in nightly-2024-09-28.
+        requirements.push(Requirement {
+            feature: "--error-format human-unicode",
+            date: Date::new(2024, 10, 1),
+        });
+    }
+    if edition == Some(Edition::Future) {
+        // That's when rustc learned about `--edition future` (rust-lang/rust#137606).
+        requirements
+            .push(Requirement { feature: "--edition future", date: Date::new(2025, 2, 28) });
+    }
+    if flags.output_format.as_deref() == Some("doctest") {
+        // That's when rustdoc learned about `--output-format doctest` (rust-lang/rust#134531).
+        requirements
+            .push(Requirement { feature: "--output-format doctest", date: Date::new(2025, 3, 1) });
+    }
+    if flags.hidden {
+        // That's when rustdoc learned about `--document-hidden-items` (rust-lang/rust#67875).
+        requirements.push(Requirement { feature: "--hidden", date: Date::new(2020, 1, 24) });
+    }
+    if flags.rustc_verbose_internals {
+        // That's when `-Zverbose` was renamed to `-Zverbose-internals`.
+        requirements.push(Requirement { feature: "--internals", date: Date::new(2023, 12, 19) });
+    }
+
+    requirements
+}
+
+/// Probe the version of the selected `rustdoc`.
+pub(crate) fn probe(
+    build_flags: &cli::BuildFlags,
+    program_flags: &cli::ProgramFlags,
+) -> Option<Version> {
+    let mut command = process::Command::new("rustdoc");
+    if let Some(toolchain) = &build_flags.toolchain {
+        command.arg(format!("+{toolchain}"));
+    }
+    command.arg("--version");
+
+    let output = Backend::new(program_flags)
+        .run(&mut command, |command| command.stderr(process::Stdio::null()).output())
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let version = String::from_utf8(output.stdout).ok()?.trim().parse::<Version>().ok()?;
+
+    if program_flags.verbose {
+        info(format!("probed toolchain: {version}")).emit();
+    }
+
+    Some(version)
+}
+
+/// Report what the toolchain resolves to for the given flags.
+pub(crate) fn report(
+    build_flags: &cli::BuildFlags,
+    program_flags: &cli::ProgramFlags,
+) -> crate::error::Result {
+    let name = match &build_flags.toolchain {
+        Some(toolchain) => format!("`{toolchain}` (via `-t`/`--toolchain`)"),
+        None => match std::env::var("RUSTUP_TOOLCHAIN") {
+            Ok(toolchain) => format!("`{toolchain}` (via `RUSTUP_TOOLCHAIN`)"),
+            Err(_) => "the one rustup resolves by default (incl. directory overrides)".to_owned(),
+        },
+    };
+
+    let query = |program, arguments: &[_]| {
+        query(program, arguments, process::Stdio::inherit(), build_flags, program_flags)
+    };
+
+    let rustdoc = query("rustdoc", &["--version"])?;
+    let rustc = query("rustc", &["--version"])?;
+    let host = query("rustc", &["-vV"])?;
+    let host = host.lines().find_map(|line| line.strip_prefix("host: ")).unwrap_or("unknown");
+    let sysroot = query("rustc", &["--print", "sysroot"])?;
+    let target_libdir = query("rustc", &["--print", "target-libdir"])?;
+    let channel = match rustdoc.parse::<Version>() {
+        Ok(version) if version.is_nightly() => "nightly",
+        Ok(_) => "not nightly (unstable features are unavailable)",
+        Err(()) => "unknown",
+    };
+
+    info(format!("the selected toolchain is {name}"))
+        .note(format!("rustdoc: {rustdoc}"))
+        .note(format!("rustc: {rustc}"))
+        .note(format!("channel: {channel}"))
+        .note(format!("host: {host}"))
+        .note(format!("sysroot: {sysroot}"))
+        .note(format!("target libdir: {target_libdir}"))
+        .emit();
+
+    Ok(())
+}
+
+/// Print the requested piece of information about the selected toolchain to stdout.
+pub(crate) fn print(
+    command: cli::PrintCommand,
+    build_flags: &cli::BuildFlags,
+    program_flags: &cli::ProgramFlags,
+) -> crate::error::Result {
+    let (program, arguments): (_, &[_]) = match command {
+        cli::PrintCommand::Sysroot => ("rustc", &["--print", "sysroot"]),
+        cli::PrintCommand::TargetLibdir => ("rustc", &["--print", "target-libdir"]),
+        cli::PrintCommand::RustdocVersion => ("rustdoc", &["--version"]),
+    };
+    println!(
+        "{}",
+        query(program, arguments, process::Stdio::inherit(), build_flags, program_flags)?
+    );
+    Ok(())
+}
+
+/// Run the given program of the selected toolchain and return its trimmed stdout.
+pub(crate) fn query(
+    program: &'static str,
+    arguments: &[&str],
+    stderr: process::Stdio,
+    build_flags: &cli::BuildFlags,
+    program_flags: &cli::ProgramFlags,
+) -> crate::error::Result<String> {
+    let mut command = process::Command::new(program);
+    if let Some(toolchain) = &build_flags.toolchain {
+        command.arg(format!("+{toolchain}"));
+    }
+    command.args(arguments);
+
+    if program_flags.verbose {
+        info(format!("running `{program} {}`", arguments.join(" "))).emit();
+    }
+
+    let output =
+        Backend::new(program_flags).run(&mut command, |command| command.stderr(stderr).output())?;
+    output.status.exit_ok()?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// The output of `rustdoc --version`, e.g., `rustdoc 1.83.0-nightly (fa724e5d8 2024-09-27)`.
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug))]
+pub(crate) struct Version {
+    pub(crate) release: String,
+    pub(crate) commit_hash: Option<String>,
+    pub(crate) commit_date: Option<Date>,
+}
+
+impl Version {
+    pub(crate) fn is_nightly(&self) -> bool {
+        self.release.ends_with("-nightly") || self.release.ends_with("-dev")
+    }
+}
+
+impl FromStr for Version {
+    type Err = ();
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let source = source.strip_prefix("rustdoc ").ok_or(())?;
+        let (release, commit) = match source.split_once(' ') {
+            Some((release, commit)) => (release, Some(commit)),
+            None => (source, None),
+        };
+
+        let commit = commit.and_then(|commit| commit.strip_prefix('(')?.strip_suffix(')'));
+        let (commit_hash, commit_date) = match commit.and_then(|commit| commit.split_once(' ')) {
+            Some((hash, date)) => (Some(hash.to_owned()), date.parse().ok()),
+            None => (None, None),
+        };
+
+        Ok(Self { release: release.to_owned(), commit_hash, commit_date })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rustdoc {}", self.release)?;
+        match (&self.commit_hash, self.commit_date) {
+            (Some(hash), Some(date)) => write!(f, " ({hash} {date})"),
+            (Some(hash), None) => write!(f, " ({hash})"),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(test, derive(Debug))]
+pub(crate) struct Date {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl Date {
+    pub(crate) const fn new(year: u16, month: u8, day: u8) -> Self {
+        Self { year, month, day }
+    }
+
+    /// The day after this one.
+    pub(crate) fn next(self) -> Self {
+        let leap = self.year % 4 == 0 && (self.year % 100 != 0 || self.year % 400 == 0);
+        let days = match self.month {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        };
+        match (self.day < days, self.month < 12) {
+            (true, _) => Self { day: self.day + 1, ..self },
+            (false, true) => Self { month: self.month + 1, day: 1, ..self },
+            (false, false) => Self { year: self.year + 1, month: 1, day: 1 },
+        }
+    }
+}
+
+impl FromStr for Date {
+    type Err = ();
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let mut parts = source.splitn(3, '-');
+        let mut part = || parts.next().ok_or(());
+        let year = part()?.parse().map_err(drop)?;
+        let month = part()?.parse().map_err(drop)?;
+        let day = part()?.parse().map_err(drop)?;
+
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return Err(());
+        }
+
+        Ok(Self { year, month, day })
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+pub(crate) struct Requirement {
+    feature: &'static str,
+    date: Date,
+}
+
+pub(crate) enum Error {
+    UnsupportedFeature { requirement: Requirement, found: Version },
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::UnsupportedFeature { requirement, found } => {
+                error(format!("`{}` requires nightly >= {}", requirement.feature, requirement.date))
+                    .note(format!("the selected toolchain is `{found}`"))
+                    .note("you can select a different toolchain with `-t`/`--toolchain`")
+            }
+        }
+    }
+}
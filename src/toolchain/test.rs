@@ -0,0 +1,49 @@
+use super::{Date, Version};
+
+fn parse(source: &str) -> Version {
+    source.parse().unwrap()
+}
+
+#[test]
+fn version_nightly() {
+    let version = parse("rustdoc 1.83.0-nightly (fa724e5d8 2024-09-27)");
+    assert_eq!(version.release, "1.83.0-nightly");
+    assert_eq!(version.commit_hash.as_deref(), Some("fa724e5d8"));
+    assert_eq!(version.commit_date, Some(Date::new(2024, 9, 27)));
+}
+
+#[test]
+fn version_without_commit_info() {
+    let version = parse("rustdoc 1.84.0-dev");
+    assert_eq!(version.release, "1.84.0-dev");
+    assert_eq!(version.commit_hash, None);
+    assert_eq!(version.commit_date, None);
+}
+
+#[test]
+fn version_garbage() {
+    assert!("rustc 1.83.0".parse::<Version>().is_err());
+}
+
+#[test]
+fn date_order() {
+    assert!("2024-09-27".parse::<Date>().unwrap() < Date::new(2024, 10, 1));
+    assert!("2024-13-01".parse::<Date>().is_err());
+}
+
+#[test]
+fn date_next() {
+    assert_eq!(Date::new(2024, 9, 27).next(), Date::new(2024, 9, 28));
+    assert_eq!(Date::new(2024, 9, 30).next(), Date::new(2024, 10, 1));
+    assert_eq!(Date::new(2024, 2, 28).next(), Date::new(2024, 2, 29));
+    assert_eq!(Date::new(2100, 2, 28).next(), Date::new(2100, 3, 1));
+    assert_eq!(Date::new(2024, 12, 31).next(), Date::new(2025, 1, 1));
+}
+
+#[test]
+fn version_channel() {
+    assert!(parse("rustdoc 1.83.0-nightly (fa724e5d8 2024-09-27)").is_nightly());
+    assert!(parse("rustdoc 1.84.0-dev").is_nightly());
+    assert!(!parse("rustdoc 1.95.0 (59807616e 2026-04-14)").is_nightly());
+    assert!(!parse("rustdoc 1.96.0-beta.3 (2d31a8e4b 2026-05-02)").is_nightly());
+}
@@ -0,0 +1,203 @@
+//! Explaining how rustdoc resolved and rendered a given item path.
+//!
+//! This correlates the `use` items of the JSON output with the pages of the HTML output.
+
+use crate::{
+    consistency::{is_redirect, page_prefix},
+    data::CrateNameRef,
+    diagnostic::{Diagnostic, IntoDiagnostic, error},
+    json::{id_to_key, item_kind},
+};
+use joinery::JoinableIterator;
+use serde_json::Value;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(test)]
+mod test;
+
+/// Print how the item at the given path of the given crate found in `root` got resolved & rendered.
+pub(crate) fn trace(root: &Path, crate_name: CrateNameRef<'_>, path: &str) -> crate::error::Result {
+    let json = std::fs::read(root.join(format!("{crate_name}.json")))?;
+    let json: Value = serde_json::from_slice(&json).map_err(io::Error::from)?;
+    let json = Json(&json);
+
+    let mut segments: Vec<_> = path.split("::").collect();
+    if segments.first() == Some(&crate_name.as_str()) {
+        segments.remove(0);
+    }
+    let Some((name, modules)) = segments.split_last() else {
+        return Err(Error::UnknownItem(path.to_owned()).into());
+    };
+
+    let mut module = json.item(&json.0["root"]);
+    for segment in modules {
+        module = module.and_then(|module| json.module(module, segment));
+    }
+    let module = module.ok_or_else(|| Error::UnknownItem(path.to_owned()))?;
+
+    let module_path: Vec<_> =
+        std::iter::once(crate_name.as_str()).chain(modules.iter().copied()).collect();
+    let directory: PathBuf =
+        std::iter::once(root).chain(module_path.iter().map(Path::new)).collect();
+    let module_path = module_path.join("::");
+
+    let mut candidates = json.candidates(module, name);
+    // Only look through glob re-exports if there's nothing more specific (like rustc does).
+    if candidates.is_empty() {
+        for item in json.items(module) {
+            let reexport = &item["inner"]["use"];
+            if reexport["is_glob"] == true
+                && let Some(target) = json.item(&reexport["id"])
+                && item_kind(target) == Some("module")
+            {
+                candidates.extend(
+                    json.candidates(target, name)
+                        .into_iter()
+                        .map(|(target, _)| (target, Some(item))),
+                );
+            }
+        }
+    }
+
+    if candidates.is_empty() {
+        return Err(Error::UnknownItem(path.to_owned()).into());
+    }
+
+    for (target, reexport) in candidates {
+        println!("`{module_path}::{name}`");
+
+        if let Some(reexport) = reexport {
+            let source = reexport["inner"]["use"]["source"].as_str().unwrap_or_default();
+            let glob = if reexport["inner"]["use"]["is_glob"] == true { "::*" } else { "" };
+            println!("    re-exported via `pub use {source}{glob};` in `{module_path}`");
+            let attrs: Vec<_> = reexport["attrs"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            if !attrs.is_empty() {
+                println!(
+                    "    with the attributes {}",
+                    attrs.iter().map(|attr| format!("`{attr}`")).join_with(", ")
+                );
+            }
+        } else {
+            println!("    defined in `{module_path}`");
+        }
+
+        let summary = &json.0["paths"][id_to_key(target)];
+        let (kind, inner) = match json.item(target) {
+            Some(target) => {
+                let kind = item_kind(target).unwrap_or_default();
+                (kind, &target["inner"][kind])
+            }
+            None => (summary["kind"].as_str().unwrap_or_default(), &Value::Null),
+        };
+
+        let canonical = summary["path"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .join_with("::");
+        match summary["crate_id"].as_u64() {
+            // Unreachable items don't have an entry.
+            None => println!("    resolves to a local {kind} that's unreachable by other means"),
+            Some(0) => println!("    resolves to the local {kind} `{canonical}`"),
+            Some(id) => {
+                let krate =
+                    json.0["external_crates"][id.to_string()]["name"].as_str().unwrap_or("?");
+                println!("    resolves to the {kind} `{canonical}` from the crate `{krate}`");
+            }
+        }
+
+        let page =
+            page_prefix(kind, inner).map(|prefix| directory.join(format!("{prefix}.{name}.html")));
+        let page = page.filter(|page| page.exists());
+        let relative = |path: &Path| path.strip_prefix(root).unwrap_or(path).display().to_string();
+
+        match page {
+            Some(page) if !is_redirect(&page)? => {
+                let verb = if reexport.is_some() { "inlined" } else { "rendered" };
+                println!("    {verb} on the page `{}`", relative(&page));
+            }
+            _ if kind == "module" && directory.join(name).join("index.html").exists() => {
+                println!(
+                    "    rendered on the page `{}`",
+                    relative(&directory.join(name).join("index.html"))
+                );
+            }
+            _ => {
+                let index = directory.join("index.html");
+                let listed = std::fs::read_to_string(&index)
+                    .is_ok_and(|index| index.contains(&format!("id=\"reexport.{name}\"")));
+                if listed {
+                    println!("    listed as a re-export on the page `{}`", relative(&index));
+                } else {
+                    println!("    not rendered at all");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+struct Json<'a>(&'a Value);
+
+impl<'a> Json<'a> {
+    fn item(self, id: &Value) -> Option<&'a Value> {
+        self.0["index"].get(id_to_key(id))
+    }
+
+    fn items(self, module: &'a Value) -> impl Iterator<Item = &'a Value> {
+        module["inner"]["module"]["items"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(move |id| self.item(id))
+    }
+
+    /// The items of the given name found in the module alongside the re-export referring to them.
+    fn candidates(self, module: &'a Value, name: &str) -> Vec<(&'a Value, Option<&'a Value>)> {
+        self.items(module)
+            .filter_map(|item| match item_kind(item)? {
+                "use" => {
+                    let reexport = &item["inner"]["use"];
+                    (reexport["is_glob"] != true && reexport["name"].as_str() == Some(name))
+                        .then_some((&reexport["id"], Some(item)))
+                }
+                _ => (item["name"].as_str() == Some(name)).then_some((&item["id"], None)),
+            })
+            .collect()
+    }
+
+    /// Find the submodule of the given name, looking through re-exports.
+    fn module(self, module: &'a Value, name: &str) -> Option<&'a Value> {
+        self.items(module).find_map(|item| match item_kind(item)? {
+            "module" if item["name"].as_str() == Some(name) => Some(item),
+            "use" if item["inner"]["use"]["name"].as_str() == Some(name) => self
+                .item(&item["inner"]["use"]["id"])
+                .filter(|item| item_kind(item) == Some("module")),
+            _ => None,
+        })
+    }
+}
+
+pub(crate) enum Error {
+    UnknownItem(String),
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::UnknownItem(path) => error(format!("failed to find the item `{path}`"))
+                .note("only local modules are looked through"),
+        }
+    }
+}
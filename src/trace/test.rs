@@ -0,0 +1,56 @@
+use super::Json;
+use serde_json::{Value, json};
+
+fn krate() -> Value {
+    json!({
+        "root": 0,
+        "index": {
+            "0": { "id": 0, "name": "krate", "inner": { "module": { "items": [1, 2, 3, 4, 5] } } },
+            "1": { "id": 1, "name": "inner", "inner": { "module": { "items": [6, 7] } } },
+            "2": { "id": 2, "inner": { "use": { "id": 6, "name": "Alias", "is_glob": false } } },
+            "3": { "id": 3, "inner": { "use": { "id": 1, "name": "renamed", "is_glob": false } } },
+            "4": { "id": 4, "inner": { "use": { "id": 1, "name": "inner", "is_glob": true } } },
+            "5": { "id": 5, "name": "f", "inner": { "function": {} } },
+            "6": { "id": 6, "name": "S", "inner": { "struct": {} } },
+            "7": { "id": 7, "name": "E", "inner": { "enum": {} } },
+        },
+    })
+}
+
+/// The IDs of the candidates alongside the IDs of the re-exports referring to them.
+fn candidates(json: Json<'_>, module: &Value, name: &str) -> Vec<(u64, Option<u64>)> {
+    json.candidates(module, name)
+        .into_iter()
+        .map(|(target, reexport)| {
+            (target.as_u64().unwrap(), reexport.map(|reexport| reexport["id"].as_u64().unwrap()))
+        })
+        .collect()
+}
+
+#[test]
+fn candidates_of_names() {
+    let krate = krate();
+    let json = Json(&krate);
+    let root = json.item(&json.0["root"]).unwrap();
+
+    assert_eq!(candidates(json, root, "f"), [(5, None)]);
+    assert_eq!(candidates(json, root, "Alias"), [(6, Some(2))]);
+    // Glob re-exports are only looked through if there's nothing more specific.
+    assert_eq!(candidates(json, root, "E"), []);
+    assert_eq!(candidates(json, root, "S"), []);
+}
+
+#[test]
+fn modules() {
+    let krate = krate();
+    let json = Json(&krate);
+    let root = json.item(&json.0["root"]).unwrap();
+
+    assert_eq!(json.module(root, "inner").unwrap()["id"], 1);
+    assert_eq!(json.module(root, "renamed").unwrap()["id"], 1);
+    assert!(json.module(root, "f").is_none());
+    assert!(json.module(root, "Alias").is_none());
+
+    let inner = json.module(root, "inner").unwrap();
+    assert_eq!(candidates(json, inner, "E"), [(7, None)]);
+}
@@ -0,0 +1,124 @@
+//! Uploading the generated docs and the report to a destination after a successful run.
+//!
+//! Apart from copying to local paths, we merely shell out to `rsync` (or `scp`) and `aws`.
+
+use crate::{
+    cli,
+    diagnostic::{Diagnostic, IntoDiagnostic, error, info},
+    utility::copy_dir,
+};
+use std::{io, path::Path, process};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, PartialEq)]
+enum Destination<'a> {
+    Local(&'a Path),
+    /// An rsync/scp target like `host:path` or `user@host:path`.
+    Remote(&'a str),
+    /// An S3(-compatible) URL like `s3://bucket/prefix`.
+    ///
+    /// The endpoint of S3-compatible services can be set via `AWS_ENDPOINT_URL`.
+    S3(&'a str),
+}
+
+impl<'a> Destination<'a> {
+    fn parse(destination: &'a str) -> Self {
+        if destination.starts_with("s3://") {
+            return Self::S3(destination);
+        }
+
+        // Like rsync and scp, we consider paths with a colon before the first slash to be remote.
+        match destination.split_once(':') {
+            Some((host, _)) if !host.is_empty() && !host.contains('/') => Self::Remote(destination),
+            _ => Self::Local(Path::new(destination)),
+        }
+    }
+}
+
+/// Upload the file or directory at `source` to the given destination.
+pub(crate) fn upload(
+    source: &Path,
+    destination: &str,
+    flags: &cli::ProgramFlags,
+) -> crate::error::Result {
+    if flags.verbose {
+        let verb = if !flags.dry_run { "uploading" } else { "skipping the upload of" };
+        info(format!("{verb} `{}` to `{destination}`", source.display())).emit();
+    }
+
+    if flags.dry_run {
+        return Ok(());
+    }
+
+    let is_dir = source.is_dir();
+
+    match Destination::parse(destination) {
+        Destination::Local(target) => match is_dir {
+            true => copy_dir(source, target)?,
+            false => {
+                std::fs::create_dir_all(target)?;
+                std::fs::copy(source, target.join(source.file_name().unwrap_or_default()))?;
+            }
+        },
+        Destination::Remote(target) => {
+            // The trailing slash makes rsync copy the contents of the directory.
+            let mut contents = source.as_os_str().to_owned();
+            if is_dir {
+                contents.push("/");
+            }
+            let mut rsync = process::Command::new("rsync");
+            rsync.arg("-a").arg(&contents).arg(target);
+
+            let status = match rsync.status() {
+                Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                    // Like above, the trailing `/.` makes scp copy the contents of the directory.
+                    let mut contents = source.as_os_str().to_owned();
+                    if is_dir {
+                        contents.push("/.");
+                    }
+                    let mut scp = process::Command::new("scp");
+                    scp.arg("-r").arg(&contents).arg(target);
+                    execute(scp, "scp")?
+                }
+                result => result?,
+            };
+            status.exit_ok()?;
+        }
+        Destination::S3(target) => {
+            let mut aws = process::Command::new("aws");
+            aws.arg("s3").arg(if is_dir { "sync" } else { "cp" }).arg(source).arg(target);
+            execute(aws, "aws")?.exit_ok()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn execute(
+    mut command: process::Command,
+    program: &'static str,
+) -> crate::error::Result<process::ExitStatus> {
+    command.status().map_err(|error| match error.kind() {
+        io::ErrorKind::NotFound => Error::MissingProgram(program).into(),
+        _ => crate::error::Error::from(error),
+    })
+}
+
+pub(crate) enum Error {
+    MissingProgram(&'static str),
+}
+
+impl IntoDiagnostic for Error {
+    fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Self::MissingProgram(program) => {
+                error(format!("failed to find `{program}`")).note(match program {
+                    "aws" => "uploading to S3 requires the AWS CLI",
+                    _ => "uploading to remote hosts requires `rsync` or `scp`",
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use super::Destination;
+use std::path::Path;
+
+#[test]
+fn destinations() {
+    assert_eq!(Destination::parse("s3://bucket/docs"), Destination::S3("s3://bucket/docs"));
+    assert_eq!(Destination::parse("user@host:docs/"), Destination::Remote("user@host:docs/"));
+    assert_eq!(Destination::parse("host:"), Destination::Remote("host:"));
+    assert_eq!(Destination::parse("/srv/docs"), Destination::Local(Path::new("/srv/docs")));
+    assert_eq!(Destination::parse("./a:b"), Destination::Local(Path::new("./a:b")));
+}
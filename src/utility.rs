@@ -1,4 +1,10 @@
-use std::borrow::Cow;
+use crate::cli;
+use std::{
+    borrow::Cow,
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
 
 pub(crate) type Str = Cow<'static, str>;
 
@@ -7,3 +13,136 @@ pub(crate) fn default<T: Default>() -> T {
 }
 
 pub(crate) type SmallVec<T, const N: usize> = smallvec::SmallVec<[T; N]>;
+
+/// The directory for temporary files: the one passed via `--scratch-dir` if provided,
+/// the temporary directory of the system otherwise.
+pub(crate) fn scratch_dir(flags: &cli::ProgramFlags) -> io::Result<PathBuf> {
+    let Some(directory) = &flags.scratch_dir else {
+        return Ok(std::env::temp_dir());
+    };
+    if !flags.dry_run {
+        std::fs::create_dir_all(directory)?;
+    }
+    Ok(directory.clone())
+}
+
+/// The path to the given artifact relative to the output directory passed via `--out-dir`
+/// if provided, relative to the current directory otherwise.
+pub(crate) fn out_path(flags: &cli::BuildFlags, artifact: impl AsRef<Path>) -> PathBuf {
+    match &flags.out_dir {
+        Some(directory) => directory.join(artifact),
+        None => artifact.as_ref().to_owned(),
+    }
+}
+
+/// The absolute path to the documentation root.
+pub(crate) fn doc_root(flags: &cli::BuildFlags) -> io::Result<PathBuf> {
+    Ok(std::env::current_dir()?.join(out_path(flags, "doc")))
+}
+
+/// Write the source code read from stdin to a file in a fresh temporary directory.
+///
+/// The file is called `rust_out.rs` after the crate name `rustc` uses for stdin.
+pub(crate) fn stdin_source(flags: &cli::ProgramFlags) -> io::Result<PathBuf> {
+    let directory = scratch_dir(flags)?.join(format!("rruxwry-stdin-{}", std::process::id()));
+    std::fs::create_dir_all(&directory)?;
+    let path = directory.join("rust_out.rs");
+    std::fs::write(&path, io::read_to_string(io::stdin())?)?;
+    Ok(path)
+}
+
+/// The files found in the directory `root` (recursively) that have one of the given extensions.
+pub(crate) fn files(root: &Path, extensions: &[&str]) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut directories = vec![root.to_owned()];
+
+    while let Some(directory) = directories.pop() {
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                directories.push(path);
+                continue;
+            }
+
+            let extension = path.extension().and_then(|extension| extension.to_str());
+            if extension.is_some_and(|extension| extensions.contains(&extension)) {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Block until one of the given files gets modified, created or removed and return it.
+///
+/// We simply poll the modification times which is good enough for a handful of files.
+pub(crate) fn wait_for_change(paths: &[PathBuf]) -> PathBuf {
+    const INTERVAL: Duration = Duration::from_millis(250);
+
+    let modified =
+        |path: &Path| -> Option<SystemTime> { std::fs::metadata(path).ok()?.modified().ok() };
+    let initial: Vec<_> = paths.iter().map(|path| modified(path)).collect();
+
+    loop {
+        std::thread::sleep(INTERVAL);
+        if let Some((path, _)) =
+            paths.iter().zip(&initial).find(|(path, initial)| modified(path) != **initial)
+        {
+            return path.clone();
+        }
+    }
+}
+
+/// Copy the directory `source` (recursively) to `destination`, merging it with existing contents.
+pub(crate) fn copy_dir(source: &Path, destination: &Path) -> io::Result<()> {
+    let mut directories = vec![(source.to_owned(), destination.to_owned())];
+
+    while let Some((source, destination)) = directories.pop() {
+        std::fs::create_dir_all(&destination)?;
+
+        for entry in std::fs::read_dir(&source)? {
+            let path = entry?.path();
+            let target = destination.join(path.file_name().unwrap_or_default());
+            if path.is_dir() {
+                directories.push((path, target));
+            } else {
+                std::fs::copy(&path, target)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove the ANSI escape sequences (e.g., colors) from the given text.
+pub(crate) fn strip_ansi_escapes(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(char) = chars.next() {
+        if char == '\x1b' {
+            // Skip the control sequence which is terminated by an ASCII letter.
+            for char in chars.by_ref() {
+                if char.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        result.push(char);
+    }
+
+    result
+}
+
+/// The reason why the given regular expression failed to compile, on a single line.
+///
+/// The syntax errors reported by the `regex` crate span several lines and point at the
+/// offending part of the pattern, which doesn't fit into our diagnostics.
+pub(crate) fn regex_error(error: &regex::Error) -> String {
+    let error = error.to_string();
+    let reason = error.lines().last().unwrap_or_default();
+    reason.strip_prefix("error: ").unwrap_or(reason).to_owned()
+}
@@ -0,0 +1,96 @@
+//! A small static viewer for comparing two builds of the docs side by side.
+//!
+//! The baseline gets preserved in `doc.baseline/` and the viewer is written to `doc.viewer/`,
+//! both next to the documentation root.
+
+use crate::{
+    html::escape,
+    output::{self, Difference},
+    utility::copy_dir,
+};
+use std::{
+    fmt::Write,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Preserve the current contents of the documentation root `root` as the baseline.
+pub(crate) fn preserve_baseline(root: &Path) -> io::Result<()> {
+    preserve_as_baseline(root, root)
+}
+
+/// Preserve the docs in `docs` as the baseline of the documentation root `root`.
+pub(crate) fn preserve_as_baseline(docs: &Path, root: &Path) -> io::Result<()> {
+    let baseline = sibling(root, "baseline");
+    if baseline.exists() {
+        std::fs::remove_dir_all(&baseline)?;
+    }
+    copy_dir(docs, &baseline)
+}
+
+/// Generate a viewer for the differences between the baseline and `root`
+/// and return the path to its index page.
+pub(crate) fn generate(
+    root: &Path,
+    differences: &[Difference<'_>],
+    title: &str,
+) -> io::Result<PathBuf> {
+    let viewer = sibling(root, "viewer");
+    let directory =
+        |root: &Path| root.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let (before, after) = (directory(&sibling(root, "baseline")), directory(root));
+
+    let mut html = String::new();
+    let title = escape(title);
+    let summary = output::summarize(differences);
+    writeln!(html, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">")
+        .unwrap();
+    writeln!(html, "<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>").unwrap();
+    writeln!(html, "<h1>{title}</h1>\n<p>{summary}.</p>\n<ul>").unwrap();
+    for (index, difference) in differences.iter().enumerate() {
+        let entry = escape(&difference.to_string()).into_owned();
+        writeln!(html, "<li><a href=\"#file-{index}\"><code>{entry}</code></a></li>").unwrap();
+    }
+    writeln!(html, "</ul>").unwrap();
+
+    for (index, difference) in differences.iter().enumerate() {
+        let path = difference.path().to_string_lossy().replace('\\', "/");
+        let frame = |directory: &str| {
+            format!("<iframe src=\"../{}/{}\"></iframe>", escape(directory), escape(&path))
+        };
+        let placeholder = |text: &str| format!("<div class=\"missing\">{text}</div>");
+
+        let (left, right) = match difference {
+            Difference::Added(_) => (placeholder("absent in the baseline"), frame(&after)),
+            Difference::Removed(_) => (frame(&before), placeholder("absent in the new build")),
+            Difference::Changed(_) => (frame(&before), frame(&after)),
+        };
+        writeln!(
+            html,
+            "<details id=\"file-{index}\" open><summary><code>{}</code></summary>\
+             <div class=\"pair\">{left}{right}</div></details>",
+            escape(&difference.to_string())
+        )
+        .unwrap();
+    }
+    writeln!(html, "</body>\n</html>").unwrap();
+
+    std::fs::create_dir_all(&viewer)?;
+    let index = viewer.join("index.html");
+    std::fs::write(&index, html)?;
+    Ok(index)
+}
+
+fn sibling(root: &Path, suffix: &str) -> PathBuf {
+    let mut name = root.file_name().unwrap_or_default().to_owned();
+    name.push(".");
+    name.push(suffix);
+    root.with_file_name(name)
+}
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 1em 2em; }
+.pair { display: flex; gap: 1em; }
+.pair > * { flex: 1; height: 70vh; border: 1px solid #888; }
+.missing { display: flex; align-items: center; justify-content: center; color: #888; }
+summary { cursor: pointer; margin: 0.5em 0; }";
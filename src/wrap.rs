@@ -0,0 +1,57 @@
+//! Embedding snippets that aren't valid crate roots into a compilable skeleton (`--wrap`).
+
+use crate::{cli, utility::scratch_dir};
+use std::path::{Path, PathBuf};
+
+#[cfg(test)]
+mod test;
+
+/// Write the wrapped source file for the snippet at the given path, returning its path.
+///
+/// Like the dependent crate of the cross-crate mode, it's placed next to the snippet unless
+/// `--scratch-dir` is passed, so relative paths in directives still resolve.
+pub(crate) fn write(
+    path: &Path,
+    template: cli::Wrap,
+    flags: &cli::ProgramFlags,
+) -> std::io::Result<PathBuf> {
+    let source = std::fs::read_to_string(path)?;
+    let file_name = format!("w_{}.rs", path.file_stem().unwrap_or_default().to_string_lossy());
+    let wrapped = match flags.scratch_dir {
+        Some(_) => scratch_dir(flags)?.join(file_name),
+        None => path.with_file_name(file_name),
+    };
+    std::fs::write(&wrapped, wrap(&source, template))?;
+    Ok(wrapped)
+}
+
+fn wrap(source: &str, template: cli::Wrap) -> String {
+    // Inner attributes like `#![feature(…)]` have to stay at the crate root. We also keep the
+    // comments around them there since they likely contain directives.
+    let (header, body) = split_header(source);
+    let body = body.trim_end();
+
+    match template {
+        cli::Wrap::Item => format!("#![allow(dead_code, unused)]\n{header}{body}\n"),
+        // We don't indent the body since that would alter multi-line string literals.
+        cli::Wrap::Fn => format!("{header}pub fn snippet() {{\n{body}\n}}\n"),
+        cli::Wrap::Module => format!("{header}pub mod snippet {{\n{body}\n}}\n"),
+    }
+}
+
+/// Split the source into the leading lines that are empty, comments or inner attributes and the
+/// rest.
+fn split_header(source: &str) -> (&str, &str) {
+    let mut end = 0;
+    for line in source.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let is_header = trimmed.is_empty()
+            || (trimmed.starts_with("//") && !trimmed.starts_with("///"))
+            || trimmed.starts_with("#![");
+        if !is_header {
+            break;
+        }
+        end += line.len();
+    }
+    source.split_at(end)
+}
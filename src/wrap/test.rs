@@ -0,0 +1,24 @@
+use super::wrap;
+use crate::cli::Wrap;
+
+#[test]
+fn function() {
+    assert_eq!(wrap("let x = 1;\nx + 1\n", Wrap::Fn), "pub fn snippet() {\nlet x = 1;\nx + 1\n}\n");
+}
+
+#[test]
+fn inner_attributes_stay_at_the_root() {
+    let source = "//@ edition: 2021\n#![feature(doc_cfg)]\n\n/// Docs.\npub struct S;\n";
+    assert_eq!(
+        wrap(source, Wrap::Module),
+        "//@ edition: 2021\n#![feature(doc_cfg)]\n\npub mod snippet {\n/// Docs.\npub struct S;\n}\n"
+    );
+}
+
+#[test]
+fn items() {
+    assert_eq!(
+        wrap("#![no_std]\nimpl S {}\n", Wrap::Item),
+        "#![allow(dead_code, unused)]\n#![no_std]\nimpl S {}\n"
+    );
+}
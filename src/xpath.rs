@@ -0,0 +1,552 @@
+//! A minimal DOM of the HTML output of rustdoc and an evaluator of `htmldocck` XPaths.
+//!
+//! Like `htmldocck` which uses Python's `xml.etree.ElementTree`, we only support a small subset
+//! of XPath: Paths have to be absolute (`//…` or `.//…`) and consist of steps separated by `/`
+//! or `//`. A step is a name, `*`, `.` or `..` followed by any number of the predicates
+//! `[@attr]`, `[@attr='value']`, `[@attr!='value']`, `[tag]`, `[tag='text']`, `[.='text']`,
+//! `[N]`, `[last()]` and `[last()-N]`. The final step may also be `@attr` to select the value of
+//! an attribute or `text()` to select the text of the elements.
+
+use crate::html::{self, Token};
+use std::fmt;
+
+#[cfg(test)]
+mod test;
+
+pub(crate) struct Document {
+    nodes: Vec<Node>,
+}
+
+pub(crate) type NodeId = usize;
+
+/// The synthetic node containing the root element(s).
+pub(crate) const ROOT: NodeId = 0;
+
+struct Node {
+    parent: NodeId,
+    kind: NodeKind,
+    children: Vec<NodeId>,
+}
+
+enum NodeKind {
+    Element { name: String, attributes: Vec<(String, String)> },
+    Text(String),
+}
+
+/// Elements that never have any contents and thus no end tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+impl Document {
+    pub(crate) fn parse(source: &str) -> Self {
+        let root = Node {
+            parent: ROOT,
+            kind: NodeKind::Element { name: String::new(), attributes: Vec::new() },
+            children: Vec::new(),
+        };
+        let mut document = Self { nodes: vec![root] };
+        let mut stack = vec![ROOT];
+
+        for token in html::tokenize(source) {
+            let parent = *stack.last().unwrap();
+            match token {
+                Token::StartTag(tag) => {
+                    let name = tag.name.to_ascii_lowercase();
+                    let void = tag.self_closing || VOID_ELEMENTS.contains(&name.as_str());
+                    let attributes = tag
+                        .attributes
+                        .into_iter()
+                        .map(|(key, value)| (key.to_ascii_lowercase(), value.into_owned()))
+                        .collect();
+                    let node = document.push(parent, NodeKind::Element { name, attributes });
+                    if !void {
+                        stack.push(node);
+                    }
+                }
+                Token::EndTag(name) => {
+                    // Stray end tags get ignored and unclosed elements get closed implicitly.
+                    if let Some(index) = stack.iter().rposition(|&node| {
+                        document.name(node).is_some_and(|it| it.eq_ignore_ascii_case(name))
+                    }) && index > 0
+                    {
+                        stack.truncate(index);
+                    }
+                }
+                Token::Text(text) => {
+                    let text = match document.name(parent) {
+                        Some("script" | "style") => text.to_owned(),
+                        _ => html::decode(text).into_owned(),
+                    };
+                    document.push(parent, NodeKind::Text(text));
+                }
+            }
+        }
+
+        document
+    }
+
+    fn push(&mut self, parent: NodeId, kind: NodeKind) -> NodeId {
+        let node = self.nodes.len();
+        self.nodes.push(Node { parent, kind, children: Vec::new() });
+        self.nodes[parent].children.push(node);
+        node
+    }
+
+    /// The name of the given element or `None` if it's a text node.
+    pub(crate) fn name(&self, node: NodeId) -> Option<&str> {
+        match &self.nodes[node].kind {
+            NodeKind::Element { name, .. } => Some(name),
+            NodeKind::Text(_) => None,
+        }
+    }
+
+    pub(crate) fn attribute(&self, node: NodeId, name: &str) -> Option<&str> {
+        match &self.nodes[node].kind {
+            NodeKind::Element { attributes, .. } => {
+                attributes.iter().find(|(key, _)| key == name).map(|(_, value)| &**value)
+            }
+            NodeKind::Text(_) => None,
+        }
+    }
+
+    /// The contents of the given text node.
+    pub(crate) fn contents(&self, node: NodeId) -> Option<&str> {
+        match &self.nodes[node].kind {
+            NodeKind::Element { .. } => None,
+            NodeKind::Text(text) => Some(text),
+        }
+    }
+
+    pub(crate) fn parent(&self, node: NodeId) -> NodeId {
+        self.nodes[node].parent
+    }
+
+    /// The child nodes of the given node incl. text nodes.
+    pub(crate) fn child_nodes(&self, node: NodeId) -> &[NodeId] {
+        &self.nodes[node].children
+    }
+
+    /// The concatenated text of all descendants of the given node.
+    pub(crate) fn text(&self, node: NodeId) -> String {
+        let mut text = String::new();
+        self.collect_text(node, &mut text);
+        text
+    }
+
+    fn collect_text(&self, node: NodeId, text: &mut String) {
+        match &self.nodes[node].kind {
+            NodeKind::Element { .. } => {
+                for &child in &self.nodes[node].children {
+                    self.collect_text(child, text);
+                }
+            }
+            NodeKind::Text(contents) => text.push_str(contents),
+        }
+    }
+
+    /// Serialize the given node back to HTML.
+    pub(crate) fn serialize(&self, node: NodeId) -> String {
+        let mut html = String::new();
+        self.write(node, &mut html);
+        html
+    }
+
+    fn write(&self, node: NodeId, html: &mut String) {
+        match &self.nodes[node].kind {
+            NodeKind::Element { name, attributes } => {
+                html.push('<');
+                html.push_str(name);
+                for (key, value) in attributes {
+                    html.push_str(&format!(" {key}=\"{}\"", html::escape(value)));
+                }
+                if VOID_ELEMENTS.contains(&name.as_str()) {
+                    html.push_str(" />");
+                    return;
+                }
+                html.push('>');
+                for &child in &self.nodes[node].children {
+                    self.write(child, html);
+                }
+                html.push_str(&format!("</{name}>"));
+            }
+            NodeKind::Text(text) => match self.name(self.nodes[node].parent) {
+                Some("script" | "style") => html.push_str(text),
+                _ => html.push_str(&html::escape(text)),
+            },
+        }
+    }
+
+    /// The child elements of the given node.
+    fn children(&self, node: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes[node].children.iter().copied().filter(|&child| self.name(child).is_some())
+    }
+
+    fn push_descendants(&self, node: NodeId, nodes: &mut Vec<NodeId>) {
+        nodes.push(node);
+        for child in self.children(node) {
+            self.push_descendants(child, nodes);
+        }
+    }
+
+    /// The elements selected by the XPath in document order.
+    pub(crate) fn select(&self, xpath: &XPath) -> Vec<NodeId> {
+        let mut context = vec![ROOT];
+
+        for step in &xpath.steps {
+            if step.descendant {
+                let mut descendants = Vec::new();
+                for &node in &context {
+                    self.push_descendants(node, &mut descendants);
+                }
+                descendants.sort_unstable();
+                descendants.dedup();
+                context = descendants;
+            }
+
+            let mut selected = Vec::new();
+            for &node in &context {
+                let children = self.children(node);
+                let mut candidates: Vec<_> = match &step.test {
+                    Test::Current => vec![node],
+                    Test::Parent if node == ROOT => Vec::new(),
+                    Test::Parent => vec![self.nodes[node].parent],
+                    Test::Any => children.collect(),
+                    Test::Name(name) => {
+                        children.filter(|&it| self.name(it) == Some(name)).collect()
+                    }
+                };
+                for predicate in &step.predicates {
+                    candidates = self.filter(candidates, predicate);
+                }
+                selected.extend(candidates);
+            }
+
+            selected.sort_unstable();
+            selected.dedup();
+            context = selected;
+        }
+
+        context.retain(|&node| node != ROOT);
+        context
+    }
+
+    fn filter(&self, candidates: Vec<NodeId>, predicate: &Predicate) -> Vec<NodeId> {
+        let child_text = |node: NodeId, name: &str, expected: Option<&str>| {
+            self.children(node).any(|child| {
+                self.name(child) == Some(name)
+                    && expected.is_none_or(|expected| self.text(child) == expected)
+            })
+        };
+
+        match predicate {
+            Predicate::Position(position) => {
+                let index = match *position {
+                    Position::Index(index) => index.checked_sub(1),
+                    Position::FromLast(offset) => candidates.len().checked_sub(offset + 1),
+                };
+                index.and_then(|index| candidates.get(index)).copied().into_iter().collect()
+            }
+            Predicate::Attribute(name) => candidates
+                .into_iter()
+                .filter(|&node| self.attribute(node, name).is_some())
+                .collect(),
+            Predicate::AttributeEquals { name, value, negated } => candidates
+                .into_iter()
+                .filter(|&node| (self.attribute(node, name) == Some(value)) != *negated)
+                .collect(),
+            Predicate::Child { name, text } => candidates
+                .into_iter()
+                .filter(|&node| child_text(node, name, text.as_deref()))
+                .collect(),
+            Predicate::Text(text) => {
+                candidates.into_iter().filter(|&node| self.text(node) == *text).collect()
+            }
+        }
+    }
+
+    /// The values selected by the XPath, i.e., the attribute values or the text of the elements.
+    pub(crate) fn values(&self, xpath: &XPath) -> Vec<String> {
+        let nodes = self.select(xpath);
+        match &xpath.target {
+            Target::Attribute(name) => nodes
+                .into_iter()
+                .filter_map(|node| self.attribute(node, name).map(ToOwned::to_owned))
+                .collect(),
+            Target::Elements | Target::Text => {
+                nodes.into_iter().map(|node| self.text(node)).collect()
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct XPath {
+    steps: Vec<Step>,
+    pub(crate) target: Target,
+}
+
+#[derive(Clone)]
+pub(crate) enum Target {
+    Elements,
+    Attribute(String),
+    Text,
+}
+
+#[derive(Clone)]
+struct Step {
+    /// Whether the step is preceded by `//` instead of `/`.
+    descendant: bool,
+    test: Test,
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Clone)]
+enum Test {
+    Name(String),
+    Any,
+    Current,
+    Parent,
+}
+
+#[derive(Clone)]
+enum Predicate {
+    Position(Position),
+    Attribute(String),
+    AttributeEquals { name: String, value: String, negated: bool },
+    Child { name: String, text: Option<String> },
+    Text(String),
+}
+
+#[derive(Clone, Copy)]
+enum Position {
+    /// The one-based index.
+    Index(usize),
+    /// The offset from the last candidate.
+    FromLast(usize),
+}
+
+impl XPath {
+    pub(crate) fn parse(source: &str) -> Result<Self, Error> {
+        let source = source.strip_prefix('.').unwrap_or(source);
+        if !source.starts_with("//") {
+            return Err(Error::NotAbsolute);
+        }
+
+        let mut parser = Parser { chars: source.chars().collect(), index: 0 };
+        let mut steps = Vec::new();
+
+        loop {
+            if !parser.consume('/') {
+                return match parser.peek() {
+                    Some(char) => Err(Error::UnexpectedChar(char)),
+                    None => Ok(Self { steps, target: Target::Elements }),
+                };
+            }
+            let descendant = parser.consume('/');
+
+            if parser.consume('@') {
+                let name = parser.name()?;
+                return parser.finish(Self { steps, target: Target::Attribute(name) });
+            }
+            if parser.consume_str("text()") {
+                return parser.finish(Self { steps, target: Target::Text });
+            }
+
+            let test = if parser.consume_str("..") {
+                Test::Parent
+            } else if parser.consume('.') {
+                Test::Current
+            } else if parser.consume('*') {
+                Test::Any
+            } else {
+                Test::Name(parser.name()?)
+            };
+
+            let mut predicates = Vec::new();
+            while parser.consume('[') {
+                predicates.push(parser.predicate()?);
+                parser.skip_whitespace();
+                parser.expect(']')?;
+            }
+
+            steps.push(Step { descendant, test, predicates });
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn consume(&mut self, expected: char) -> bool {
+        let consumed = self.peek() == Some(expected);
+        if consumed {
+            self.index += 1;
+        }
+        consumed
+    }
+
+    fn consume_str(&mut self, expected: &str) -> bool {
+        let consumed =
+            self.chars[self.index..].iter().copied().take(expected.len()).eq(expected.chars());
+        if consumed {
+            self.index += expected.len();
+        }
+        consumed
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        match self.peek() {
+            Some(char) if char == expected => {
+                self.index += 1;
+                Ok(())
+            }
+            Some(char) => Err(Error::UnexpectedChar(char)),
+            None => Err(Error::UnexpectedEnd),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.index += 1;
+        }
+    }
+
+    fn finish(&self, xpath: XPath) -> Result<XPath, Error> {
+        match self.peek() {
+            Some(_) => Err(Error::TrailingStep),
+            None => Ok(xpath),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, Error> {
+        let start = self.index;
+        while self
+            .peek()
+            .is_some_and(|char| char.is_alphanumeric() || matches!(char, '-' | '_' | ':'))
+        {
+            self.index += 1;
+        }
+        if start == self.index {
+            return Err(self.peek().map_or(Error::UnexpectedEnd, Error::UnexpectedChar));
+        }
+        Ok(self.chars[start..self.index].iter().collect())
+    }
+
+    fn number(&mut self) -> Result<usize, Error> {
+        let start = self.index;
+        while self.peek().is_some_and(|char| char.is_ascii_digit()) {
+            self.index += 1;
+        }
+        let number: String = self.chars[start..self.index].iter().collect();
+        number.parse().map_err(|_| self.peek().map_or(Error::UnexpectedEnd, Error::UnexpectedChar))
+    }
+
+    fn literal(&mut self) -> Result<String, Error> {
+        self.skip_whitespace();
+        let quote = match self.peek() {
+            Some(quote @ ('\'' | '"')) => quote,
+            Some(char) => return Err(Error::UnexpectedChar(char)),
+            None => return Err(Error::UnexpectedEnd),
+        };
+        self.index += 1;
+
+        let start = self.index;
+        while self.peek().is_some_and(|char| char != quote) {
+            self.index += 1;
+        }
+        let literal = self.chars[start..self.index].iter().collect();
+        self.expect(quote)?;
+        Ok(literal)
+    }
+
+    /// Parse `=` followed by a literal if present.
+    fn comparison(&mut self) -> Result<Option<String>, Error> {
+        self.skip_whitespace();
+        if !self.consume('=') {
+            return Ok(None);
+        }
+        self.literal().map(Some)
+    }
+
+    fn predicate(&mut self) -> Result<Predicate, Error> {
+        self.skip_whitespace();
+
+        if self.consume('@') {
+            let name = self.name()?;
+            self.skip_whitespace();
+            let negated = self.consume_str("!=");
+            if negated {
+                let value = self.literal()?;
+                return Ok(Predicate::AttributeEquals { name, value, negated });
+            }
+            return Ok(match self.comparison()? {
+                Some(value) => Predicate::AttributeEquals { name, value, negated },
+                None => Predicate::Attribute(name),
+            });
+        }
+
+        if self.peek().is_some_and(|char| char.is_ascii_digit()) {
+            return match self.number()? {
+                0 => Err(Error::InvalidPosition),
+                index => Ok(Predicate::Position(Position::Index(index))),
+            };
+        }
+
+        if self.consume_str("last()") {
+            self.skip_whitespace();
+            let offset = match self.consume('-') {
+                true => {
+                    self.skip_whitespace();
+                    self.number()?
+                }
+                false => 0,
+            };
+            return Ok(Predicate::Position(Position::FromLast(offset)));
+        }
+
+        if self.consume('.') {
+            return match self.comparison()? {
+                Some(text) => Ok(Predicate::Text(text)),
+                None => Err(self.peek().map_or(Error::UnexpectedEnd, Error::UnexpectedChar)),
+            };
+        }
+
+        let name = self.name()?;
+        let text = self.comparison()?;
+        Ok(Predicate::Child { name, text })
+    }
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub(crate) enum Error {
+    NotAbsolute,
+    UnexpectedEnd,
+    UnexpectedChar(char),
+    InvalidPosition,
+    TrailingStep,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAbsolute => {
+                write!(f, "only absolute paths (starting with `//`) are supported")
+            }
+            Self::UnexpectedEnd => write!(f, "unexpected end of path"),
+            Self::UnexpectedChar(char) => write!(f, "unexpected `{char}`"),
+            Self::InvalidPosition => write!(f, "positions start at 1"),
+            Self::TrailingStep => {
+                write!(f, "attributes and `text()` may only be selected in the final step")
+            }
+        }
+    }
+}
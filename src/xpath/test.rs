@@ -0,0 +1,47 @@
+use super::{Document, Error, XPath};
+
+const PAGE: &str = r#"<html><body>
+<div id="main" class="docblock"><p>First <code>a &amp; b</code></p><p>Second</p><br></div>
+<ul><li>x</li><li class="last">y</li></ul>
+<a href="struct.S.html" title="struct k::S">S</a>
+</body></html>"#;
+
+fn texts(xpath: &str) -> Vec<String> {
+    Document::parse(PAGE).values(&XPath::parse(xpath).ok().unwrap())
+}
+
+#[test]
+fn selection() {
+    assert_eq!(texts("//p"), ["First a & b", "Second"]);
+    assert_eq!(texts(".//div[@id='main']/p[2]"), ["Second"]);
+    assert_eq!(texts(r#"//*[@class="docblock"]//code"#), ["a & b"]);
+    assert_eq!(texts("//li[last()]"), ["y"]);
+    assert_eq!(texts("//li[last()-1]"), ["x"]);
+    assert_eq!(texts("//li[@class!='last']"), ["x"]);
+    assert_eq!(texts("//ul[li='y']/li[1]"), ["x"]);
+    assert_eq!(texts("//p[.='Second']/../@id"), ["main"]);
+    assert_eq!(texts("//a/@href"), ["struct.S.html"]);
+    assert_eq!(texts("//a[@title]/text()"), ["S"]);
+    assert!(texts("//div/br/p").is_empty());
+}
+
+#[test]
+fn serialization() {
+    let document = Document::parse(PAGE);
+    let nodes = document.select(&XPath::parse("//div").ok().unwrap());
+    assert_eq!(
+        document.serialize(nodes[0]),
+        r#"<div id="main" class="docblock"><p>First <code>a &amp; b</code></p><p>Second</p><br /></div>"#
+    );
+}
+
+#[test]
+fn errors() {
+    let error = |source| XPath::parse(source).err().unwrap();
+
+    assert_eq!(error("div/p"), Error::NotAbsolute);
+    assert_eq!(error("//div[@id='main'"), Error::UnexpectedEnd);
+    assert_eq!(error("//li[0]"), Error::InvalidPosition);
+    assert_eq!(error("//a/@href/b"), Error::TrailingStep);
+    assert_eq!(error("//a[@href=main]"), Error::UnexpectedChar('m'));
+}